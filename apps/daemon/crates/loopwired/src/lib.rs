@@ -143,6 +143,58 @@ pub struct ShareStatusResponse {
     pub pin_required: bool,
 }
 
+// ---------------------------------------------------------------------------
+// API token helpers
+// ---------------------------------------------------------------------------
+
+/// Parses a token lifetime such as `90d`, `12h`, `30m` or `3600` (seconds).
+pub fn parse_expires_in(value: &str) -> anyhow::Result<chrono::Duration> {
+    let value = value.trim();
+    let (number, unit) = match value.find(|c: char| !c.is_ascii_digit()) {
+        Some(at) => value.split_at(at),
+        None => (value, "s"),
+    };
+    let number: i64 = number
+        .parse()
+        .map_err(|_| anyhow::anyhow!("invalid duration '{}'", value))?;
+    let seconds_per_unit = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => anyhow::bail!("invalid duration '{}' (use s, m, h, d or w)", value),
+    };
+    number
+        .checked_mul(seconds_per_unit)
+        .filter(|seconds| *seconds > 0)
+        .and_then(chrono::Duration::try_seconds)
+        .ok_or_else(|| anyhow::anyhow!("invalid duration '{}'", value))
+}
+
+/// Resolves a `--workspace` argument, either a workspace id or the path of a
+/// registered workspace, to its id.
+pub fn resolve_workspace(
+    workspaces: &[lw_api::rest::workspace::WorkspaceEntry],
+    value: &str,
+) -> anyhow::Result<uuid::Uuid> {
+    if let Ok(id) = value.parse::<uuid::Uuid>() {
+        if workspaces.iter().any(|workspace| workspace.id == id) {
+            return Ok(id);
+        }
+        anyhow::bail!("no registered workspace with id {}", id);
+    }
+    let path = fs::canonicalize(value).unwrap_or_else(|_| Path::new(value).to_path_buf());
+    workspaces
+        .iter()
+        .find(|workspace| {
+            let root = Path::new(&workspace.path);
+            root == path || fs::canonicalize(root).is_ok_and(|root| root == path)
+        })
+        .map(|workspace| workspace.id)
+        .ok_or_else(|| anyhow::anyhow!("no registered workspace at {}", value))
+}
+
 // ---------------------------------------------------------------------------
 // Tests
 // ---------------------------------------------------------------------------
@@ -433,6 +485,47 @@ mod tests {
         assert!(result.is_err());
     }
 
+    // -- API token helpers -------------------------------------------------
+
+    #[test]
+    fn parse_expires_in_units() {
+        assert_eq!(parse_expires_in("3600").unwrap().num_seconds(), 3600);
+        assert_eq!(parse_expires_in("30m").unwrap().num_seconds(), 1800);
+        assert_eq!(parse_expires_in("12h").unwrap().num_hours(), 12);
+        assert_eq!(parse_expires_in("90d").unwrap().num_days(), 90);
+        assert_eq!(parse_expires_in("2w").unwrap().num_days(), 14);
+    }
+
+    #[test]
+    fn parse_expires_in_rejects_garbage() {
+        assert!(parse_expires_in("").is_err());
+        assert!(parse_expires_in("0d").is_err());
+        assert!(parse_expires_in("10y").is_err());
+        assert!(parse_expires_in("d").is_err());
+        assert!(parse_expires_in("99999999999999999d").is_err());
+    }
+
+    #[test]
+    fn resolve_workspace_by_id_or_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let id = uuid::Uuid::new_v4();
+        let workspaces = vec![lw_api::rest::workspace::WorkspaceEntry {
+            id,
+            path: dir.path().to_string_lossy().to_string(),
+            name: "app".to_string(),
+            pinned: false,
+            icon: None,
+        }];
+
+        assert_eq!(resolve_workspace(&workspaces, &id.to_string()).unwrap(), id);
+        assert_eq!(
+            resolve_workspace(&workspaces, &dir.path().to_string_lossy()).unwrap(),
+            id
+        );
+        assert!(resolve_workspace(&workspaces, &uuid::Uuid::new_v4().to_string()).is_err());
+        assert!(resolve_workspace(&workspaces, "/nonexistent/workspace").is_err());
+    }
+
     // -- register_mdns (smoke test) ----------------------------------------
 
     #[test]
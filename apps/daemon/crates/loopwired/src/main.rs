@@ -1,11 +1,16 @@
 use clap::{Parser, Subcommand};
 use loopwired::{
-    is_process_alive, read_pid_file, register_mdns, remove_pid_file, write_pid_file,
-    ShareStartResponse, ShareStatusResponse,
+    is_process_alive, parse_expires_in, read_pid_file, register_mdns, remove_pid_file,
+    resolve_workspace, write_pid_file, ShareStartResponse, ShareStatusResponse,
 };
-use lw_api::auth::{load_or_create_bootstrap_token, regenerate_bootstrap_token};
+use lw_api::auth::{
+    create_api_token, list_api_tokens, list_sessions, load_or_create_bootstrap_token,
+    regenerate_bootstrap_token, revoke_api_token, revoke_session_by_id, NewApiToken, SessionOrigin,
+    TokenScope,
+};
+use lw_api::rest::export::{export_from_disk, ExportFormat};
 use lw_api::rest::health::init_start_time;
-use lw_api::{build_router, AppState};
+use lw_api::{build_preview_router, build_router, AppState};
 use lw_config::{ConfigPaths, DaemonConfig};
 use std::net::SocketAddr;
 
@@ -38,8 +43,33 @@ enum Commands {
     Status,
     /// Stop a running daemon
     Stop,
-    /// Generate a new bootstrap token
-    Token,
+    /// Generate a new bootstrap token, or manage API tokens
+    Token {
+        #[command(subcommand)]
+        command: Option<TokenCommands>,
+    },
+    /// List or revoke the session tokens held by browsers and devices
+    Sessions {
+        #[command(subcommand)]
+        command: Option<SessionCommands>,
+    },
+    /// Export a session's output as an asciinema recording, HTML or text
+    Export {
+        /// Agent, shell or task run session id
+        session_id: uuid::Uuid,
+        /// Output format: cast, html or text
+        #[arg(long, default_value = "cast")]
+        format: ExportFormat,
+        /// File to write; prints to stdout if unset
+        #[arg(long, short)]
+        output: Option<std::path::PathBuf>,
+        /// Terminal width the session used
+        #[arg(long, default_value_t = 80)]
+        cols: u16,
+        /// Terminal height the session used
+        #[arg(long, default_value_t = 24)]
+        rows: u16,
+    },
     /// Manage remote sharing links
     Share {
         #[command(subcommand)]
@@ -49,6 +79,44 @@ enum Commands {
     Version,
 }
 
+#[derive(Subcommand)]
+enum TokenCommands {
+    /// Generate a new bootstrap token (the default)
+    Bootstrap,
+    /// Create a named API token for automation and print it
+    Create {
+        /// Unique name, e.g. the bot that will use it
+        name: String,
+        /// Allowed scopes: read-only, fs-write, agents, git-write, remote-share
+        #[arg(long = "scope", required = true, value_delimiter = ',')]
+        scopes: Vec<TokenScope>,
+        /// Limit the token to a workspace (id or path); repeatable
+        #[arg(long = "workspace")]
+        workspaces: Vec<String>,
+        /// Lifetime such as 90d, 12h or 3600 (seconds); never expires if unset
+        #[arg(long)]
+        expires_in: Option<String>,
+    },
+    /// List API tokens
+    List,
+    /// Revoke an API token by id or name
+    Revoke {
+        /// Token id or name
+        token: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum SessionCommands {
+    /// List active sessions (the default)
+    List,
+    /// Revoke a session so its device has to pair again
+    Revoke {
+        /// Session id, as shown by `loopwired sessions`
+        id: uuid::Uuid,
+    },
+}
+
 #[derive(Subcommand)]
 enum ShareCommands {
     /// Start remote sharing and print a connection link
@@ -66,6 +134,12 @@ enum ShareCommands {
     Stop,
 }
 
+/// Where to open the UI: the daemon when it serves one, else `frontend_url`.
+fn open_url(config: &DaemonConfig, paths: &ConfigPaths) -> String {
+    let serves_web_ui = lw_api::web::WebUi::load(config, paths).is_some();
+    lw_api::web::frontend_url(config, serves_web_ui)
+}
+
 fn read_pid(paths: &ConfigPaths) -> Option<u32> {
     read_pid_file(&paths.pid_path())
 }
@@ -239,7 +313,11 @@ async fn main() -> anyhow::Result<()> {
                     let (bootstrap_token, _) = load_or_create_bootstrap_token(&paths);
                     println!("Loopwire daemon started.");
                     println!();
-                    println!("  Open: {}/?token={}", config.frontend_url, bootstrap_token);
+                    println!(
+                        "  Open: {}/?token={}",
+                        open_url(&config, &paths),
+                        bootstrap_token
+                    );
                     println!();
                     println!("  API:  http://{}:{}", config.host, config.port);
                     println!();
@@ -284,7 +362,13 @@ async fn main() -> anyhow::Result<()> {
 
             println!("Loopwire daemon started.");
             println!();
-            println!("  Open: {}/?token={}", config.frontend_url, bootstrap_token);
+            let mut open_config = config.clone();
+            open_config.port = port;
+            println!(
+                "  Open: {}/?token={}",
+                open_url(&open_config, &paths),
+                bootstrap_token
+            );
             println!();
             println!("  API:  http://{}:{}", config.host, port);
             println!();
@@ -321,7 +405,7 @@ async fn main() -> anyhow::Result<()> {
 
             let (bootstrap_token, bootstrap_hash) = load_or_create_bootstrap_token(&paths);
 
-            let frontend_url = &config.frontend_url;
+            let frontend_url = open_url(&config, &paths);
             println!("Loopwire daemon running...");
             println!();
             println!("  Open: {}/?token={}", frontend_url, bootstrap_token);
@@ -349,8 +433,18 @@ async fn main() -> anyhow::Result<()> {
             };
 
             let state = AppState::new(config.clone(), bootstrap_hash)?;
+            if let Some(web_ui) = &state.web_ui {
+                tracing::info!("Serving web UI from {}", web_ui.source());
+            }
             state.agent_manager.restore_persisted_agents().await;
+            lw_api::notifications::spawn_notifier(state.clone());
+            lw_api::prompt_queue::spawn_prompt_queue(state.clone());
+            lw_api::preview::spawn_port_watcher(state.clone());
             let shutdown_state = state.clone();
+            let preview_app = state
+                .web_ui
+                .is_some()
+                .then(|| build_preview_router(state.clone()));
             let app = build_router(state);
 
             let addr: SocketAddr = config.bind_addr().parse()?;
@@ -358,6 +452,17 @@ async fn main() -> anyhow::Result<()> {
 
             let listener = tokio::net::TcpListener::bind(addr).await?;
 
+            if let Some(preview_app) = preview_app {
+                let preview_addr = SocketAddr::new(config.host, config.preview_port());
+                let preview_listener = tokio::net::TcpListener::bind(preview_addr).await?;
+                tracing::info!("Serving dev server previews on {}", preview_addr);
+                tokio::spawn(async move {
+                    if let Err(e) = axum::serve(preview_listener, preview_app).await {
+                        tracing::warn!("Preview listener stopped: {}", e);
+                    }
+                });
+            }
+
             let shutdown = async move {
                 #[cfg(unix)]
                 {
@@ -467,20 +572,164 @@ async fn main() -> anyhow::Result<()> {
             Ok(())
         }
 
-        Commands::Token => {
+        Commands::Token {
+            command: None | Some(TokenCommands::Bootstrap),
+        } => {
             paths.ensure_config_dir()?;
             let token = regenerate_bootstrap_token(&paths);
             println!("{}", token);
             let config = DaemonConfig::load()?;
             if let Some(pid) = read_pid(&paths) {
                 if is_process_alive(pid) {
-                    println!("\nOpen: {}/?token={}", config.frontend_url, token);
+                    println!("\nOpen: {}/?token={}", open_url(&config, &paths), token);
                     println!("\nNote: restart the daemon for the new token to take effect.");
                 }
             }
             Ok(())
         }
 
+        // API tokens are read from disk by the daemon on each request, so
+        // these work whether or not it is running.
+        Commands::Token {
+            command: Some(command),
+        } => {
+            paths.ensure_config_dir()?;
+            match command {
+                TokenCommands::Bootstrap => unreachable!("handled above"),
+                TokenCommands::Create {
+                    name,
+                    scopes,
+                    workspaces,
+                    expires_in,
+                } => {
+                    let registered = lw_api::rest::workspace::load_workspaces(&paths);
+                    let workspace_ids = workspaces
+                        .iter()
+                        .map(|workspace| resolve_workspace(&registered, workspace))
+                        .collect::<anyhow::Result<Vec<_>>>()?;
+                    let expires_at = match expires_in {
+                        Some(value) => Some(chrono::Utc::now() + parse_expires_in(&value)?),
+                        None => None,
+                    };
+                    let (token, api_token) = create_api_token(
+                        &paths,
+                        NewApiToken {
+                            name,
+                            scopes,
+                            workspace_ids,
+                            expires_at,
+                        },
+                    )?;
+                    println!("{}", token);
+                    eprintln!();
+                    eprintln!("Created API token '{}' ({})", api_token.name, api_token.id);
+                    eprintln!("Store it now; it can't be shown again.");
+                }
+                TokenCommands::List => {
+                    let tokens = list_api_tokens(&paths)?;
+                    if tokens.is_empty() {
+                        println!("No API tokens");
+                    }
+                    let now = chrono::Utc::now();
+                    for token in tokens {
+                        let scopes: Vec<&str> = token.scopes.iter().map(|s| s.as_str()).collect();
+                        println!("{}  {}", token.id, token.name);
+                        println!("  Scopes:     {}", scopes.join(", "));
+                        if token.is_workspace_restricted() {
+                            let ids: Vec<String> = token
+                                .workspace_ids
+                                .iter()
+                                .map(|id| id.to_string())
+                                .collect();
+                            println!("  Workspaces: {}", ids.join(", "));
+                        }
+                        println!("  Created:    {}", token.created_at.to_rfc3339());
+                        match token.expires_at {
+                            Some(expires_at) if token.is_expired(now) => {
+                                println!("  Expired:    {}", expires_at.to_rfc3339())
+                            }
+                            Some(expires_at) => {
+                                println!("  Expires:    {}", expires_at.to_rfc3339())
+                            }
+                            None => println!("  Expires:    never"),
+                        }
+                    }
+                }
+                TokenCommands::Revoke { token } => match revoke_api_token(&paths, &token)? {
+                    Some(revoked) => println!("Revoked API token '{}'", revoked.name),
+                    None => anyhow::bail!("No API token with id or name '{}'", token),
+                },
+            }
+            Ok(())
+        }
+
+        // Like API tokens, the daemon notices changes to the sessions file
+        // on the next request.
+        Commands::Sessions { command } => {
+            paths.ensure_config_dir()?;
+            match command.unwrap_or(SessionCommands::List) {
+                SessionCommands::List => {
+                    let sessions = list_sessions(&paths)?;
+                    if sessions.is_empty() {
+                        println!("No active sessions");
+                    }
+                    for session in sessions {
+                        let origin = match session.origin {
+                            Some(SessionOrigin::Local) => "local",
+                            Some(SessionOrigin::Remote) => "remote",
+                            None => "unknown",
+                        };
+                        println!(
+                            "{}  {}",
+                            session.id,
+                            session.label.as_deref().unwrap_or("(unnamed)")
+                        );
+                        println!("  Origin:     {}", origin);
+                        if let Some(user_agent) = &session.user_agent {
+                            println!("  User agent: {}", user_agent);
+                        }
+                        println!("  Created:    {}", session.created_at.to_rfc3339());
+                        println!("  Last seen:  {}", session.last_seen_at.to_rfc3339());
+                        println!("  Expires:    {}", session.expires_at.to_rfc3339());
+                    }
+                }
+                SessionCommands::Revoke { id } => match revoke_session_by_id(&paths, &id)? {
+                    Some(session) => println!(
+                        "Revoked session {}",
+                        session.label.as_deref().unwrap_or(&session.id.to_string())
+                    ),
+                    None => anyhow::bail!("No active session with id {}", id),
+                },
+            }
+            Ok(())
+        }
+
+        // Reads the output logs directly, so it works whether or not the
+        // daemon is running and doesn't need a token.
+        Commands::Export {
+            session_id,
+            format,
+            output,
+            cols,
+            rows,
+        } => {
+            let Some(exported) = export_from_disk(&paths, &session_id, format, Some((cols, rows)))?
+            else {
+                anyhow::bail!("No output recorded for session {}", session_id);
+            };
+            match output {
+                Some(path) => {
+                    std::fs::write(&path, exported)?;
+                    eprintln!("Exported session {} to {}", session_id, path.display());
+                }
+                None => {
+                    use std::io::Write;
+                    std::io::stdout().write_all(&exported)?;
+                }
+            }
+            Ok(())
+        }
+
         Commands::Share { command } => {
             let config = DaemonConfig::load()?;
             let base = format!("http://127.0.0.1:{}", config.port);
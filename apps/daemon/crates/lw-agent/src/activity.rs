@@ -1,3 +1,5 @@
+use crate::approval::ApprovalRequest;
+use crate::hooks::HookEvent;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
@@ -11,6 +13,8 @@ pub enum AgentActivityPhase {
     UserInput,
     Processing,
     StreamingOutput,
+    /// Blocked on a tool-approval dialog; see [`AgentActivity::approval`].
+    AwaitingApproval,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -21,6 +25,9 @@ pub struct AgentActivity {
     pub last_input_at: Option<DateTime<Utc>>,
     pub last_output_at: Option<DateTime<Utc>>,
     pub reason: String,
+    /// What the agent asks to be approved, while in `AwaitingApproval`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub approval: Option<ApprovalRequest>,
 }
 
 impl AgentActivity {
@@ -32,6 +39,7 @@ impl AgentActivity {
             last_input_at: None,
             last_output_at: None,
             reason: reason.to_string(),
+            approval: None,
         }
     }
 }
@@ -48,6 +56,10 @@ pub struct ActivityTiming {
     pub busy_min_hold: Duration,
     pub processing_stale: Duration,
     pub streaming_quiet_to_processing: Duration,
+    /// Quiet period after which a hook-driven session counts as idle even
+    /// though no hook said so (an Esc interrupt or a slash command fires no
+    /// Stop hook).
+    pub hook_idle_fallback: Duration,
 }
 
 impl Default for ActivityTiming {
@@ -57,6 +69,7 @@ impl Default for ActivityTiming {
             busy_min_hold: Duration::from_millis(500),
             processing_stale: Duration::from_secs(120),
             streaming_quiet_to_processing: Duration::from_millis(1500),
+            hook_idle_fallback: Duration::from_secs(15),
         }
     }
 }
@@ -66,6 +79,15 @@ pub struct SessionActivityState {
     activity: AgentActivity,
     pending_command: bool,
     busy_since: Option<DateTime<Utc>>,
+    /// Set once the agent reports through its own hooks; from then on the
+    /// timing heuristics no longer decide when it is idle.
+    hooks_active: bool,
+    /// When the last hook arrived; hook-driven turns go idle only once both
+    /// hooks and output have been quiet for a while.
+    last_hook_at: Option<DateTime<Utc>>,
+    /// Last tool call announced by a hook, used to describe a following
+    /// permission request.
+    pending_tool: Option<ApprovalRequest>,
 }
 
 impl SessionActivityState {
@@ -74,6 +96,9 @@ impl SessionActivityState {
             activity: AgentActivity::unknown(reason, now),
             pending_command: false,
             busy_since: None,
+            hooks_active: false,
+            last_hook_at: None,
+            pending_tool: None,
         }
     }
 
@@ -83,6 +108,19 @@ impl SessionActivityState {
 
     pub fn on_input(&mut self, now: DateTime<Utc>, bytes: &[u8]) -> bool {
         self.activity.last_input_at = Some(now);
+        if self.activity.phase == AgentActivityPhase::AwaitingApproval {
+            // Navigating the dialog (arrow keys) does not answer it.
+            if !is_approval_answer(bytes) {
+                return false;
+            }
+            self.pending_command = true;
+            return self.transition(
+                now,
+                AgentActivityPhase::Processing,
+                false,
+                "approval_answered",
+            );
+        }
         let submitted = bytes.iter().any(|b| matches!(b, b'\n' | b'\r'));
         if submitted {
             self.pending_command = true;
@@ -105,6 +143,28 @@ impl SessionActivityState {
 
     pub fn on_output(&mut self, now: DateTime<Utc>, prompt_hint: bool) -> bool {
         self.activity.last_output_at = Some(now);
+        // The dialog stays up (and may be redrawn) until the user answers,
+        // unless the agent is back at its prompt without one.
+        if self.activity.phase == AgentActivityPhase::AwaitingApproval {
+            if !prompt_hint {
+                return false;
+            }
+            self.pending_command = false;
+            return self.transition(now, AgentActivityPhase::AwaitingUser, true, "prompt_hint");
+        }
+        // With hooks, output only distinguishes streaming from thinking
+        // while the agent is known to be busy.
+        if self.hooks_active {
+            if self.activity.phase != AgentActivityPhase::Processing {
+                return false;
+            }
+            return self.transition(
+                now,
+                AgentActivityPhase::StreamingOutput,
+                false,
+                "output_activity",
+            );
+        }
         if prompt_hint {
             self.pending_command = false;
             return self.transition(now, AgentActivityPhase::AwaitingUser, true, "prompt_hint");
@@ -124,6 +184,106 @@ impl SessionActivityState {
         )
     }
 
+    pub fn on_approval_request(&mut self, now: DateTime<Utc>, request: ApprovalRequest) -> bool {
+        self.activity.last_output_at = Some(now);
+        self.pending_command = false;
+        let request_changed = self.activity.approval.as_ref() != Some(&request);
+        let changed = self.transition(
+            now,
+            AgentActivityPhase::AwaitingApproval,
+            true,
+            "approval_requested",
+        );
+        if request_changed {
+            self.activity.approval = Some(request);
+            self.activity.updated_at = now;
+        }
+        changed || request_changed
+    }
+
+    /// The approval dialog left the screen without a recognised answer: the
+    /// agent timed it out, the user answered some other way, or it was
+    /// never a dialog.
+    pub fn on_approval_dismissed(&mut self, now: DateTime<Utc>) -> bool {
+        if self.activity.phase != AgentActivityPhase::AwaitingApproval {
+            return false;
+        }
+        self.pending_command = true;
+        self.transition(
+            now,
+            AgentActivityPhase::Processing,
+            false,
+            "approval_dismissed",
+        )
+    }
+
+    pub(crate) fn on_hook(&mut self, now: DateTime<Utc>, event: HookEvent) -> bool {
+        self.hooks_active = true;
+        self.last_hook_at = Some(now);
+        match event {
+            HookEvent::PromptSubmitted => {
+                self.pending_command = true;
+                self.transition(
+                    now,
+                    AgentActivityPhase::Processing,
+                    false,
+                    "hook_prompt_submitted",
+                )
+            }
+            HookEvent::ToolStarted(request) => {
+                self.pending_tool = Some(request);
+                if self.activity.phase == AgentActivityPhase::AwaitingApproval {
+                    return false;
+                }
+                self.transition(
+                    now,
+                    AgentActivityPhase::Processing,
+                    false,
+                    "hook_tool_started",
+                )
+            }
+            HookEvent::ToolFinished => {
+                self.pending_tool = None;
+                self.transition(
+                    now,
+                    AgentActivityPhase::Processing,
+                    false,
+                    "hook_tool_finished",
+                )
+            }
+            HookEvent::PermissionRequested { tool } => {
+                let request = match self.pending_tool.take() {
+                    Some(pending) if tool.as_ref().is_none_or(|tool| *tool == pending.tool) => {
+                        pending
+                    }
+                    _ => ApprovalRequest {
+                        tool: tool.unwrap_or_else(|| "unknown".to_string()),
+                        command: None,
+                        file: None,
+                        prompt: String::new(),
+                    },
+                };
+                // A dialog parsed from the screen carries the prompt text.
+                if let Some(current) = &self.activity.approval {
+                    if current.tool == request.tool {
+                        return false;
+                    }
+                }
+                self.on_approval_request(now, request)
+            }
+            HookEvent::WaitingForInput | HookEvent::TurnCompleted => {
+                self.pending_command = false;
+                self.pending_tool = None;
+                let reason = if event == HookEvent::TurnCompleted {
+                    "hook_turn_completed"
+                } else {
+                    "hook_waiting_for_input"
+                };
+                self.transition(now, AgentActivityPhase::AwaitingUser, true, reason)
+            }
+        }
+    }
+
     pub fn on_session_stopped(&mut self, now: DateTime<Utc>, reason: &str) -> bool {
         self.pending_command = false;
         self.transition(now, AgentActivityPhase::Unknown, false, reason)
@@ -151,6 +311,41 @@ impl SessionActivityState {
         };
 
         let quiet_for = now.signed_duration_since(last_output_at);
+
+        // Hooks report idleness sooner; the quiet fallback only covers turns
+        // that end without one. Prompt hints stay unused here since agent
+        // TUIs keep their input box drawn while they work.
+        if self.hooks_active {
+            let quiet_since = match self.last_hook_at {
+                Some(last_hook_at) => last_output_at.max(last_hook_at),
+                None => last_output_at,
+            };
+            if matches!(
+                self.activity.phase,
+                AgentActivityPhase::Processing | AgentActivityPhase::StreamingOutput
+            ) && now.signed_duration_since(quiet_since)
+                >= chrono_from_std(timing.hook_idle_fallback)
+            {
+                self.pending_command = false;
+                return self.transition(
+                    now,
+                    AgentActivityPhase::AwaitingUser,
+                    true,
+                    "hook_idle_timeout",
+                );
+            }
+            if matches!(self.activity.phase, AgentActivityPhase::StreamingOutput)
+                && quiet_for >= chrono_from_std(timing.streaming_quiet_to_processing)
+            {
+                return self.transition(
+                    now,
+                    AgentActivityPhase::Processing,
+                    false,
+                    "awaiting_completion",
+                );
+            }
+            return false;
+        }
         let busy_for = self
             .busy_since
             .map(|busy_since| now.signed_duration_since(busy_since));
@@ -227,6 +422,10 @@ impl SessionActivityState {
             || self.activity.is_idle != is_idle
             || self.activity.reason != reason;
 
+        if phase != AgentActivityPhase::AwaitingApproval {
+            self.activity.approval = None;
+        }
+
         if is_idle {
             self.busy_since = None;
         } else if self.busy_since.is_none() {
@@ -244,6 +443,14 @@ impl SessionActivityState {
     }
 }
 
+/// Enter, a digit/`y`/`n` shortcut or a lone Esc.
+fn is_approval_answer(bytes: &[u8]) -> bool {
+    if bytes.iter().any(|b| matches!(b, b'\n' | b'\r')) {
+        return true;
+    }
+    matches!(bytes, [b'0'..=b'9' | b'y' | b'Y' | b'n' | b'N' | 0x1b])
+}
+
 fn chrono_from_std(duration: Duration) -> chrono::Duration {
     chrono::Duration::from_std(duration).unwrap_or(chrono::Duration::MAX)
 }
@@ -299,6 +506,10 @@ mod tests {
             serde_json::to_string(&AgentActivityPhase::StreamingOutput).unwrap(),
             "\"streaming_output\""
         );
+        assert_eq!(
+            serde_json::to_string(&AgentActivityPhase::AwaitingApproval).unwrap(),
+            "\"awaiting_approval\""
+        );
     }
 
     #[test]
@@ -628,6 +839,194 @@ mod tests {
         assert!(!first);
     }
 
+    fn bash_request(command: &str) -> ApprovalRequest {
+        ApprovalRequest {
+            tool: "Bash".to_string(),
+            command: Some(command.to_string()),
+            file: None,
+            prompt: "Do you want to proceed?".to_string(),
+        }
+    }
+
+    #[test]
+    fn approval_request_holds_until_answered() {
+        let now = Utc::now();
+        let mut state = SessionActivityState::new_unknown(now, "init");
+        state.on_input(now, b"fix the tests\n");
+        assert!(state.on_approval_request(now, bash_request("cargo test")));
+        let snapshot = state.snapshot();
+        assert_eq!(snapshot.phase, AgentActivityPhase::AwaitingApproval);
+        assert!(snapshot.is_idle);
+        assert_eq!(snapshot.approval, Some(bash_request("cargo test")));
+
+        // Redraws, idle ticks and dialog navigation keep the request.
+        assert!(!state.on_approval_request(now, bash_request("cargo test")));
+        assert!(!state.on_output(now, false));
+        let later = now + chrono::Duration::seconds(300);
+        assert!(!state.on_tick(later, ActivityTiming::default()));
+        assert!(!state.on_input(later, b"\x1b[B"));
+        assert_eq!(state.snapshot().phase, AgentActivityPhase::AwaitingApproval);
+
+        assert!(state.on_input(later, b"\r"));
+        let snapshot = state.snapshot();
+        assert_eq!(snapshot.phase, AgentActivityPhase::Processing);
+        assert_eq!(snapshot.reason, "approval_answered");
+        assert_eq!(snapshot.approval, None);
+    }
+
+    #[test]
+    fn prompt_hint_leaves_approval() {
+        let now = Utc::now();
+        let mut state = SessionActivityState::new_unknown(now, "init");
+        state.on_approval_request(now, bash_request("cargo test"));
+        assert!(state.on_output(now, true));
+        let snapshot = state.snapshot();
+        assert_eq!(snapshot.phase, AgentActivityPhase::AwaitingUser);
+        assert!(snapshot.is_idle);
+        assert_eq!(snapshot.reason, "prompt_hint");
+        assert_eq!(snapshot.approval, None);
+    }
+
+    #[test]
+    fn dismissed_dialog_leaves_approval() {
+        let now = Utc::now();
+        let mut state = SessionActivityState::new_unknown(now, "init");
+        assert!(!state.on_approval_dismissed(now));
+
+        state.on_approval_request(now, bash_request("cargo test"));
+        assert!(state.on_approval_dismissed(now));
+        let snapshot = state.snapshot();
+        assert_eq!(snapshot.phase, AgentActivityPhase::Processing);
+        assert_eq!(snapshot.reason, "approval_dismissed");
+        assert_eq!(snapshot.approval, None);
+
+        // Settles like any other turn once output goes quiet.
+        let timing = ActivityTiming::default();
+        let later = now + chrono::Duration::seconds(5);
+        assert!(state.on_tick(later, timing));
+        assert_eq!(state.snapshot().phase, AgentActivityPhase::AwaitingUser);
+    }
+
+    #[test]
+    fn new_approval_request_replaces_previous() {
+        let now = Utc::now();
+        let mut state = SessionActivityState::new_unknown(now, "init");
+        state.on_approval_request(now, bash_request("ls"));
+        assert!(state.on_approval_request(now, bash_request("rm -rf target")));
+        assert_eq!(
+            state.snapshot().approval,
+            Some(bash_request("rm -rf target"))
+        );
+        assert!(state.on_session_stopped(now, "session_exit"));
+        assert_eq!(state.snapshot().approval, None);
+    }
+
+    #[test]
+    fn approval_answers() {
+        assert!(is_approval_answer(b"1"));
+        assert!(is_approval_answer(b"y"));
+        assert!(is_approval_answer(b"\x1b"));
+        assert!(is_approval_answer(b"\r"));
+        assert!(!is_approval_answer(b"\x1b[A"));
+        assert!(!is_approval_answer(b"hello"));
+    }
+
+    #[test]
+    fn hooks_drive_turn_lifecycle() {
+        let start = Utc::now();
+        let mut state = SessionActivityState::new_unknown(start, "init");
+        assert!(state.on_hook(start, HookEvent::PromptSubmitted));
+        assert_eq!(state.snapshot().phase, AgentActivityPhase::Processing);
+
+        // Pauses shorter than the fallback don't end a hook-driven turn.
+        state.on_output(start, false);
+        assert_eq!(state.snapshot().phase, AgentActivityPhase::StreamingOutput);
+        let later = start + chrono::Duration::seconds(10);
+        assert!(state.on_tick(later, ActivityTiming::default()));
+        assert_eq!(state.snapshot().phase, AgentActivityPhase::Processing);
+        assert!(!state.on_tick(later, ActivityTiming::default()));
+
+        assert!(state.on_hook(later, HookEvent::TurnCompleted));
+        let snapshot = state.snapshot();
+        assert_eq!(snapshot.phase, AgentActivityPhase::AwaitingUser);
+        assert!(snapshot.is_idle);
+        assert_eq!(snapshot.reason, "hook_turn_completed");
+
+        // Trailing redraws and prompt-looking output do not reopen the turn.
+        assert!(!state.on_output(later, false));
+        assert!(!state.on_output(later, true));
+        assert_eq!(state.snapshot().phase, AgentActivityPhase::AwaitingUser);
+    }
+
+    #[test]
+    fn hook_session_interrupted_without_stop_falls_back_to_idle() {
+        let start = Utc::now();
+        let timing = ActivityTiming::default();
+        let mut state = SessionActivityState::new_unknown(start, "init");
+        state.on_output(start, false);
+
+        // A prompt after a long quiet spell is not idle straight away.
+        let submitted = start + chrono::Duration::seconds(60);
+        state.on_hook(submitted, HookEvent::PromptSubmitted);
+        assert!(!state.on_tick(submitted + chrono::Duration::seconds(1), timing));
+        assert_eq!(state.snapshot().phase, AgentActivityPhase::Processing);
+
+        // Esc: output stops and no Stop hook arrives.
+        state.on_output(submitted + chrono::Duration::seconds(2), false);
+        let interrupted =
+            submitted + chrono::Duration::seconds(2) + chrono_from_std(timing.hook_idle_fallback);
+        assert!(state.on_tick(interrupted, timing));
+        let snapshot = state.snapshot();
+        assert_eq!(snapshot.phase, AgentActivityPhase::AwaitingUser);
+        assert!(snapshot.is_idle);
+        assert_eq!(snapshot.reason, "hook_idle_timeout");
+
+        // The next prompt starts a turn as usual.
+        assert!(state.on_hook(interrupted, HookEvent::PromptSubmitted));
+        assert_eq!(state.snapshot().phase, AgentActivityPhase::Processing);
+    }
+
+    #[test]
+    fn hook_permission_request_uses_announced_tool() {
+        let now = Utc::now();
+        let mut state = SessionActivityState::new_unknown(now, "init");
+        state.on_hook(now, HookEvent::ToolStarted(bash_request("cargo test")));
+        assert!(state.on_hook(
+            now,
+            HookEvent::PermissionRequested {
+                tool: Some("Bash".to_string())
+            }
+        ));
+        let snapshot = state.snapshot();
+        assert_eq!(snapshot.phase, AgentActivityPhase::AwaitingApproval);
+        assert_eq!(
+            snapshot.approval.and_then(|request| request.command),
+            Some("cargo test".to_string())
+        );
+
+        assert!(state.on_hook(now, HookEvent::ToolFinished));
+        let snapshot = state.snapshot();
+        assert_eq!(snapshot.phase, AgentActivityPhase::Processing);
+        assert_eq!(snapshot.approval, None);
+    }
+
+    #[test]
+    fn hook_permission_request_keeps_screen_parsed_dialog() {
+        let now = Utc::now();
+        let mut state = SessionActivityState::new_unknown(now, "init");
+        state.on_approval_request(now, bash_request("ls"));
+        assert!(!state.on_hook(
+            now,
+            HookEvent::PermissionRequested {
+                tool: Some("Bash".to_string())
+            }
+        ));
+        assert_eq!(
+            state.snapshot().approval.map(|request| request.prompt),
+            Some("Do you want to proceed?".to_string())
+        );
+    }
+
     #[test]
     fn chrono_from_std_normal() {
         let d = Duration::from_secs(5);
@@ -0,0 +1,439 @@
+use crate::runners::AgentType;
+use crate::terminal_text::TerminalTextNormalizer;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// Number of recent non-empty lines kept for matching a dialog.
+const WINDOW_LINES: usize = 40;
+
+/// A tool call the agent is blocked on until the user approves it.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ApprovalRequest {
+    /// Tool name as the agent displays it, e.g. `Bash`, `Edit`, `exec`, `Shell`.
+    pub tool: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    /// The question shown in the dialog.
+    pub prompt: String,
+}
+
+/// What a chunk of output did to the approval dialog.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum DialogChange {
+    Shown(ApprovalRequest),
+    /// The last dialog shown has left the window without its question being
+    /// redrawn: answered, dismissed by the agent, or never a dialog at all.
+    Gone,
+}
+
+/// Watches an agent's output for its tool-approval dialog.
+///
+/// Output is fed through a [`TerminalTextNormalizer`] and the completed
+/// lines are matched against the dialog layout of the agent's CLI. A match
+/// is reported once; the window is then cleared so a redraw of the same
+/// dialog has to arrive in full before it is reported again. Once a full
+/// window of lines has followed a dialog with no redraw of its question, the
+/// dialog is reported gone.
+pub(crate) struct ApprovalDetector {
+    detect: fn(&[&str]) -> Option<ApprovalRequest>,
+    normalizer: TerminalTextNormalizer,
+    lines: VecDeque<String>,
+    /// Question of the dialog last shown, and the lines seen since it was
+    /// last drawn.
+    shown: Option<(String, usize)>,
+}
+
+impl ApprovalDetector {
+    /// Detector for `agent_type`, or `None` for agents whose dialogs are
+    /// unknown (custom runners).
+    pub(crate) fn for_agent(agent_type: &AgentType) -> Option<Self> {
+        let detect = match agent_type {
+            AgentType::ClaudeCode => detect_claude,
+            AgentType::Codex => detect_codex,
+            AgentType::Gemini => detect_gemini,
+            AgentType::Custom(_) => return None,
+        };
+        Some(Self {
+            detect,
+            normalizer: TerminalTextNormalizer::new(),
+            lines: VecDeque::new(),
+            shown: None,
+        })
+    }
+
+    pub(crate) fn ingest(&mut self, bytes: &[u8]) -> Option<DialogChange> {
+        let text = self.normalizer.ingest(bytes);
+        let mut change = None;
+        for raw in text.lines() {
+            let line = strip_frame(raw);
+            if line.is_empty() {
+                continue;
+            }
+            if self.lines.len() == WINDOW_LINES {
+                self.lines.pop_front();
+            }
+            self.lines.push_back(line.to_string());
+            let window: Vec<&str> = self.lines.iter().map(String::as_str).collect();
+            if let Some(request) = (self.detect)(&window) {
+                self.lines.clear();
+                self.shown = Some((request.prompt.clone(), 0));
+                change = Some(DialogChange::Shown(request));
+                continue;
+            }
+            if let Some((question, since)) = &mut self.shown {
+                *since = if line == question { 0 } else { *since + 1 };
+                if *since >= WINDOW_LINES {
+                    self.shown = None;
+                    change = Some(DialogChange::Gone);
+                }
+            }
+        }
+        change
+    }
+}
+
+/// Strips dialog borders and selection markers around a line.
+fn strip_frame(line: &str) -> &str {
+    const FRAME: &[char] = &[
+        '│', '┃', '║', '╭', '╮', '╰', '╯', '─', '━', '═', '▌', '❯', '›', '●', '○', '>', '|',
+    ];
+    line.trim_matches(|c: char| c.is_whitespace() || FRAME.contains(&c))
+}
+
+fn is_first_yes_option(line: &str) -> bool {
+    line.starts_with("1. Yes") || line.starts_with("Yes, proceed") || line == "Yes (y)"
+}
+
+/// Index of the question right above the options whose first entry is the
+/// last line of `lines`.
+fn question_above_options(lines: &[&str], trigger: fn(&str) -> bool) -> Option<usize> {
+    let (last, body) = lines.split_last()?;
+    if !trigger(last) {
+        return None;
+    }
+    body.iter().rposition(|line| line.ends_with('?'))
+}
+
+fn between_quotes(text: &str) -> Option<&str> {
+    let (_, rest) = text.split_once(['\'', '"', '`'])?;
+    let (inner, _) = rest.split_once(['\'', '"', '`'])?;
+    Some(inner)
+}
+
+/// Claude Code:
+///
+/// ```text
+/// Bash command
+///   cargo test
+///   Run the test suite
+/// Do you want to proceed?
+/// ❯ 1. Yes
+/// ```
+fn detect_claude(lines: &[&str]) -> Option<ApprovalRequest> {
+    let question_idx = question_above_options(lines, |line| line.starts_with("1. Yes"))?;
+    let question = lines[question_idx];
+    if !question.starts_with("Do you want to") {
+        return None;
+    }
+    let body = &lines[..question_idx];
+    let header_idx = body.iter().rposition(|line| {
+        matches!(
+            *line,
+            "Bash command" | "Edit file" | "Create file" | "Read file" | "Fetch" | "Tool use"
+        )
+    });
+    let detail = header_idx.and_then(|idx| body.get(idx + 1).copied());
+
+    let file_in_question = ["make this edit to ", "create ", "overwrite ", "read "]
+        .iter()
+        .find_map(|prefix| question.split_once(prefix))
+        .map(|(_, rest)| rest.trim_end_matches('?').trim());
+    let file = file_in_question.map(|name| {
+        // The dialog body usually shows the full relative path.
+        body[header_idx.unwrap_or(0)..]
+            .iter()
+            .find(|line| line.ends_with(name) && !line.contains(' '))
+            .copied()
+            .unwrap_or(name)
+            .to_string()
+    });
+
+    let (tool, command, file) = match header_idx.map(|idx| body[idx]) {
+        Some("Bash command") => ("Bash".to_string(), detail.map(str::to_string), None),
+        Some("Edit file") => ("Edit".to_string(), None, file),
+        Some("Create file") => ("Write".to_string(), None, file),
+        Some("Read file") => ("Read".to_string(), None, file),
+        Some("Fetch") => ("WebFetch".to_string(), detail.map(str::to_string), None),
+        Some("Tool use") => {
+            let tool = detail
+                .map(|line| line.split('(').next().unwrap_or(line).trim().to_string())
+                .unwrap_or_else(|| "unknown".to_string());
+            (tool, None, None)
+        }
+        _ => ("unknown".to_string(), None, file),
+    };
+    Some(ApprovalRequest {
+        tool,
+        command,
+        file,
+        prompt: question.to_string(),
+    })
+}
+
+/// Codex CLI:
+///
+/// ```text
+/// Would you like to run the following command?
+/// $ cargo test
+/// ▌ 1. Yes, proceed
+/// ```
+fn detect_codex(lines: &[&str]) -> Option<ApprovalRequest> {
+    let question_idx = question_above_options(lines, is_first_yes_option)?;
+    let question = lines[question_idx];
+    let after = &lines[question_idx + 1..lines.len() - 1];
+    let lower = question.to_ascii_lowercase();
+
+    let (tool, command, file) = if lower.contains("command") {
+        let command = after
+            .iter()
+            .find_map(|line| line.strip_prefix("$ "))
+            .or_else(|| {
+                lines[..question_idx]
+                    .iter()
+                    .rev()
+                    .find_map(|line| line.strip_prefix("$ "))
+            })
+            .map(str::to_string);
+        ("exec", command, None)
+    } else if lower.contains("edit") || lower.contains("patch") {
+        let file = after
+            .iter()
+            .find(|line| !line.starts_with("Reason:"))
+            .map(|line| match line.rsplit_once(" (+") {
+                Some((path, _)) => path.to_string(),
+                None => line.to_string(),
+            });
+        ("apply_patch", None, file)
+    } else {
+        return None;
+    };
+    Some(ApprovalRequest {
+        tool: tool.to_string(),
+        command,
+        file,
+        prompt: question.to_string(),
+    })
+}
+
+/// Gemini CLI:
+///
+/// ```text
+/// ?  Shell cargo test [current working directory /repo]
+/// cargo test
+/// Allow execution of: 'cargo'?
+/// ● 1. Yes, allow once
+/// ```
+fn detect_gemini(lines: &[&str]) -> Option<ApprovalRequest> {
+    let question_idx = question_above_options(lines, |line| {
+        line.starts_with("1. Yes, allow") || line.starts_with("Yes, allow once")
+    })?;
+    let question = lines[question_idx];
+    let body = &lines[..question_idx];
+    let header_idx = body
+        .iter()
+        .rposition(|line| line.starts_with('?') && line.len() > 1);
+    let (tool, params) = header_idx
+        .map(|idx| {
+            let header = body[idx][1..].trim();
+            header.split_once(' ').unwrap_or((header, ""))
+        })
+        .unwrap_or(("unknown", ""));
+    let detail = header_idx.and_then(|idx| body.get(idx + 1).copied());
+
+    if question.starts_with("Allow execution of MCP tool") {
+        return Some(ApprovalRequest {
+            tool: between_quotes(question).unwrap_or(tool).to_string(),
+            command: None,
+            file: None,
+            prompt: question.to_string(),
+        });
+    }
+
+    let (command, file) = match tool {
+        "Shell" => {
+            let command = detail
+                .or_else(|| params.split(" [").next())
+                .filter(|command| !command.is_empty())
+                .map(str::to_string);
+            (command, None)
+        }
+        "Edit" | "WriteFile" | "ReadFile" | "ReadManyFiles" => {
+            let target = params.strip_prefix("Writing to ").unwrap_or(params);
+            let file = target
+                .split(": ")
+                .next()
+                .filter(|file| !file.is_empty())
+                .map(str::to_string);
+            (None, file)
+        }
+        _ => (None, None),
+    };
+    Some(ApprovalRequest {
+        tool: tool.to_string(),
+        command,
+        file,
+        prompt: question.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detect(agent_type: AgentType, screen: &str) -> Option<ApprovalRequest> {
+        match ApprovalDetector::for_agent(&agent_type)
+            .unwrap()
+            .ingest(screen.as_bytes())
+        {
+            Some(DialogChange::Shown(request)) => Some(request),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn claude_bash_command() {
+        let screen = "\x1b[1m╭──────────────────────────────╮\x1b[0m\r\n\
+                      │ Bash command                 │\r\n\
+                      │                              │\r\n\
+                      │   cargo test --workspace     │\r\n\
+                      │   Run the test suite         │\r\n\
+                      │                              │\r\n\
+                      │ Do you want to proceed?      │\r\n\
+                      │ \x1b[36m❯ 1. Yes\x1b[0m                     │\r\n\
+                      │   2. No, and tell Claude     │\r\n";
+        let request = detect(AgentType::ClaudeCode, screen).unwrap();
+        assert_eq!(request.tool, "Bash");
+        assert_eq!(request.command.as_deref(), Some("cargo test --workspace"));
+        assert_eq!(request.file, None);
+        assert_eq!(request.prompt, "Do you want to proceed?");
+    }
+
+    #[test]
+    fn claude_edit_file_uses_full_path() {
+        let screen = "│ Edit file                          │\r\n\
+                      │ ╭────────────────────────────────╮ │\r\n\
+                      │ │ src/lib/parser.rs              │ │\r\n\
+                      │ │ 12 - old                       │ │\r\n\
+                      │ Do you want to make this edit to parser.rs? │\r\n\
+                      │ ❯ 1. Yes                           │\r\n";
+        let request = detect(AgentType::ClaudeCode, screen).unwrap();
+        assert_eq!(request.tool, "Edit");
+        assert_eq!(request.file.as_deref(), Some("src/lib/parser.rs"));
+    }
+
+    #[test]
+    fn claude_mcp_tool() {
+        let screen = "Tool use\n\
+                      github - create_issue(title: \"x\") (MCP)\n\
+                      Do you want to proceed?\n\
+                      1. Yes\n";
+        let request = detect(AgentType::ClaudeCode, screen).unwrap();
+        assert_eq!(request.tool, "github - create_issue");
+    }
+
+    #[test]
+    fn claude_ignores_plain_questions() {
+        let screen = "Do you want to proceed?\nSure, here is the plan.\n";
+        assert_eq!(detect(AgentType::ClaudeCode, screen), None);
+        let screen = "Should I continue?\n1. Yes\n";
+        assert_eq!(detect(AgentType::ClaudeCode, screen), None);
+    }
+
+    #[test]
+    fn codex_command_and_patch() {
+        let screen = "Would you like to run the following command?\n\
+                      Reason: run tests\n\
+                      $ cargo test\n\
+                      ▌ 1. Yes, proceed\n\
+                      2. No, and tell Codex what to do differently\n";
+        let request = detect(AgentType::Codex, screen).unwrap();
+        assert_eq!(request.tool, "exec");
+        assert_eq!(request.command.as_deref(), Some("cargo test"));
+
+        let screen = "Would you like to make the following edits?\n\
+                      src/main.rs (+3 -1)\n\
+                      › Yes, proceed\n";
+        let request = detect(AgentType::Codex, screen).unwrap();
+        assert_eq!(request.tool, "apply_patch");
+        assert_eq!(request.file.as_deref(), Some("src/main.rs"));
+    }
+
+    #[test]
+    fn gemini_shell_and_edit() {
+        let screen = "╭──────────────────────────────────────────╮\n\
+                      │ ?  Shell npm test [current working directory /repo] │\n\
+                      │                                          │\n\
+                      │ npm test                                 │\n\
+                      │                                          │\n\
+                      │ Allow execution of: 'npm'?               │\n\
+                      │ ● 1. Yes, allow once                     │\n";
+        let request = detect(AgentType::Gemini, screen).unwrap();
+        assert_eq!(request.tool, "Shell");
+        assert_eq!(request.command.as_deref(), Some("npm test"));
+        assert_eq!(request.prompt, "Allow execution of: 'npm'?");
+
+        let screen = "│ ?  WriteFile Writing to notes/todo.md │\n\
+                      │ Apply this change?                    │\n\
+                      │ ● 1. Yes, allow once                  │\n";
+        let request = detect(AgentType::Gemini, screen).unwrap();
+        assert_eq!(request.tool, "WriteFile");
+        assert_eq!(request.file.as_deref(), Some("notes/todo.md"));
+    }
+
+    #[test]
+    fn gemini_mcp_tool() {
+        let screen = "?  create_issue (github MCP Server)\n\
+                      Allow execution of MCP tool \"create_issue\" from server \"github\"?\n\
+                      1. Yes, allow once\n";
+        let request = detect(AgentType::Gemini, screen).unwrap();
+        assert_eq!(request.tool, "create_issue");
+    }
+
+    #[test]
+    fn dialog_split_across_chunks_is_reported_once() {
+        let mut detector = ApprovalDetector::for_agent(&AgentType::ClaudeCode).unwrap();
+        assert_eq!(detector.ingest(b"Bash command\r\n  ls -la\r\n"), None);
+        assert_eq!(detector.ingest(b"Do you want to pro"), None);
+        assert!(matches!(
+            detector.ingest(b"ceed?\r\n1. Yes\r\n"),
+            Some(DialogChange::Shown(_))
+        ));
+        assert_eq!(detector.ingest(b"2. No\r\n"), None);
+    }
+
+    #[test]
+    fn dialog_is_gone_once_a_window_of_other_lines_follows() {
+        let mut detector = ApprovalDetector::for_agent(&AgentType::ClaudeCode).unwrap();
+        let dialog = b"Bash command\r\n  ls\r\nDo you want to proceed?\r\n1. Yes\r\n";
+        assert!(matches!(
+            detector.ingest(dialog),
+            Some(DialogChange::Shown(_))
+        ));
+
+        // A redraw of the question keeps the dialog up.
+        let almost_full: String = (1..WINDOW_LINES).map(|i| format!("line {i}\r\n")).collect();
+        assert_eq!(detector.ingest(almost_full.as_bytes()), None);
+        assert_eq!(detector.ingest(b"Do you want to proceed?\r\n"), None);
+        assert_eq!(detector.ingest(almost_full.as_bytes()), None);
+
+        assert_eq!(detector.ingest(b"Running ls\r\n"), Some(DialogChange::Gone));
+        assert_eq!(detector.ingest(almost_full.as_bytes()), None);
+    }
+
+    #[test]
+    fn custom_agents_have_no_detector() {
+        assert!(ApprovalDetector::for_agent(&AgentType::Custom("aider".into())).is_none());
+    }
+}
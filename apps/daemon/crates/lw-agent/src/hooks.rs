@@ -0,0 +1,317 @@
+use crate::approval::ApprovalRequest;
+use crate::runners::AgentType;
+use serde_json::{json, Value};
+use std::path::PathBuf;
+
+/// Environment variable holding the session's callback URL inside the agent
+/// process.
+pub(crate) const HOOK_URL_ENV: &str = "LOOPWIRE_HOOK_URL";
+
+/// Lifecycle event reported by an agent's own hook mechanism. These are
+/// authoritative, unlike the output-timing heuristics.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum HookEvent {
+    /// The user submitted a prompt; the agent is working.
+    PromptSubmitted,
+    /// A tool call is about to run (or be put up for approval).
+    ToolStarted(ApprovalRequest),
+    ToolFinished,
+    /// The agent is blocked on a permission dialog for `tool`.
+    PermissionRequested {
+        tool: Option<String>,
+    },
+    /// The agent has been idle waiting for a prompt.
+    WaitingForInput,
+    /// The agent finished its turn.
+    TurnCompleted,
+}
+
+/// Extra arguments and environment that make the agent report its lifecycle
+/// to `url`. Empty for agents without a hook mechanism.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub(crate) struct HookLaunch {
+    pub args: Vec<String>,
+    pub env: Vec<(String, String)>,
+}
+
+impl HookLaunch {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.args.is_empty() && self.env.is_empty()
+    }
+}
+
+pub(crate) fn hook_launch(agent_type: &AgentType, url: &str) -> HookLaunch {
+    match agent_type {
+        AgentType::ClaudeCode => HookLaunch {
+            args: vec!["--settings".to_string(), claude_hook_settings().to_string()],
+            env: vec![(HOOK_URL_ENV.to_string(), url.to_string())],
+        },
+        AgentType::Codex => codex_hook_launch(url, codex_user_notify().as_deref()),
+        AgentType::Gemini | AgentType::Custom(_) => HookLaunch::default(),
+    }
+}
+
+/// Codex takes a single `notify` program, so one the user configured is
+/// chained after ours instead of being replaced.
+fn codex_hook_launch(url: &str, user_notify: Option<&[String]>) -> HookLaunch {
+    // Codex appends the JSON payload as the last argument.
+    let notify: Vec<String> = match user_notify {
+        Some(user_notify) if !user_notify.is_empty() => {
+            let user_command: Vec<String> =
+                user_notify.iter().map(|arg| shell_quote(arg)).collect();
+            let script = format!(
+                "curl -sf -m 2 -X POST -H 'Content-Type: application/json' \"${HOOK_URL_ENV}\" --data-binary \"$1\"; exec {} \"$1\"",
+                user_command.join(" ")
+            );
+            ["sh", "-c", &script, "loopwire-notify"]
+                .map(str::to_string)
+                .to_vec()
+        }
+        _ => [
+            "curl",
+            "-sf",
+            "-m",
+            "2",
+            "-X",
+            "POST",
+            "-H",
+            "Content-Type: application/json",
+            url,
+            "--data-binary",
+        ]
+        .map(str::to_string)
+        .to_vec(),
+    };
+    let notify = serde_json::to_string(&notify).unwrap_or_default();
+    HookLaunch {
+        args: vec!["-c".to_string(), format!("notify={notify}")],
+        env: vec![(HOOK_URL_ENV.to_string(), url.to_string())],
+    }
+}
+
+/// The `notify` command from the user's Codex config, if any.
+fn codex_user_notify() -> Option<Vec<String>> {
+    let home = std::env::var_os("CODEX_HOME")
+        .filter(|home| !home.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| dirs::home_dir().map(|home| home.join(".codex")))?;
+    let config = std::fs::read_to_string(home.join("config.toml")).ok()?;
+    parse_codex_notify(&config)
+}
+
+fn parse_codex_notify(config: &str) -> Option<Vec<String>> {
+    let config: toml::Table = toml::from_str(config).ok()?;
+    config
+        .get("notify")?
+        .as_array()?
+        .iter()
+        .map(|arg| arg.as_str().map(str::to_string))
+        .collect()
+}
+
+fn shell_quote(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', "'\\''"))
+}
+
+/// Claude Code `--settings` that pipe each hook's JSON input to the daemon.
+fn claude_hook_settings() -> Value {
+    let command = format!(
+        "curl -sf -m 2 -X POST -H 'Content-Type: application/json' --data-binary @- \"${HOOK_URL_ENV}\" || true"
+    );
+    let hook = json!([{ "hooks": [{ "type": "command", "command": command }] }]);
+    let tool_hook =
+        json!([{ "matcher": "*", "hooks": [{ "type": "command", "command": command }] }]);
+    json!({
+        "hooks": {
+            "UserPromptSubmit": hook,
+            "PreToolUse": tool_hook,
+            "PostToolUse": tool_hook,
+            "Notification": hook,
+            "Stop": hook,
+        }
+    })
+}
+
+/// Parses a Claude Code hook input or a Codex `notify` payload. Unknown
+/// events are ignored.
+pub(crate) fn parse_hook_event(payload: &Value) -> Option<HookEvent> {
+    if let Some(name) = payload.get("hook_event_name").and_then(Value::as_str) {
+        return parse_claude_event(name, payload);
+    }
+    match payload.get("type").and_then(Value::as_str)? {
+        "agent-turn-complete" => Some(HookEvent::TurnCompleted),
+        _ => None,
+    }
+}
+
+fn parse_claude_event(name: &str, payload: &Value) -> Option<HookEvent> {
+    match name {
+        "UserPromptSubmit" => Some(HookEvent::PromptSubmitted),
+        "PreToolUse" => {
+            let tool = payload.get("tool_name").and_then(Value::as_str)?;
+            let input = payload.get("tool_input");
+            let field = |key: &str| {
+                input
+                    .and_then(|input| input.get(key))
+                    .and_then(Value::as_str)
+                    .map(str::to_string)
+            };
+            Some(HookEvent::ToolStarted(ApprovalRequest {
+                tool: tool.to_string(),
+                command: field("command").or_else(|| field("url")),
+                file: field("file_path").or_else(|| field("notebook_path")),
+                prompt: String::new(),
+            }))
+        }
+        "PostToolUse" => Some(HookEvent::ToolFinished),
+        "Notification" => {
+            let message = payload
+                .get("message")
+                .and_then(Value::as_str)
+                .unwrap_or_default();
+            if let Some((_, tool)) = message.split_once("permission to use ") {
+                Some(HookEvent::PermissionRequested {
+                    tool: Some(tool.trim().to_string()),
+                })
+            } else if message.contains("permission") {
+                Some(HookEvent::PermissionRequested { tool: None })
+            } else if message.contains("waiting for your input") {
+                Some(HookEvent::WaitingForInput)
+            } else {
+                None
+            }
+        }
+        "Stop" => Some(HookEvent::TurnCompleted),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn claude_launch_passes_settings_and_url() {
+        let launch = hook_launch(&AgentType::ClaudeCode, "http://127.0.0.1:9400/hook");
+        assert_eq!(launch.args[0], "--settings");
+        let settings: Value = serde_json::from_str(&launch.args[1]).unwrap();
+        for event in ["UserPromptSubmit", "PreToolUse", "Notification", "Stop"] {
+            let command = settings["hooks"][event][0]["hooks"][0]["command"]
+                .as_str()
+                .unwrap();
+            assert!(command.contains("$LOOPWIRE_HOOK_URL"), "{event}");
+        }
+        assert_eq!(
+            launch.env,
+            vec![(
+                HOOK_URL_ENV.to_string(),
+                "http://127.0.0.1:9400/hook".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn codex_launch_sets_notify_override() {
+        let launch = codex_hook_launch("http://127.0.0.1:9400/hook?token=t", None);
+        assert_eq!(launch.args[0], "-c");
+        let notify = launch.args[1].strip_prefix("notify=").unwrap();
+        let notify: Vec<String> = serde_json::from_str(notify).unwrap();
+        assert_eq!(notify[0], "curl");
+        assert_eq!(notify.last().unwrap(), "--data-binary");
+        assert!(notify.contains(&"http://127.0.0.1:9400/hook?token=t".to_string()));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn codex_launch_chains_user_notify() {
+        let config = "model = \"o3\"\nnotify = [\"notify-send\", \"Codex's turn\"]\n";
+        let user_notify = parse_codex_notify(config).unwrap();
+        assert_eq!(user_notify, vec!["notify-send", "Codex's turn"]);
+        assert_eq!(parse_codex_notify("model = \"o3\"\n"), None);
+
+        let launch = codex_hook_launch("http://127.0.0.1:9400/hook", Some(&user_notify));
+        let notify = launch.args[1].strip_prefix("notify=").unwrap();
+        let notify: Vec<String> = serde_json::from_str(notify).unwrap();
+        assert_eq!(notify[..2], ["sh", "-c"]);
+        assert!(notify[2].contains("\"$LOOPWIRE_HOOK_URL\""));
+        assert!(notify[2].ends_with("exec 'notify-send' 'Codex'\\''s turn' \"$1\""));
+
+        // The user's command still gets the payload.
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("out");
+        let user_notify = vec![
+            "sh".to_string(),
+            "-c".to_string(),
+            format!("printf %s \"$0\" > '{}'", out.display()),
+        ];
+        let launch = codex_hook_launch("http://127.0.0.1:9/hook", Some(&user_notify));
+        let notify: Vec<String> =
+            serde_json::from_str(launch.args[1].strip_prefix("notify=").unwrap()).unwrap();
+        let status = std::process::Command::new(&notify[0])
+            .args(&notify[1..])
+            .arg(r#"{"type":"agent-turn-complete"}"#)
+            .env(HOOK_URL_ENV, "http://127.0.0.1:9/hook")
+            .status()
+            .unwrap();
+        assert!(status.success());
+        assert_eq!(
+            std::fs::read_to_string(out).unwrap(),
+            r#"{"type":"agent-turn-complete"}"#
+        );
+    }
+
+    #[test]
+    fn agents_without_hooks_get_nothing() {
+        assert!(hook_launch(&AgentType::Gemini, "http://x").is_empty());
+        assert!(hook_launch(&AgentType::Custom("aider".into()), "http://x").is_empty());
+    }
+
+    #[test]
+    fn parses_claude_events() {
+        let pre = json!({
+            "hook_event_name": "PreToolUse",
+            "tool_name": "Bash",
+            "tool_input": { "command": "cargo test", "description": "Run tests" }
+        });
+        assert_eq!(
+            parse_hook_event(&pre),
+            Some(HookEvent::ToolStarted(ApprovalRequest {
+                tool: "Bash".to_string(),
+                command: Some("cargo test".to_string()),
+                file: None,
+                prompt: String::new(),
+            }))
+        );
+        let permission = json!({
+            "hook_event_name": "Notification",
+            "message": "Claude needs your permission to use Bash"
+        });
+        assert_eq!(
+            parse_hook_event(&permission),
+            Some(HookEvent::PermissionRequested {
+                tool: Some("Bash".to_string())
+            })
+        );
+        let idle = json!({
+            "hook_event_name": "Notification",
+            "message": "Claude is waiting for your input"
+        });
+        assert_eq!(parse_hook_event(&idle), Some(HookEvent::WaitingForInput));
+        let stop = json!({ "hook_event_name": "Stop", "stop_hook_active": false });
+        assert_eq!(parse_hook_event(&stop), Some(HookEvent::TurnCompleted));
+        let other = json!({ "hook_event_name": "SessionStart" });
+        assert_eq!(parse_hook_event(&other), None);
+    }
+
+    #[test]
+    fn parses_codex_notify() {
+        let payload = json!({
+            "type": "agent-turn-complete",
+            "turn-id": "1",
+            "last-assistant-message": "Done"
+        });
+        assert_eq!(parse_hook_event(&payload), Some(HookEvent::TurnCompleted));
+        assert_eq!(parse_hook_event(&json!({ "type": "other" })), None);
+        assert_eq!(parse_hook_event(&json!({})), None);
+    }
+}
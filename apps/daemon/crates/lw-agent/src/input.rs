@@ -0,0 +1,205 @@
+//! Keystrokes and text for driving an agent's terminal without a terminal.
+
+use serde::Deserialize;
+use std::fmt;
+use std::str::FromStr;
+
+const PASTE_START: &[u8] = b"\x1b[200~";
+const PASTE_END: &[u8] = b"\x1b[201~";
+
+/// A named key, written as the bytes a terminal would send for it.
+///
+/// Parsed case-insensitively from names such as `Enter`, `Esc`, `Tab`,
+/// `Backspace`, `Up`/`Down`/`Left`/`Right` and `Ctrl-C` (any `Ctrl-<letter>`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum InputKey {
+    Enter,
+    Escape,
+    Tab,
+    Backspace,
+    Up,
+    Down,
+    Left,
+    Right,
+    /// `Ctrl` plus a lowercase ASCII letter.
+    Ctrl(char),
+}
+
+impl InputKey {
+    pub fn bytes(self) -> Vec<u8> {
+        match self {
+            InputKey::Enter => b"\r".to_vec(),
+            InputKey::Escape => b"\x1b".to_vec(),
+            InputKey::Tab => b"\t".to_vec(),
+            InputKey::Backspace => b"\x7f".to_vec(),
+            InputKey::Up => b"\x1b[A".to_vec(),
+            InputKey::Down => b"\x1b[B".to_vec(),
+            InputKey::Right => b"\x1b[C".to_vec(),
+            InputKey::Left => b"\x1b[D".to_vec(),
+            InputKey::Ctrl(letter) => vec![letter as u8 - b'a' + 1],
+        }
+    }
+}
+
+impl FromStr for InputKey {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        let normalized: String = name
+            .chars()
+            .filter(|c| !matches!(c, '-' | '_' | '+' | ' '))
+            .flat_map(char::to_lowercase)
+            .collect();
+        let key = match normalized.as_str() {
+            "enter" | "return" => InputKey::Enter,
+            "esc" | "escape" => InputKey::Escape,
+            "tab" => InputKey::Tab,
+            "backspace" => InputKey::Backspace,
+            "up" | "arrowup" => InputKey::Up,
+            "down" | "arrowdown" => InputKey::Down,
+            "left" | "arrowleft" => InputKey::Left,
+            "right" | "arrowright" => InputKey::Right,
+            other => match other.strip_prefix("ctrl").map(str::as_bytes) {
+                Some([letter @ b'a'..=b'z']) => InputKey::Ctrl(*letter as char),
+                _ => return Err(format!("unknown key {name:?}")),
+            },
+        };
+        Ok(key)
+    }
+}
+
+impl TryFrom<String> for InputKey {
+    type Error = String;
+
+    fn try_from(name: String) -> Result<Self, Self::Error> {
+        name.parse()
+    }
+}
+
+impl fmt::Display for InputKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InputKey::Enter => f.write_str("Enter"),
+            InputKey::Escape => f.write_str("Esc"),
+            InputKey::Tab => f.write_str("Tab"),
+            InputKey::Backspace => f.write_str("Backspace"),
+            InputKey::Up => f.write_str("Up"),
+            InputKey::Down => f.write_str("Down"),
+            InputKey::Left => f.write_str("Left"),
+            InputKey::Right => f.write_str("Right"),
+            InputKey::Ctrl(letter) => write!(f, "Ctrl-{}", letter.to_ascii_uppercase()),
+        }
+    }
+}
+
+/// Input for one request: `text` first, then Enter when `submit` is set,
+/// then `keys` in order.
+#[derive(Debug, Clone, Default)]
+pub struct SessionInput {
+    pub text: Option<String>,
+    /// Wrap `text` in bracketed-paste markers so multi-line prompts arrive
+    /// as one paste instead of being submitted line by line.
+    pub bracketed_paste: bool,
+    pub submit: bool,
+    pub keys: Vec<InputKey>,
+}
+
+impl SessionInput {
+    pub fn is_empty(&self) -> bool {
+        self.text.as_deref().is_none_or(str::is_empty) && !self.submit && self.keys.is_empty()
+    }
+
+    /// The terminal writes this input expands to. Keys are kept as
+    /// separate writes: TUIs tell a lone Esc from an escape sequence, and
+    /// a paste from typing, by how bytes are grouped.
+    pub fn chunks(&self) -> Vec<Vec<u8>> {
+        let mut chunks = Vec::new();
+        if let Some(text) = self.text.as_deref().filter(|text| !text.is_empty()) {
+            if self.bracketed_paste {
+                // A stray end marker would close the paste early.
+                let text = text.replace("\x1b[201~", "");
+                chunks.push([PASTE_START, text.as_bytes(), PASTE_END].concat());
+            } else {
+                chunks.push(text.as_bytes().to_vec());
+            }
+        }
+        if self.submit {
+            chunks.push(InputKey::Enter.bytes());
+        }
+        chunks.extend(self.keys.iter().map(|key| key.bytes()));
+        chunks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_key_names_loosely() {
+        for (name, key) in [
+            ("Enter", InputKey::Enter),
+            ("return", InputKey::Enter),
+            ("Esc", InputKey::Escape),
+            ("ESCAPE", InputKey::Escape),
+            ("ArrowUp", InputKey::Up),
+            ("left", InputKey::Left),
+            ("Ctrl-C", InputKey::Ctrl('c')),
+            ("ctrl+d", InputKey::Ctrl('d')),
+            ("CTRL_Z", InputKey::Ctrl('z')),
+        ] {
+            assert_eq!(name.parse::<InputKey>().unwrap(), key, "{name}");
+        }
+        assert!("Ctrl-1".parse::<InputKey>().is_err());
+        assert!("F13".parse::<InputKey>().is_err());
+    }
+
+    #[test]
+    fn key_bytes() {
+        assert_eq!(InputKey::Ctrl('c').bytes(), vec![0x03]);
+        assert_eq!(InputKey::Up.bytes(), b"\x1b[A");
+        assert_eq!(InputKey::Enter.bytes(), b"\r");
+        assert_eq!(InputKey::Ctrl('c').to_string(), "Ctrl-C");
+    }
+
+    #[test]
+    fn deserializes_from_json_strings() {
+        let keys: Vec<InputKey> = serde_json::from_str(r#"["Down", "Enter"]"#).unwrap();
+        assert_eq!(keys, vec![InputKey::Down, InputKey::Enter]);
+        assert!(serde_json::from_str::<InputKey>(r#""Hyper""#).is_err());
+    }
+
+    #[test]
+    fn chunks_order_text_submit_keys() {
+        let input = SessionInput {
+            text: Some("fix\nthe bug\x1b[201~".to_string()),
+            bracketed_paste: true,
+            submit: true,
+            keys: vec![InputKey::Escape],
+        };
+        assert_eq!(
+            input.chunks(),
+            vec![
+                b"\x1b[200~fix\nthe bug\x1b[201~".to_vec(),
+                b"\r".to_vec(),
+                b"\x1b".to_vec(),
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_input() {
+        assert!(SessionInput::default().is_empty());
+        assert!(SessionInput {
+            text: Some(String::new()),
+            ..Default::default()
+        }
+        .is_empty());
+        assert!(!SessionInput {
+            submit: true,
+            ..Default::default()
+        }
+        .is_empty());
+    }
+}
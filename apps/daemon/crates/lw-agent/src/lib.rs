@@ -1,12 +1,35 @@
 pub mod activity;
+mod approval;
+mod hooks;
+pub mod input;
 mod manager;
+pub mod ports;
 mod process;
 mod prompt;
+pub mod queue;
+mod record_dir;
 pub mod runners;
+pub mod shell;
+pub mod task;
+pub mod terminal_export;
 pub mod terminal_text;
+mod transcript;
+pub mod workspace_task;
 
 pub use activity::{AgentActivity, AgentActivityEvent, AgentActivityPhase};
-pub use manager::session::{AgentHandle, AgentStatus, ResumabilityStatus, ScrollbackRawResult};
+pub use approval::ApprovalRequest;
+pub use input::{InputKey, SessionInput};
+pub use manager::session::{
+    AgentHandle, AgentStatus, AgentWorktree, ResumabilityStatus, ScrollbackRawResult,
+    SessionLaunchOptions, TerminalSize,
+};
 pub use manager::AgentManager;
 pub use manager::PersistedAgentInfo;
+pub use queue::{PromptQueueError, PromptQueueEvent, QueuedPrompt, QueuedPromptStatus};
 pub use runners::{AgentRunner, AgentType, AvailableAgent};
+pub use shell::{PersistedShellInfo, ShellHandle, ShellManager, ShellStatus};
+pub use task::{TaskEvent, TaskManager, TaskRecord, TaskStatus, TaskStream, TaskSummary};
+pub use workspace_task::{
+    discover_tasks, TaskRun, TaskRunEvent, TaskRunStatus, WorkspaceTask, WorkspaceTaskManager,
+    WorkspaceTaskSource,
+};
@@ -1,19 +1,22 @@
+mod hooks;
+mod queue;
 mod reconcile;
 mod recorder;
 pub(crate) mod session;
 
 use crate::activity::AgentActivityEvent;
 use crate::activity::{ActivityTiming, AgentActivity};
-use crate::runners::{default_runners, AgentRunner, AgentType, AvailableAgent};
+use crate::queue::{PromptQueue, PromptQueueEvent};
+use crate::runners::{runners_with_custom, AgentRunner, AgentType, AvailableAgent};
+use lw_config::CustomAgentConfig;
 use lw_pty::PtyManager;
-use recorder::ActivityRecorder;
-use session::{AgentHandle, AgentStatus, ResumabilityStatus};
+use recorder::{ActivityMonitor, ActivityRecorder};
+use session::{AgentHandle, AgentStatus, AgentWorktree, ResumabilityStatus, SessionLaunchOptions};
 use std::collections::HashMap;
 use std::path::PathBuf;
-use std::sync::{Arc, RwLock as StdRwLock};
+use std::sync::{Arc, Mutex as StdMutex, RwLock as StdRwLock};
 use std::time::{Duration, Instant};
 use tokio::sync::{broadcast, RwLock};
-use tokio::task::JoinHandle;
 use uuid::Uuid;
 
 /// Minimal info about a persisted agent from `workspace.json`.
@@ -21,6 +24,8 @@ use uuid::Uuid;
 pub struct PersistedAgentInfo {
     pub session_id: Uuid,
     pub workspace_path: PathBuf,
+    pub worktree: Option<AgentWorktree>,
+    pub launch_options: SessionLaunchOptions,
     pub agent_type: AgentType,
     pub conversation_id: Option<String>,
     pub custom_name: Option<String>,
@@ -45,15 +50,28 @@ pub struct AgentManager {
     pub(crate) pty_manager: Arc<PtyManager>,
     pub(crate) runners: Vec<Box<dyn AgentRunner>>,
     pub(crate) handles: Arc<RwLock<HashMap<Uuid, AgentHandle>>>,
-    pub(crate) activity_monitors: Arc<RwLock<HashMap<Uuid, JoinHandle<()>>>>,
+    pub(crate) activity_monitors: Arc<RwLock<HashMap<Uuid, ActivityMonitor>>>,
     pub(crate) recorder: ActivityRecorder,
     available_agents_cache: StdRwLock<AvailableAgentsCache>,
     pending_restorations: std::sync::Mutex<Vec<PersistedAgentInfo>>,
+    /// Base URL of the daemon's hook callback endpoint; hooks are only
+    /// injected into agents when set.
+    hooks_url: Option<String>,
+    /// Secret each agent process must present when calling back.
+    hook_tokens: StdRwLock<HashMap<Uuid, String>>,
+    prompt_queues: StdMutex<HashMap<Uuid, PromptQueue>>,
+    prompt_queue_tx: broadcast::Sender<PromptQueueEvent>,
+    /// Serializes queued-prompt delivery across sessions.
+    prompt_delivery: tokio::sync::Mutex<()>,
 }
 
 impl AgentManager {
-    pub fn new(pty_manager: Arc<PtyManager>, persisted_agents: Vec<PersistedAgentInfo>) -> Self {
-        let runners = default_runners();
+    pub fn new(
+        pty_manager: Arc<PtyManager>,
+        persisted_agents: Vec<PersistedAgentInfo>,
+        custom_agents: &[CustomAgentConfig],
+    ) -> Self {
+        let runners = runners_with_custom(custom_agents);
         #[cfg(not(test))]
         let initial_available_agents = Self::collect_available_agents(&runners, None);
         #[cfg(test)]
@@ -83,6 +101,11 @@ impl AgentManager {
                 refreshed_at: Instant::now(),
             }),
             pending_restorations: std::sync::Mutex::new(persisted_agents),
+            hooks_url: None,
+            hook_tokens: StdRwLock::new(HashMap::new()),
+            prompt_queues: StdMutex::new(HashMap::new()),
+            prompt_queue_tx: broadcast::channel(64).0,
+            prompt_delivery: tokio::sync::Mutex::new(()),
         }
     }
 
@@ -124,7 +147,7 @@ impl AgentManager {
                 agent
                     .version
                     .as_ref()
-                    .map(|version| (agent.agent_type, version.clone()))
+                    .map(|version| (agent.agent_type.clone(), version.clone()))
             })
             .collect();
 
@@ -155,8 +178,8 @@ impl AgentManager {
 
     pub async fn shutdown_all(&self) {
         let mut monitors = self.activity_monitors.write().await;
-        for (_, task) in monitors.drain() {
-            task.abort();
+        for (_, monitor) in monitors.drain() {
+            monitor.abort();
         }
         drop(monitors);
 
@@ -209,6 +232,8 @@ impl AgentManager {
                 icon: agent.icon,
                 sort_order: agent.sort_order,
                 workspace_path: agent.workspace_path,
+                worktree: agent.worktree,
+                launch_options: agent.launch_options,
                 status: session::AgentStatus::Restored,
                 process_id: None,
                 resumability_status,
@@ -248,13 +273,15 @@ impl AgentManager {
                 .unwrap_or(ResumabilityStatus::Resumable);
             let handle = session::AgentHandle {
                 session_id: agent.session_id,
-                agent_type: agent.agent_type,
+                agent_type: agent.agent_type.clone(),
                 conversation_id: agent.conversation_id.clone(),
                 custom_name: agent.custom_name.clone(),
                 pinned: agent.pinned,
                 icon: agent.icon.clone(),
                 sort_order: agent.sort_order,
                 workspace_path: agent.workspace_path.clone(),
+                worktree: agent.worktree.clone(),
+                launch_options: agent.launch_options.clone(),
                 status: AgentStatus::Restored,
                 process_id: None,
                 resumability_status,
@@ -306,6 +333,8 @@ mod tests {
         let info = PersistedAgentInfo {
             session_id: Uuid::nil(),
             workspace_path: PathBuf::from("/tmp"),
+            worktree: None,
+            launch_options: Default::default(),
             agent_type: AgentType::ClaudeCode,
             conversation_id: Some("conv-1".to_string()),
             custom_name: Some("test".to_string()),
@@ -333,6 +362,8 @@ mod tests {
         let info = PersistedAgentInfo {
             session_id: Uuid::new_v4(),
             workspace_path: PathBuf::from("/ws"),
+            worktree: None,
+            launch_options: Default::default(),
             agent_type: AgentType::Codex,
             conversation_id: None,
             custom_name: None,
@@ -385,7 +416,7 @@ mod tests {
 
     impl AgentRunner for MockRunner {
         fn agent_type(&self) -> AgentType {
-            self.agent_type
+            self.agent_type.clone()
         }
         fn name(&self) -> &str {
             self.name
@@ -505,16 +536,48 @@ mod tests {
     #[test]
     fn agent_manager_new_with_empty_persisted() {
         let pty = Arc::new(PtyManager::new());
-        let manager = AgentManager::new(pty, vec![]);
+        let manager = AgentManager::new(pty, vec![], &[]);
         let agents = manager.available_agents();
         // Should have 3 default runners (claude, codex, gemini)
         assert_eq!(agents.len(), 3);
     }
 
+    #[test]
+    fn agent_manager_new_includes_custom_agents() {
+        let pty = Arc::new(PtyManager::new());
+        let custom: CustomAgentConfig =
+            serde_json::from_str(r#"{"id":"aider","name":"Aider","command":"aider"}"#).unwrap();
+        let manager = AgentManager::new(pty, vec![], &[custom]);
+        let agents = manager.available_agents();
+        assert_eq!(agents.len(), 4);
+        assert_eq!(agents[3].agent_type, AgentType::Custom("aider".to_string()));
+        assert_eq!(agents[3].name, "Aider");
+    }
+
+    #[tokio::test]
+    async fn start_session_unknown_custom_agent_fails() {
+        let pty = Arc::new(PtyManager::new());
+        let manager = AgentManager::new(pty, vec![], &[]);
+        let result = manager
+            .start_session(
+                AgentType::Custom("missing".to_string()),
+                std::env::temp_dir(),
+                None,
+                None,
+                Default::default(),
+                None,
+            )
+            .await;
+        let Err(err) = result else {
+            panic!("expected unknown custom agent to fail");
+        };
+        assert!(err.to_string().contains("custom:missing"));
+    }
+
     #[test]
     fn agent_manager_available_agents_cached() {
         let pty = Arc::new(PtyManager::new());
-        let manager = AgentManager::new(pty, vec![]);
+        let manager = AgentManager::new(pty, vec![], &[]);
         let first = manager.available_agents();
         let second = manager.available_agents();
         // Both calls should return the same result (from cache)
@@ -534,6 +597,8 @@ mod tests {
         let persisted = vec![PersistedAgentInfo {
             session_id,
             workspace_path: PathBuf::from("/tmp/ws"),
+            worktree: None,
+            launch_options: Default::default(),
             agent_type: AgentType::ClaudeCode,
             conversation_id: Some("conv-1".to_string()),
             custom_name: Some("test".to_string()),
@@ -547,7 +612,7 @@ mod tests {
         }];
 
         let pty = Arc::new(PtyManager::new());
-        let manager = AgentManager::new(pty, persisted);
+        let manager = AgentManager::new(pty, persisted, &[]);
         manager.restore_persisted_agents().await;
 
         let handles = manager.handles.read().await;
@@ -565,7 +630,7 @@ mod tests {
     #[tokio::test]
     async fn restore_persisted_agents_idempotent() {
         let pty = Arc::new(PtyManager::new());
-        let manager = AgentManager::new(pty, vec![]);
+        let manager = AgentManager::new(pty, vec![], &[]);
         // Second call with no pending should be a no-op
         manager.restore_persisted_agents().await;
         let handles = manager.handles.read().await;
@@ -578,6 +643,8 @@ mod tests {
         let persisted = vec![PersistedAgentInfo {
             session_id,
             workspace_path: PathBuf::from("/tmp/ws"),
+            worktree: None,
+            launch_options: Default::default(),
             agent_type: AgentType::ClaudeCode,
             conversation_id: Some("conv-1".to_string()),
             custom_name: None,
@@ -591,7 +658,7 @@ mod tests {
         }];
 
         let pty = Arc::new(PtyManager::new());
-        let manager = AgentManager::new(pty, persisted.clone());
+        let manager = AgentManager::new(pty, persisted.clone(), &[]);
         manager.restore_persisted_agents().await;
 
         // Mark as Running
@@ -609,12 +676,14 @@ mod tests {
     #[tokio::test]
     async fn ensure_persisted_handles_adds_missing() {
         let pty = Arc::new(PtyManager::new());
-        let manager = AgentManager::new(pty, vec![]);
+        let manager = AgentManager::new(pty, vec![], &[]);
 
         let session_id = Uuid::new_v4();
         let persisted = vec![PersistedAgentInfo {
             session_id,
             workspace_path: PathBuf::from("/tmp/ws"),
+            worktree: None,
+            launch_options: Default::default(),
             agent_type: AgentType::Gemini,
             conversation_id: None,
             custom_name: None,
@@ -641,7 +710,7 @@ mod tests {
     #[tokio::test]
     async fn shutdown_all_clears_state() {
         let pty = Arc::new(PtyManager::new());
-        let manager = AgentManager::new(pty, vec![]);
+        let manager = AgentManager::new(pty, vec![], &[]);
 
         // Insert a fake handle
         let session_id = Uuid::new_v4();
@@ -655,6 +724,8 @@ mod tests {
             icon: None,
             sort_order: None,
             workspace_path: PathBuf::from("/tmp"),
+            worktree: None,
+            launch_options: Default::default(),
             status: AgentStatus::Running,
             process_id: None,
             resumability_status: ResumabilityStatus::Resumable,
@@ -687,7 +758,7 @@ mod tests {
     #[tokio::test]
     async fn update_status_sets_status() {
         let pty = Arc::new(PtyManager::new());
-        let manager = AgentManager::new(pty, vec![]);
+        let manager = AgentManager::new(pty, vec![], &[]);
 
         let session_id = Uuid::new_v4();
         let now = chrono::Utc::now();
@@ -700,6 +771,8 @@ mod tests {
             icon: None,
             sort_order: None,
             workspace_path: PathBuf::from("/tmp"),
+            worktree: None,
+            launch_options: Default::default(),
             status: AgentStatus::Running,
             process_id: None,
             resumability_status: ResumabilityStatus::Resumable,
@@ -722,7 +795,7 @@ mod tests {
     #[tokio::test]
     async fn rename_session_updates_name() {
         let pty = Arc::new(PtyManager::new());
-        let manager = AgentManager::new(pty, vec![]);
+        let manager = AgentManager::new(pty, vec![], &[]);
 
         let session_id = Uuid::new_v4();
         let now = chrono::Utc::now();
@@ -735,6 +808,8 @@ mod tests {
             icon: None,
             sort_order: None,
             workspace_path: PathBuf::from("/tmp"),
+            worktree: None,
+            launch_options: Default::default(),
             status: AgentStatus::Running,
             process_id: None,
             resumability_status: ResumabilityStatus::Resumable,
@@ -760,7 +835,7 @@ mod tests {
     #[tokio::test]
     async fn rename_session_nonexistent_returns_false() {
         let pty = Arc::new(PtyManager::new());
-        let manager = AgentManager::new(pty, vec![]);
+        let manager = AgentManager::new(pty, vec![], &[]);
         let result = manager
             .rename_session(&Uuid::new_v4(), Some("name".to_string()))
             .await;
@@ -772,7 +847,7 @@ mod tests {
     #[tokio::test]
     async fn update_session_settings_partial() {
         let pty = Arc::new(PtyManager::new());
-        let manager = AgentManager::new(pty, vec![]);
+        let manager = AgentManager::new(pty, vec![], &[]);
 
         let session_id = Uuid::new_v4();
         let now = chrono::Utc::now();
@@ -785,6 +860,8 @@ mod tests {
             icon: None,
             sort_order: None,
             workspace_path: PathBuf::from("/tmp"),
+            worktree: None,
+            launch_options: Default::default(),
             status: AgentStatus::Running,
             process_id: None,
             resumability_status: ResumabilityStatus::Resumable,
@@ -816,7 +893,7 @@ mod tests {
     #[tokio::test]
     async fn update_session_settings_nonexistent_returns_false() {
         let pty = Arc::new(PtyManager::new());
-        let manager = AgentManager::new(pty, vec![]);
+        let manager = AgentManager::new(pty, vec![], &[]);
         let result = manager
             .update_session_settings(&Uuid::new_v4(), Some(true), None, None)
             .await;
@@ -828,7 +905,7 @@ mod tests {
     #[test]
     fn subscribe_activity_returns_receiver() {
         let pty = Arc::new(PtyManager::new());
-        let manager = AgentManager::new(pty, vec![]);
+        let manager = AgentManager::new(pty, vec![], &[]);
         let _rx = manager.subscribe_activity();
     }
 }
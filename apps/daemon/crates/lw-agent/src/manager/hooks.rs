@@ -0,0 +1,111 @@
+use crate::hooks::{hook_launch, parse_hook_event};
+use crate::runners::AgentType;
+use uuid::Uuid;
+
+use super::AgentManager;
+
+impl AgentManager {
+    /// Enable lifecycle hooks for agents launched from now on. Each session
+    /// calls back to `{url}/{session_id}?token=...`.
+    pub fn set_hooks_url(&mut self, url: String) {
+        self.hooks_url = Some(url.trim_end_matches('/').to_string());
+    }
+
+    /// Adds the agent's hook configuration to a launch, minting a fresh
+    /// callback token for the session.
+    pub(crate) fn with_hooks(
+        &self,
+        session_id: Uuid,
+        agent_type: &AgentType,
+        mut args: Vec<String>,
+        mut env: Vec<(String, String)>,
+    ) -> (Vec<String>, Vec<(String, String)>) {
+        let Some(base) = &self.hooks_url else {
+            return (args, env);
+        };
+        let token = Uuid::new_v4().simple().to_string();
+        let launch = hook_launch(agent_type, &format!("{base}/{session_id}?token={token}"));
+        if launch.is_empty() {
+            return (args, env);
+        }
+        self.hook_tokens
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(session_id, token);
+        // Global options go first so they precede subcommands like `resume`.
+        args.splice(0..0, launch.args);
+        env.extend(launch.env);
+        (args, env)
+    }
+
+    /// Feeds a hook callback into the session's activity state. Returns
+    /// `false` when `token` does not belong to the session.
+    pub async fn ingest_hook(
+        &self,
+        session_id: &Uuid,
+        token: &str,
+        payload: &serde_json::Value,
+    ) -> bool {
+        let authorized = self
+            .hook_tokens
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(session_id)
+            .is_some_and(|expected| expected == token);
+        if !authorized {
+            return false;
+        }
+        if let Some(event) = parse_hook_event(payload) {
+            self.recorder.record_hook(*session_id, event).await;
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::activity::AgentActivityPhase;
+    use lw_pty::PtyManager;
+    use std::sync::Arc;
+
+    #[test]
+    fn with_hooks_is_noop_without_url() {
+        let manager = AgentManager::new(Arc::new(PtyManager::new()), vec![], &[]);
+        let (args, env) = manager.with_hooks(
+            Uuid::new_v4(),
+            &AgentType::ClaudeCode,
+            vec!["--resume".into(), "abc".into()],
+            vec![],
+        );
+        assert_eq!(args, vec!["--resume", "abc"]);
+        assert!(env.is_empty());
+    }
+
+    #[tokio::test]
+    async fn hook_callbacks_require_the_session_token() {
+        let mut manager = AgentManager::new(Arc::new(PtyManager::new()), vec![], &[]);
+        manager.set_hooks_url("http://127.0.0.1:9400/api/v1/hooks/".into());
+        let session_id = Uuid::new_v4();
+        let (args, env) = manager.with_hooks(
+            session_id,
+            &AgentType::Codex,
+            vec!["resume".into(), "abc".into()],
+            vec![],
+        );
+        assert_eq!(args[0], "-c");
+        assert_eq!(&args[2..], ["resume", "abc"]);
+        let url = &env[0].1;
+        let prefix = format!("http://127.0.0.1:9400/api/v1/hooks/{session_id}?token=");
+        let token = url.strip_prefix(&prefix).unwrap();
+
+        let payload = serde_json::json!({ "type": "agent-turn-complete" });
+        assert!(!manager.ingest_hook(&session_id, "wrong", &payload).await);
+        assert!(!manager.ingest_hook(&Uuid::new_v4(), token, &payload).await);
+        assert!(manager.ingest_hook(&session_id, token, &payload).await);
+
+        let activity = manager.get_activity(&session_id).await;
+        assert_eq!(activity.phase, AgentActivityPhase::AwaitingUser);
+        assert_eq!(activity.reason, "hook_turn_completed");
+    }
+}
@@ -0,0 +1,232 @@
+use crate::activity::AgentActivityPhase;
+use crate::input::SessionInput;
+use crate::queue::{PromptQueue, PromptQueueError, PromptQueueEvent, QueuedPrompt};
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use super::AgentManager;
+
+impl AgentManager {
+    pub fn subscribe_prompt_queue(&self) -> broadcast::Receiver<PromptQueueEvent> {
+        self.prompt_queue_tx.subscribe()
+    }
+
+    /// Delivered and pending prompts of a session, oldest first.
+    pub fn prompt_queue(&self, session_id: &Uuid) -> Vec<QueuedPrompt> {
+        self.prompt_queues
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(session_id)
+            .map(PromptQueue::items)
+            .unwrap_or_default()
+    }
+
+    /// Loads a queue persisted in `workspace.json`.
+    pub fn restore_prompt_queue(&self, session_id: Uuid, items: Vec<QueuedPrompt>) {
+        if items.is_empty() {
+            return;
+        }
+        self.prompt_queues
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(session_id, PromptQueue::from_items(items));
+    }
+
+    /// Applies `f` to the session's queue and announces the result.
+    async fn update_prompt_queue<T>(
+        &self,
+        session_id: &Uuid,
+        f: impl FnOnce(&mut PromptQueue) -> Result<T, PromptQueueError>,
+    ) -> Result<T, PromptQueueError> {
+        if !self.handles.read().await.contains_key(session_id) {
+            return Err(PromptQueueError::SessionNotFound);
+        }
+        let (result, prompts) = {
+            let mut queues = self.prompt_queues.lock().unwrap_or_else(|e| e.into_inner());
+            let queue = queues.entry(*session_id).or_default();
+            let result = f(queue);
+            let prompts = queue.items();
+            if queue.is_empty() {
+                queues.remove(session_id);
+            }
+            (result?, prompts)
+        };
+        let _ = self.prompt_queue_tx.send(PromptQueueEvent {
+            session_id: *session_id,
+            prompts,
+        });
+        Ok(result)
+    }
+
+    /// Queues `text` for the session. Delivered right away if the agent is
+    /// already waiting for input.
+    pub async fn enqueue_prompt(
+        &self,
+        session_id: &Uuid,
+        text: String,
+    ) -> Result<QueuedPrompt, PromptQueueError> {
+        let prompt = self
+            .update_prompt_queue(session_id, |queue| queue.push(text))
+            .await?;
+        self.deliver_queued_prompt(session_id).await;
+        Ok(prompt)
+    }
+
+    pub async fn edit_queued_prompt(
+        &self,
+        session_id: &Uuid,
+        prompt_id: &Uuid,
+        text: String,
+    ) -> Result<QueuedPrompt, PromptQueueError> {
+        self.update_prompt_queue(session_id, |queue| queue.edit(prompt_id, text))
+            .await
+    }
+
+    pub async fn cancel_queued_prompt(
+        &self,
+        session_id: &Uuid,
+        prompt_id: &Uuid,
+    ) -> Result<(), PromptQueueError> {
+        self.update_prompt_queue(session_id, |queue| queue.cancel(prompt_id))
+            .await
+    }
+
+    /// Reorders the pending prompts; returns the whole queue.
+    pub async fn reorder_prompt_queue(
+        &self,
+        session_id: &Uuid,
+        prompt_ids: &[Uuid],
+    ) -> Result<Vec<QueuedPrompt>, PromptQueueError> {
+        self.update_prompt_queue(session_id, |queue| {
+            queue.reorder(prompt_ids)?;
+            Ok(queue.items())
+        })
+        .await
+    }
+
+    /// Types the next pending prompt into the session if the agent is
+    /// waiting for input. Returns the prompt that was delivered.
+    pub async fn deliver_queued_prompt(&self, session_id: &Uuid) -> Option<QueuedPrompt> {
+        // One delivery at a time: the phase check below only holds until
+        // the input has been written.
+        let _delivering = self.prompt_delivery.lock().await;
+        if self.get_activity(session_id).await.phase != AgentActivityPhase::AwaitingUser {
+            return None;
+        }
+        // Taken off the queue before typing, so that a concurrent edit or
+        // cancel either lands first or is refused as already delivered.
+        let next = self
+            .update_prompt_queue(session_id, |queue| {
+                queue
+                    .deliver_next(chrono::Utc::now())
+                    .ok_or(PromptQueueError::PromptNotFound)
+            })
+            .await
+            .ok()?;
+
+        let input = SessionInput {
+            // Pasted so that the agent receives a multi-line prompt as one.
+            bracketed_paste: next.text.contains('\n'),
+            text: Some(next.text.clone()),
+            submit: true,
+            keys: Vec::new(),
+        };
+        if let Err(err) = self.send_input(session_id, &input).await {
+            tracing::warn!(session_id = %session_id, "failed to deliver queued prompt: {err:#}");
+            let _ = self
+                .update_prompt_queue(session_id, |queue| {
+                    queue.requeue(next);
+                    Ok(())
+                })
+                .await;
+            return None;
+        }
+        Some(next)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::queue::QueuedPromptStatus;
+    use lw_pty::PtyManager;
+    use std::sync::Arc;
+
+    async fn manager_with_session() -> (AgentManager, Uuid) {
+        let manager = AgentManager::new(Arc::new(PtyManager::new()), vec![], &[]);
+        let id = Uuid::new_v4();
+        manager
+            .ensure_persisted_handles(&[crate::PersistedAgentInfo {
+                session_id: id,
+                workspace_path: "/tmp".into(),
+                worktree: None,
+                launch_options: Default::default(),
+                agent_type: crate::AgentType::ClaudeCode,
+                conversation_id: None,
+                custom_name: None,
+                pinned: false,
+                icon: None,
+                sort_order: None,
+                resumability_status: None,
+                resume_failure_reason: None,
+                created_at: None,
+                pid: None,
+            }])
+            .await;
+        (manager, id)
+    }
+
+    #[tokio::test]
+    async fn queue_operations_emit_events() {
+        let (manager, id) = manager_with_session().await;
+        let mut events = manager.subscribe_prompt_queue();
+
+        // The hydrated session is not awaiting input, so nothing is typed.
+        let first = manager
+            .enqueue_prompt(&id, "first".to_string())
+            .await
+            .unwrap();
+        let second = manager
+            .enqueue_prompt(&id, "second".to_string())
+            .await
+            .unwrap();
+        assert_eq!(events.recv().await.unwrap().prompts.len(), 1);
+        assert_eq!(events.recv().await.unwrap().prompts.len(), 2);
+
+        manager
+            .reorder_prompt_queue(&id, &[second.id, first.id])
+            .await
+            .unwrap();
+        manager
+            .edit_queued_prompt(&id, &first.id, "edited".to_string())
+            .await
+            .unwrap();
+        manager.cancel_queued_prompt(&id, &second.id).await.unwrap();
+
+        let queue = manager.prompt_queue(&id);
+        assert_eq!(queue.len(), 1);
+        assert_eq!(queue[0].text, "edited");
+        assert_eq!(queue[0].status, QueuedPromptStatus::Pending);
+        assert!(manager.deliver_queued_prompt(&id).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn unknown_session_is_rejected() {
+        let manager = AgentManager::new(Arc::new(PtyManager::new()), vec![], &[]);
+        assert!(matches!(
+            manager
+                .enqueue_prompt(&Uuid::new_v4(), "x".to_string())
+                .await,
+            Err(PromptQueueError::SessionNotFound)
+        ));
+    }
+
+    #[tokio::test]
+    async fn restored_queue_is_listed() {
+        let (manager, id) = manager_with_session().await;
+        let mut queue = PromptQueue::default();
+        queue.push("later".to_string()).unwrap();
+        manager.restore_prompt_queue(id, queue.items());
+        assert_eq!(manager.prompt_queue(&id), queue.items());
+    }
+}
@@ -1,6 +1,8 @@
 use crate::activity::{
     ActivityTiming, AgentActivity, AgentActivityEvent, AgentActivityPhase, SessionActivityState,
 };
+use crate::approval::{ApprovalDetector, ApprovalRequest, DialogChange};
+use crate::hooks::HookEvent;
 use crate::manager::session::AgentHandle;
 use crate::prompt::has_prompt_hint;
 use lw_pty::PtySession;
@@ -12,6 +14,18 @@ use tokio::task::JoinHandle;
 use tokio::time::MissedTickBehavior;
 use uuid::Uuid;
 
+/// A task feeding one PTY session's output into the recorder.
+pub(crate) struct ActivityMonitor {
+    session: Arc<PtySession>,
+    task: JoinHandle<()>,
+}
+
+impl ActivityMonitor {
+    pub fn abort(&self) {
+        self.task.abort();
+    }
+}
+
 #[derive(Clone)]
 pub(crate) struct ActivityRecorder {
     pub activity_states: Arc<RwLock<HashMap<Uuid, SessionActivityState>>>,
@@ -71,6 +85,30 @@ impl ActivityRecorder {
         .await;
     }
 
+    pub async fn record_approval_request(&self, session_id: Uuid, request: ApprovalRequest) {
+        let now = chrono::Utc::now();
+        self.with_state(session_id, "output_observed", move |state| {
+            state.on_approval_request(now, request)
+        })
+        .await;
+    }
+
+    pub async fn record_approval_dismissed(&self, session_id: Uuid) {
+        let now = chrono::Utc::now();
+        self.with_state(session_id, "output_observed", move |state| {
+            state.on_approval_dismissed(now)
+        })
+        .await;
+    }
+
+    pub async fn record_hook(&self, session_id: Uuid, event: HookEvent) {
+        let now = chrono::Utc::now();
+        self.with_state(session_id, "hook_observed", move |state| {
+            state.on_hook(now, event)
+        })
+        .await;
+    }
+
     pub async fn record_tick(&self, session_id: Uuid) {
         let now = chrono::Utc::now();
         let timing = self.activity_timing;
@@ -133,6 +171,7 @@ impl ActivityRecorder {
                 last_input_at: None,
                 last_output_at: None,
                 reason: "session_not_running".to_string(),
+                approval: None,
             };
         }
 
@@ -153,13 +192,32 @@ impl ActivityRecorder {
         &self,
         session_id: Uuid,
         session: Arc<PtySession>,
-        activity_monitors: &Arc<RwLock<HashMap<Uuid, JoinHandle<()>>>>,
+        activity_monitors: &Arc<RwLock<HashMap<Uuid, ActivityMonitor>>>,
         handles: &Arc<RwLock<HashMap<Uuid, AgentHandle>>>,
     ) {
+        // Replacing a live monitor of the same PTY would drop the output
+        // still queued for it.
+        if activity_monitors
+            .read()
+            .await
+            .get(&session_id)
+            .is_some_and(|monitor| {
+                Arc::ptr_eq(&monitor.session, &session) && !monitor.task.is_finished()
+            })
+        {
+            return;
+        }
+
         let recorder = self.clone();
+        let mut approval_detector = handles
+            .read()
+            .await
+            .get(&session_id)
+            .and_then(|handle| ApprovalDetector::for_agent(&handle.agent_type));
         let handles = Arc::clone(handles);
         let mut output_rx = session.subscribe();
         let mut exit_rx = session.subscribe_exit();
+        let monitored = Arc::clone(&session);
         let mut tick = tokio::time::interval(Duration::from_millis(250));
         tick.set_missed_tick_behavior(MissedTickBehavior::Skip);
 
@@ -169,7 +227,19 @@ impl ActivityRecorder {
                     output = output_rx.recv() => {
                         match output {
                             Ok(data) => {
-                                recorder.record_output(session_id, &data).await;
+                                let approval = approval_detector
+                                    .as_mut()
+                                    .and_then(|detector| detector.ingest(&data));
+                                match approval {
+                                    Some(DialogChange::Shown(request)) => {
+                                        recorder.record_approval_request(session_id, request).await;
+                                    }
+                                    Some(DialogChange::Gone) => {
+                                        recorder.record_approval_dismissed(session_id).await;
+                                        recorder.record_output(session_id, &data).await;
+                                    }
+                                    None => recorder.record_output(session_id, &data).await,
+                                }
                             }
                             Err(broadcast::error::RecvError::Lagged(skipped)) => {
                                 tracing::warn!(
@@ -220,8 +290,12 @@ impl ActivityRecorder {
             }
         });
 
-        if let Some(old_task) = activity_monitors.write().await.insert(session_id, task) {
-            old_task.abort();
+        let monitor = ActivityMonitor {
+            session: monitored,
+            task,
+        };
+        if let Some(old) = activity_monitors.write().await.insert(session_id, monitor) {
+            old.abort();
         }
     }
 }
@@ -343,4 +417,75 @@ mod tests {
         let snapshot = recorder.activity_snapshot(id, "check").await;
         assert_eq!(snapshot.phase, AgentActivityPhase::AwaitingUser);
     }
+
+    #[tokio::test]
+    async fn record_approval_request_exposes_request() {
+        let (tx, mut rx) = broadcast::channel(64);
+        let recorder = ActivityRecorder::new(
+            Arc::new(RwLock::new(HashMap::new())),
+            tx,
+            ActivityTiming::default(),
+        );
+        let id = Uuid::new_v4();
+        let request = ApprovalRequest {
+            tool: "Edit".to_string(),
+            command: None,
+            file: Some("src/main.rs".to_string()),
+            prompt: "Do you want to make this edit to main.rs?".to_string(),
+        };
+        recorder.record_approval_request(id, request.clone()).await;
+        let event = rx.recv().await.unwrap();
+        assert_eq!(event.activity.phase, AgentActivityPhase::AwaitingApproval);
+        assert_eq!(event.activity.approval, Some(request));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn ensure_activity_monitor_keeps_live_monitor_of_same_session() {
+        let recorder = make_recorder();
+        let pty = lw_pty::PtyManager::new();
+        let id = Uuid::new_v4();
+        let session = pty
+            .create(
+                id,
+                "sh",
+                &["-c", "sleep 5"],
+                &std::env::temp_dir(),
+                vec![],
+                (80, 24),
+            )
+            .await
+            .unwrap();
+        let monitors = Arc::new(RwLock::new(HashMap::new()));
+        let handles = Arc::new(RwLock::new(HashMap::new()));
+
+        recorder
+            .ensure_activity_monitor(id, session.clone(), &monitors, &handles)
+            .await;
+        let first = monitors.read().await[&id].task.id();
+        recorder
+            .ensure_activity_monitor(id, session.clone(), &monitors, &handles)
+            .await;
+        assert_eq!(monitors.read().await[&id].task.id(), first);
+
+        // A new PTY for the session gets a new monitor.
+        pty.kill(&id).await.unwrap();
+        pty.remove(&id).await.unwrap();
+        let respawned = pty
+            .create(
+                id,
+                "sh",
+                &["-c", "sleep 5"],
+                &std::env::temp_dir(),
+                vec![],
+                (80, 24),
+            )
+            .await
+            .unwrap();
+        recorder
+            .ensure_activity_monitor(id, respawned, &monitors, &handles)
+            .await;
+        assert_ne!(monitors.read().await[&id].task.id(), first);
+        pty.kill_all().await;
+    }
 }
@@ -1,13 +1,25 @@
-use crate::activity::AgentActivity;
-use crate::runners::AgentType;
+use crate::activity::{AgentActivity, AgentActivityPhase};
+use crate::input::SessionInput;
+use crate::runners::{AgentRunner, AgentType};
 use lw_pty::PtySession;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
 use uuid::Uuid;
 
 use super::AgentManager;
 
+const INPUT_CHUNK_PAUSE: Duration = Duration::from_millis(30);
+
+/// Terminal size used when the client did not report one.
+const DEFAULT_TERMINAL_SIZE: TerminalSize = TerminalSize {
+    cols: 120,
+    rows: 40,
+};
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub enum ResumabilityStatus {
@@ -25,6 +37,62 @@ pub enum AgentStatus {
     Restored,
 }
 
+/// A `git worktree` the agent runs in instead of the workspace checkout.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AgentWorktree {
+    pub path: PathBuf,
+    pub branch: String,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TerminalSize {
+    pub cols: u16,
+    pub rows: u16,
+}
+
+/// How a session's agent is launched on top of its runner's defaults.
+/// Persisted with the session so restores relaunch it the same way.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SessionLaunchOptions {
+    /// Appended to the agent's arguments on every launch, e.g. a model or
+    /// permission mode flag.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extra_args: Vec<String>,
+    /// Variables set on top of the runner's environment.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    /// Directory to run in, relative to the workspace (or worktree) root.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subdirectory: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<TerminalSize>,
+}
+
+impl SessionLaunchOptions {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    fn size(&self) -> (u16, u16) {
+        let size = self.size.unwrap_or(DEFAULT_TERMINAL_SIZE);
+        (size.cols, size.rows)
+    }
+
+    /// Adds the extra arguments and environment to a launch.
+    fn apply(
+        &self,
+        mut args: Vec<String>,
+        mut env: Vec<(String, String)>,
+    ) -> (Vec<String>, Vec<(String, String)>) {
+        args.extend(self.extra_args.iter().cloned());
+        for (key, value) in &self.env {
+            env.retain(|(existing, _)| existing != key);
+            env.push((key.clone(), value.clone()));
+        }
+        (args, env)
+    }
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct AgentHandle {
     pub session_id: Uuid,
@@ -39,6 +107,11 @@ pub struct AgentHandle {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub sort_order: Option<i32>,
     pub workspace_path: PathBuf,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub worktree: Option<AgentWorktree>,
+    /// Not serialized: `env` may hold credentials.
+    #[serde(skip_serializing)]
+    pub launch_options: SessionLaunchOptions,
     pub status: AgentStatus,
     #[serde(skip_serializing)]
     pub process_id: Option<u32>,
@@ -50,6 +123,40 @@ pub struct AgentHandle {
     pub activity: AgentActivity,
 }
 
+impl AgentHandle {
+    /// Directory the agent process runs in: its worktree when isolated,
+    /// otherwise the workspace itself.
+    pub fn working_dir(&self) -> &Path {
+        working_dir(&self.workspace_path, self.worktree.as_ref())
+    }
+
+    /// Directory the agent is launched in: the working dir, or the
+    /// configured subdirectory of it.
+    pub fn launch_dir(&self) -> PathBuf {
+        launch_dir(
+            &self.workspace_path,
+            self.worktree.as_ref(),
+            &self.launch_options,
+        )
+    }
+}
+
+fn working_dir<'a>(workspace_path: &'a Path, worktree: Option<&'a AgentWorktree>) -> &'a Path {
+    worktree.map_or(workspace_path, |worktree| worktree.path.as_path())
+}
+
+fn launch_dir(
+    workspace_path: &Path,
+    worktree: Option<&AgentWorktree>,
+    options: &SessionLaunchOptions,
+) -> PathBuf {
+    let dir = working_dir(workspace_path, worktree);
+    match &options.subdirectory {
+        Some(subdirectory) => dir.join(subdirectory),
+        None => dir.to_path_buf(),
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ScrollbackRawResult {
     pub data: Vec<u8>,
@@ -73,7 +180,7 @@ fn generate_conversation_id() -> String {
 ///   without these Claude Code's Ink TUI degrades to dumb/broken rendering.
 ///
 /// Runner-supplied values always take precedence over the injected defaults.
-fn build_env(runner: &dyn crate::runners::AgentRunner) -> Vec<(String, String)> {
+pub(crate) fn build_env(runner: &dyn AgentRunner) -> Vec<(String, String)> {
     let mut env: Vec<(String, String)> = runner.env().into_iter().collect();
 
     if !env.iter().any(|(k, _)| k == "PATH") {
@@ -99,55 +206,201 @@ fn normalized_name(custom_name: Option<String>) -> Option<String> {
         .map(ToOwned::to_owned)
 }
 
+fn hands_turn_back(activity: &AgentActivity) -> bool {
+    matches!(
+        activity.phase,
+        AgentActivityPhase::AwaitingUser | AgentActivityPhase::AwaitingApproval
+    )
+}
+
 fn launch_for_start(
-    agent_type: AgentType,
+    runner: &dyn AgentRunner,
     command: String,
     base_args: Vec<String>,
     conversation_id: &str,
 ) -> (String, Vec<String>) {
-    match agent_type {
+    match runner.agent_type() {
         AgentType::ClaudeCode => {
             let mut args = vec!["--session-id".to_string(), conversation_id.to_string()];
             args.extend(base_args);
             (command, args)
         }
+        AgentType::Custom(_) => {
+            let mut args = runner.session_id_args(conversation_id);
+            args.extend(base_args);
+            (command, args)
+        }
         _ => (command, base_args),
     }
 }
 
+/// Returns the program and arguments that resume `conversation_id`, or
+/// `None` when the agent has no way to resume a conversation.
 fn launch_for_resume(
-    agent_type: AgentType,
+    runner: &dyn AgentRunner,
     command: String,
     conversation_id: &str,
-) -> (String, Vec<String>) {
-    match agent_type {
-        AgentType::ClaudeCode => (
+) -> Option<(String, Vec<String>)> {
+    match runner.agent_type() {
+        AgentType::ClaudeCode => Some((
             command,
             vec!["--resume".to_string(), conversation_id.to_string()],
-        ),
-        AgentType::Codex => (
+        )),
+        AgentType::Codex => Some((
             command,
             vec!["resume".to_string(), conversation_id.to_string()],
-        ),
-        AgentType::Gemini => (
+        )),
+        AgentType::Gemini => Some((
             command,
             vec!["--resume".to_string(), conversation_id.to_string()],
-        ),
+        )),
+        AgentType::Custom(_) => runner
+            .resume_args(conversation_id)
+            .map(|args| (command, args)),
+    }
+}
+
+/// Returns the program and arguments that continue `conversation_id` as a
+/// new conversation `new_conversation_id`, or `None` when the agent cannot
+/// fork. The original conversation is left as it was.
+fn launch_for_fork(
+    runner: &dyn AgentRunner,
+    command: String,
+    conversation_id: &str,
+    new_conversation_id: &str,
+) -> Option<(String, Vec<String>)> {
+    match runner.agent_type() {
+        AgentType::ClaudeCode => {
+            let (command, mut args) = launch_for_resume(runner, command, conversation_id)?;
+            args.extend([
+                "--fork-session".to_string(),
+                "--session-id".to_string(),
+                new_conversation_id.to_string(),
+            ]);
+            Some((command, args))
+        }
+        AgentType::Custom(_) => runner
+            .fork_args(conversation_id, new_conversation_id)
+            .map(|args| (command, args)),
+        _ => None,
     }
 }
 
+/// A session about to be launched with a new conversation.
+struct NewSession<'a> {
+    agent_type: AgentType,
+    workspace_path: PathBuf,
+    custom_name: Option<String>,
+    worktree: Option<AgentWorktree>,
+    options: SessionLaunchOptions,
+    /// Conversation to branch off, when forking.
+    fork_from: Option<&'a str>,
+}
+
 impl AgentManager {
     pub async fn start_session(
         &self,
         agent_type: AgentType,
         workspace_path: PathBuf,
         custom_name: Option<String>,
+        worktree: Option<AgentWorktree>,
+        options: SessionLaunchOptions,
+        scrollback_dir: Option<PathBuf>,
+    ) -> anyhow::Result<(Uuid, Arc<PtySession>)> {
+        self.launch_session(
+            NewSession {
+                agent_type,
+                workspace_path,
+                custom_name,
+                worktree,
+                options,
+                fork_from: None,
+            },
+            scrollback_dir,
+        )
+        .await
+    }
+
+    /// Whether sessions of `agent_type` can be forked.
+    pub fn supports_fork(&self, agent_type: &AgentType) -> bool {
+        self.runners
+            .iter()
+            .find(|r| r.agent_type() == *agent_type)
+            .is_some_and(|runner| launch_for_fork(runner.as_ref(), String::new(), "", "").is_some())
+    }
+
+    /// Starts a new session that continues `source_id`'s conversation on a
+    /// branch of its own, optionally in `worktree`, with the source's launch
+    /// options. The source session and its conversation are left untouched.
+    pub async fn fork_session(
+        &self,
+        source_id: &Uuid,
+        custom_name: Option<String>,
+        worktree: Option<AgentWorktree>,
+        scrollback_dir: Option<PathBuf>,
+    ) -> anyhow::Result<(Uuid, Arc<PtySession>)> {
+        let source = self
+            .get_handle(source_id)
+            .await
+            .ok_or_else(|| anyhow::anyhow!("Session not found"))?;
+        let conversation_id = source
+            .conversation_id
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("Session has no conversation to fork"))?;
+
+        if source.agent_type == AgentType::ClaudeCode {
+            // Claude looks conversations up by working directory.
+            let cwd = launch_dir(
+                &source.workspace_path,
+                worktree.as_ref(),
+                &source.launch_options,
+            );
+            crate::transcript::copy_claude_transcript(
+                &source.launch_dir(),
+                &cwd,
+                &conversation_id,
+            )?;
+        }
+
+        let custom_name = custom_name.or_else(|| {
+            source
+                .custom_name
+                .as_ref()
+                .map(|name| format!("{name} (fork)"))
+        });
+        self.launch_session(
+            NewSession {
+                agent_type: source.agent_type,
+                workspace_path: source.workspace_path,
+                custom_name,
+                worktree,
+                options: source.launch_options,
+                fork_from: Some(&conversation_id),
+            },
+            scrollback_dir,
+        )
+        .await
+    }
+
+    /// Spawns `new` and starts tracking it.
+    async fn launch_session(
+        &self,
+        new: NewSession<'_>,
+        scrollback_dir: Option<PathBuf>,
     ) -> anyhow::Result<(Uuid, Arc<PtySession>)> {
+        let NewSession {
+            agent_type,
+            workspace_path,
+            custom_name,
+            worktree,
+            options,
+            fork_from,
+        } = new;
         let runner = self
             .runners
             .iter()
             .find(|r| r.agent_type() == agent_type)
-            .ok_or_else(|| anyhow::anyhow!("Unknown agent type: {:?}", agent_type))?;
+            .ok_or_else(|| anyhow::anyhow!("Unknown agent type: {}", agent_type))?;
 
         if !runner.is_installed() {
             anyhow::bail!("Agent {} is not installed", runner.name());
@@ -157,29 +410,43 @@ impl AgentManager {
         let created_at = chrono::Utc::now();
         let normalized_name = normalized_name(custom_name);
         let conversation_id = generate_conversation_id();
+        let cwd = launch_dir(&workspace_path, worktree.as_ref(), &options);
 
-        let args = runner.args(&workspace_path);
         let env = build_env(runner.as_ref());
-
-        let (program, args) = launch_for_start(
-            agent_type,
-            crate::runners::resolve_command_path(&runner.command())
-                .unwrap_or_else(|| runner.command()),
-            args,
-            &conversation_id,
-        );
+        let command = crate::runners::resolve_command_path(&runner.command())
+            .unwrap_or_else(|| runner.command());
+        let (program, args) = match fork_from {
+            Some(source_conversation_id) => launch_for_fork(
+                runner.as_ref(),
+                command,
+                source_conversation_id,
+                &conversation_id,
+            )
+            .ok_or_else(|| anyhow::anyhow!("{} does not support forking", runner.name()))?,
+            None => launch_for_start(
+                runner.as_ref(),
+                command,
+                runner.args(&cwd),
+                &conversation_id,
+            ),
+        };
+        let (args, env) = options.apply(args, env);
+        let (args, env) = self.with_hooks(session_id, &agent_type, args, env);
+        let resumability_status =
+            if launch_for_resume(runner.as_ref(), String::new(), &conversation_id).is_some() {
+                ResumabilityStatus::Resumable
+            } else {
+                ResumabilityStatus::Unresumable
+            };
         let args_refs: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
 
+        if let Some(dir) = scrollback_dir {
+            self.pty_manager
+                .set_output_log_dir(session_id, dir.join(session_id.to_string()));
+        }
         let session = self
             .pty_manager
-            .create(
-                session_id,
-                &program,
-                &args_refs,
-                &workspace_path,
-                env,
-                (120, 40),
-            )
+            .create(session_id, &program, &args_refs, &cwd, env, options.size())
             .await?;
 
         let process_id = session.child_pid;
@@ -193,9 +460,11 @@ impl AgentManager {
             icon: None,
             sort_order: None,
             workspace_path,
+            worktree,
+            launch_options: options,
             status: AgentStatus::Running,
             process_id,
-            resumability_status: ResumabilityStatus::Resumable,
+            resumability_status,
             resume_failure_reason: None,
             recovered_from_previous: false,
             created_at,
@@ -214,6 +483,12 @@ impl AgentManager {
                 &self.handles,
             )
             .await;
+        // Output from before the monitor subscribed, such as a prompt the
+        // agent printed right away, would otherwise go unnoticed.
+        let early_output = session.output_snapshot();
+        if !early_output.is_empty() {
+            self.recorder.record_output(session.id, &early_output).await;
+        }
         Ok((session.id, session))
     }
 
@@ -237,7 +512,7 @@ impl AgentManager {
             .runners
             .iter()
             .find(|r| r.agent_type() == persisted.agent_type)
-            .ok_or_else(|| anyhow::anyhow!("Unknown agent type: {:?}", persisted.agent_type))?;
+            .ok_or_else(|| anyhow::anyhow!("Unknown agent type: {}", persisted.agent_type))?;
 
         if !runner.is_installed() {
             anyhow::bail!("Agent {} is not installed", runner.name());
@@ -246,6 +521,13 @@ impl AgentManager {
         let session_id = persisted.session_id;
         let created_at = persisted.created_at.unwrap_or_else(chrono::Utc::now);
         let workspace_path = persisted.workspace_path;
+        let worktree = persisted.worktree;
+        let options = persisted.launch_options;
+        let checkout = working_dir(&workspace_path, worktree.as_ref());
+        if worktree.is_some() && !checkout.is_dir() {
+            anyhow::bail!("Agent worktree {} no longer exists", checkout.display());
+        }
+        let cwd = launch_dir(&workspace_path, worktree.as_ref(), &options);
         let normalized_name = normalized_name(persisted.custom_name.clone());
 
         let preferred_conversation_id = persisted
@@ -253,26 +535,24 @@ impl AgentManager {
             .clone()
             .unwrap_or_else(generate_conversation_id);
 
-        let resume_result = async {
+        let resume_result: anyhow::Result<Arc<PtySession>> = async {
             let env = build_env(runner.as_ref());
 
             let (program, args) = launch_for_resume(
-                persisted.agent_type,
+                runner.as_ref(),
                 crate::runners::resolve_command_path(&runner.command())
                     .unwrap_or_else(|| runner.command()),
                 &preferred_conversation_id,
-            );
+            )
+            .ok_or_else(|| anyhow::anyhow!("{} does not support resuming", runner.name()))?;
+            let (args, env) = options.apply(args, env);
+            let (args, env) = self.with_hooks(session_id, &persisted.agent_type, args, env);
             let args_refs: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
-            self.pty_manager
-                .create(
-                    session_id,
-                    &program,
-                    &args_refs,
-                    &workspace_path,
-                    env,
-                    (120, 40),
-                )
-                .await
+            let session = self
+                .pty_manager
+                .create(session_id, &program, &args_refs, &cwd, env, options.size())
+                .await?;
+            Ok(session)
         }
         .await;
 
@@ -295,23 +575,18 @@ impl AgentManager {
                 let env = build_env(runner.as_ref());
 
                 let (program, args) = launch_for_start(
-                    persisted.agent_type,
+                    runner.as_ref(),
                     crate::runners::resolve_command_path(&runner.command())
                         .unwrap_or_else(|| runner.command()),
-                    runner.args(&workspace_path),
+                    runner.args(&cwd),
                     &fresh_conversation_id,
                 );
+                let (args, env) = options.apply(args, env);
+                let (args, env) = self.with_hooks(session_id, &persisted.agent_type, args, env);
                 let args_refs: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
                 let session = self
                     .pty_manager
-                    .create(
-                        session_id,
-                        &program,
-                        &args_refs,
-                        &workspace_path,
-                        env,
-                        (120, 40),
-                    )
+                    .create(session_id, &program, &args_refs, &cwd, env, options.size())
                     .await?;
                 (
                     session,
@@ -330,13 +605,15 @@ impl AgentManager {
 
         let handle = AgentHandle {
             session_id,
-            agent_type: persisted.agent_type,
+            agent_type: persisted.agent_type.clone(),
             conversation_id: Some(conversation_id),
             custom_name: normalized_name,
             pinned: persisted.pinned,
             icon: persisted.icon,
             sort_order: persisted.sort_order,
             workspace_path,
+            worktree,
+            launch_options: options,
             status: AgentStatus::Restored,
             process_id,
             resumability_status,
@@ -364,8 +641,8 @@ impl AgentManager {
     pub async fn stop_session(&self, session_id: &Uuid) -> anyhow::Result<()> {
         let handle = self.handles.read().await.get(session_id).cloned();
 
-        if let Some(task) = self.activity_monitors.write().await.remove(session_id) {
-            task.abort();
+        if let Some(monitor) = self.activity_monitors.write().await.remove(session_id) {
+            monitor.abort();
         }
 
         if let Ok(session) = self.pty_manager.get(session_id).await {
@@ -379,6 +656,14 @@ impl AgentManager {
         if let Some(handle) = self.handles.write().await.get_mut(session_id) {
             handle.status = AgentStatus::Stopped;
         }
+        self.hook_tokens
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(session_id);
+        self.prompt_queues
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(session_id);
         self.recorder
             .record_stopped(*session_id, "session_stopped")
             .await;
@@ -436,7 +721,9 @@ impl AgentManager {
         self.restore_session(super::PersistedAgentInfo {
             session_id: handle.session_id,
             workspace_path: handle.workspace_path.clone(),
-            agent_type: handle.agent_type,
+            worktree: handle.worktree.clone(),
+            launch_options: handle.launch_options.clone(),
+            agent_type: handle.agent_type.clone(),
             conversation_id: handle.conversation_id.clone(),
             custom_name: handle.custom_name.clone(),
             pinned: handle.pinned,
@@ -475,8 +762,8 @@ impl AgentManager {
         session_id: &Uuid,
         handle: &AgentHandle,
     ) -> anyhow::Result<Arc<PtySession>> {
-        // Clean up any leftover PTY from the failed resume attempt so its
-        // output history doesn't bleed into the fresh session.
+        // Clean up any leftover PTY from the failed resume attempt. Its
+        // output stays in the on-disk log, if any, ahead of the fresh run.
         let _ = self.pty_manager.kill(session_id).await;
         let _ = self.pty_manager.remove(session_id).await;
 
@@ -484,7 +771,7 @@ impl AgentManager {
             .runners
             .iter()
             .find(|r| r.agent_type() == handle.agent_type)
-            .ok_or_else(|| anyhow::anyhow!("Unknown agent type: {:?}", handle.agent_type))?;
+            .ok_or_else(|| anyhow::anyhow!("Unknown agent type: {}", handle.agent_type))?;
 
         if !runner.is_installed() {
             anyhow::bail!("Agent {} is not installed", runner.name());
@@ -493,12 +780,14 @@ impl AgentManager {
         let fresh_conversation_id = Uuid::new_v4().to_string();
         let env = build_env(runner.as_ref());
         let (program, args) = launch_for_start(
-            handle.agent_type,
+            runner.as_ref(),
             crate::runners::resolve_command_path(&runner.command())
                 .unwrap_or_else(|| runner.command()),
-            runner.args(&handle.workspace_path),
+            runner.args(&handle.launch_dir()),
             &fresh_conversation_id,
         );
+        let (args, env) = handle.launch_options.apply(args, env);
+        let (args, env) = self.with_hooks(*session_id, &handle.agent_type, args, env);
         let args_refs: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
 
         let session = self
@@ -507,9 +796,9 @@ impl AgentManager {
                 *session_id,
                 &program,
                 &args_refs,
-                &handle.workspace_path,
+                &handle.launch_dir(),
                 env,
-                (120, 40),
+                handle.launch_options.size(),
             )
             .await?;
 
@@ -602,6 +891,60 @@ impl AgentManager {
         Ok(())
     }
 
+    /// Writes `input` as separate chunks with a short pause in between, so
+    /// TUIs see pastes and keys as distinct events rather than one burst of
+    /// typing.
+    pub async fn send_input(&self, session_id: &Uuid, input: &SessionInput) -> anyhow::Result<()> {
+        for (index, chunk) in input.chunks().iter().enumerate() {
+            if index > 0 {
+                tokio::time::sleep(INPUT_CHUNK_PAUSE).await;
+            }
+            self.input_session(session_id, chunk).await?;
+        }
+        Ok(())
+    }
+
+    /// Waits until the agent hands the turn back: `AwaitingUser`, or
+    /// `AwaitingApproval` since it cannot go on without an answer either.
+    /// Also returns once the session's process is gone. `None` on timeout.
+    pub async fn wait_for_turn(
+        &self,
+        session_id: &Uuid,
+        timeout: Duration,
+    ) -> Option<AgentActivity> {
+        let mut activity_rx = self.subscribe_activity();
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            // Re-read after subscribing (and after lagging) so a transition
+            // that happened in between is not missed.
+            let activity = self.get_activity(session_id).await;
+            if hands_turn_back(&activity) || !self.has_live_pty(session_id).await {
+                return Some(activity);
+            }
+            loop {
+                match tokio::time::timeout_at(deadline, activity_rx.recv()).await {
+                    Err(_) => return None,
+                    Ok(Ok(event)) if event.session_id == *session_id => {
+                        if hands_turn_back(&event.activity) || !self.has_live_pty(session_id).await
+                        {
+                            return Some(event.activity);
+                        }
+                    }
+                    Ok(Ok(_)) => {}
+                    Ok(Err(broadcast::error::RecvError::Lagged(_))) => break,
+                    Ok(Err(broadcast::error::RecvError::Closed)) => return None,
+                }
+            }
+        }
+    }
+
+    async fn has_live_pty(&self, session_id: &Uuid) -> bool {
+        self.pty_manager
+            .get(session_id)
+            .await
+            .is_ok_and(|session| !session.is_stopped())
+    }
+
     pub async fn get_activity(&self, session_id: &Uuid) -> AgentActivity {
         self.recorder
             .activity_snapshot(*session_id, "activity_requested")
@@ -614,15 +957,13 @@ impl AgentManager {
         before_offset: Option<usize>,
         max_bytes: usize,
     ) -> anyhow::Result<ScrollbackRawResult> {
-        // Read from whatever PTY exists — do NOT spawn a new process just
-        // to read scrollback history.
-        let session = self
+        // Read from whatever PTY exists, or its on-disk log — do NOT spawn a
+        // new process just to read scrollback history.
+        let (data, start_offset, end_offset, has_more) = self
             .pty_manager
-            .get(session_id)
+            .output_slice_before(session_id, before_offset, max_bytes)
             .await
             .map_err(|_| anyhow::anyhow!("No terminal history available for {}", session_id))?;
-        let (data, start_offset, end_offset, has_more) =
-            session.output_slice_before(before_offset, max_bytes);
         Ok(ScrollbackRawResult {
             data,
             start_offset,
@@ -668,6 +1009,7 @@ impl AgentManager {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::runners::{ClaudeCodeRunner, CodexRunner, ConfigRunner, GeminiRunner};
 
     // ── normalized_name ──────────────────────────────────────────────
 
@@ -702,12 +1044,75 @@ mod tests {
         );
     }
 
+    // ── SessionLaunchOptions ─────────────────────────────────────────
+
+    #[test]
+    fn launch_options_append_args_and_override_env() {
+        let options = SessionLaunchOptions {
+            extra_args: vec!["--model".to_string(), "opus".to_string()],
+            env: BTreeMap::from([("TERM".to_string(), "dumb".to_string())]),
+            ..Default::default()
+        };
+        let (args, env) = options.apply(
+            vec!["--resume".to_string(), "c1".to_string()],
+            vec![
+                ("TERM".to_string(), "xterm-256color".to_string()),
+                ("PATH".to_string(), "/bin".to_string()),
+            ],
+        );
+        assert_eq!(args, vec!["--resume", "c1", "--model", "opus"]);
+        assert_eq!(
+            env,
+            vec![
+                ("PATH".to_string(), "/bin".to_string()),
+                ("TERM".to_string(), "dumb".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn launch_options_size_and_dir() {
+        let mut options = SessionLaunchOptions::default();
+        assert_eq!(options.size(), (120, 40));
+        assert_eq!(
+            launch_dir(Path::new("/ws"), None, &options),
+            PathBuf::from("/ws")
+        );
+
+        options.size = Some(TerminalSize { cols: 80, rows: 24 });
+        options.subdirectory = Some(PathBuf::from("apps/web"));
+        let worktree = AgentWorktree {
+            path: PathBuf::from("/wt"),
+            branch: "b".to_string(),
+        };
+        assert_eq!(options.size(), (80, 24));
+        assert_eq!(
+            launch_dir(Path::new("/ws"), Some(&worktree), &options),
+            PathBuf::from("/wt/apps/web")
+        );
+    }
+
+    #[test]
+    fn launch_options_serde_skips_defaults() {
+        assert_eq!(
+            serde_json::to_value(SessionLaunchOptions::default()).unwrap(),
+            serde_json::json!({})
+        );
+        let options: SessionLaunchOptions = serde_json::from_value(serde_json::json!({
+            "extra_args": ["--permission-mode", "plan"],
+            "size": {"cols": 100, "rows": 30},
+        }))
+        .unwrap();
+        assert_eq!(options.extra_args.len(), 2);
+        assert_eq!(options.size(), (100, 30));
+    }
+
     // ── launch_for_start ─────────────────────────────────────────────
 
     #[test]
     fn launch_for_start_claude_code_prepends_session_id() {
         let (cmd, args) = launch_for_start(
-            AgentType::ClaudeCode,
+            &ClaudeCodeRunner,
             "claude".to_string(),
             vec!["--flag".to_string()],
             "conv-123",
@@ -719,7 +1124,7 @@ mod tests {
     #[test]
     fn launch_for_start_codex_passes_through() {
         let (cmd, args) = launch_for_start(
-            AgentType::Codex,
+            &CodexRunner,
             "codex".to_string(),
             vec!["arg1".to_string()],
             "conv-456",
@@ -730,8 +1135,7 @@ mod tests {
 
     #[test]
     fn launch_for_start_gemini_passes_through() {
-        let (cmd, args) =
-            launch_for_start(AgentType::Gemini, "gemini".to_string(), vec![], "conv-789");
+        let (cmd, args) = launch_for_start(&GeminiRunner, "gemini".to_string(), vec![], "conv-789");
         assert_eq!(cmd, "gemini");
         assert!(args.is_empty());
     }
@@ -741,25 +1145,97 @@ mod tests {
     #[test]
     fn launch_for_resume_claude_code() {
         let (cmd, args) =
-            launch_for_resume(AgentType::ClaudeCode, "claude".to_string(), "conv-123");
+            launch_for_resume(&ClaudeCodeRunner, "claude".to_string(), "conv-123").unwrap();
         assert_eq!(cmd, "claude");
         assert_eq!(args, vec!["--resume", "conv-123"]);
     }
 
     #[test]
     fn launch_for_resume_codex() {
-        let (cmd, args) = launch_for_resume(AgentType::Codex, "codex".to_string(), "conv-456");
+        let (cmd, args) = launch_for_resume(&CodexRunner, "codex".to_string(), "conv-456").unwrap();
         assert_eq!(cmd, "codex");
         assert_eq!(args, vec!["resume", "conv-456"]);
     }
 
     #[test]
     fn launch_for_resume_gemini() {
-        let (cmd, args) = launch_for_resume(AgentType::Gemini, "gemini".to_string(), "conv-789");
+        let (cmd, args) =
+            launch_for_resume(&GeminiRunner, "gemini".to_string(), "conv-789").unwrap();
         assert_eq!(cmd, "gemini");
         assert_eq!(args, vec!["--resume", "conv-789"]);
     }
 
+    // ── launch_for_fork ──────────────────────────────────────────────
+
+    #[test]
+    fn launch_for_fork_claude_code_resumes_into_new_session() {
+        let (cmd, args) =
+            launch_for_fork(&ClaudeCodeRunner, "claude".to_string(), "conv-1", "conv-2").unwrap();
+        assert_eq!(cmd, "claude");
+        assert_eq!(
+            args,
+            vec![
+                "--resume",
+                "conv-1",
+                "--fork-session",
+                "--session-id",
+                "conv-2"
+            ]
+        );
+    }
+
+    #[test]
+    fn launch_for_fork_unsupported_agents() {
+        assert!(launch_for_fork(&CodexRunner, "codex".to_string(), "a", "b").is_none());
+        assert!(launch_for_fork(&GeminiRunner, "gemini".to_string(), "a", "b").is_none());
+        let runner = custom_runner(&[], &["--resume={conversation_id}"]);
+        assert!(launch_for_fork(&runner, "aider".to_string(), "a", "b").is_none());
+    }
+
+    // ── custom agents ────────────────────────────────────────────────
+
+    fn custom_runner(session_id_args: &[&str], resume_args: &[&str]) -> ConfigRunner {
+        ConfigRunner::new(lw_config::CustomAgentConfig {
+            id: "aider".to_string(),
+            name: None,
+            command: "aider".to_string(),
+            args: vec![],
+            env: Default::default(),
+            version_args: vec!["--version".to_string()],
+            session_id_args: session_id_args.iter().map(|s| s.to_string()).collect(),
+            resume_args: resume_args.iter().map(|s| s.to_string()).collect(),
+            fork_args: vec![],
+            headless_args: vec![],
+        })
+    }
+
+    #[test]
+    fn launch_for_start_custom_prepends_session_id_args() {
+        let runner = custom_runner(&["--session", "{conversation_id}"], &[]);
+        let (cmd, args) = launch_for_start(
+            &runner,
+            "aider".to_string(),
+            vec!["--flag".to_string()],
+            "conv-1",
+        );
+        assert_eq!(cmd, "aider");
+        assert_eq!(args, vec!["--session", "conv-1", "--flag"]);
+    }
+
+    #[test]
+    fn launch_for_resume_custom_uses_template() {
+        let runner = custom_runner(&[], &["--resume={conversation_id}"]);
+        let (cmd, args) = launch_for_resume(&runner, "aider".to_string(), "conv-2").unwrap();
+        assert_eq!(cmd, "aider");
+        assert_eq!(args, vec!["--resume=conv-2"]);
+    }
+
+    #[test]
+    fn launch_for_resume_custom_without_template_is_none() {
+        let runner = custom_runner(&[], &[]);
+        assert!(launch_for_resume(&runner, "aider".to_string(), "conv-3").is_none());
+    }
+
     // ── AgentStatus serde ────────────────────────────────────────────
 
     #[test]
@@ -840,6 +1316,8 @@ mod tests {
             icon: None,
             sort_order: None,
             workspace_path: PathBuf::from("/tmp/ws"),
+            worktree: None,
+            launch_options: Default::default(),
             status: AgentStatus::Running,
             process_id: Some(12345),
             resumability_status: ResumabilityStatus::Resumable,
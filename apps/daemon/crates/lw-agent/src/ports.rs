@@ -0,0 +1,218 @@
+//! Listening TCP sockets of session process trees, read from `/proc`.
+//!
+//! Only Linux exposes sockets this way; elsewhere no ports are found.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+
+/// Listening sockets owned by each of `root_pids` or its descendants, keyed
+/// by root pid.
+pub fn listening_ports(root_pids: &[u32]) -> HashMap<u32, Vec<SocketAddr>> {
+    #[cfg(target_os = "linux")]
+    {
+        linux::listening_ports(root_pids)
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = root_pids;
+        HashMap::new()
+    }
+}
+
+#[cfg(any(target_os = "linux", test))]
+mod parse {
+    use std::collections::{HashMap, HashSet};
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+    /// `st` value of a listening socket in `/proc/net/tcp{,6}`.
+    const TCP_LISTEN: &str = "0A";
+
+    /// Parent pid from the contents of `/proc/{pid}/stat`. The command name
+    /// may itself contain `)`, so fields are read after the last one.
+    pub(crate) fn stat_parent_pid(stat: &str) -> Option<u32> {
+        let (_, fields) = stat.rsplit_once(')')?;
+        fields.split_whitespace().nth(1)?.parse().ok()
+    }
+
+    /// `roots` and all their descendants, given `(pid, parent pid)` pairs.
+    pub(crate) fn descendants(roots: &[u32], parents: &[(u32, u32)]) -> HashSet<u32> {
+        let mut children: HashMap<u32, Vec<u32>> = HashMap::new();
+        for &(pid, ppid) in parents {
+            children.entry(ppid).or_default().push(pid);
+        }
+        let mut tree: HashSet<u32> = HashSet::new();
+        let mut pending: Vec<u32> = roots.to_vec();
+        while let Some(pid) = pending.pop() {
+            if tree.insert(pid) {
+                pending.extend(children.get(&pid).into_iter().flatten());
+            }
+        }
+        tree
+    }
+
+    /// Socket inode from an fd link target such as `socket:[12345]`.
+    pub(crate) fn socket_inode(link: &str) -> Option<u64> {
+        link.strip_prefix("socket:[")?
+            .strip_suffix(']')?
+            .parse()
+            .ok()
+    }
+
+    /// Listening sockets in a `/proc/net/tcp` or `/proc/net/tcp6` table,
+    /// keyed by inode.
+    pub(crate) fn listening_sockets(table: &str) -> HashMap<u64, SocketAddr> {
+        table
+            .lines()
+            .skip(1)
+            .filter_map(|line| {
+                let fields: Vec<&str> = line.split_whitespace().collect();
+                if fields.get(3) != Some(&TCP_LISTEN) {
+                    return None;
+                }
+                let address = parse_address(fields.get(1)?)?;
+                let inode = fields.get(9)?.parse().ok()?;
+                Some((inode, address))
+            })
+            .collect()
+    }
+
+    /// `0100007F:1F90` or its 32-digit IPv6 form. The kernel prints each
+    /// 32-bit word of the address in host byte order.
+    fn parse_address(field: &str) -> Option<SocketAddr> {
+        let (ip, port) = field.split_once(':')?;
+        let port = u16::from_str_radix(port, 16).ok()?;
+        let mut words = Vec::with_capacity(4);
+        for chunk in ip.as_bytes().chunks(8) {
+            let word = u32::from_str_radix(std::str::from_utf8(chunk).ok()?, 16).ok()?;
+            words.push(word.to_ne_bytes());
+        }
+        let ip = match words.as_slice() {
+            [word] => IpAddr::V4(Ipv4Addr::from(*word)),
+            [a, b, c, d] => {
+                let mut octets = [0u8; 16];
+                for (i, word) in [a, b, c, d].into_iter().enumerate() {
+                    octets[i * 4..i * 4 + 4].copy_from_slice(word);
+                }
+                IpAddr::V6(Ipv6Addr::from(octets))
+            }
+            _ => return None,
+        };
+        Some(SocketAddr::new(ip, port))
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use super::parse;
+    use std::collections::{HashMap, HashSet};
+    use std::net::SocketAddr;
+
+    fn process_parents() -> Vec<(u32, u32)> {
+        let Ok(entries) = std::fs::read_dir("/proc") else {
+            return Vec::new();
+        };
+        entries
+            .flatten()
+            .filter_map(|entry| {
+                let pid: u32 = entry.file_name().to_str()?.parse().ok()?;
+                let stat = std::fs::read_to_string(entry.path().join("stat")).ok()?;
+                Some((pid, parse::stat_parent_pid(&stat)?))
+            })
+            .collect()
+    }
+
+    fn socket_inodes(pid: u32) -> HashSet<u64> {
+        let Ok(entries) = std::fs::read_dir(format!("/proc/{pid}/fd")) else {
+            return HashSet::new();
+        };
+        entries
+            .flatten()
+            .filter_map(|entry| {
+                let link = std::fs::read_link(entry.path()).ok()?;
+                parse::socket_inode(link.to_str()?)
+            })
+            .collect()
+    }
+
+    pub(super) fn listening_ports(root_pids: &[u32]) -> HashMap<u32, Vec<SocketAddr>> {
+        if root_pids.is_empty() {
+            return HashMap::new();
+        }
+        let mut sockets = HashMap::new();
+        for table in ["/proc/net/tcp", "/proc/net/tcp6"] {
+            if let Ok(content) = std::fs::read_to_string(table) {
+                sockets.extend(parse::listening_sockets(&content));
+            }
+        }
+        if sockets.is_empty() {
+            return HashMap::new();
+        }
+        let parents = process_parents();
+        root_pids
+            .iter()
+            .map(|&root| {
+                let mut ports: Vec<SocketAddr> = parse::descendants(&[root], &parents)
+                    .into_iter()
+                    .flat_map(socket_inodes)
+                    .filter_map(|inode| sockets.get(&inode).copied())
+                    .collect();
+                ports.sort();
+                ports.dedup();
+                (root, ports)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse::*;
+    use std::net::{Ipv6Addr, SocketAddr};
+
+    #[test]
+    fn stat_parent_pid_skips_command_names_with_parens() {
+        assert_eq!(
+            stat_parent_pid("4242 (node (vite)) S 4000 4242 4000 0 -1"),
+            Some(4000)
+        );
+        assert_eq!(stat_parent_pid("garbage"), None);
+    }
+
+    #[test]
+    fn descendants_follow_the_whole_tree() {
+        let parents = [(10, 1), (11, 10), (12, 11), (20, 1), (13, 10)];
+        let tree = descendants(&[10], &parents);
+        assert_eq!(tree.len(), 4);
+        assert!(tree.contains(&12) && tree.contains(&13));
+        assert!(!tree.contains(&20));
+    }
+
+    #[test]
+    fn socket_inode_from_fd_link() {
+        assert_eq!(socket_inode("socket:[98765]"), Some(98765));
+        assert_eq!(socket_inode("pipe:[98765]"), None);
+        assert_eq!(socket_inode("/dev/null"), None);
+    }
+
+    #[cfg(target_endian = "little")]
+    #[test]
+    fn listening_sockets_parse_ipv4_and_ipv6_tables() {
+        let tcp = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode\n\
+           0: 0100007F:1F90 00000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 111 1 0 100 0 0 10 0\n\
+           1: 0100007F:C350 0100007F:1F90 01 00000000:00000000 00:00000000 00000000  1000        0 222 1 0 100 0 0 10 0\n";
+        let sockets = listening_sockets(tcp);
+        assert_eq!(sockets.len(), 1);
+        assert_eq!(
+            sockets[&111],
+            "127.0.0.1:8080".parse::<SocketAddr>().unwrap()
+        );
+
+        let tcp6 = "  sl  local_address                         remote_address                        st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode\n\
+           0: 00000000000000000000000001000000:14E9 00000000000000000000000000000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 333 1 0 100 0 0 10 0\n";
+        let sockets = listening_sockets(tcp6);
+        assert_eq!(
+            sockets[&333],
+            SocketAddr::new(Ipv6Addr::LOCALHOST.into(), 5353)
+        );
+    }
+}
@@ -0,0 +1,299 @@
+//! Prompts queued for an agent while it is busy.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use uuid::Uuid;
+
+/// Delivered prompts kept per session, oldest dropped first.
+pub const PROMPT_HISTORY_LIMIT: usize = 50;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum QueuedPromptStatus {
+    Pending,
+    Delivered,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct QueuedPrompt {
+    pub id: Uuid,
+    pub text: String,
+    pub status: QueuedPromptStatus,
+    pub created_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+/// The full queue of a session after it changed.
+#[derive(Debug, Clone, Serialize)]
+pub struct PromptQueueEvent {
+    pub session_id: Uuid,
+    pub prompts: Vec<QueuedPrompt>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum PromptQueueError {
+    #[error("Session not found")]
+    SessionNotFound,
+    #[error("Queued prompt not found")]
+    PromptNotFound,
+    #[error("Prompt was already delivered")]
+    AlreadyDelivered,
+    #[error("Prompt text must not be empty")]
+    EmptyPrompt,
+    #[error("Order must list every pending prompt exactly once")]
+    InvalidOrder,
+}
+
+impl PromptQueueError {
+    pub fn error_code(&self) -> &'static str {
+        match self {
+            PromptQueueError::SessionNotFound => "NOT_FOUND",
+            PromptQueueError::PromptNotFound => "NOT_FOUND",
+            PromptQueueError::AlreadyDelivered => "PROMPT_ALREADY_DELIVERED",
+            PromptQueueError::EmptyPrompt => "INVALID_PROMPT",
+            PromptQueueError::InvalidOrder => "INVALID_ORDER",
+        }
+    }
+}
+
+/// A session's queue: delivered prompts (its history) followed by the
+/// pending ones in delivery order.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct PromptQueue {
+    delivered: Vec<QueuedPrompt>,
+    pending: Vec<QueuedPrompt>,
+}
+
+fn non_empty(text: String) -> Result<String, PromptQueueError> {
+    if text.trim().is_empty() {
+        return Err(PromptQueueError::EmptyPrompt);
+    }
+    Ok(text)
+}
+
+impl PromptQueue {
+    /// Rebuilds a queue from its persisted items.
+    pub fn from_items(items: Vec<QueuedPrompt>) -> Self {
+        let (delivered, pending) = items
+            .into_iter()
+            .partition(|item| item.status == QueuedPromptStatus::Delivered);
+        let mut queue = Self { delivered, pending };
+        queue.trim_history();
+        queue
+    }
+
+    pub fn items(&self) -> Vec<QueuedPrompt> {
+        self.delivered
+            .iter()
+            .chain(&self.pending)
+            .cloned()
+            .collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.delivered.is_empty() && self.pending.is_empty()
+    }
+
+    pub fn next_pending(&self) -> Option<&QueuedPrompt> {
+        self.pending.first()
+    }
+
+    pub fn push(&mut self, text: String) -> Result<QueuedPrompt, PromptQueueError> {
+        let prompt = QueuedPrompt {
+            id: Uuid::new_v4(),
+            text: non_empty(text)?,
+            status: QueuedPromptStatus::Pending,
+            created_at: Utc::now(),
+            delivered_at: None,
+        };
+        self.pending.push(prompt.clone());
+        Ok(prompt)
+    }
+
+    fn pending_mut(&mut self, id: &Uuid) -> Result<&mut QueuedPrompt, PromptQueueError> {
+        if self.delivered.iter().any(|item| item.id == *id) {
+            return Err(PromptQueueError::AlreadyDelivered);
+        }
+        self.pending
+            .iter_mut()
+            .find(|item| item.id == *id)
+            .ok_or(PromptQueueError::PromptNotFound)
+    }
+
+    pub fn edit(&mut self, id: &Uuid, text: String) -> Result<QueuedPrompt, PromptQueueError> {
+        let text = non_empty(text)?;
+        let prompt = self.pending_mut(id)?;
+        prompt.text = text;
+        Ok(prompt.clone())
+    }
+
+    pub fn cancel(&mut self, id: &Uuid) -> Result<(), PromptQueueError> {
+        self.pending_mut(id)?;
+        self.pending.retain(|item| item.id != *id);
+        Ok(())
+    }
+
+    /// Puts the pending prompts in the order of `ids`, which must name each
+    /// of them once.
+    pub fn reorder(&mut self, ids: &[Uuid]) -> Result<(), PromptQueueError> {
+        let unique: HashSet<&Uuid> = ids.iter().collect();
+        if ids.len() != self.pending.len()
+            || unique.len() != ids.len()
+            || !self.pending.iter().all(|item| unique.contains(&item.id))
+        {
+            return Err(PromptQueueError::InvalidOrder);
+        }
+        self.pending.sort_by_key(|item| {
+            ids.iter()
+                .position(|id| *id == item.id)
+                .unwrap_or(usize::MAX)
+        });
+        Ok(())
+    }
+
+    /// Moves a pending prompt into the history.
+    pub fn mark_delivered(&mut self, id: &Uuid, now: DateTime<Utc>) -> Option<QueuedPrompt> {
+        let index = self.pending.iter().position(|item| item.id == *id)?;
+        let mut prompt = self.pending.remove(index);
+        prompt.status = QueuedPromptStatus::Delivered;
+        prompt.delivered_at = Some(now);
+        self.delivered.push(prompt.clone());
+        self.trim_history();
+        Some(prompt)
+    }
+
+    /// Moves the next pending prompt into the history, so that it can no
+    /// longer be edited or cancelled while it is being typed.
+    pub fn deliver_next(&mut self, now: DateTime<Utc>) -> Option<QueuedPrompt> {
+        let id = self.next_pending()?.id;
+        self.mark_delivered(&id, now)
+    }
+
+    /// Puts a prompt that could not be typed back at the head of the queue.
+    pub fn requeue(&mut self, mut prompt: QueuedPrompt) {
+        self.delivered.retain(|item| item.id != prompt.id);
+        prompt.status = QueuedPromptStatus::Pending;
+        prompt.delivered_at = None;
+        self.pending.insert(0, prompt);
+    }
+
+    fn trim_history(&mut self) {
+        let excess = self.delivered.len().saturating_sub(PROMPT_HISTORY_LIMIT);
+        self.delivered.drain(..excess);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(queue: &PromptQueue) -> Vec<Uuid> {
+        queue.items().iter().map(|item| item.id).collect()
+    }
+
+    #[test]
+    fn push_edit_cancel() {
+        let mut queue = PromptQueue::default();
+        let first = queue.push("first".to_string()).unwrap();
+        let second = queue.push("second".to_string()).unwrap();
+        assert!(matches!(
+            queue.push("  ".to_string()),
+            Err(PromptQueueError::EmptyPrompt)
+        ));
+
+        assert_eq!(
+            queue.edit(&first.id, "edited".to_string()).unwrap().text,
+            "edited"
+        );
+        queue.cancel(&first.id).unwrap();
+        assert_eq!(ids(&queue), vec![second.id]);
+        assert!(matches!(
+            queue.cancel(&first.id),
+            Err(PromptQueueError::PromptNotFound)
+        ));
+    }
+
+    #[test]
+    fn reorder_requires_every_pending_prompt_once() {
+        let mut queue = PromptQueue::default();
+        let a = queue.push("a".to_string()).unwrap().id;
+        let b = queue.push("b".to_string()).unwrap().id;
+        let c = queue.push("c".to_string()).unwrap().id;
+
+        queue.reorder(&[c, a, b]).unwrap();
+        assert_eq!(ids(&queue), vec![c, a, b]);
+        assert_eq!(queue.next_pending().unwrap().id, c);
+
+        for bad in [vec![a, b], vec![a, a, b], vec![a, b, Uuid::new_v4()]] {
+            assert!(matches!(
+                queue.reorder(&bad),
+                Err(PromptQueueError::InvalidOrder)
+            ));
+        }
+        assert_eq!(ids(&queue), vec![c, a, b]);
+    }
+
+    #[test]
+    fn delivered_prompts_move_to_history_and_are_frozen() {
+        let mut queue = PromptQueue::default();
+        let a = queue.push("a".to_string()).unwrap().id;
+        let b = queue.push("b".to_string()).unwrap().id;
+
+        let delivered = queue.mark_delivered(&a, Utc::now()).unwrap();
+        assert_eq!(delivered.status, QueuedPromptStatus::Delivered);
+        assert!(delivered.delivered_at.is_some());
+        assert_eq!(ids(&queue), vec![a, b]);
+        assert_eq!(queue.next_pending().unwrap().id, b);
+
+        assert!(matches!(
+            queue.edit(&a, "again".to_string()),
+            Err(PromptQueueError::AlreadyDelivered)
+        ));
+        assert!(matches!(
+            queue.cancel(&a),
+            Err(PromptQueueError::AlreadyDelivered)
+        ));
+        // Only pending prompts take part in reordering.
+        queue.reorder(&[b]).unwrap();
+    }
+
+    #[test]
+    fn deliver_next_and_requeue() {
+        let mut queue = PromptQueue::default();
+        let a = queue.push("a".to_string()).unwrap().id;
+        let b = queue.push("b".to_string()).unwrap().id;
+
+        let taken = queue.deliver_next(Utc::now()).unwrap();
+        assert_eq!(taken.id, a);
+        assert!(matches!(
+            queue.cancel(&a),
+            Err(PromptQueueError::AlreadyDelivered)
+        ));
+
+        queue.requeue(taken);
+        assert_eq!(ids(&queue), vec![a, b]);
+        let next = queue.next_pending().unwrap();
+        assert_eq!(next.id, a);
+        assert_eq!(next.status, QueuedPromptStatus::Pending);
+        assert!(next.delivered_at.is_none());
+    }
+
+    #[test]
+    fn history_is_capped_and_survives_a_roundtrip() {
+        let mut queue = PromptQueue::default();
+        for i in 0..PROMPT_HISTORY_LIMIT + 5 {
+            let id = queue.push(format!("p{i}")).unwrap().id;
+            queue.mark_delivered(&id, Utc::now());
+        }
+        let pending = queue.push("next".to_string()).unwrap();
+        let items = queue.items();
+        assert_eq!(items.len(), PROMPT_HISTORY_LIMIT + 1);
+        assert_eq!(items[0].text, "p5");
+
+        let restored = PromptQueue::from_items(items.clone());
+        assert_eq!(restored.items(), items);
+        assert_eq!(restored.next_pending().unwrap().id, pending.id);
+    }
+}
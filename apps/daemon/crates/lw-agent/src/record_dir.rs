@@ -0,0 +1,53 @@
+//! A directory of JSON records, one `{id}.json` file each, as kept by the
+//! headless task and workspace task run stores.
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+pub(crate) fn record_path(dir: &Path, id: &Uuid) -> PathBuf {
+    dir.join(format!("{id}.json"))
+}
+
+/// Writes the record through a temporary file, so that a crash never leaves
+/// a truncated one. Failures are logged.
+pub(crate) fn save<T: Serialize>(dir: &Path, id: &Uuid, record: &T) {
+    let result = (|| -> anyhow::Result<()> {
+        std::fs::create_dir_all(dir)?;
+        let path = record_path(dir, id);
+        let temp = path.with_extension("json.tmp");
+        std::fs::write(&temp, serde_json::to_vec_pretty(record)?)?;
+        std::fs::rename(&temp, &path)?;
+        Ok(())
+    })();
+    if let Err(err) = result {
+        tracing::warn!(id = %id, dir = %dir.display(), "failed to persist record: {err:#}");
+    }
+}
+
+pub(crate) fn remove(dir: &Path, id: &Uuid) {
+    let _ = std::fs::remove_file(record_path(dir, id));
+}
+
+/// Every record in `dir` that parses; others are skipped with a warning.
+pub(crate) fn load<T: DeserializeOwned>(dir: &Path) -> Vec<T> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut records = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+            continue;
+        }
+        match std::fs::read(&path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        {
+            Some(record) => records.push(record),
+            None => tracing::warn!(path = %path.display(), "skipping unreadable record"),
+        }
+    }
+    records
+}
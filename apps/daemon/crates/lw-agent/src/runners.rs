@@ -1,3 +1,8 @@
+use lw_config::agents::{
+    CONVERSATION_ID_PLACEHOLDER, NEW_CONVERSATION_ID_PLACEHOLDER, PROMPT_PLACEHOLDER,
+    WORKSPACE_PLACEHOLDER,
+};
+use lw_config::CustomAgentConfig;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::Path;
@@ -6,12 +11,16 @@ use std::sync::{OnceLock, RwLock};
 use std::thread;
 use std::time::{Duration, Instant};
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
-#[serde(rename_all = "snake_case")]
+/// Prefix used to persist and parse user-defined agent types.
+const CUSTOM_AGENT_PREFIX: &str = "custom:";
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum AgentType {
     ClaudeCode,
     Codex,
     Gemini,
+    /// A user-defined agent declared in `config.toml`, keyed by its id.
+    Custom(String),
 }
 
 impl std::fmt::Display for AgentType {
@@ -20,6 +29,7 @@ impl std::fmt::Display for AgentType {
             AgentType::ClaudeCode => write!(f, "claude_code"),
             AgentType::Codex => write!(f, "codex"),
             AgentType::Gemini => write!(f, "gemini"),
+            AgentType::Custom(id) => write!(f, "{CUSTOM_AGENT_PREFIX}{id}"),
         }
     }
 }
@@ -31,11 +41,27 @@ impl std::str::FromStr for AgentType {
             "claude_code" => Ok(AgentType::ClaudeCode),
             "codex" => Ok(AgentType::Codex),
             "gemini" => Ok(AgentType::Gemini),
-            _ => Err(format!("Unknown agent type: {}", s)),
+            _ => match s.strip_prefix(CUSTOM_AGENT_PREFIX) {
+                Some(id) if !id.is_empty() => Ok(AgentType::Custom(id.to_string())),
+                _ => Err(format!("Unknown agent type: {}", s)),
+            },
         }
     }
 }
 
+impl Serialize for AgentType {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for AgentType {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct AvailableAgent {
     pub agent_type: AgentType,
@@ -52,6 +78,32 @@ pub trait AgentRunner: Send + Sync {
     fn env(&self) -> HashMap<String, String>;
     fn is_installed(&self) -> bool;
     fn detect_version(&self) -> Option<String>;
+
+    /// Arguments prepended to `args` on a fresh start to pin the
+    /// conversation id. Only consulted for custom agents; built-ins are
+    /// handled by the session launcher.
+    fn session_id_args(&self, _conversation_id: &str) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Arguments that resume `conversation_id`, or `None` if the agent
+    /// cannot resume. Only consulted for custom agents.
+    fn resume_args(&self, _conversation_id: &str) -> Option<Vec<String>> {
+        None
+    }
+
+    /// Arguments that branch `conversation_id` into a new conversation
+    /// pinned to `new_conversation_id`, or `None` if the agent cannot fork.
+    /// Only consulted for custom agents.
+    fn fork_args(&self, _conversation_id: &str, _new_conversation_id: &str) -> Option<Vec<String>> {
+        None
+    }
+
+    /// Arguments that run `prompt` non-interactively and exit, or `None`
+    /// if the agent has no headless mode. Only consulted for custom agents.
+    fn headless_args(&self, _prompt: &str) -> Option<Vec<String>> {
+        None
+    }
 }
 
 /// Returns the shells to try for binary detection, in order.
@@ -391,6 +443,127 @@ pub fn default_runners() -> Vec<Box<dyn AgentRunner>> {
     ]
 }
 
+/// A runner for a user-defined agent declared under `[[agents]]` in
+/// `config.toml`.
+pub struct ConfigRunner {
+    agent_type: AgentType,
+    config: CustomAgentConfig,
+}
+
+impl ConfigRunner {
+    pub fn new(config: CustomAgentConfig) -> Self {
+        Self {
+            agent_type: AgentType::Custom(config.id.clone()),
+            config,
+        }
+    }
+}
+
+fn substitute(template: &[String], placeholder: &str, value: &str) -> Vec<String> {
+    template
+        .iter()
+        .map(|arg| arg.replace(placeholder, value))
+        .collect()
+}
+
+impl AgentRunner for ConfigRunner {
+    fn agent_type(&self) -> AgentType {
+        self.agent_type.clone()
+    }
+
+    fn name(&self) -> &str {
+        self.config.display_name()
+    }
+
+    fn command(&self) -> String {
+        self.config.command.clone()
+    }
+
+    fn args(&self, workspace: &Path) -> Vec<String> {
+        substitute(
+            &self.config.args,
+            WORKSPACE_PLACEHOLDER,
+            &workspace.to_string_lossy(),
+        )
+    }
+
+    fn env(&self) -> HashMap<String, String> {
+        self.config.env.clone()
+    }
+
+    fn is_installed(&self) -> bool {
+        is_command_available(&self.config.command)
+    }
+
+    fn detect_version(&self) -> Option<String> {
+        let args: Vec<&str> = self
+            .config
+            .version_args
+            .iter()
+            .map(String::as_str)
+            .collect();
+        detect_version_from_command(&self.config.command, &args)
+    }
+
+    fn session_id_args(&self, conversation_id: &str) -> Vec<String> {
+        substitute(
+            &self.config.session_id_args,
+            CONVERSATION_ID_PLACEHOLDER,
+            conversation_id,
+        )
+    }
+
+    fn resume_args(&self, conversation_id: &str) -> Option<Vec<String>> {
+        if self.config.resume_args.is_empty() {
+            return None;
+        }
+        Some(substitute(
+            &self.config.resume_args,
+            CONVERSATION_ID_PLACEHOLDER,
+            conversation_id,
+        ))
+    }
+
+    fn fork_args(&self, conversation_id: &str, new_conversation_id: &str) -> Option<Vec<String>> {
+        if self.config.fork_args.is_empty() {
+            return None;
+        }
+        let args = substitute(
+            &self.config.fork_args,
+            CONVERSATION_ID_PLACEHOLDER,
+            conversation_id,
+        );
+        Some(substitute(
+            &args,
+            NEW_CONVERSATION_ID_PLACEHOLDER,
+            new_conversation_id,
+        ))
+    }
+
+    fn headless_args(&self, prompt: &str) -> Option<Vec<String>> {
+        if self.config.headless_args.is_empty() {
+            return None;
+        }
+        Some(substitute(
+            &self.config.headless_args,
+            PROMPT_PLACEHOLDER,
+            prompt,
+        ))
+    }
+}
+
+/// Built-in runners followed by one [`ConfigRunner`] per custom agent.
+pub fn runners_with_custom(custom_agents: &[CustomAgentConfig]) -> Vec<Box<dyn AgentRunner>> {
+    let mut runners = default_runners();
+    runners.extend(
+        custom_agents
+            .iter()
+            .cloned()
+            .map(|config| Box::new(ConfigRunner::new(config)) as Box<dyn AgentRunner>),
+    );
+    runners
+}
+
 // ---------------------------------------------------------------------------
 // Tests
 // ---------------------------------------------------------------------------
@@ -446,6 +619,20 @@ mod tests {
         );
     }
 
+    #[test]
+    fn agent_type_custom_roundtrip() {
+        let custom = AgentType::Custom("aider".to_string());
+        assert_eq!(custom.to_string(), "custom:aider");
+        assert_eq!("custom:aider".parse::<AgentType>().unwrap(), custom);
+        assert!("custom:".parse::<AgentType>().is_err());
+
+        let json = serde_json::to_string(&custom).unwrap();
+        assert_eq!(json, "\"custom:aider\"");
+        let parsed: AgentType = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, custom);
+        assert!(serde_json::from_str::<AgentType>("\"unknown\"").is_err());
+    }
+
     #[test]
     fn agent_type_hash_eq() {
         let mut set = HashSet::new();
@@ -575,6 +762,91 @@ mod tests {
         assert_eq!(types.len(), 3);
     }
 
+    fn custom_config() -> CustomAgentConfig {
+        CustomAgentConfig {
+            id: "opencode".to_string(),
+            name: Some("OpenCode".to_string()),
+            command: "__nonexistent_binary_12345__".to_string(),
+            args: vec!["--cwd".to_string(), "{workspace}".to_string()],
+            env: HashMap::from([("THEME".to_string(), "dark".to_string())]),
+            version_args: vec!["--version".to_string()],
+            session_id_args: vec!["--session".to_string(), "{conversation_id}".to_string()],
+            resume_args: vec!["--continue".to_string(), "{conversation_id}".to_string()],
+            fork_args: vec![
+                "--continue".to_string(),
+                "{conversation_id}".to_string(),
+                "--as".to_string(),
+                "{new_conversation_id}".to_string(),
+            ],
+            headless_args: vec!["run".to_string(), "{prompt}".to_string()],
+        }
+    }
+
+    #[test]
+    fn config_runner_properties() {
+        let runner = ConfigRunner::new(custom_config());
+        assert_eq!(
+            runner.agent_type(),
+            AgentType::Custom("opencode".to_string())
+        );
+        assert_eq!(runner.name(), "OpenCode");
+        assert_eq!(runner.command(), "__nonexistent_binary_12345__");
+        assert_eq!(runner.args(Path::new("/tmp/ws")), vec!["--cwd", "/tmp/ws"]);
+        assert_eq!(runner.env().get("THEME").map(String::as_str), Some("dark"));
+        assert_eq!(runner.session_id_args("c1"), vec!["--session", "c1"]);
+        assert_eq!(
+            runner.resume_args("c1"),
+            Some(vec!["--continue".to_string(), "c1".to_string()])
+        );
+        assert_eq!(
+            runner.fork_args("c1", "c2"),
+            Some(vec![
+                "--continue".to_string(),
+                "c1".to_string(),
+                "--as".to_string(),
+                "c2".to_string()
+            ])
+        );
+        assert_eq!(
+            runner.headless_args("fix it"),
+            Some(vec!["run".to_string(), "fix it".to_string()])
+        );
+        assert!(!runner.is_installed());
+        assert!(runner.detect_version().is_none());
+    }
+
+    #[test]
+    fn config_runner_without_resume_args_cannot_resume() {
+        let mut config = custom_config();
+        config.resume_args.clear();
+        let runner = ConfigRunner::new(config);
+        assert!(runner.resume_args("c1").is_none());
+        assert!(ConfigRunner::new(CustomAgentConfig {
+            fork_args: vec![],
+            ..custom_config()
+        })
+        .fork_args("c1", "c2")
+        .is_none());
+    }
+
+    #[test]
+    fn builtin_runners_have_no_custom_launch_args() {
+        assert!(ClaudeCodeRunner.session_id_args("c1").is_empty());
+        assert!(ClaudeCodeRunner.resume_args("c1").is_none());
+        assert!(ClaudeCodeRunner.fork_args("c1", "c2").is_none());
+        assert!(ClaudeCodeRunner.headless_args("hi").is_none());
+    }
+
+    #[test]
+    fn runners_with_custom_appends_after_builtins() {
+        let runners = runners_with_custom(&[custom_config()]);
+        assert_eq!(runners.len(), 4);
+        assert_eq!(
+            runners[3].agent_type(),
+            AgentType::Custom("opencode".to_string())
+        );
+    }
+
     #[test]
     fn lookup_binary_in_path_finds_executable_in_given_path() {
         let dir = std::env::temp_dir().join(format!("lw-agent-test-{}", uuid::Uuid::new_v4()));
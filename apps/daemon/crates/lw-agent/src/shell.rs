@@ -0,0 +1,463 @@
+//! Plain shell terminal sessions.
+//!
+//! A shell session is a `$SHELL` PTY opened in a workspace. It shares the
+//! `PtyManager` with agent sessions (so `/api/v1/term/{id}` can stream it)
+//! but has no conversation id, resume semantics or activity tracking. After
+//! a daemon restart persisted shells come back as `Restored` handles and a
+//! fresh shell is spawned lazily on the next attach.
+
+use lw_pty::{PtyManager, PtySession};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ShellStatus {
+    Running,
+    Stopped,
+    Restored,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ShellHandle {
+    pub session_id: Uuid,
+    pub workspace_path: PathBuf,
+    pub shell: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub custom_name: Option<String>,
+    pub pinned: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort_order: Option<i32>,
+    pub status: ShellStatus,
+    #[serde(skip_serializing)]
+    pub process_id: Option<u32>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Minimal info about a persisted shell from `workspace.json`.
+#[derive(Clone)]
+pub struct PersistedShellInfo {
+    pub session_id: Uuid,
+    pub workspace_path: PathBuf,
+    pub shell: String,
+    pub custom_name: Option<String>,
+    pub pinned: bool,
+    pub sort_order: Option<i32>,
+    pub created_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub pid: Option<u32>,
+}
+
+/// Returns the user's login shell, falling back to `/bin/sh`.
+pub fn default_shell() -> String {
+    #[cfg(windows)]
+    {
+        std::env::var("COMSPEC").unwrap_or_else(|_| "cmd.exe".to_string())
+    }
+    #[cfg(not(windows))]
+    {
+        std::env::var("SHELL")
+            .ok()
+            .filter(|shell| !shell.trim().is_empty())
+            .unwrap_or_else(|| "/bin/sh".to_string())
+    }
+}
+
+/// Shells that understand `-l` get started as login shells so profile
+/// PATH additions are visible even when the daemon has a sparse environment.
+fn shell_args(shell: &str) -> Vec<String> {
+    let basename = shell.rsplit('/').next().unwrap_or(shell);
+    if matches!(basename, "sh" | "bash" | "zsh" | "dash" | "ksh" | "fish") {
+        vec!["-l".to_string()]
+    } else {
+        Vec::new()
+    }
+}
+
+pub(crate) fn shell_env() -> Vec<(String, String)> {
+    vec![
+        ("TERM".to_string(), "xterm-256color".to_string()),
+        ("COLORTERM".to_string(), "truecolor".to_string()),
+    ]
+}
+
+fn normalized_name(custom_name: Option<String>) -> Option<String> {
+    custom_name
+        .as_deref()
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(ToOwned::to_owned)
+}
+
+pub struct ShellManager {
+    pty_manager: Arc<PtyManager>,
+    handles: RwLock<HashMap<Uuid, ShellHandle>>,
+}
+
+impl ShellManager {
+    pub fn new(pty_manager: Arc<PtyManager>, persisted_shells: Vec<PersistedShellInfo>) -> Self {
+        let handles = persisted_shells
+            .into_iter()
+            .map(|persisted| {
+                (
+                    persisted.session_id,
+                    ShellHandle {
+                        session_id: persisted.session_id,
+                        workspace_path: persisted.workspace_path,
+                        shell: persisted.shell,
+                        custom_name: normalized_name(persisted.custom_name),
+                        pinned: persisted.pinned,
+                        sort_order: persisted.sort_order,
+                        status: ShellStatus::Restored,
+                        process_id: persisted.pid,
+                        created_at: persisted.created_at.unwrap_or_else(chrono::Utc::now),
+                    },
+                )
+            })
+            .collect();
+        Self {
+            pty_manager,
+            handles: RwLock::new(handles),
+        }
+    }
+
+    async fn spawn(
+        &self,
+        session_id: Uuid,
+        shell: &str,
+        workspace_path: &Path,
+    ) -> anyhow::Result<Arc<PtySession>> {
+        let args = shell_args(shell);
+        let args_refs: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
+        let session = self
+            .pty_manager
+            .create(
+                session_id,
+                shell,
+                &args_refs,
+                workspace_path,
+                shell_env(),
+                (120, 40),
+            )
+            .await?;
+        Ok(session)
+    }
+
+    pub async fn start_session(
+        &self,
+        workspace_path: PathBuf,
+        shell: Option<String>,
+        custom_name: Option<String>,
+        scrollback_dir: Option<PathBuf>,
+    ) -> anyhow::Result<(Uuid, Arc<PtySession>)> {
+        let shell = shell
+            .filter(|shell| !shell.trim().is_empty())
+            .unwrap_or_else(default_shell);
+        let session_id = Uuid::new_v4();
+        if let Some(dir) = scrollback_dir {
+            self.pty_manager
+                .set_output_log_dir(session_id, dir.join(session_id.to_string()));
+        }
+        let session = self.spawn(session_id, &shell, &workspace_path).await?;
+
+        let handle = ShellHandle {
+            session_id,
+            workspace_path,
+            shell,
+            custom_name: normalized_name(custom_name),
+            pinned: false,
+            sort_order: None,
+            status: ShellStatus::Running,
+            process_id: session.child_pid,
+            created_at: chrono::Utc::now(),
+        };
+        self.handles.write().await.insert(session_id, handle);
+        Ok((session_id, session))
+    }
+
+    pub async fn is_shell_session(&self, session_id: &Uuid) -> bool {
+        self.handles.read().await.contains_key(session_id)
+    }
+
+    /// Returns the live PTY for a shell, spawning a fresh shell for handles
+    /// restored from a previous daemon run.
+    pub async fn ensure_pty_attached(&self, session_id: &Uuid) -> anyhow::Result<Arc<PtySession>> {
+        let handle = self
+            .get_handle(session_id)
+            .await
+            .ok_or_else(|| anyhow::anyhow!("Session not found"))?;
+
+        if let Ok(existing) = self.pty_manager.get(session_id).await {
+            if !existing.is_stopped() {
+                return Ok(existing);
+            }
+        }
+
+        if handle.status != ShellStatus::Restored {
+            anyhow::bail!("Shell session is not running");
+        }
+
+        if let Some(old_pid) = handle.process_id {
+            if crate::process::is_process_alive(old_pid) {
+                crate::process::terminate_process(old_pid);
+            }
+        }
+        let _ = self.pty_manager.remove(session_id).await;
+        let session = self
+            .spawn(*session_id, &handle.shell, &handle.workspace_path)
+            .await?;
+        if let Some(h) = self.handles.write().await.get_mut(session_id) {
+            h.status = ShellStatus::Running;
+            h.process_id = session.child_pid;
+        }
+        Ok(session)
+    }
+
+    pub async fn stop_session(&self, session_id: &Uuid) -> anyhow::Result<()> {
+        let handle = self
+            .handles
+            .write()
+            .await
+            .remove(session_id)
+            .ok_or_else(|| anyhow::anyhow!("Session not found"))?;
+        if let Ok(session) = self.pty_manager.get(session_id).await {
+            let _ = session.kill().await;
+        }
+        if handle.status == ShellStatus::Restored {
+            if let Some(pid) = handle.process_id {
+                if crate::process::is_process_alive(pid) {
+                    crate::process::terminate_process(pid);
+                }
+            }
+        }
+        let _ = self.pty_manager.remove(session_id).await;
+        self.pty_manager.remove_output_log(session_id);
+        Ok(())
+    }
+
+    /// Marks running shells whose PTY has exited as stopped.
+    async fn reconcile_statuses(&self) {
+        let mut handles = self.handles.write().await;
+        for handle in handles.values_mut() {
+            if handle.status != ShellStatus::Running {
+                continue;
+            }
+            let alive = match self.pty_manager.get(&handle.session_id).await {
+                Ok(session) => !session.is_stopped(),
+                Err(_) => false,
+            };
+            if !alive {
+                handle.status = ShellStatus::Stopped;
+                handle.process_id = None;
+            }
+        }
+    }
+
+    pub async fn get_handle(&self, session_id: &Uuid) -> Option<ShellHandle> {
+        self.reconcile_statuses().await;
+        self.handles.read().await.get(session_id).cloned()
+    }
+
+    pub async fn list_sessions(&self) -> Vec<ShellHandle> {
+        self.reconcile_statuses().await;
+        self.handles.read().await.values().cloned().collect()
+    }
+
+    pub async fn input_session(&self, session_id: &Uuid, data: &[u8]) -> anyhow::Result<()> {
+        let session = match self.pty_manager.get(session_id).await {
+            Ok(session) if !session.is_stopped() => session,
+            _ => self.ensure_pty_attached(session_id).await?,
+        };
+        session.write(data).await?;
+        Ok(())
+    }
+
+    pub async fn rename_session(&self, session_id: &Uuid, custom_name: Option<String>) -> bool {
+        let mut handles = self.handles.write().await;
+        let Some(handle) = handles.get_mut(session_id) else {
+            return false;
+        };
+        handle.custom_name = normalized_name(custom_name);
+        true
+    }
+
+    pub async fn update_session_settings(
+        &self,
+        session_id: &Uuid,
+        pinned: Option<bool>,
+        sort_order: Option<Option<i32>>,
+    ) -> bool {
+        let mut handles = self.handles.write().await;
+        let Some(handle) = handles.get_mut(session_id) else {
+            return false;
+        };
+        if let Some(pinned) = pinned {
+            handle.pinned = pinned;
+        }
+        if let Some(sort_order) = sort_order {
+            handle.sort_order = sort_order;
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn persisted(session_id: Uuid) -> PersistedShellInfo {
+        PersistedShellInfo {
+            session_id,
+            workspace_path: std::env::temp_dir(),
+            shell: "/bin/sh".to_string(),
+            custom_name: Some("  tests  ".to_string()),
+            pinned: true,
+            sort_order: Some(2),
+            created_at: None,
+            pid: None,
+        }
+    }
+
+    #[test]
+    fn shell_args_uses_login_flag_for_known_shells() {
+        assert_eq!(shell_args("/bin/bash"), vec!["-l"]);
+        assert_eq!(shell_args("zsh"), vec!["-l"]);
+        assert!(shell_args("/usr/bin/nu").is_empty());
+    }
+
+    #[test]
+    fn default_shell_is_not_empty() {
+        assert!(!default_shell().is_empty());
+    }
+
+    #[test]
+    fn shell_status_lowercase_serialization() {
+        assert_eq!(
+            serde_json::to_string(&ShellStatus::Restored).unwrap(),
+            "\"restored\""
+        );
+    }
+
+    #[tokio::test]
+    async fn new_hydrates_persisted_shells_as_restored() {
+        let id = Uuid::new_v4();
+        let manager = ShellManager::new(Arc::new(PtyManager::new()), vec![persisted(id)]);
+        let handle = manager.get_handle(&id).await.unwrap();
+        assert_eq!(handle.status, ShellStatus::Restored);
+        assert_eq!(handle.custom_name.as_deref(), Some("tests"));
+        assert!(handle.pinned);
+        assert_eq!(handle.sort_order, Some(2));
+    }
+
+    #[tokio::test]
+    async fn rename_and_settings_update_handle() {
+        let id = Uuid::new_v4();
+        let manager = ShellManager::new(Arc::new(PtyManager::new()), vec![persisted(id)]);
+        assert!(manager.rename_session(&id, Some("build".into())).await);
+        assert!(
+            manager
+                .update_session_settings(&id, Some(false), Some(None))
+                .await
+        );
+        let handle = manager.get_handle(&id).await.unwrap();
+        assert_eq!(handle.custom_name.as_deref(), Some("build"));
+        assert!(!handle.pinned);
+        assert_eq!(handle.sort_order, None);
+
+        let missing = Uuid::new_v4();
+        assert!(!manager.rename_session(&missing, None).await);
+        assert!(!manager.update_session_settings(&missing, None, None).await);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn start_input_and_stop_shell_session() {
+        let manager = ShellManager::new(Arc::new(PtyManager::new()), vec![]);
+        let (id, session) = manager
+            .start_session(std::env::temp_dir(), Some("/bin/sh".into()), None, None)
+            .await
+            .unwrap();
+        let handle = manager.get_handle(&id).await.unwrap();
+        assert_eq!(handle.status, ShellStatus::Running);
+        assert_eq!(handle.shell, "/bin/sh");
+
+        let mut rx = session.subscribe();
+        manager
+            .input_session(&id, b"echo shell-ok\n")
+            .await
+            .unwrap();
+        let mut seen = String::new();
+        let deadline = tokio::time::Instant::now() + std::time::Duration::from_secs(5);
+        while !seen.contains("shell-ok\r\n") && tokio::time::Instant::now() < deadline {
+            if let Ok(Ok(chunk)) =
+                tokio::time::timeout(std::time::Duration::from_millis(200), rx.recv()).await
+            {
+                seen.push_str(&String::from_utf8_lossy(&chunk));
+            }
+        }
+        assert!(seen.contains("shell-ok"));
+
+        manager.stop_session(&id).await.unwrap();
+        assert!(manager.get_handle(&id).await.is_none());
+        assert!(manager.stop_session(&id).await.is_err());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn restored_shell_spawns_on_attach() {
+        let id = Uuid::new_v4();
+        let manager = ShellManager::new(Arc::new(PtyManager::new()), vec![persisted(id)]);
+        let session = manager.ensure_pty_attached(&id).await.unwrap();
+        assert!(!session.is_stopped());
+        let handle = manager.get_handle(&id).await.unwrap();
+        assert_eq!(handle.status, ShellStatus::Running);
+        manager.stop_session(&id).await.unwrap();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn scrollback_survives_daemon_restart() {
+        let scrollback = tempfile::TempDir::new().unwrap();
+        let first_pty = Arc::new(PtyManager::new());
+        let manager = ShellManager::new(first_pty.clone(), vec![]);
+        let (id, session) = manager
+            .start_session(
+                std::env::temp_dir(),
+                Some("/bin/sh".into()),
+                None,
+                Some(scrollback.path().to_path_buf()),
+            )
+            .await
+            .unwrap();
+        manager
+            .input_session(&id, b"echo before-restart\n")
+            .await
+            .unwrap();
+        let deadline = tokio::time::Instant::now() + std::time::Duration::from_secs(5);
+        while tokio::time::Instant::now() < deadline {
+            let (data, ..) = session.output_slice_before(None, 1 << 20);
+            if String::from_utf8_lossy(&data).contains("before-restart\r\n") {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        }
+        first_pty.kill_all().await;
+
+        let pty = Arc::new(PtyManager::new());
+        pty.set_output_log_dir(id, scrollback.path().join(id.to_string()));
+        let (data, start, ..) = pty.output_slice_before(&id, None, 1 << 20).await.unwrap();
+        assert_eq!(start, 0);
+        assert!(String::from_utf8_lossy(&data).contains("before-restart"));
+
+        let manager = ShellManager::new(pty, vec![persisted(id)]);
+        let session = manager.ensure_pty_attached(&id).await.unwrap();
+        let (data, ..) = session.output_slice_before(None, 1 << 20);
+        assert!(String::from_utf8_lossy(&data).contains("before-restart"));
+
+        manager.stop_session(&id).await.unwrap();
+        assert!(!scrollback.path().join(id.to_string()).exists());
+    }
+}
@@ -0,0 +1,780 @@
+//! Headless one-shot agent tasks.
+//!
+//! A task runs an agent non-interactively (`claude -p`, `codex exec`,
+//! `gemini -p`) with piped stdio instead of a PTY. Output is streamed as
+//! [`TaskEvent`]s while it runs; once the process exits the record captures
+//! the exit code, duration and the git diff the run produced. Records are
+//! persisted as one JSON file per task, and tasks that were still running
+//! when the daemon stopped come back as `Interrupted`. Only the latest
+//! [`TASK_HISTORY_LIMIT`] finished tasks are kept.
+
+mod diff;
+
+use crate::record_dir;
+use crate::runners::{runners_with_custom, AgentRunner, AgentType};
+use chrono::{DateTime, Utc};
+use lw_config::CustomAgentConfig;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::{Arc, Mutex as StdMutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::Command;
+use tokio::sync::{broadcast, mpsc, Notify, RwLock};
+use uuid::Uuid;
+
+/// Output kept per stream on a task record; older output is dropped first.
+pub const TASK_OUTPUT_MAX_BYTES: usize = 1024 * 1024;
+
+/// Finished tasks kept; older records are deleted.
+pub const TASK_HISTORY_LIMIT: usize = 50;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskStatus {
+    Running,
+    Succeeded,
+    Failed,
+    Cancelled,
+    /// The daemon stopped while the task was running.
+    Interrupted,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskStream {
+    Stdout,
+    Stderr,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskRecord {
+    pub task_id: Uuid,
+    pub agent_type: AgentType,
+    pub workspace_path: PathBuf,
+    pub prompt: String,
+    pub status: TaskStatus,
+    pub created_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    #[serde(default)]
+    pub stdout: String,
+    #[serde(default)]
+    pub stderr: String,
+    /// Older output was dropped to stay within [`TASK_OUTPUT_MAX_BYTES`].
+    #[serde(default)]
+    pub output_truncated: bool,
+    /// Changes the task made to the workspace; `None` outside git repos.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub diff: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// A [`TaskRecord`] without its output, for listings and events.
+#[derive(Debug, Clone, Serialize)]
+pub struct TaskSummary {
+    pub task_id: Uuid,
+    pub agent_type: AgentType,
+    pub workspace_path: PathBuf,
+    pub prompt: String,
+    pub status: TaskStatus,
+    pub created_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    pub has_diff: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl TaskRecord {
+    pub fn summary(&self) -> TaskSummary {
+        TaskSummary {
+            task_id: self.task_id,
+            agent_type: self.agent_type.clone(),
+            workspace_path: self.workspace_path.clone(),
+            prompt: self.prompt.clone(),
+            status: self.status,
+            created_at: self.created_at,
+            finished_at: self.finished_at,
+            duration_ms: self.duration_ms,
+            exit_code: self.exit_code,
+            has_diff: self.diff.as_deref().is_some_and(|diff| !diff.is_empty()),
+            error: self.error.clone(),
+        }
+    }
+
+    fn append_output(&mut self, stream: TaskStream, chunk: &str) {
+        let buffer = match stream {
+            TaskStream::Stdout => &mut self.stdout,
+            TaskStream::Stderr => &mut self.stderr,
+        };
+        buffer.push_str(chunk);
+        if buffer.len() > TASK_OUTPUT_MAX_BYTES {
+            let mut cut = buffer.len() - TASK_OUTPUT_MAX_BYTES;
+            while !buffer.is_char_boundary(cut) {
+                cut += 1;
+            }
+            buffer.drain(..cut);
+            self.output_truncated = true;
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TaskEvent {
+    Started {
+        task: TaskSummary,
+    },
+    Output {
+        task_id: Uuid,
+        stream: TaskStream,
+        data: String,
+    },
+    Finished {
+        task: TaskSummary,
+    },
+}
+
+/// Arguments that run `prompt` once and exit, or `None` when the agent has
+/// no headless mode. Built-ins may edit files in the workspace without
+/// asking, since nobody is there to answer a prompt.
+fn headless_args(runner: &dyn AgentRunner, prompt: &str) -> Option<Vec<String>> {
+    let prompt = prompt.to_string();
+    match runner.agent_type() {
+        AgentType::ClaudeCode => Some(vec![
+            "-p".to_string(),
+            "--permission-mode".to_string(),
+            "acceptEdits".to_string(),
+            "--".to_string(),
+            prompt,
+        ]),
+        AgentType::Codex => Some(vec![
+            "exec".to_string(),
+            "--full-auto".to_string(),
+            "--".to_string(),
+            prompt,
+        ]),
+        AgentType::Gemini => Some(vec![
+            "--approval-mode".to_string(),
+            "auto_edit".to_string(),
+            "-p".to_string(),
+            prompt,
+        ]),
+        AgentType::Custom(_) => runner.headless_args(&prompt),
+    }
+}
+
+/// Environment for a task: the agent's PTY environment, minus colour, since
+/// output goes to a record rather than a terminal.
+fn task_env(runner: &dyn AgentRunner) -> Vec<(String, String)> {
+    let mut env: Vec<(String, String)> = crate::manager::session::build_env(runner)
+        .into_iter()
+        .filter(|(key, _)| key != "TERM" && key != "COLORTERM")
+        .collect();
+    env.push(("TERM".to_string(), "dumb".to_string()));
+    env.push(("NO_COLOR".to_string(), "1".to_string()));
+    env
+}
+
+pub struct TaskManager {
+    runners: Vec<Box<dyn AgentRunner>>,
+    records: Arc<RwLock<HashMap<Uuid, TaskRecord>>>,
+    cancels: Arc<StdMutex<HashMap<Uuid, Arc<Notify>>>>,
+    events_tx: broadcast::Sender<TaskEvent>,
+    store_dir: Option<PathBuf>,
+}
+
+impl TaskManager {
+    /// Loads persisted records from `store_dir`; without one, records live
+    /// in memory only.
+    pub fn new(custom_agents: &[CustomAgentConfig], store_dir: Option<PathBuf>) -> Self {
+        let records = store_dir.as_deref().map(load_records).unwrap_or_default();
+        let (events_tx, _) = broadcast::channel(512);
+        Self {
+            runners: runners_with_custom(custom_agents),
+            records: Arc::new(RwLock::new(records)),
+            cancels: Arc::new(StdMutex::new(HashMap::new())),
+            events_tx,
+            store_dir,
+        }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<TaskEvent> {
+        self.events_tx.subscribe()
+    }
+
+    /// Spawns the agent and returns the running record. The process is
+    /// watched in the background until it exits, is cancelled or exceeds
+    /// `timeout`.
+    pub async fn start_task(
+        &self,
+        agent_type: AgentType,
+        workspace_path: PathBuf,
+        prompt: String,
+        timeout: Option<Duration>,
+    ) -> anyhow::Result<TaskRecord> {
+        let runner = self
+            .runners
+            .iter()
+            .find(|r| r.agent_type() == agent_type)
+            .ok_or_else(|| anyhow::anyhow!("Unknown agent type: {}", agent_type))?;
+        let args = headless_args(runner.as_ref(), &prompt).ok_or_else(|| {
+            anyhow::anyhow!("Agent {} does not support headless tasks", runner.name())
+        })?;
+        if !runner.is_installed() {
+            anyhow::bail!("Agent {} is not installed", runner.name());
+        }
+        let program = crate::runners::resolve_command_path(&runner.command())
+            .unwrap_or_else(|| runner.command());
+        let env = task_env(runner.as_ref());
+
+        let cwd = workspace_path.clone();
+        let baseline = tokio::task::spawn_blocking(move || diff::capture_baseline(&cwd)).await?;
+
+        let mut child = Command::new(&program)
+            .args(&args)
+            .current_dir(&workspace_path)
+            .envs(env)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()?;
+
+        let record = TaskRecord {
+            task_id: Uuid::new_v4(),
+            agent_type,
+            workspace_path,
+            prompt,
+            status: TaskStatus::Running,
+            created_at: Utc::now(),
+            finished_at: None,
+            duration_ms: None,
+            exit_code: None,
+            stdout: String::new(),
+            stderr: String::new(),
+            output_truncated: false,
+            diff: None,
+            error: None,
+        };
+        let task_id = record.task_id;
+        self.records.write().await.insert(task_id, record.clone());
+        self.persist(&record);
+        let cancel = Arc::new(Notify::new());
+        self.cancels
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(task_id, cancel.clone());
+        let _ = self.events_tx.send(TaskEvent::Started {
+            task: record.summary(),
+        });
+        tracing::info!(task_id = %task_id, program, "headless task started");
+
+        let (chunk_tx, mut chunk_rx) = mpsc::channel::<(TaskStream, String)>(256);
+        if let Some(stdout) = child.stdout.take() {
+            spawn_pipe_reader(stdout, TaskStream::Stdout, chunk_tx.clone());
+        }
+        if let Some(stderr) = child.stderr.take() {
+            spawn_pipe_reader(stderr, TaskStream::Stderr, chunk_tx);
+        }
+
+        let records = Arc::clone(&self.records);
+        let cancels = Arc::clone(&self.cancels);
+        let events_tx = self.events_tx.clone();
+        let store_dir = self.store_dir.clone();
+        let started = Instant::now();
+        tokio::spawn(async move {
+            let deadline = async {
+                match timeout {
+                    Some(timeout) => tokio::time::sleep(timeout).await,
+                    None => std::future::pending().await,
+                }
+            };
+            tokio::pin!(deadline);
+            let mut stopped: Option<(TaskStatus, Option<String>)> = None;
+
+            let append = |stream: TaskStream, data: String| {
+                let records = Arc::clone(&records);
+                let events_tx = events_tx.clone();
+                async move {
+                    if let Some(record) = records.write().await.get_mut(&task_id) {
+                        record.append_output(stream, &data);
+                    }
+                    let _ = events_tx.send(TaskEvent::Output {
+                        task_id,
+                        stream,
+                        data,
+                    });
+                }
+            };
+
+            let exit = loop {
+                tokio::select! {
+                    Some((stream, data)) = chunk_rx.recv() => append(stream, data).await,
+                    status = child.wait() => break status,
+                    _ = cancel.notified(), if stopped.is_none() => {
+                        let _ = child.start_kill();
+                        stopped = Some((TaskStatus::Cancelled, None));
+                    }
+                    _ = &mut deadline, if stopped.is_none() => {
+                        let _ = child.start_kill();
+                        let message = format!(
+                            "Timed out after {}s",
+                            timeout.unwrap_or_default().as_secs()
+                        );
+                        stopped = Some((TaskStatus::Failed, Some(message)));
+                    }
+                }
+            };
+            // Drain what is left in the pipes. Bounded, since a grandchild
+            // may keep them open after the agent itself exited.
+            while let Ok(Some((stream, data))) =
+                tokio::time::timeout(Duration::from_millis(500), chunk_rx.recv()).await
+            {
+                append(stream, data).await;
+            }
+
+            let duration_ms = u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX);
+            let diff = match baseline {
+                Some(baseline) => {
+                    let cwd = records
+                        .read()
+                        .await
+                        .get(&task_id)
+                        .map(|record| record.workspace_path.clone());
+                    match cwd {
+                        Some(cwd) => {
+                            tokio::task::spawn_blocking(move || diff::diff_since(&cwd, &baseline))
+                                .await
+                                .ok()
+                        }
+                        None => None,
+                    }
+                }
+                None => None,
+            };
+
+            let (finished, pruned) = {
+                let mut records = records.write().await;
+                let Some(record) = records.get_mut(&task_id) else {
+                    return;
+                };
+                let exit_code = exit.as_ref().ok().and_then(|status| status.code());
+                let (status, error) = match (stopped, exit) {
+                    (Some(stopped), _) => stopped,
+                    (None, Ok(status)) if status.success() => (TaskStatus::Succeeded, None),
+                    (None, Ok(_)) => (TaskStatus::Failed, None),
+                    (None, Err(err)) => (TaskStatus::Failed, Some(err.to_string())),
+                };
+                record.status = status;
+                record.error = error;
+                record.exit_code = exit_code;
+                record.finished_at = Some(Utc::now());
+                record.duration_ms = Some(duration_ms);
+                record.diff = diff;
+                let finished = record.clone();
+                (finished, prune_history(&mut records))
+            };
+            cancels
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .remove(&task_id);
+            if let Some(dir) = store_dir.as_deref() {
+                save_record(dir, &finished);
+                for task_id in &pruned {
+                    record_dir::remove(dir, task_id);
+                }
+            }
+            tracing::info!(
+                task_id = %task_id,
+                status = ?finished.status,
+                exit_code = ?finished.exit_code,
+                "headless task finished"
+            );
+            let _ = events_tx.send(TaskEvent::Finished {
+                task: finished.summary(),
+            });
+        });
+
+        Ok(record)
+    }
+
+    pub async fn get(&self, task_id: &Uuid) -> Option<TaskRecord> {
+        self.records.read().await.get(task_id).cloned()
+    }
+
+    /// All tasks, newest first.
+    pub async fn list(&self) -> Vec<TaskSummary> {
+        let mut tasks: Vec<TaskSummary> = self
+            .records
+            .read()
+            .await
+            .values()
+            .map(TaskRecord::summary)
+            .collect();
+        tasks.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        tasks
+    }
+
+    /// Kills a running task. Returns `false` if it is not running.
+    pub fn cancel(&self, task_id: &Uuid) -> bool {
+        match self
+            .cancels
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(task_id)
+        {
+            Some(cancel) => {
+                cancel.notify_one();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Deletes a finished task's record. Running tasks must be cancelled
+    /// first.
+    pub async fn remove(&self, task_id: &Uuid) -> anyhow::Result<bool> {
+        let mut records = self.records.write().await;
+        match records.get(task_id) {
+            None => return Ok(false),
+            Some(record) if record.status == TaskStatus::Running => {
+                anyhow::bail!("Task is still running")
+            }
+            Some(_) => {}
+        }
+        records.remove(task_id);
+        if let Some(dir) = self.store_dir.as_deref() {
+            record_dir::remove(dir, task_id);
+        }
+        Ok(true)
+    }
+
+    fn persist(&self, record: &TaskRecord) {
+        if let Some(dir) = self.store_dir.as_deref() {
+            save_record(dir, record);
+        }
+    }
+}
+
+fn spawn_pipe_reader<R>(pipe: R, stream: TaskStream, tx: mpsc::Sender<(TaskStream, String)>)
+where
+    R: AsyncRead + Unpin + Send + 'static,
+{
+    tokio::spawn(async move {
+        let mut reader = BufReader::new(pipe);
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            match reader.read_until(b'\n', &mut line).await {
+                Ok(0) | Err(_) => break,
+                Ok(_) => {
+                    let data = String::from_utf8_lossy(&line).into_owned();
+                    if tx.send((stream, data)).await.is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    });
+}
+
+/// Drops the oldest finished records beyond [`TASK_HISTORY_LIMIT`] and
+/// returns their ids.
+fn prune_history(records: &mut HashMap<Uuid, TaskRecord>) -> Vec<Uuid> {
+    let mut finished: Vec<(DateTime<Utc>, Uuid)> = records
+        .values()
+        .filter(|record| record.status != TaskStatus::Running)
+        .map(|record| (record.created_at, record.task_id))
+        .collect();
+    if finished.len() <= TASK_HISTORY_LIMIT {
+        return Vec::new();
+    }
+    finished.sort();
+    let excess = finished.len() - TASK_HISTORY_LIMIT;
+    finished
+        .into_iter()
+        .take(excess)
+        .map(|(_, task_id)| {
+            records.remove(&task_id);
+            task_id
+        })
+        .collect()
+}
+
+fn save_record(dir: &Path, record: &TaskRecord) {
+    record_dir::save(dir, &record.task_id, record);
+}
+
+fn load_records(dir: &Path) -> HashMap<Uuid, TaskRecord> {
+    let mut records = HashMap::new();
+    for mut record in record_dir::load::<TaskRecord>(dir) {
+        if record.status == TaskStatus::Running {
+            record.status = TaskStatus::Interrupted;
+            save_record(dir, &record);
+        }
+        records.insert(record.task_id, record);
+    }
+    for task_id in prune_history(&mut records) {
+        record_dir::remove(dir, &task_id);
+    }
+    records
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::record_dir::record_path;
+
+    fn shell_agent() -> CustomAgentConfig {
+        CustomAgentConfig {
+            id: "sh".to_string(),
+            name: None,
+            command: "sh".to_string(),
+            args: vec![],
+            env: HashMap::new(),
+            version_args: vec!["--version".to_string()],
+            session_id_args: vec![],
+            resume_args: vec![],
+            fork_args: vec![],
+            headless_args: vec!["-c".to_string(), "{prompt}".to_string()],
+        }
+    }
+
+    fn sh() -> AgentType {
+        AgentType::Custom("sh".to_string())
+    }
+
+    async fn wait_finished(rx: &mut broadcast::Receiver<TaskEvent>, task_id: Uuid) -> TaskSummary {
+        tokio::time::timeout(Duration::from_secs(10), async {
+            loop {
+                if let Ok(TaskEvent::Finished { task }) = rx.recv().await {
+                    if task.task_id == task_id {
+                        return task;
+                    }
+                }
+            }
+        })
+        .await
+        .expect("task did not finish")
+    }
+
+    #[test]
+    fn builtin_headless_args_end_with_prompt() {
+        let runners = crate::runners::default_runners();
+        for runner in &runners {
+            let args = headless_args(runner.as_ref(), "-fix it").unwrap();
+            assert_eq!(args.last().unwrap(), "-fix it");
+        }
+        let custom = crate::runners::ConfigRunner::new(CustomAgentConfig {
+            headless_args: vec![],
+            ..shell_agent()
+        });
+        assert!(headless_args(&custom, "x").is_none());
+    }
+
+    #[test]
+    fn append_output_keeps_the_tail() {
+        let mut record = TaskRecord {
+            task_id: Uuid::new_v4(),
+            agent_type: sh(),
+            workspace_path: PathBuf::from("/tmp"),
+            prompt: String::new(),
+            status: TaskStatus::Running,
+            created_at: Utc::now(),
+            finished_at: None,
+            duration_ms: None,
+            exit_code: None,
+            stdout: String::new(),
+            stderr: String::new(),
+            output_truncated: false,
+            diff: None,
+            error: None,
+        };
+        record.append_output(TaskStream::Stdout, &"a".repeat(TASK_OUTPUT_MAX_BYTES));
+        record.append_output(TaskStream::Stdout, "tail");
+        assert_eq!(record.stdout.len(), TASK_OUTPUT_MAX_BYTES);
+        assert!(record.stdout.ends_with("tail"));
+        assert!(record.output_truncated);
+        assert!(record.stderr.is_empty());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn task_captures_output_exit_code_and_diff() {
+        let store = tempfile::tempdir().unwrap();
+        let workspace = tempfile::tempdir().unwrap();
+        assert!(std::process::Command::new("git")
+            .args(["init", "-q"])
+            .current_dir(workspace.path())
+            .status()
+            .unwrap()
+            .success());
+        let manager = TaskManager::new(&[shell_agent()], Some(store.path().to_path_buf()));
+        let mut events = manager.subscribe();
+
+        let record = manager
+            .start_task(
+                sh(),
+                workspace.path().to_path_buf(),
+                "echo hello; echo oops >&2; echo new > made.txt; exit 3".to_string(),
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(record.status, TaskStatus::Running);
+        let summary = wait_finished(&mut events, record.task_id).await;
+        assert_eq!(summary.status, TaskStatus::Failed);
+        assert_eq!(summary.exit_code, Some(3));
+        assert!(summary.has_diff);
+
+        let finished = manager.get(&record.task_id).await.unwrap();
+        assert_eq!(finished.stdout, "hello\n");
+        assert_eq!(finished.stderr, "oops\n");
+        assert!(finished.diff.unwrap().contains("+new"));
+        assert!(finished.duration_ms.is_some());
+
+        // The record survives a restart.
+        let reloaded = TaskManager::new(&[shell_agent()], Some(store.path().to_path_buf()));
+        let persisted = reloaded.get(&record.task_id).await.unwrap();
+        assert_eq!(persisted.exit_code, Some(3));
+        assert_eq!(persisted.stdout, "hello\n");
+        assert_eq!(reloaded.list().await.len(), 1);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn cancel_kills_running_task() {
+        let workspace = tempfile::tempdir().unwrap();
+        let manager = TaskManager::new(&[shell_agent()], None);
+        let mut events = manager.subscribe();
+        let record = manager
+            .start_task(
+                sh(),
+                workspace.path().to_path_buf(),
+                "sleep 30".to_string(),
+                None,
+            )
+            .await
+            .unwrap();
+        assert!(manager.cancel(&record.task_id));
+        let summary = wait_finished(&mut events, record.task_id).await;
+        assert_eq!(summary.status, TaskStatus::Cancelled);
+        assert!(!summary.has_diff);
+        assert!(!manager.cancel(&record.task_id));
+        assert!(manager.remove(&record.task_id).await.unwrap());
+        assert!(manager.get(&record.task_id).await.is_none());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn timeout_fails_task() {
+        let workspace = tempfile::tempdir().unwrap();
+        let manager = TaskManager::new(&[shell_agent()], None);
+        let mut events = manager.subscribe();
+        let record = manager
+            .start_task(
+                sh(),
+                workspace.path().to_path_buf(),
+                "sleep 30".to_string(),
+                Some(Duration::from_millis(100)),
+            )
+            .await
+            .unwrap();
+        assert!(manager.remove(&record.task_id).await.is_err());
+        let summary = wait_finished(&mut events, record.task_id).await;
+        assert_eq!(summary.status, TaskStatus::Failed);
+        assert!(summary.error.unwrap().contains("Timed out"));
+    }
+
+    #[test]
+    fn running_records_load_as_interrupted() {
+        let store = tempfile::tempdir().unwrap();
+        let task_id = Uuid::new_v4();
+        std::fs::write(
+            record_path(store.path(), &task_id),
+            serde_json::json!({
+                "task_id": task_id,
+                "agent_type": "codex",
+                "workspace_path": "/repo",
+                "prompt": "bump deps",
+                "status": "running",
+                "created_at": Utc::now(),
+            })
+            .to_string(),
+        )
+        .unwrap();
+        std::fs::write(store.path().join("junk.json"), "{").unwrap();
+
+        let records = load_records(store.path());
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[&task_id].status, TaskStatus::Interrupted);
+        // Persisted, so the next load agrees.
+        assert_eq!(
+            load_records(store.path())[&task_id].status,
+            TaskStatus::Interrupted
+        );
+    }
+
+    #[test]
+    fn load_keeps_only_the_latest_finished_records() {
+        let store = tempfile::tempdir().unwrap();
+        let write = |status: &str, age_minutes: i64| {
+            let task_id = Uuid::new_v4();
+            std::fs::write(
+                record_path(store.path(), &task_id),
+                serde_json::json!({
+                    "task_id": task_id,
+                    "agent_type": "codex",
+                    "workspace_path": "/repo",
+                    "prompt": "bump deps",
+                    "status": status,
+                    "created_at": Utc::now() - chrono::Duration::minutes(age_minutes),
+                })
+                .to_string(),
+            )
+            .unwrap();
+            task_id
+        };
+        let oldest = write("succeeded", 1000);
+        let interrupted = write("running", 999);
+        let kept: Vec<Uuid> = (0..TASK_HISTORY_LIMIT as i64 - 1)
+            .map(|age| write("failed", age))
+            .collect();
+
+        let records = load_records(store.path());
+        assert_eq!(records.len(), TASK_HISTORY_LIMIT);
+        assert!(!records.contains_key(&oldest));
+        assert!(!record_path(store.path(), &oldest).exists());
+        assert!(records.contains_key(&interrupted));
+        assert!(kept.iter().all(|task_id| records.contains_key(task_id)));
+    }
+
+    #[tokio::test]
+    async fn start_rejects_agents_without_headless_mode() {
+        let manager = TaskManager::new(
+            &[CustomAgentConfig {
+                headless_args: vec![],
+                ..shell_agent()
+            }],
+            None,
+        );
+        let err = manager
+            .start_task(sh(), PathBuf::from("/tmp"), "x".to_string(), None)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("does not support headless"));
+    }
+}
@@ -0,0 +1,167 @@
+//! Git diff of what a task changed in its working directory.
+
+use std::collections::HashSet;
+use std::path::Path;
+use std::process::{Command, Output};
+
+/// Tree object of an empty repository, for repos without a commit yet.
+const EMPTY_TREE: &str = "4b825dc642cb6eb9a060e54bf8d69288fbee4904";
+
+/// Largest diff kept on a task record.
+pub(crate) const TASK_DIFF_MAX_BYTES: usize = 1024 * 1024;
+
+/// Working-tree state before a task ran.
+pub(crate) struct Baseline {
+    /// Commit-ish capturing tracked files, including uncommitted edits.
+    base: String,
+    /// Untracked files that already existed.
+    untracked: HashSet<String>,
+}
+
+fn git(cwd: &Path, args: &[&str]) -> Option<Output> {
+    Command::new("git")
+        .args(args)
+        .current_dir(cwd)
+        .output()
+        .ok()
+}
+
+fn git_stdout(cwd: &Path, args: &[&str]) -> Option<String> {
+    git(cwd, args)
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn untracked_files(cwd: &Path) -> HashSet<String> {
+    git_stdout(cwd, &["ls-files", "--others", "--exclude-standard", "-z"])
+        .map(|out| {
+            out.split('\0')
+                .filter(|path| !path.is_empty())
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Snapshots `cwd` without touching the index or working tree. `None` when
+/// it is not inside a git repository.
+pub(crate) fn capture_baseline(cwd: &Path) -> Option<Baseline> {
+    git_stdout(cwd, &["rev-parse", "--is-inside-work-tree"])?;
+    // `stash create` records a dirty tree as a dangling commit and prints
+    // nothing when the tree is clean.
+    let stash = git_stdout(cwd, &["stash", "create"]).unwrap_or_default();
+    let base = match stash.trim() {
+        "" => git_stdout(cwd, &["rev-parse", "--verify", "--quiet", "HEAD"])
+            .map(|head| head.trim().to_string())
+            .unwrap_or_else(|| EMPTY_TREE.to_string()),
+        stash => stash.to_string(),
+    };
+    Some(Baseline {
+        base,
+        untracked: untracked_files(cwd),
+    })
+}
+
+/// Unified diff from `baseline` to the current working tree, including
+/// files the task created. Capped at [`TASK_DIFF_MAX_BYTES`].
+pub(crate) fn diff_since(cwd: &Path, baseline: &Baseline) -> String {
+    let mut diff = git_stdout(
+        cwd,
+        &["diff", "--no-color", "--no-ext-diff", &baseline.base, "--"],
+    )
+    .unwrap_or_default();
+
+    let mut created: Vec<String> = untracked_files(cwd)
+        .into_iter()
+        .filter(|path| !baseline.untracked.contains(path))
+        .collect();
+    created.sort();
+    for path in created {
+        // `--no-index` exits 1 when the files differ, which they always do.
+        if let Some(output) = git(
+            cwd,
+            &[
+                "diff",
+                "--no-color",
+                "--no-ext-diff",
+                "--no-index",
+                "--",
+                "/dev/null",
+                &path,
+            ],
+        ) {
+            diff.push_str(&String::from_utf8_lossy(&output.stdout));
+        }
+    }
+
+    if diff.len() > TASK_DIFF_MAX_BYTES {
+        let mut end = TASK_DIFF_MAX_BYTES;
+        while !diff.is_char_boundary(end) {
+            end -= 1;
+        }
+        diff.truncate(end);
+        diff.push_str("\n[diff truncated]\n");
+    }
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn init_repo(dir: &Path) {
+        for args in [
+            vec!["init", "-q"],
+            vec!["config", "user.email", "t@example.com"],
+            vec!["config", "user.name", "t"],
+        ] {
+            assert!(git(dir, &args).unwrap().status.success());
+        }
+    }
+
+    #[test]
+    fn not_a_repo_has_no_baseline() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(capture_baseline(dir.path()).is_none());
+    }
+
+    #[test]
+    fn diff_covers_edits_and_new_files_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        init_repo(root);
+        fs::write(root.join("a.txt"), "one\n").unwrap();
+        git(root, &["add", "a.txt"]).unwrap();
+        git(root, &["commit", "-qm", "init"]).unwrap();
+        // Pre-existing uncommitted state must not show up in the diff.
+        fs::write(root.join("a.txt"), "one\ntwo\n").unwrap();
+        fs::write(root.join("notes.txt"), "scratch\n").unwrap();
+
+        let baseline = capture_baseline(root).unwrap();
+        fs::write(root.join("a.txt"), "one\ntwo\nthree\n").unwrap();
+        fs::write(root.join("new.txt"), "hello\n").unwrap();
+
+        let diff = diff_since(root, &baseline);
+        assert!(diff.contains("+three"));
+        assert!(!diff.contains("+two"));
+        assert!(diff.contains("new.txt"));
+        assert!(diff.contains("+hello"));
+        assert!(!diff.contains("notes.txt"));
+        // Nothing was staged or stashed.
+        assert_eq!(
+            git_stdout(root, &["diff", "--cached", "--name-only"]).unwrap(),
+            ""
+        );
+        assert_eq!(git_stdout(root, &["stash", "list"]).unwrap(), "");
+    }
+
+    #[test]
+    fn diff_in_repo_without_commits() {
+        let dir = tempfile::tempdir().unwrap();
+        init_repo(dir.path());
+        let baseline = capture_baseline(dir.path()).unwrap();
+        fs::write(dir.path().join("first.txt"), "x\n").unwrap();
+        assert!(diff_since(dir.path(), &baseline).contains("first.txt"));
+    }
+}
@@ -0,0 +1,456 @@
+//! Session output exported for use outside Loopwire: asciinema recordings
+//! that replay at the original pace, standalone HTML keeping the colors, and
+//! plain text.
+
+use crate::terminal_text::normalize_terminal_bytes_for_analysis;
+use lw_pty::OutputRecording;
+use serde::Deserialize;
+use std::fmt::Write as _;
+use std::ops::Range;
+
+/// `(cols, rows)` assumed for recordings taken without a running PTY.
+pub const DEFAULT_EXPORT_SIZE: (u16, u16) = (80, 24);
+
+/// Lines of scrollback kept when laying out HTML; older lines are dropped.
+const HTML_SCROLLBACK_LINES: usize = 100_000;
+
+const DEFAULT_FG: &str = "#d4d4d4";
+const DEFAULT_BG: &str = "#1e1e1e";
+const ANSI_COLORS: [&str; 16] = [
+    "#000000", "#cd3131", "#0dbc79", "#e5e510", "#2472c8", "#bc3fbc", "#11a8cd", "#e5e5e5",
+    "#666666", "#f14c4c", "#23d18b", "#f5f543", "#3b8eea", "#d670d6", "#29b8db", "#ffffff",
+];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// asciicast v2, playable with `asciinema play`.
+    #[default]
+    Cast,
+    Html,
+    Text,
+}
+
+impl ExportFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Cast => "cast",
+            Self::Html => "html",
+            Self::Text => "txt",
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Cast => "application/x-asciicast",
+            Self::Html => "text/html; charset=utf-8",
+            Self::Text => "text/plain; charset=utf-8",
+        }
+    }
+}
+
+impl std::str::FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "cast" => Ok(Self::Cast),
+            "html" => Ok(Self::Html),
+            "text" => Ok(Self::Text),
+            other => Err(format!(
+                "unknown export format '{other}' (expected cast, html or text)"
+            )),
+        }
+    }
+}
+
+pub fn export_recording(recording: &OutputRecording, format: ExportFormat, title: &str) -> Vec<u8> {
+    match format {
+        ExportFormat::Cast => asciicast(recording, title),
+        ExportFormat::Html => html(recording, title),
+        ExportFormat::Text => normalize_terminal_bytes_for_analysis(&recording.data),
+    }
+    .into_bytes()
+}
+
+/// Output ranges of `recording.data` paired with when they were read.
+/// Output logged before timings were recorded plays at the start.
+fn timed_ranges(recording: &OutputRecording) -> Vec<(u64, Range<usize>)> {
+    let len = recording.data.len();
+    let relative = |offset: usize| offset.saturating_sub(recording.start_offset).min(len);
+    let base_ms = recording.timings.first().map_or(0, |timing| timing.unix_ms);
+    let mut ranges = Vec::with_capacity(recording.timings.len() + 1);
+    let first = recording
+        .timings
+        .first()
+        .map_or(len, |timing| relative(timing.offset));
+    if first > 0 {
+        ranges.push((base_ms, 0..first));
+    }
+    for (index, timing) in recording.timings.iter().enumerate() {
+        let end = recording
+            .timings
+            .get(index + 1)
+            .map_or(len, |next| relative(next.offset));
+        ranges.push((timing.unix_ms, relative(timing.offset)..end));
+    }
+    ranges
+}
+
+/// Length of a UTF-8 sequence cut off at the end of `bytes`, so it can be
+/// carried over to the next event instead of becoming replacement chars.
+fn incomplete_utf8_tail(bytes: &[u8]) -> usize {
+    for back in 1..=bytes.len().min(3) {
+        let byte = bytes[bytes.len() - back];
+        if byte & 0xC0 != 0x80 {
+            let needed = match byte {
+                0xC0..=0xDF => 2,
+                0xE0..=0xEF => 3,
+                0xF0..=0xF7 => 4,
+                _ => 1,
+            };
+            return if needed > back { back } else { 0 };
+        }
+    }
+    0
+}
+
+fn asciicast(recording: &OutputRecording, title: &str) -> String {
+    let (width, height) = recording.size.unwrap_or(DEFAULT_EXPORT_SIZE);
+    let mut header = serde_json::json!({
+        "version": 2,
+        "width": width,
+        "height": height,
+        "title": title,
+    });
+    if let Some(first) = recording.timings.first() {
+        header["timestamp"] = (first.unix_ms / 1000).into();
+    }
+    let mut out = header.to_string();
+    out.push('\n');
+
+    let ranges = timed_ranges(recording);
+    let base_ms = ranges.first().map_or(0, |(unix_ms, _)| *unix_ms);
+    let mut last_ms = base_ms;
+    let mut pending = Vec::new();
+    for (index, (unix_ms, range)) in ranges.iter().enumerate() {
+        pending.extend_from_slice(&recording.data[range.clone()]);
+        let keep = if index + 1 == ranges.len() {
+            0
+        } else {
+            incomplete_utf8_tail(&pending)
+        };
+        let tail = pending.split_off(pending.len() - keep);
+        let text = String::from_utf8_lossy(&pending).into_owned();
+        pending = tail;
+        if text.is_empty() {
+            continue;
+        }
+        // Wall clock jumps backwards must not reorder events.
+        last_ms = last_ms.max(*unix_ms);
+        let seconds = (last_ms - base_ms) as f64 / 1000.0;
+        out.push_str(&serde_json::json!([seconds, "o", text]).to_string());
+        out.push('\n');
+    }
+    out
+}
+
+#[derive(Clone, Copy, PartialEq)]
+struct CellStyle {
+    fg: vt100::Color,
+    bg: vt100::Color,
+    bold: bool,
+    dim: bool,
+    italic: bool,
+    underline: bool,
+    inverse: bool,
+}
+
+impl CellStyle {
+    fn of(cell: &vt100::Cell) -> Self {
+        Self {
+            fg: cell.fgcolor(),
+            bg: cell.bgcolor(),
+            bold: cell.bold(),
+            dim: cell.dim(),
+            italic: cell.italic(),
+            underline: cell.underline(),
+            inverse: cell.inverse(),
+        }
+    }
+
+    fn css(&self) -> String {
+        let (mut fg, mut bg) = (css_color(self.fg), css_color(self.bg));
+        if self.inverse {
+            (fg, bg) = (
+                Some(bg.unwrap_or_else(|| DEFAULT_BG.to_string())),
+                Some(fg.unwrap_or_else(|| DEFAULT_FG.to_string())),
+            );
+        }
+        let mut css = String::new();
+        if let Some(fg) = fg {
+            let _ = write!(css, "color:{fg};");
+        }
+        if let Some(bg) = bg {
+            let _ = write!(css, "background:{bg};");
+        }
+        if self.bold {
+            css.push_str("font-weight:bold;");
+        }
+        if self.dim {
+            css.push_str("opacity:.7;");
+        }
+        if self.italic {
+            css.push_str("font-style:italic;");
+        }
+        if self.underline {
+            css.push_str("text-decoration:underline;");
+        }
+        css
+    }
+}
+
+fn css_color(color: vt100::Color) -> Option<String> {
+    match color {
+        vt100::Color::Default => None,
+        vt100::Color::Idx(index @ 0..=15) => Some(ANSI_COLORS[usize::from(index)].to_string()),
+        vt100::Color::Idx(index @ 16..=231) => {
+            let level = |value: u8| if value == 0 { 0 } else { 55 + value * 40 };
+            let index = index - 16;
+            Some(format!(
+                "#{:02x}{:02x}{:02x}",
+                level(index / 36),
+                level(index / 6 % 6),
+                level(index % 6)
+            ))
+        }
+        vt100::Color::Idx(index) => {
+            let gray = 8 + (index - 232) * 10;
+            Some(format!("#{gray:02x}{gray:02x}{gray:02x}"))
+        }
+        vt100::Color::Rgb(r, g, b) => Some(format!("#{r:02x}{g:02x}{b:02x}")),
+    }
+}
+
+fn escape_html(text: &str, out: &mut String) {
+    for ch in text.chars() {
+        match ch {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            _ => out.push(ch),
+        }
+    }
+}
+
+/// A visible row as HTML, or `None` if it's blank.
+fn render_row(screen: &vt100::Screen, row: u16, cols: u16) -> Option<String> {
+    let cells: Vec<&vt100::Cell> = (0..cols)
+        .filter_map(|col| screen.cell(row, col))
+        .filter(|cell| !cell.is_wide_continuation())
+        .collect();
+    let used = cells
+        .iter()
+        .rposition(|cell| {
+            cell.has_contents() || cell.bgcolor() != vt100::Color::Default || cell.inverse()
+        })
+        .map_or(0, |last| last + 1);
+    if used == 0 {
+        return None;
+    }
+
+    let mut html = String::new();
+    let mut run: Option<(CellStyle, String)> = None;
+    let flush = |run: Option<(CellStyle, String)>, html: &mut String| {
+        let Some((style, text)) = run else {
+            return;
+        };
+        let css = style.css();
+        if css.is_empty() {
+            escape_html(&text, html);
+        } else {
+            let _ = write!(html, "<span style=\"{css}\">");
+            escape_html(&text, html);
+            html.push_str("</span>");
+        }
+    };
+    for cell in &cells[..used] {
+        let style = CellStyle::of(cell);
+        let contents = if cell.has_contents() {
+            cell.contents()
+        } else {
+            " "
+        };
+        match run.as_mut() {
+            Some((run_style, text)) if *run_style == style => text.push_str(contents),
+            _ => {
+                flush(run.take(), &mut html);
+                run = Some((style, contents.to_string()));
+            }
+        }
+    }
+    flush(run, &mut html);
+    Some(html)
+}
+
+/// Lays the output out on a terminal of the recording's size and renders
+/// the scrollback and final screen. Full-screen apps on the alternate
+/// screen are left, as a terminal would when they exit.
+fn html(recording: &OutputRecording, title: &str) -> String {
+    let (cols, rows) = recording.size.unwrap_or(DEFAULT_EXPORT_SIZE);
+    let (cols, rows) = (cols.max(1), rows.max(1));
+    let mut parser = vt100::Parser::new(rows, cols, HTML_SCROLLBACK_LINES);
+    parser.process(&recording.data);
+    if parser.screen().alternate_screen() {
+        parser.process(b"\x1b[?1049l");
+    }
+
+    // Scrollback lines are brought to the top row one at a time.
+    let screen = parser.screen_mut();
+    screen.set_scrollback(usize::MAX);
+    let scrollback = screen.scrollback();
+    let mut lines = Vec::new();
+    for line in 0..scrollback {
+        screen.set_scrollback(scrollback - line);
+        lines.push((render_row(screen, 0, cols), screen.row_wrapped(0)));
+    }
+    screen.set_scrollback(0);
+    for row in 0..rows {
+        lines.push((render_row(screen, row, cols), screen.row_wrapped(row)));
+    }
+    while lines.last().is_some_and(|(line, _)| line.is_none()) {
+        lines.pop();
+    }
+
+    let mut body = String::new();
+    for (index, (line, wrapped)) in lines.iter().enumerate() {
+        body.push_str(line.as_deref().unwrap_or(""));
+        if !wrapped && index + 1 < lines.len() {
+            body.push('\n');
+        }
+    }
+
+    let mut escaped_title = String::new();
+    escape_html(title, &mut escaped_title);
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{escaped_title}</title>\n\
+         <style>body{{margin:0;background:{DEFAULT_BG};color:{DEFAULT_FG}}}\
+         pre{{margin:0;padding:16px;font:13px/1.35 ui-monospace,SFMono-Regular,Menlo,Consolas,monospace;white-space:pre}}</style>\n\
+         </head>\n<body>\n<pre>{body}</pre>\n</body>\n</html>\n"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lw_pty::OutputTiming;
+
+    fn recording(data: &[u8], timings: &[(usize, u64)]) -> OutputRecording {
+        OutputRecording {
+            data: data.to_vec(),
+            start_offset: 100,
+            timings: timings
+                .iter()
+                .map(|&(offset, unix_ms)| OutputTiming { offset, unix_ms })
+                .collect(),
+            size: Some((40, 10)),
+        }
+    }
+
+    fn cast_lines(recording: &OutputRecording) -> Vec<serde_json::Value> {
+        let cast =
+            String::from_utf8(export_recording(recording, ExportFormat::Cast, "demo")).unwrap();
+        cast.lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn cast_events_follow_timings() {
+        // "é" is split across the second and third chunks.
+        let data = "$ ls\r\nfile\u{e9}\r\n".as_bytes();
+        let lines = cast_lines(&recording(
+            data,
+            &[
+                (100, 1_700_000_000_000),
+                (106, 1_700_000_001_500),
+                (111, 1_700_000_002_000),
+            ],
+        ));
+        assert_eq!(
+            lines[0],
+            serde_json::json!({
+                "version": 2,
+                "width": 40,
+                "height": 10,
+                "title": "demo",
+                "timestamp": 1_700_000_000u64,
+            })
+        );
+        assert_eq!(lines[1], serde_json::json!([0.0, "o", "$ ls\r\n"]));
+        assert_eq!(lines[2], serde_json::json!([1.5, "o", "file"]));
+        assert_eq!(lines[3], serde_json::json!([2.0, "o", "\u{e9}\r\n"]));
+        assert_eq!(lines.len(), 4);
+    }
+
+    #[test]
+    fn cast_without_timings_plays_at_once() {
+        let mut untimed = recording(b"old output\r\n", &[]);
+        untimed.size = None;
+        let lines = cast_lines(&untimed);
+        assert_eq!(lines[0]["width"], 80);
+        assert!(lines[0].get("timestamp").is_none());
+        assert_eq!(lines[1], serde_json::json!([0.0, "o", "old output\r\n"]));
+    }
+
+    #[test]
+    fn html_keeps_colors_and_scrollback() {
+        let mut data = Vec::new();
+        for i in 0..30 {
+            data.extend_from_slice(format!("line {i}\r\n").as_bytes());
+        }
+        data.extend_from_slice(b"\x1b[31merror\x1b[0m <tag> & \x1b[1;38;2;1;2;3mrgb\x1b[0m\r\n");
+        let html = String::from_utf8(export_recording(
+            &recording(&data, &[]),
+            ExportFormat::Html,
+            "a <b>",
+        ))
+        .unwrap();
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<title>a &lt;b&gt;</title>"));
+        assert!(html.contains("<pre>line 0\nline 1\n"));
+        assert!(html
+            .contains("line 29\n<span style=\"color:#cd3131;\">error</span> &lt;tag&gt; &amp; "));
+        assert!(html.contains("<span style=\"color:#010203;font-weight:bold;\">rgb</span></pre>"));
+    }
+
+    #[test]
+    fn html_leaves_the_alternate_screen() {
+        let data = b"$ vim\r\n\x1b[?1049h\x1b[Hediting";
+        let html = String::from_utf8(export_recording(
+            &recording(data, &[]),
+            ExportFormat::Html,
+            "",
+        ))
+        .unwrap();
+        assert!(html.contains("<pre>$ vim</pre>"));
+        assert!(!html.contains("editing"));
+    }
+
+    #[test]
+    fn text_is_normalized() {
+        let text = export_recording(
+            &recording(b"\x1b[32mok\x1b[0m\r\nloading\r\x1b[Kdone\r\n", &[]),
+            ExportFormat::Text,
+            "",
+        );
+        assert_eq!(String::from_utf8(text).unwrap(), "ok\ndone\n");
+    }
+
+    #[test]
+    fn format_parses_from_str() {
+        assert_eq!("html".parse::<ExportFormat>(), Ok(ExportFormat::Html));
+        assert!("mp4".parse::<ExportFormat>().is_err());
+    }
+}
@@ -1,3 +1,5 @@
+use std::ops::{ControlFlow, Range};
+
 enum EscapeState {
     None,
     Esc,
@@ -42,6 +44,15 @@ impl TerminalTextNormalizer {
         emitted
     }
 
+    /// The line being written, not yet ended by a newline.
+    pub fn current_line(&self) -> String {
+        let mut line: String = self.line.iter().collect();
+        while line.ends_with(' ') {
+            line.pop();
+        }
+        line
+    }
+
     pub fn finish(mut self) -> String {
         self.flush_pending_utf8();
         let mut line: String = self.line.iter().collect();
@@ -313,6 +324,137 @@ pub fn normalize_terminal_bytes_for_analysis(bytes: &[u8]) -> String {
     out
 }
 
+/// Braille dots and the star/circle frames agent CLIs cycle through in
+/// front of a status line. ASCII spinners (`|/-\\`) aren't included since
+/// `-` and `*` also start list items.
+fn is_spinner_glyph(ch: char) -> bool {
+    matches!(
+        ch,
+        '\u{2800}'..='\u{28ff}' | '✻' | '✶' | '✳' | '✢' | '✽' | '·' | '◐' | '◓' | '◑' | '◒'
+    )
+}
+
+/// Drops lines that only exist because a TUI redrew its status line: a
+/// spinner line following one with the same status, e.g. `⠙ Thinking… (2s)`
+/// after `⠋ Thinking… (1s)`. Every other line is kept, repeated or not.
+#[derive(Debug, Default)]
+pub struct SpinnerLineFilter {
+    last_status: Option<String>,
+}
+
+impl SpinnerLineFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether `line` should be kept.
+    pub fn keep(&mut self, line: &str) -> bool {
+        let Some(rest) = line.trim_start().strip_prefix(is_spinner_glyph) else {
+            self.last_status = None;
+            return true;
+        };
+        let status = status_text(rest);
+        if self.last_status.as_deref() == Some(status) {
+            return false;
+        }
+        self.last_status = Some(status.to_string());
+        true
+    }
+}
+
+/// A spinner line's text after the glyph, without a trailing `(…)` such as
+/// the elapsed time.
+fn status_text(rest: &str) -> &str {
+    let rest = rest.trim();
+    rest.strip_suffix(')')
+        .and_then(|head| head.rfind('(').map(|open| head[..open].trim_end()))
+        .unwrap_or(rest)
+}
+
+/// Raw output in, readable lines out, with spinner redraws filtered. Each
+/// call returns only completed lines; `partial_line` has the one in progress,
+/// such as a prompt waiting for input.
+#[derive(Default)]
+pub struct TerminalTextStream {
+    normalizer: TerminalTextNormalizer,
+    filter: SpinnerLineFilter,
+}
+
+impl TerminalTextStream {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, bytes: &[u8]) -> String {
+        let text = self.normalizer.ingest(bytes);
+        let mut out = String::new();
+        for line in text.lines() {
+            if self.filter.keep(line) {
+                out.push_str(line);
+                out.push('\n');
+            }
+        }
+        out
+    }
+
+    pub fn partial_line(&self) -> String {
+        self.normalizer.current_line()
+    }
+
+    pub fn finish(mut self) -> String {
+        let remainder = std::mem::take(&mut self.normalizer).finish();
+        if !remainder.is_empty() && self.filter.keep(&remainder) {
+            remainder
+        } else {
+            String::new()
+        }
+    }
+}
+
+/// Like [`normalize_terminal_bytes_for_analysis`], with spinner redraws
+/// filtered out as by [`TerminalTextStream`].
+pub fn normalize_terminal_bytes_deduped(bytes: &[u8]) -> String {
+    let mut stream = TerminalTextStream::new();
+    let mut out = stream.push(bytes);
+    out.push_str(&stream.finish());
+    out
+}
+
+/// Calls `visit` with each normalized line of `bytes` and the range of raw
+/// output it came from, offset by `base_offset`. Spinner redraws are skipped
+/// as by [`TerminalTextStream`]; the last line may be unterminated.
+pub fn visit_terminal_lines(
+    bytes: &[u8],
+    base_offset: usize,
+    mut visit: impl FnMut(&str, Range<usize>) -> ControlFlow<()>,
+) {
+    let mut normalizer = TerminalTextNormalizer::new();
+    let mut filter = SpinnerLineFilter::new();
+    let mut start = 0;
+    for end in newline_positions(bytes) {
+        let text = normalizer.ingest(&bytes[start..=end]);
+        let line = text.strip_suffix('\n').unwrap_or(&text);
+        if filter.keep(line) && visit(line, base_offset + start..base_offset + end + 1).is_break() {
+            return;
+        }
+        start = end + 1;
+    }
+    if start < bytes.len() {
+        normalizer.ingest(&bytes[start..]);
+        let line = normalizer.finish();
+        if filter.keep(&line) {
+            let _ = visit(&line, base_offset + start..base_offset + bytes.len());
+        }
+    }
+}
+
+fn newline_positions(bytes: &[u8]) -> impl Iterator<Item = usize> + '_ {
+    bytes
+        .iter()
+        .enumerate()
+        .filter_map(|(index, byte)| (*byte == b'\n').then_some(index))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -726,4 +868,92 @@ mod tests {
         let out = n.ingest(b"hello\x1b[3Kworld\n"); // mode 3 → _ => {} catchall
         assert_eq!(out, "helloworld\n");
     }
+
+    #[test]
+    fn current_line_peeks_without_consuming() {
+        let mut normalizer = TerminalTextNormalizer::new();
+        assert_eq!(normalizer.ingest(b"done\nProceed? (y/n) "), "done\n");
+        assert_eq!(normalizer.current_line(), "Proceed? (y/n)");
+        assert_eq!(normalizer.ingest(b"y\n"), "Proceed? (y/n) y\n");
+    }
+
+    #[test]
+    fn spinner_filter_collapses_status_redraws() {
+        let mut filter = SpinnerLineFilter::new();
+        let kept: Vec<&str> = [
+            "Reading files",
+            "⠋ Thinking… (1s)",
+            "⠙ Thinking… (2s)",
+            "✻ Thinking… (3s)",
+            "⠹ Running tests",
+            "⠸ Running tests",
+            "Wrote main.rs",
+            "⠼ Running tests",
+        ]
+        .into_iter()
+        .filter(|line| filter.keep(line))
+        .collect();
+        assert_eq!(
+            kept,
+            vec![
+                "Reading files",
+                "⠋ Thinking… (1s)",
+                "⠹ Running tests",
+                "Wrote main.rs",
+                "⠼ Running tests",
+            ]
+        );
+    }
+
+    #[test]
+    fn spinner_filter_keeps_repeated_output_lines() {
+        let mut filter = SpinnerLineFilter::new();
+        let lines = ["ok", "ok", "", "", "test a ... ok", "test a ... ok"];
+        assert!(lines.iter().all(|line| filter.keep(line)));
+    }
+
+    #[test]
+    fn text_stream_emits_complete_filtered_lines() {
+        let mut stream = TerminalTextStream::new();
+        assert_eq!(
+            stream.push(b"\x1b[32mok\x1b[0m\r\n\xe2\xa0\x8b wait"),
+            "ok\n"
+        );
+        assert_eq!(stream.partial_line(), "⠋ wait");
+        assert_eq!(stream.push(b"\r\n\xe2\xa0\x99 wait\r\n"), "⠋ wait\n");
+        assert_eq!(stream.push(b"ok\r\n$ "), "ok\n");
+        assert_eq!(stream.finish(), "$");
+    }
+
+    #[test]
+    fn normalize_deduped_filters_whole_buffers() {
+        let out =
+            normalize_terminal_bytes_deduped(b"a\r\na\r\n\xe2\xa0\x8b x\r\n\xe2\xa0\x99 x\r\nb");
+        assert_eq!(out, "a\na\n\u{280b} x\nb");
+    }
+
+    #[test]
+    fn visit_lines_reports_raw_ranges() {
+        let bytes = b"\x1b[1mfirst\x1b[0m\r\n\xe2\xa0\x8b second\r\n\xe2\xa0\x99 second\r\ntail";
+        let mut lines = Vec::new();
+        visit_terminal_lines(bytes, 100, |line, range| {
+            lines.push((line.to_string(), range));
+            ControlFlow::Continue(())
+        });
+        assert_eq!(
+            lines,
+            vec![
+                ("first".to_string(), 100..115),
+                ("\u{280b} second".to_string(), 115..127),
+                ("tail".to_string(), 139..143),
+            ]
+        );
+
+        let mut visited = 0;
+        visit_terminal_lines(bytes, 0, |_, _| {
+            visited += 1;
+            ControlFlow::Break(())
+        });
+        assert_eq!(visited, 1);
+    }
 }
@@ -0,0 +1,101 @@
+//! Claude Code conversation transcripts on disk.
+
+use anyhow::Context;
+use std::path::{Path, PathBuf};
+
+/// Claude's config directory: `$CLAUDE_CONFIG_DIR`, else `~/.claude`.
+fn claude_config_dir() -> Option<PathBuf> {
+    std::env::var_os("CLAUDE_CONFIG_DIR")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| dirs::home_dir().map(|home| home.join(".claude")))
+}
+
+/// Directory name Claude files a project's transcripts under: the working
+/// directory with every character but ASCII letters and digits replaced by
+/// `-`.
+fn project_dir_name(cwd: &Path) -> String {
+    cwd.to_string_lossy()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect()
+}
+
+fn transcript_path(config_dir: &Path, cwd: &Path, conversation_id: &str) -> PathBuf {
+    config_dir
+        .join("projects")
+        .join(project_dir_name(cwd))
+        .join(format!("{conversation_id}.jsonl"))
+}
+
+/// Makes the transcript of `conversation_id`, recorded in `from_cwd`,
+/// resumable from `to_cwd`. The original is only read.
+pub(crate) fn copy_claude_transcript(
+    from_cwd: &Path,
+    to_cwd: &Path,
+    conversation_id: &str,
+) -> anyhow::Result<()> {
+    let config_dir =
+        claude_config_dir().context("Cannot locate the Claude Code config directory")?;
+    copy_transcript_in(&config_dir, from_cwd, to_cwd, conversation_id)
+}
+
+fn copy_transcript_in(
+    config_dir: &Path,
+    from_cwd: &Path,
+    to_cwd: &Path,
+    conversation_id: &str,
+) -> anyhow::Result<()> {
+    let source = transcript_path(config_dir, from_cwd, conversation_id);
+    if !source.is_file() {
+        anyhow::bail!("Conversation {conversation_id} has no transcript to fork yet");
+    }
+    let target = transcript_path(config_dir, to_cwd, conversation_id);
+    if target == source {
+        return Ok(());
+    }
+    if let Some(parent) = target.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    std::fs::copy(&source, &target)
+        .with_context(|| format!("Failed to copy transcript to {}", target.display()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn project_dir_name_replaces_non_alphanumerics() {
+        assert_eq!(
+            project_dir_name(Path::new("/Users/me/my_app.v2")),
+            "-Users-me-my-app-v2"
+        );
+    }
+
+    #[test]
+    fn copies_transcript_to_other_working_directory() {
+        let config = tempfile::tempdir().unwrap();
+        let from = Path::new("/repo");
+        let to = Path::new("/repo-worktrees/fork");
+
+        let err = copy_transcript_in(config.path(), from, to, "c1").unwrap_err();
+        assert!(err.to_string().contains("no transcript"));
+
+        let source = transcript_path(config.path(), from, "c1");
+        std::fs::create_dir_all(source.parent().unwrap()).unwrap();
+        std::fs::write(&source, "{\"type\":\"user\"}\n").unwrap();
+
+        copy_transcript_in(config.path(), from, to, "c1").unwrap();
+        let copied = config.path().join("projects/-repo-worktrees-fork/c1.jsonl");
+        assert_eq!(
+            std::fs::read_to_string(copied).unwrap(),
+            "{\"type\":\"user\"}\n"
+        );
+        // Forking in place leaves the transcript alone.
+        copy_transcript_in(config.path(), from, from, "c1").unwrap();
+        assert!(source.is_file());
+    }
+}
@@ -0,0 +1,586 @@
+//! Workspace task runs.
+//!
+//! A workspace task is a named command, discovered from `.loopwire.toml`,
+//! `package.json`, a `Makefile` or Cargo. Each run gets its own PTY in the
+//! shared `PtyManager`, keyed by the run id, so `/api/v1/term/{run_id}`
+//! streams it like any other session. Once the process exits the run
+//! records its exit code and duration, and the PTY is dropped: its output
+//! is served from the run's log on disk from then on. Records are persisted
+//! as one JSON file per run next to that log, and runs that were still
+//! going when the daemon stopped come back as `Interrupted`.
+
+mod discover;
+
+pub use discover::discover_tasks;
+
+use crate::record_dir;
+use chrono::{DateTime, Utc};
+use lw_pty::{PtyManager, PtySession};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex as StdMutex};
+use std::time::Instant;
+use tokio::sync::{broadcast, RwLock};
+use uuid::Uuid;
+
+/// Finished runs kept per workspace and task name; older ones are deleted
+/// together with their output.
+pub const TASK_RUN_HISTORY_LIMIT: usize = 20;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum WorkspaceTaskSource {
+    /// `[tasks]` in `.loopwire.toml`.
+    Loopwire,
+    /// `scripts` in `package.json`.
+    Npm,
+    Make,
+    Cargo,
+}
+
+/// A named command a workspace offers.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct WorkspaceTask {
+    pub name: String,
+    /// Shell command, run from the workspace root.
+    pub command: String,
+    pub source: WorkspaceTaskSource,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskRunStatus {
+    Running,
+    Succeeded,
+    Failed,
+    Cancelled,
+    /// The daemon stopped while the run was in progress.
+    Interrupted,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskRun {
+    pub run_id: Uuid,
+    pub workspace_path: PathBuf,
+    #[serde(flatten)]
+    pub task: WorkspaceTask,
+    pub status: TaskRunStatus,
+    pub started_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TaskRunEvent {
+    Started { run: TaskRun },
+    Finished { run: TaskRun },
+}
+
+/// How a task's command is started: through the platform shell, so that
+/// pipes and `&&` in `.loopwire.toml` commands work.
+fn shell_command(command: &str) -> (&'static str, Vec<&str>) {
+    #[cfg(windows)]
+    {
+        ("cmd.exe", vec!["/C", command])
+    }
+    #[cfg(not(windows))]
+    {
+        ("/bin/sh", vec!["-c", command])
+    }
+}
+
+pub struct WorkspaceTaskManager {
+    pty_manager: Arc<PtyManager>,
+    runs: Arc<RwLock<HashMap<Uuid, TaskRun>>>,
+    cancelled: Arc<StdMutex<HashSet<Uuid>>>,
+    events_tx: broadcast::Sender<TaskRunEvent>,
+    store_dir: Option<PathBuf>,
+}
+
+impl WorkspaceTaskManager {
+    /// Loads persisted runs from `store_dir`; without one, runs and their
+    /// output live in memory only.
+    pub fn new(pty_manager: Arc<PtyManager>, store_dir: Option<PathBuf>) -> Self {
+        let runs = store_dir.as_deref().map(load_runs).unwrap_or_default();
+        if let Some(dir) = store_dir.as_deref() {
+            for run_id in runs.keys() {
+                pty_manager.set_output_log_dir(*run_id, output_log_dir(dir, run_id));
+            }
+        }
+        let (events_tx, _) = broadcast::channel(128);
+        Self {
+            pty_manager,
+            runs: Arc::new(RwLock::new(runs)),
+            cancelled: Arc::new(StdMutex::new(HashSet::new())),
+            events_tx,
+            store_dir,
+        }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<TaskRunEvent> {
+        self.events_tx.subscribe()
+    }
+
+    /// Starts `task` in `workspace_path` and returns the running record.
+    /// The run is watched in the background until its process exits.
+    pub async fn start_run(
+        &self,
+        workspace_path: PathBuf,
+        task: WorkspaceTask,
+        env: Vec<(String, String)>,
+    ) -> anyhow::Result<TaskRun> {
+        let run_id = Uuid::new_v4();
+        if let Some(dir) = self.store_dir.as_deref() {
+            self.pty_manager
+                .set_output_log_dir(run_id, output_log_dir(dir, &run_id));
+        }
+        let mut env = env;
+        env.extend(crate::shell::shell_env());
+        let (program, args) = shell_command(&task.command);
+        let session = match self
+            .pty_manager
+            .create(run_id, program, &args, &workspace_path, env, (120, 40))
+            .await
+        {
+            Ok(session) => session,
+            Err(err) => {
+                self.pty_manager.remove_output_log(&run_id);
+                return Err(err.into());
+            }
+        };
+
+        let run = TaskRun {
+            run_id,
+            workspace_path,
+            task,
+            status: TaskRunStatus::Running,
+            started_at: Utc::now(),
+            finished_at: None,
+            duration_ms: None,
+            exit_code: None,
+        };
+        self.runs.write().await.insert(run_id, run.clone());
+        self.persist(&run);
+        let _ = self
+            .events_tx
+            .send(TaskRunEvent::Started { run: run.clone() });
+        tracing::info!(run_id = %run_id, task = %run.task.name, "workspace task started");
+
+        tokio::spawn(watch_run(
+            session,
+            Arc::clone(&self.pty_manager),
+            Arc::clone(&self.runs),
+            Arc::clone(&self.cancelled),
+            self.events_tx.clone(),
+            self.store_dir.clone(),
+            Instant::now(),
+        ));
+        Ok(run)
+    }
+
+    pub async fn is_task_run(&self, run_id: &Uuid) -> bool {
+        self.runs.read().await.contains_key(run_id)
+    }
+
+    pub async fn get(&self, run_id: &Uuid) -> Option<TaskRun> {
+        self.runs.read().await.get(run_id).cloned()
+    }
+
+    /// Runs in `workspace_path`, optionally only those of one task, newest
+    /// first.
+    pub async fn list(&self, workspace_path: &Path, name: Option<&str>) -> Vec<TaskRun> {
+        let mut runs: Vec<TaskRun> = self
+            .runs
+            .read()
+            .await
+            .values()
+            .filter(|run| run.workspace_path == workspace_path)
+            .filter(|run| name.is_none_or(|name| run.task.name == name))
+            .cloned()
+            .collect();
+        runs.sort_by(|a, b| b.started_at.cmp(&a.started_at));
+        runs
+    }
+
+    /// Runs in every workspace, newest first.
+    pub async fn list_all(&self) -> Vec<TaskRun> {
+        let mut runs: Vec<TaskRun> = self.runs.read().await.values().cloned().collect();
+        runs.sort_by(|a, b| b.started_at.cmp(&a.started_at));
+        runs
+    }
+
+    /// The PTY of a running run. Finished runs only have their output log
+    /// (unless runs are kept in memory only).
+    pub async fn pty_session(&self, run_id: &Uuid) -> anyhow::Result<Arc<PtySession>> {
+        if !self.is_task_run(run_id).await {
+            anyhow::bail!("Session not found");
+        }
+        Ok(self.pty_manager.get(run_id).await?)
+    }
+
+    pub async fn input_run(&self, run_id: &Uuid, data: &[u8]) -> anyhow::Result<()> {
+        let session = self.pty_session(run_id).await?;
+        if session.is_stopped() {
+            anyhow::bail!("Task run is not running");
+        }
+        session.write(data).await?;
+        Ok(())
+    }
+
+    /// Kills a running run. Returns `false` if it is not running.
+    pub async fn cancel(&self, run_id: &Uuid) -> bool {
+        let running = self
+            .runs
+            .read()
+            .await
+            .get(run_id)
+            .is_some_and(|run| run.status == TaskRunStatus::Running);
+        if !running {
+            return false;
+        }
+        self.cancelled
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(*run_id);
+        if let Err(err) = self.pty_manager.kill(run_id).await {
+            tracing::warn!(run_id = %run_id, "failed to kill workspace task: {err}");
+        }
+        true
+    }
+
+    /// Deletes a finished run and its output. Running runs must be
+    /// cancelled first.
+    pub async fn remove(&self, run_id: &Uuid) -> anyhow::Result<bool> {
+        let mut runs = self.runs.write().await;
+        match runs.get(run_id) {
+            None => return Ok(false),
+            Some(run) if run.status == TaskRunStatus::Running => {
+                anyhow::bail!("Task run is still running")
+            }
+            Some(_) => {}
+        }
+        runs.remove(run_id);
+        drop(runs);
+        delete_run(&self.pty_manager, self.store_dir.as_deref(), run_id).await;
+        Ok(true)
+    }
+
+    fn persist(&self, run: &TaskRun) {
+        if let Some(dir) = self.store_dir.as_deref() {
+            save_run(dir, run);
+        }
+    }
+}
+
+async fn watch_run(
+    session: Arc<PtySession>,
+    pty_manager: Arc<PtyManager>,
+    runs: Arc<RwLock<HashMap<Uuid, TaskRun>>>,
+    cancelled: Arc<StdMutex<HashSet<Uuid>>>,
+    events_tx: broadcast::Sender<TaskRunEvent>,
+    store_dir: Option<PathBuf>,
+    started: Instant,
+) {
+    use broadcast::error::RecvError;
+    let run_id = session.id;
+    let mut exit_rx = session.subscribe_exit();
+    // `kill` announces the exit before the process is reaped, so wait for
+    // the reader thread to record the code.
+    let exit_code = loop {
+        if let Some(exit_code) = session.exit_code() {
+            break exit_code;
+        }
+        if let Err(RecvError::Closed) = exit_rx.recv().await {
+            break None;
+        }
+    };
+    let duration_ms = u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX);
+    let was_cancelled = cancelled
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .remove(&run_id);
+
+    let (finished, pruned) = {
+        let mut runs = runs.write().await;
+        let Some(run) = runs.get_mut(&run_id) else {
+            return;
+        };
+        run.status = match exit_code {
+            _ if was_cancelled => TaskRunStatus::Cancelled,
+            Some(0) => TaskRunStatus::Succeeded,
+            _ => TaskRunStatus::Failed,
+        };
+        run.exit_code = exit_code;
+        run.finished_at = Some(Utc::now());
+        run.duration_ms = Some(duration_ms);
+        let finished = run.clone();
+        let pruned = prune_history(&mut runs, &finished);
+        (finished, pruned)
+    };
+    if let Some(dir) = store_dir.as_deref() {
+        save_run(dir, &finished);
+        // Everything it printed is in the log.
+        pty_manager.remove(&run_id).await;
+    }
+    for run_id in &pruned {
+        delete_run(&pty_manager, store_dir.as_deref(), run_id).await;
+    }
+    tracing::info!(
+        run_id = %run_id,
+        status = ?finished.status,
+        exit_code = ?finished.exit_code,
+        "workspace task finished"
+    );
+    let _ = events_tx.send(TaskRunEvent::Finished { run: finished });
+}
+
+/// Drops the oldest finished runs of `latest`'s task beyond
+/// [`TASK_RUN_HISTORY_LIMIT`] and returns their ids.
+fn prune_history(runs: &mut HashMap<Uuid, TaskRun>, latest: &TaskRun) -> Vec<Uuid> {
+    let mut finished: Vec<(DateTime<Utc>, Uuid)> = runs
+        .values()
+        .filter(|run| {
+            run.status != TaskRunStatus::Running
+                && run.workspace_path == latest.workspace_path
+                && run.task.name == latest.task.name
+        })
+        .map(|run| (run.started_at, run.run_id))
+        .collect();
+    if finished.len() <= TASK_RUN_HISTORY_LIMIT {
+        return Vec::new();
+    }
+    finished.sort();
+    let excess = finished.len() - TASK_RUN_HISTORY_LIMIT;
+    finished
+        .into_iter()
+        .take(excess)
+        .map(|(_, run_id)| {
+            runs.remove(&run_id);
+            run_id
+        })
+        .collect()
+}
+
+async fn delete_run(pty_manager: &PtyManager, store_dir: Option<&Path>, run_id: &Uuid) {
+    let _ = pty_manager.remove(run_id).await;
+    pty_manager.remove_output_log(run_id);
+    if let Some(dir) = store_dir {
+        record_dir::remove(dir, run_id);
+    }
+}
+
+fn output_log_dir(dir: &Path, run_id: &Uuid) -> PathBuf {
+    dir.join(run_id.to_string())
+}
+
+fn save_run(dir: &Path, run: &TaskRun) {
+    record_dir::save(dir, &run.run_id, run);
+}
+
+fn load_runs(dir: &Path) -> HashMap<Uuid, TaskRun> {
+    let mut runs = HashMap::new();
+    for mut run in record_dir::load::<TaskRun>(dir) {
+        if run.status == TaskRunStatus::Running {
+            run.status = TaskRunStatus::Interrupted;
+            save_run(dir, &run);
+        }
+        runs.insert(run.run_id, run);
+    }
+    runs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::record_dir::record_path;
+    use std::time::Duration;
+
+    fn task(name: &str, command: &str) -> WorkspaceTask {
+        WorkspaceTask {
+            name: name.to_string(),
+            command: command.to_string(),
+            source: WorkspaceTaskSource::Loopwire,
+            description: None,
+        }
+    }
+
+    async fn wait_finished(rx: &mut broadcast::Receiver<TaskRunEvent>, run_id: Uuid) -> TaskRun {
+        tokio::time::timeout(Duration::from_secs(10), async {
+            loop {
+                if let Ok(TaskRunEvent::Finished { run }) = rx.recv().await {
+                    if run.run_id == run_id {
+                        return run;
+                    }
+                }
+            }
+        })
+        .await
+        .expect("run did not finish")
+    }
+
+    #[test]
+    fn task_run_serializes_flat() {
+        let run = TaskRun {
+            run_id: Uuid::nil(),
+            workspace_path: PathBuf::from("/repo"),
+            task: task("test", "npm test"),
+            status: TaskRunStatus::Succeeded,
+            started_at: Utc::now(),
+            finished_at: None,
+            duration_ms: Some(5),
+            exit_code: Some(0),
+        };
+        let value = serde_json::to_value(&run).unwrap();
+        assert_eq!(value["name"], "test");
+        assert_eq!(value["source"], "loopwire");
+        assert_eq!(value["status"], "succeeded");
+        let parsed: TaskRun = serde_json::from_value(value).unwrap();
+        assert_eq!(parsed.task, run.task);
+    }
+
+    #[test]
+    fn prune_history_keeps_the_newest_finished_runs() {
+        let mut runs = HashMap::new();
+        let base = Utc::now();
+        let mut latest = None;
+        for i in 0..TASK_RUN_HISTORY_LIMIT + 2 {
+            let run = TaskRun {
+                run_id: Uuid::new_v4(),
+                workspace_path: PathBuf::from("/repo"),
+                task: task("test", "true"),
+                status: TaskRunStatus::Failed,
+                started_at: base + chrono::Duration::seconds(i as i64),
+                finished_at: None,
+                duration_ms: None,
+                exit_code: None,
+            };
+            latest = Some(run.clone());
+            runs.insert(run.run_id, run);
+        }
+        let other = TaskRun {
+            task: task("lint", "true"),
+            run_id: Uuid::new_v4(),
+            ..latest.clone().unwrap()
+        };
+        runs.insert(other.run_id, other.clone());
+
+        let pruned = prune_history(&mut runs, &latest.unwrap());
+        assert_eq!(pruned.len(), 2);
+        assert_eq!(runs.len(), TASK_RUN_HISTORY_LIMIT + 1);
+        assert!(runs.contains_key(&other.run_id));
+        assert!(runs
+            .values()
+            .all(|run| run.started_at >= base + chrono::Duration::seconds(2)));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn run_records_exit_code_and_output() {
+        let store = tempfile::tempdir().unwrap();
+        let workspace = tempfile::tempdir().unwrap();
+        let pty = Arc::new(PtyManager::new());
+        let manager = WorkspaceTaskManager::new(pty.clone(), Some(store.path().to_path_buf()));
+        let mut events = manager.subscribe();
+
+        let run = manager
+            .start_run(
+                workspace.path().to_path_buf(),
+                task("check", "echo checking $GREETING; exit 2"),
+                vec![("GREETING".to_string(), "hi".to_string())],
+            )
+            .await
+            .unwrap();
+        assert_eq!(run.status, TaskRunStatus::Running);
+        assert!(manager.is_task_run(&run.run_id).await);
+        let finished = wait_finished(&mut events, run.run_id).await;
+        assert_eq!(finished.status, TaskRunStatus::Failed);
+        assert_eq!(finished.exit_code, Some(2));
+        assert!(finished.duration_ms.is_some());
+
+        // The PTY is gone; output comes from the log.
+        assert!(pty.get(&run.run_id).await.is_err());
+        let (data, ..) = pty
+            .output_slice_before(&run.run_id, None, 1 << 20)
+            .await
+            .unwrap();
+        assert!(String::from_utf8_lossy(&data).contains("checking hi"));
+        assert_eq!(manager.list(workspace.path(), Some("check")).await.len(), 1);
+        assert!(manager
+            .list(workspace.path(), Some("other"))
+            .await
+            .is_empty());
+
+        // Record and output survive a restart.
+        let pty = Arc::new(PtyManager::new());
+        let reloaded = WorkspaceTaskManager::new(pty.clone(), Some(store.path().to_path_buf()));
+        assert_eq!(reloaded.get(&run.run_id).await.unwrap().exit_code, Some(2));
+        let (data, ..) = pty
+            .output_slice_before(&run.run_id, None, 1 << 20)
+            .await
+            .unwrap();
+        assert!(String::from_utf8_lossy(&data).contains("checking hi"));
+
+        assert!(reloaded.remove(&run.run_id).await.unwrap());
+        assert!(!store.path().join(run.run_id.to_string()).exists());
+        assert!(!record_path(store.path(), &run.run_id).exists());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn cancel_kills_running_run() {
+        let workspace = tempfile::tempdir().unwrap();
+        let manager = WorkspaceTaskManager::new(Arc::new(PtyManager::new()), None);
+        let mut events = manager.subscribe();
+        let run = manager
+            .start_run(
+                workspace.path().to_path_buf(),
+                task("serve", "sleep 30"),
+                vec![],
+            )
+            .await
+            .unwrap();
+        assert!(manager.remove(&run.run_id).await.is_err());
+        assert!(manager.cancel(&run.run_id).await);
+        let finished = wait_finished(&mut events, run.run_id).await;
+        assert_eq!(finished.status, TaskRunStatus::Cancelled);
+        assert!(!manager.cancel(&run.run_id).await);
+        assert!(manager.input_run(&run.run_id, b"x").await.is_err());
+    }
+
+    #[test]
+    fn running_records_load_as_interrupted() {
+        let store = tempfile::tempdir().unwrap();
+        let run_id = Uuid::new_v4();
+        std::fs::write(
+            record_path(store.path(), &run_id),
+            serde_json::json!({
+                "run_id": run_id,
+                "workspace_path": "/repo",
+                "name": "build",
+                "command": "make",
+                "source": "make",
+                "status": "running",
+                "started_at": Utc::now(),
+            })
+            .to_string(),
+        )
+        .unwrap();
+        std::fs::write(store.path().join("junk.json"), "{").unwrap();
+
+        let runs = load_runs(store.path());
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[&run_id].status, TaskRunStatus::Interrupted);
+        assert_eq!(
+            load_runs(store.path())[&run_id].status,
+            TaskRunStatus::Interrupted
+        );
+    }
+}
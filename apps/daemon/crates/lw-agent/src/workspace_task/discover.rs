@@ -0,0 +1,254 @@
+//! Finds the named commands a workspace offers.
+
+use super::{WorkspaceTask, WorkspaceTaskSource};
+use lw_config::ProjectConfig;
+use std::collections::HashSet;
+use std::path::Path;
+
+const MAKEFILE_NAMES: [&str; 3] = ["GNUmakefile", "makefile", "Makefile"];
+
+/// Tasks of the workspace at `root`, from `.loopwire.toml`, `package.json`
+/// scripts, `Makefile` targets and Cargo, in that order. A name taken by an
+/// earlier source hides the same name further down, so `.loopwire.toml`
+/// can override e.g. a `test` script.
+pub fn discover_tasks(root: &Path, config: Option<&ProjectConfig>) -> Vec<WorkspaceTask> {
+    let mut tasks: Vec<WorkspaceTask> = Vec::new();
+    let mut seen = HashSet::new();
+    let mut add = |found: Vec<WorkspaceTask>| {
+        for task in found {
+            if seen.insert(task.name.clone()) {
+                tasks.push(task);
+            }
+        }
+    };
+    if let Some(config) = config {
+        add(config
+            .tasks
+            .iter()
+            .map(|(name, task)| WorkspaceTask {
+                name: name.clone(),
+                command: task.command.clone(),
+                source: WorkspaceTaskSource::Loopwire,
+                description: task.description.clone(),
+            })
+            .collect());
+    }
+    add(package_scripts(root));
+    add(make_targets(root));
+    add(cargo_tasks(root));
+    tasks
+}
+
+/// Package manager the lockfile points at; npm when there is none.
+fn package_manager(root: &Path) -> &'static str {
+    [
+        ("pnpm-lock.yaml", "pnpm"),
+        ("yarn.lock", "yarn"),
+        ("bun.lockb", "bun"),
+        ("bun.lock", "bun"),
+    ]
+    .into_iter()
+    .find(|(lockfile, _)| root.join(lockfile).is_file())
+    .map_or("npm", |(_, manager)| manager)
+}
+
+fn package_scripts(root: &Path) -> Vec<WorkspaceTask> {
+    let Ok(content) = std::fs::read_to_string(root.join("package.json")) else {
+        return Vec::new();
+    };
+    let Ok(package) = serde_json::from_str::<serde_json::Value>(&content) else {
+        tracing::debug!(root = %root.display(), "unreadable package.json");
+        return Vec::new();
+    };
+    let Some(scripts) = package["scripts"].as_object() else {
+        return Vec::new();
+    };
+    let manager = package_manager(root);
+    scripts
+        .iter()
+        .filter_map(|(name, script)| Some((name, script.as_str()?)))
+        // `pretest`/`posttest` run as part of `test` already.
+        .filter(|(name, _)| {
+            !["pre", "post"].iter().any(|prefix| {
+                name.strip_prefix(prefix)
+                    .is_some_and(|base| scripts.contains_key(base))
+            })
+        })
+        .map(|(name, script)| WorkspaceTask {
+            name: name.clone(),
+            command: format!("{manager} run {name}"),
+            source: WorkspaceTaskSource::Npm,
+            description: Some(script.to_string()),
+        })
+        .collect()
+}
+
+fn make_targets(root: &Path) -> Vec<WorkspaceTask> {
+    MAKEFILE_NAMES
+        .iter()
+        .find_map(|name| std::fs::read_to_string(root.join(name)).ok())
+        .map(|content| parse_make_targets(&content))
+        .unwrap_or_default()
+}
+
+/// Explicit targets of a Makefile, in file order. Special (`.PHONY`) and
+/// pattern (`%.o`) targets are skipped. A trailing `## text` on the rule
+/// line becomes the description.
+fn parse_make_targets(content: &str) -> Vec<WorkspaceTask> {
+    let mut targets = Vec::new();
+    let mut seen = HashSet::new();
+    for line in content.lines() {
+        if line.starts_with(['\t', ' ', '#']) {
+            continue;
+        }
+        let Some((names, rest)) = line.split_once(':') else {
+            continue;
+        };
+        // `VAR := value` and `VAR ::= value` are assignments, not rules.
+        if rest.starts_with('=') || rest.starts_with(":=") || names.contains('=') {
+            continue;
+        }
+        let description = rest
+            .split_once("##")
+            .map(|(_, text)| text.trim().to_string())
+            .filter(|text| !text.is_empty());
+        for name in names.split_whitespace() {
+            let valid = !name.starts_with('.')
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '/'));
+            if valid && seen.insert(name.to_string()) {
+                targets.push(WorkspaceTask {
+                    name: name.to_string(),
+                    command: format!("make {name}"),
+                    source: WorkspaceTaskSource::Make,
+                    description: description.clone(),
+                });
+            }
+        }
+    }
+    targets
+}
+
+/// `cargo build`, `test` and `clippy` for Cargo projects, plus the aliases
+/// from `.cargo/config.toml`.
+fn cargo_tasks(root: &Path) -> Vec<WorkspaceTask> {
+    if !root.join("Cargo.toml").is_file() {
+        return Vec::new();
+    }
+    let mut tasks: Vec<WorkspaceTask> = ["build", "test", "clippy"]
+        .into_iter()
+        .map(|name| WorkspaceTask {
+            name: name.to_string(),
+            command: format!("cargo {name}"),
+            source: WorkspaceTaskSource::Cargo,
+            description: None,
+        })
+        .collect();
+    let config = ["config.toml", "config"]
+        .iter()
+        .find_map(|name| std::fs::read_to_string(root.join(".cargo").join(name)).ok());
+    if let Some(config) = config {
+        tasks.extend(parse_cargo_aliases(&config));
+    }
+    tasks
+}
+
+fn parse_cargo_aliases(config: &str) -> Vec<WorkspaceTask> {
+    let Ok(config) = toml::from_str::<toml::Table>(config) else {
+        return Vec::new();
+    };
+    let Some(aliases) = config.get("alias").and_then(|alias| alias.as_table()) else {
+        return Vec::new();
+    };
+    aliases
+        .iter()
+        .filter_map(|(name, expansion)| {
+            let expansion = match expansion {
+                toml::Value::String(expansion) => expansion.clone(),
+                toml::Value::Array(args) => args
+                    .iter()
+                    .map(|arg| arg.as_str())
+                    .collect::<Option<Vec<_>>>()?
+                    .join(" "),
+                _ => return None,
+            };
+            Some(WorkspaceTask {
+                name: name.clone(),
+                command: format!("cargo {name}"),
+                source: WorkspaceTaskSource::Cargo,
+                description: Some(format!("cargo {expansion}")),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(tasks: &[WorkspaceTask]) -> Vec<&str> {
+        tasks.iter().map(|task| task.name.as_str()).collect()
+    }
+
+    #[test]
+    fn make_targets_skip_special_pattern_and_assignment_lines() {
+        let targets = parse_make_targets(
+            "CC := gcc\nFLAGS = -O2\n.PHONY: test lint\n\
+             build: src/main.c ## Build the binary\n\tgcc -o app src/main.c\n\
+             test lint:\n\t./run\n%.o: %.c\n\t$(CC) -c $<\n# all: nope\nbuild:\n",
+        );
+        assert_eq!(names(&targets), vec!["build", "test", "lint"]);
+        assert_eq!(targets[0].command, "make build");
+        assert_eq!(targets[0].description.as_deref(), Some("Build the binary"));
+        assert_eq!(targets[1].description, None);
+    }
+
+    #[test]
+    fn cargo_aliases_accept_strings_and_lists() {
+        let tasks = parse_cargo_aliases(
+            "[alias]\nxtask = \"run --package xtask --\"\nci = [\"test\", \"--all\"]\nbad = 3\n",
+        );
+        assert_eq!(names(&tasks), vec!["ci", "xtask"]);
+        assert_eq!(tasks[0].command, "cargo ci");
+        assert_eq!(tasks[0].description.as_deref(), Some("cargo test --all"));
+    }
+
+    #[test]
+    fn discover_merges_sources_with_config_first() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("package.json"),
+            r#"{"scripts": {"test": "vitest", "pretest": "tsc", "lint": "eslint ."}}"#,
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("pnpm-lock.yaml"), "").unwrap();
+        std::fs::write(
+            dir.path().join("Makefile"),
+            "lint:\n\ttrue\ndocs:\n\ttrue\n",
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("Cargo.toml"), "[package]\nname = \"x\"\n").unwrap();
+        let config: ProjectConfig =
+            toml::from_str("[tasks.test]\ncommand = \"make check\"\n").unwrap();
+
+        let tasks = discover_tasks(dir.path(), Some(&config));
+        assert_eq!(
+            names(&tasks),
+            vec!["test", "lint", "docs", "build", "clippy"]
+        );
+        assert_eq!(tasks[0].source, WorkspaceTaskSource::Loopwire);
+        assert_eq!(tasks[0].command, "make check");
+        assert_eq!(tasks[1].source, WorkspaceTaskSource::Npm);
+        assert_eq!(tasks[1].command, "pnpm run lint");
+        assert_eq!(tasks[2].source, WorkspaceTaskSource::Make);
+        assert_eq!(tasks[3].command, "cargo build");
+    }
+
+    #[test]
+    fn discover_empty_workspace_has_no_tasks() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("package.json"), "{ not json").unwrap();
+        assert!(discover_tasks(dir.path(), None).is_empty());
+    }
+}
@@ -1,27 +1,115 @@
+use axum::body::Body;
 use axum::extract::Request;
 use axum::http::StatusCode;
 use axum::middleware::Next;
 use axum::response::Response;
+use chrono::Utc;
 use lw_config::ConfigPaths;
 use sha2::{Digest, Sha256};
 use std::collections::HashSet;
-use std::sync::Arc;
+use std::path::Path;
+use std::time::SystemTime;
 use tokio::sync::RwLock;
+use uuid::Uuid;
 
 use crate::error::{ApiError, ApiErrorResponse};
 
+mod access;
+mod api_tokens;
+mod sessions;
+
+pub use access::{required_scope, Access};
+pub use api_tokens::{
+    create_api_token, list_api_tokens, revoke_api_token, ApiToken, ApiTokenError, NewApiToken,
+    TokenScope,
+};
+pub use sessions::{
+    list_sessions, revoke_session_by_id, NewSession, SessionInfo, SessionOrigin, SESSION_IDLE_DAYS,
+};
+
+type FileStamp = Option<(SystemTime, u64)>;
+
+/// Modification stamp of a token file, to tell when it needs re-reading.
+fn file_stamp(path: &Path) -> FileStamp {
+    let metadata = std::fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/// Session tokens as last read from or written to disk.
+struct SessionCache {
+    stamp: FileStamp,
+    sessions: Vec<sessions::StoredSession>,
+}
+
+impl SessionCache {
+    fn load(paths: &ConfigPaths) -> Self {
+        let mut cache = Self {
+            stamp: None,
+            sessions: Vec::new(),
+        };
+        cache.reload(paths);
+        cache
+    }
+
+    /// Re-reads the file. One that doesn't parse leaves the sessions as
+    /// they were rather than signing everyone out.
+    fn reload(&mut self, paths: &ConfigPaths) {
+        self.stamp = file_stamp(&paths.session_tokens_path());
+        match sessions::load_stored(paths) {
+            Ok(sessions) => self.sessions = sessions,
+            Err(err) => tracing::warn!("Failed to load sessions: {}", err),
+        }
+    }
+
+    fn position(&self, hash: &str) -> Option<usize> {
+        self.sessions
+            .iter()
+            .position(|stored| stored.token_hash == hash)
+    }
+}
+
+/// API tokens as last read from disk, with the file stamp they were read at.
+struct ApiTokenCache {
+    stamp: FileStamp,
+    tokens: Vec<api_tokens::StoredApiToken>,
+}
+
+impl ApiTokenCache {
+    fn load(paths: &ConfigPaths) -> Self {
+        let mut cache = Self {
+            stamp: None,
+            tokens: Vec::new(),
+        };
+        cache.reload(paths);
+        cache
+    }
+
+    /// Re-reads the file. One that doesn't parse leaves the tokens as they
+    /// were.
+    fn reload(&mut self, paths: &ConfigPaths) {
+        self.stamp = file_stamp(&paths.api_tokens_path());
+        match api_tokens::load_stored(paths) {
+            Ok(tokens) => self.tokens = tokens,
+            Err(err) => tracing::warn!("Failed to load API tokens: {}", err),
+        }
+    }
+}
+
 pub struct TokenStore {
     bootstrap_token_hash: RwLock<Option<String>>,
-    session_token_hashes: Arc<RwLock<HashSet<String>>>,
+    sessions: RwLock<SessionCache>,
+    api_tokens: RwLock<ApiTokenCache>,
     paths: ConfigPaths,
 }
 
 impl TokenStore {
     pub fn new(bootstrap_token_hash: String, paths: ConfigPaths) -> Self {
-        let sessions = load_session_hashes(&paths);
+        let sessions = SessionCache::load(&paths);
+        let api_tokens = ApiTokenCache::load(&paths);
         Self {
             bootstrap_token_hash: RwLock::new(Some(bootstrap_token_hash)),
-            session_token_hashes: Arc::new(RwLock::new(sessions)),
+            sessions: RwLock::new(sessions),
+            api_tokens: RwLock::new(api_tokens),
             paths,
         }
     }
@@ -48,51 +136,189 @@ impl TokenStore {
         }
     }
 
-    pub async fn add_session_token(&self, token_hash: String) {
-        self.session_token_hashes.write().await.insert(token_hash);
-        self.persist_sessions().await;
+    pub async fn add_session_token(&self, token_hash: String, new: NewSession) -> SessionInfo {
+        self.refresh_sessions().await;
+        let stored = sessions::StoredSession::new(token_hash, new);
+        let info = stored.info.clone();
+        let mut cache = self.sessions.write().await;
+        cache.sessions.push(stored);
+        self.persist_sessions(&mut cache);
+        info
     }
 
     pub async fn validate_session(&self, token: &str) -> bool {
-        let hash = Self::hash_token(token);
-        self.session_token_hashes.read().await.contains(&hash)
+        self.session(token).await.is_some()
     }
 
-    pub async fn revoke_session(&self, token: &str) -> bool {
+    /// The session `token` belongs to, recording the use: `last_seen_at`
+    /// and the sliding expiry move forward.
+    pub async fn session(&self, token: &str) -> Option<SessionInfo> {
         let hash = Self::hash_token(token);
-        let removed = self.session_token_hashes.write().await.remove(&hash);
-        if removed {
-            self.persist_sessions().await;
+        let now = Utc::now();
+        self.refresh_sessions().await;
+        {
+            let cache = self.sessions.read().await;
+            let stored = &cache.sessions[cache.position(&hash)?];
+            if !stored.info.is_expired(now) && !stored.info.is_stale(now) {
+                return Some(stored.info.clone());
+            }
+        }
+
+        let mut cache = self.sessions.write().await;
+        let index = cache.position(&hash)?;
+        if cache.sessions[index].info.is_expired(now) {
+            cache.sessions.remove(index);
+            self.persist_sessions(&mut cache);
+            return None;
         }
-        removed
+        cache.sessions[index].info.touch(now);
+        let info = cache.sessions[index].info.clone();
+        self.persist_sessions(&mut cache);
+        Some(info)
+    }
+
+    /// Unexpired sessions, most recently seen first.
+    pub async fn list_sessions(&self) -> Vec<SessionInfo> {
+        self.refresh_sessions().await;
+        let now = Utc::now();
+        let cache = self.sessions.read().await;
+        let mut sessions: Vec<SessionInfo> = cache
+            .sessions
+            .iter()
+            .map(|stored| stored.info.clone())
+            .filter(|info| !info.is_expired(now))
+            .collect();
+        sessions.sort_by(|a, b| b.last_seen_at.cmp(&a.last_seen_at));
+        sessions
     }
 
-    pub async fn rotate_session(&self, old_token: &str) -> Option<()> {
+    pub async fn revoke_session(&self, token: &str) -> bool {
+        let hash = Self::hash_token(token);
+        self.refresh_sessions().await;
+        let mut cache = self.sessions.write().await;
+        let Some(index) = cache.position(&hash) else {
+            return false;
+        };
+        cache.sessions.remove(index);
+        self.persist_sessions(&mut cache);
+        true
+    }
+
+    pub async fn revoke_session_by_id(&self, id: &Uuid) -> Option<SessionInfo> {
+        self.refresh_sessions().await;
+        let mut cache = self.sessions.write().await;
+        let index = cache
+            .sessions
+            .iter()
+            .position(|stored| stored.info.id == *id)?;
+        let removed = cache.sessions.remove(index);
+        self.persist_sessions(&mut cache);
+        Some(removed.info)
+    }
+
+    /// Swaps the session's token for `new_hash`, keeping its id and device
+    /// details.
+    pub async fn rotate_session(&self, old_token: &str, new_hash: String) -> Option<SessionInfo> {
         let old_hash = Self::hash_token(old_token);
-        let mut tokens = self.session_token_hashes.write().await;
-        if tokens.remove(&old_hash) {
-            drop(tokens);
-            self.persist_sessions().await;
-            Some(())
-        } else {
-            None
+        self.refresh_sessions().await;
+        let mut cache = self.sessions.write().await;
+        let index = cache.position(&old_hash)?;
+        let now = Utc::now();
+        if cache.sessions[index].info.is_expired(now) {
+            return None;
+        }
+        let stored = &mut cache.sessions[index];
+        stored.token_hash = new_hash;
+        stored.info.touch(now);
+        let info = stored.info.clone();
+        self.persist_sessions(&mut cache);
+        Some(info)
+    }
+
+    /// Re-reads the sessions file if something else (`loopwired sessions`)
+    /// changed it.
+    async fn refresh_sessions(&self) {
+        let stamp = file_stamp(&self.paths.session_tokens_path());
+        if self.sessions.read().await.stamp == stamp {
+            return;
+        }
+        let mut cache = self.sessions.write().await;
+        if cache.stamp != stamp {
+            cache.reload(&self.paths);
         }
     }
 
-    pub async fn set_bootstrap_hash(&self, hash: String) {
-        *self.bootstrap_token_hash.write().await = Some(hash);
+    fn persist_sessions(&self, cache: &mut SessionCache) {
+        if let Err(e) = sessions::write_stored(&self.paths, &cache.sessions) {
+            tracing::warn!("Failed to persist sessions: {}", e);
+        }
+        cache.stamp = file_stamp(&self.paths.session_tokens_path());
     }
 
-    async fn persist_sessions(&self) {
-        let hashes = self.session_token_hashes.read().await;
-        let content = hashes.iter().cloned().collect::<Vec<_>>().join("\n");
-        let path = self.paths.sessions_path();
-        if let Err(e) = std::fs::write(&path, content) {
-            tracing::warn!("Failed to persist session hashes: {}", e);
+    /// What `token` may do: everything for a session token, its scopes for
+    /// an unexpired API token, nothing otherwise.
+    pub async fn authenticate(&self, token: &str) -> Option<Access> {
+        if self.validate_session(token).await {
+            return Some(Access::Session);
         }
+        self.validate_api_token(token).await.map(Access::ApiToken)
+    }
+
+    pub async fn validate_api_token(&self, token: &str) -> Option<ApiToken> {
+        let hash = Self::hash_token(token);
+        let stamp = file_stamp(&self.paths.api_tokens_path());
+        let find = |cache: &ApiTokenCache| {
+            cache
+                .tokens
+                .iter()
+                .find(|stored| stored.token_hash == hash)
+                .map(|stored| stored.token.clone())
+        };
+
+        let cache = self.api_tokens.read().await;
+        let found = if cache.stamp == stamp {
+            find(&cache)
+        } else {
+            // Created or revoked through the CLI since we last looked.
+            drop(cache);
+            let mut cache = self.api_tokens.write().await;
+            cache.reload(&self.paths);
+            find(&cache)
+        };
+        found.filter(|token| !token.is_expired(chrono::Utc::now()))
+    }
+
+    pub async fn list_api_tokens(&self) -> Result<Vec<ApiToken>, ApiTokenError> {
+        let _cache = self.api_tokens.read().await;
+        list_api_tokens(&self.paths)
+    }
+
+    pub async fn create_api_token(
+        &self,
+        new: NewApiToken,
+    ) -> Result<(String, ApiToken), ApiTokenError> {
+        let mut cache = self.api_tokens.write().await;
+        let created = create_api_token(&self.paths, new);
+        cache.reload(&self.paths);
+        created
+    }
+
+    pub async fn revoke_api_token(
+        &self,
+        id_or_name: &str,
+    ) -> Result<Option<ApiToken>, ApiTokenError> {
+        let mut cache = self.api_tokens.write().await;
+        let revoked = revoke_api_token(&self.paths, id_or_name);
+        cache.reload(&self.paths);
+        revoked
+    }
+
+    pub async fn set_bootstrap_hash(&self, hash: String) {
+        *self.bootstrap_token_hash.write().await = Some(hash);
     }
 }
 
+/// Reads the session hash list of older versions, for migration.
 fn load_session_hashes(paths: &ConfigPaths) -> HashSet<String> {
     let path = paths.sessions_path();
     match std::fs::read_to_string(&path) {
@@ -158,16 +384,73 @@ pub async fn auth_middleware(
         error: ApiError::unauthorized(),
     })?;
 
-    if !state.token_store.validate_session(&token).await {
-        return Err(ApiErrorResponse {
+    let access = state
+        .token_store
+        .authenticate(&token)
+        .await
+        .ok_or_else(|| ApiErrorResponse {
             status: StatusCode::UNAUTHORIZED,
             error: ApiError::invalid_token(),
-        });
-    }
+        })?;
 
+    let mut req = match &access {
+        Access::Session => req,
+        Access::ApiToken(api_token) => authorize_api_token(&state, api_token, req).await?,
+    };
+    req.extensions_mut().insert(access);
     Ok(next.run(req).await)
 }
 
+/// Checks an API token request against the token's scopes and workspaces.
+/// A workspace-restricted token must name its workspace in the request, so
+/// the body is read here and put back.
+async fn authorize_api_token(
+    state: &crate::state::AppState,
+    token: &ApiToken,
+    req: Request,
+) -> Result<Request, ApiErrorResponse> {
+    let forbidden = |code: &str, message: String| ApiErrorResponse {
+        status: StatusCode::FORBIDDEN,
+        error: ApiError::new(code, message),
+    };
+
+    let Some(scope) = required_scope(req.method(), req.uri().path()) else {
+        return Err(forbidden(
+            "SESSION_REQUIRED",
+            "API tokens can't use this endpoint".to_string(),
+        ));
+    };
+    if !token.has_scope(scope) {
+        return Err(forbidden(
+            "INSUFFICIENT_SCOPE",
+            format!("Token '{}' lacks the '{}' scope", token.name, scope),
+        ));
+    }
+    if !token.is_workspace_restricted() {
+        return Ok(req);
+    }
+
+    let (parts, body) = req.into_parts();
+    let body = axum::body::to_bytes(body, crate::rest::fs::MAX_WRITE_BODY_BYTES)
+        .await
+        .map_err(|_| ApiErrorResponse {
+            status: StatusCode::PAYLOAD_TOO_LARGE,
+            error: ApiError::new("PAYLOAD_TOO_LARGE", "Request body is too large"),
+        })?;
+    let workspaces = access::request_workspaces(state, &parts.uri, &body).await;
+    let allowed = !workspaces.is_empty()
+        && workspaces
+            .iter()
+            .all(|workspace| workspace.is_some_and(|id| token.allows_workspace(&id)));
+    if !allowed {
+        return Err(forbidden(
+            "WORKSPACE_FORBIDDEN",
+            format!("Token '{}' is limited to other workspaces", token.name),
+        ));
+    }
+    Ok(Request::from_parts(parts, Body::from(body)))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -265,7 +548,7 @@ mod tests {
         let (_dir, store) = make_token_store();
         let token = "session-token-123";
         let hash = TokenStore::hash_token(token);
-        store.add_session_token(hash).await;
+        store.add_session_token(hash, NewSession::default()).await;
         assert!(store.validate_session(token).await);
         assert!(store.revoke_session(token).await);
         assert!(!store.validate_session(token).await);
@@ -276,20 +559,105 @@ mod tests {
         let (_dir, store) = make_token_store();
         let old_token = "old-token";
         let old_hash = TokenStore::hash_token(old_token);
-        store.add_session_token(old_hash).await;
+        let session = store
+            .add_session_token(old_hash, NewSession::default())
+            .await;
         assert!(store.validate_session(old_token).await);
-        let result = store.rotate_session(old_token).await;
-        assert!(result.is_some());
+        let result = store
+            .rotate_session(old_token, TokenStore::hash_token("new-token"))
+            .await;
+        assert_eq!(result.map(|rotated| rotated.id), Some(session.id));
         assert!(!store.validate_session(old_token).await);
+        assert!(store.validate_session("new-token").await);
     }
 
     #[tokio::test]
     async fn rotate_session_nonexistent_returns_none() {
         let (_dir, store) = make_token_store();
-        let result = store.rotate_session("nonexistent-token").await;
+        let result = store
+            .rotate_session("nonexistent-token", TokenStore::hash_token("new-token"))
+            .await;
         assert!(result.is_none());
     }
 
+    #[tokio::test]
+    async fn sessions_keep_device_details_and_can_be_revoked_by_id() {
+        let (_dir, store) = make_token_store();
+        let phone = store
+            .add_session_token(
+                TokenStore::hash_token("phone"),
+                NewSession {
+                    label: Some("Phone".to_string()),
+                    user_agent: Some("Mobile Safari".to_string()),
+                    origin: SessionOrigin::Remote,
+                },
+            )
+            .await;
+        store
+            .add_session_token(TokenStore::hash_token("laptop"), NewSession::default())
+            .await;
+
+        let listed = store.list_sessions().await;
+        assert_eq!(listed.len(), 2);
+        let listed_phone = listed.iter().find(|s| s.id == phone.id).unwrap();
+        assert_eq!(listed_phone.label.as_deref(), Some("Phone"));
+        assert_eq!(listed_phone.origin, Some(SessionOrigin::Remote));
+
+        assert_eq!(
+            store.revoke_session_by_id(&phone.id).await.map(|s| s.id),
+            Some(phone.id)
+        );
+        assert!(!store.validate_session("phone").await);
+        assert!(store.validate_session("laptop").await);
+    }
+
+    #[tokio::test]
+    async fn sessions_slide_their_expiry_and_expire_when_idle() {
+        let (dir, store) = make_token_store();
+        let paths = ConfigPaths::with_base(dir.path().to_path_buf());
+        let session = store
+            .add_session_token(TokenStore::hash_token("idle"), NewSession::default())
+            .await;
+
+        // Seen a while ago: a use moves last_seen and the expiry forward.
+        {
+            let mut cache = store.sessions.write().await;
+            cache.sessions[0].info.last_seen_at -= chrono::Duration::hours(1);
+            cache.sessions[0].info.expires_at -= chrono::Duration::hours(1);
+        }
+        let seen = store.session("idle").await.unwrap();
+        assert!(seen.last_seen_at > session.last_seen_at - chrono::Duration::minutes(1));
+        assert!(seen.expires_at >= session.expires_at);
+
+        store.sessions.write().await.sessions[0].info.expires_at = Utc::now();
+        assert!(!store.validate_session("idle").await);
+        assert!(list_sessions(&paths).unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn sessions_revoked_by_the_cli_are_picked_up() {
+        let (dir, store) = make_token_store();
+        let paths = ConfigPaths::with_base(dir.path().to_path_buf());
+        let session = store
+            .add_session_token(TokenStore::hash_token("device"), NewSession::default())
+            .await;
+
+        revoke_session_by_id(&paths, &session.id).unwrap();
+        assert!(!store.validate_session("device").await);
+    }
+
+    #[tokio::test]
+    async fn legacy_session_hashes_keep_working() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = ConfigPaths::with_base(dir.path().to_path_buf());
+        std::fs::write(paths.sessions_path(), TokenStore::hash_token("old-device")).unwrap();
+
+        let store = TokenStore::new(TokenStore::hash_token("bootstrap"), paths.clone());
+        assert!(store.validate_session("old-device").await);
+        assert!(paths.session_tokens_path().exists());
+        assert!(!paths.sessions_path().exists());
+    }
+
     #[tokio::test]
     async fn consumed_bootstrap_rejects_validation() {
         let (_dir, store) = make_token_store();
@@ -308,6 +676,175 @@ mod tests {
         assert!(store.validate_bootstrap("new-secret").await);
     }
 
+    fn api_token(name: &str, scopes: &[TokenScope], workspace_ids: Vec<uuid::Uuid>) -> NewApiToken {
+        NewApiToken {
+            name: name.to_string(),
+            scopes: scopes.to_vec(),
+            workspace_ids,
+            expires_at: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn authenticate_tells_sessions_from_api_tokens() {
+        let (_dir, store) = make_token_store();
+        store
+            .add_session_token(TokenStore::hash_token("session"), NewSession::default())
+            .await;
+        let (secret, created) = store
+            .create_api_token(api_token("ci", &[TokenScope::ReadOnly], Vec::new()))
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            store.authenticate("session").await,
+            Some(Access::Session)
+        ));
+        match store.authenticate(&secret).await {
+            Some(Access::ApiToken(token)) => assert_eq!(token, created),
+            other => panic!("unexpected access: {other:?}"),
+        }
+        // API tokens are not session tokens.
+        assert!(!store.validate_session(&secret).await);
+
+        store.revoke_api_token("ci").await.unwrap();
+        assert!(store.authenticate(&secret).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn api_tokens_written_by_the_cli_are_picked_up() {
+        let (dir, store) = make_token_store();
+        let paths = ConfigPaths::with_base(dir.path().to_path_buf());
+        assert!(store.authenticate("unknown").await.is_none());
+
+        let (secret, _) =
+            create_api_token(&paths, api_token("bot", &[TokenScope::Agents], Vec::new())).unwrap();
+        assert!(store.validate_api_token(&secret).await.is_some());
+
+        revoke_api_token(&paths, "bot").unwrap();
+        assert!(store.validate_api_token(&secret).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn corrupt_token_files_keep_the_cached_tokens() {
+        let (dir, store) = make_token_store();
+        let paths = ConfigPaths::with_base(dir.path().to_path_buf());
+        store
+            .add_session_token(TokenStore::hash_token("device"), NewSession::default())
+            .await;
+        let (secret, _) = store
+            .create_api_token(api_token("ci", &[TokenScope::ReadOnly], Vec::new()))
+            .await
+            .unwrap();
+
+        std::fs::write(paths.session_tokens_path(), "{\"sessions\": [").unwrap();
+        std::fs::write(paths.api_tokens_path(), "{\"tokens\": [").unwrap();
+        assert!(store.validate_session("device").await);
+        assert!(store.validate_api_token(&secret).await.is_some());
+    }
+
+    #[tokio::test]
+    async fn expired_api_tokens_are_rejected() {
+        let (_dir, store) = make_token_store();
+        let mut new = api_token("short", &[TokenScope::ReadOnly], Vec::new());
+        new.expires_at = Some(chrono::Utc::now() + chrono::Duration::milliseconds(50));
+        let (secret, _) = store.create_api_token(new).await.unwrap();
+        assert!(store.validate_api_token(&secret).await.is_some());
+
+        tokio::time::sleep(std::time::Duration::from_millis(60)).await;
+        assert!(store.validate_api_token(&secret).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn api_tokens_are_held_to_their_scopes_and_workspaces() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = lw_config::DaemonConfig::default();
+        config.set_paths(ConfigPaths::with_base(dir.path().join("data")));
+        let state = crate::state::AppState::new(config, TokenStore::hash_token("test")).unwrap();
+        let allowed = uuid::Uuid::new_v4();
+        let other = uuid::Uuid::new_v4();
+
+        let token = |scopes: &[TokenScope], workspace_ids: Vec<uuid::Uuid>| ApiToken {
+            id: uuid::Uuid::new_v4(),
+            name: "ci".to_string(),
+            scopes: scopes.to_vec(),
+            workspace_ids,
+            created_at: chrono::Utc::now(),
+            expires_at: None,
+        };
+        let request = |method: &str, uri: String, body: serde_json::Value| {
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+        let status = |result: Result<Request, ApiErrorResponse>| match result {
+            Ok(_) => StatusCode::OK,
+            Err(err) => err.status,
+        };
+
+        let ci = token(&[TokenScope::ReadOnly, TokenScope::FsWrite], Vec::new());
+        let share = request(
+            "POST",
+            "/api/v1/remote/share/start".to_string(),
+            serde_json::json!({}),
+        );
+        assert_eq!(
+            status(authorize_api_token(&state, &ci, share).await),
+            StatusCode::FORBIDDEN
+        );
+        let tokens = request(
+            "GET",
+            "/api/v1/auth/tokens".to_string(),
+            serde_json::json!({}),
+        );
+        assert_eq!(
+            status(authorize_api_token(&state, &ci, tokens).await),
+            StatusCode::FORBIDDEN
+        );
+        let write = request(
+            "POST",
+            "/api/v1/fs/write".to_string(),
+            serde_json::json!({ "workspace_id": other }),
+        );
+        assert_eq!(
+            status(authorize_api_token(&state, &ci, write).await),
+            StatusCode::OK
+        );
+
+        let limited = token(&[TokenScope::FsWrite], vec![allowed]);
+        let write = |workspace_id: uuid::Uuid| {
+            request(
+                "POST",
+                "/api/v1/fs/write".to_string(),
+                serde_json::json!({ "workspace_id": workspace_id, "content": "x" }),
+            )
+        };
+        let passed = authorize_api_token(&state, &limited, write(allowed))
+            .await
+            .unwrap();
+        // The body is still there for the handler.
+        let body = axum::body::to_bytes(passed.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert!(String::from_utf8_lossy(&body).contains("\"content\":\"x\""));
+        assert_eq!(
+            status(authorize_api_token(&state, &limited, write(other)).await),
+            StatusCode::FORBIDDEN
+        );
+        let smuggled = request(
+            "POST",
+            format!("/api/v1/fs/write?workspace_id={allowed}"),
+            serde_json::json!({ "workspace_id": other }),
+        );
+        assert_eq!(
+            status(authorize_api_token(&state, &limited, smuggled).await),
+            StatusCode::FORBIDDEN
+        );
+    }
+
     #[test]
     fn load_session_hashes_reads_from_file() {
         let dir = tempfile::tempdir().unwrap();
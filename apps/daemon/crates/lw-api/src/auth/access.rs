@@ -0,0 +1,226 @@
+//! What an authenticated request may do: everything for session tokens, the
+//! token's scopes and workspaces for API tokens.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use axum::extract::Query;
+use axum::http::{Method, Uri};
+use uuid::Uuid;
+
+use super::api_tokens::{ApiToken, TokenScope};
+use crate::state::AppState;
+
+/// Added to the extensions of requests that passed `auth_middleware`.
+#[derive(Debug, Clone)]
+pub enum Access {
+    Session,
+    ApiToken(ApiToken),
+}
+
+impl Access {
+    pub fn allows(&self, scope: TokenScope) -> bool {
+        match self {
+            Access::Session => true,
+            Access::ApiToken(token) => token.has_scope(scope),
+        }
+    }
+
+    pub fn allows_workspace(&self, workspace_id: &Uuid) -> bool {
+        match self {
+            Access::Session => true,
+            Access::ApiToken(token) => token.allows_workspace(workspace_id),
+        }
+    }
+
+    pub fn is_workspace_restricted(&self) -> bool {
+        match self {
+            Access::Session => false,
+            Access::ApiToken(token) => token.is_workspace_restricted(),
+        }
+    }
+}
+
+/// Scope an API token needs for a protected route. `None` leaves the route
+/// to session tokens: managing tokens and registering workspaces.
+pub fn required_scope(method: &Method, path: &str) -> Option<TokenScope> {
+    let path = path.strip_prefix("/api/v1/")?;
+    let section = path.split('/').next().unwrap_or_default();
+    if section == "auth" {
+        return None;
+    }
+    if matches!(*method, Method::GET | Method::HEAD) {
+        return Some(TokenScope::ReadOnly);
+    }
+    if *method != Method::POST {
+        return None;
+    }
+    match section {
+        "fs" if path == "fs/read_many" => Some(TokenScope::ReadOnly),
+        "fs" => Some(TokenScope::FsWrite),
+        "git" => Some(TokenScope::GitWrite),
+        "agents" | "shells" | "tasks" | "workspace-tasks" => Some(TokenScope::Agents),
+        "remote" if path.starts_with("remote/share/") => Some(TokenScope::RemoteShare),
+        _ => None,
+    }
+}
+
+/// Every workspace a request refers to, through a `workspace_id` or
+/// `workspace_path` in its query or JSON body, or the session, task or run
+/// in its path. `None` stands for a reference that matches no workspace.
+pub(crate) async fn request_workspaces(
+    state: &AppState,
+    uri: &Uri,
+    body: &[u8],
+) -> Vec<Option<Uuid>> {
+    let mut fields: Vec<(String, String)> = Vec::new();
+    if let Ok(Query(query)) = Query::<HashMap<String, String>>::try_from_uri(uri) {
+        fields.extend(query);
+    }
+    if let Ok(serde_json::Value::Object(body)) = serde_json::from_slice(body) {
+        fields.extend(body.into_iter().filter_map(|(name, value)| match value {
+            serde_json::Value::String(value) => Some((name, value)),
+            _ => None,
+        }));
+    }
+
+    let mut workspaces = Vec::new();
+    for (name, value) in fields {
+        match name.as_str() {
+            "workspace_id" => workspaces.push(value.parse().ok()),
+            "workspace_path" => workspaces.push(workspace_by_path(state, Path::new(&value)).await),
+            _ => {}
+        }
+    }
+
+    let segments: Vec<&str> = uri
+        .path()
+        .strip_prefix("/api/v1/")
+        .unwrap_or_default()
+        .split('/')
+        .collect();
+    match segments.as_slice() {
+        ["agents" | "shells", "sessions", id, ..] | ["workspace-tasks", "runs", id, ..] => {
+            let workspace = match id.parse() {
+                Ok(id) => crate::preview::session_workspace_id(state, id).await,
+                Err(_) => None,
+            };
+            workspaces.push(workspace);
+        }
+        ["tasks", id, ..] => {
+            let task = match id.parse() {
+                Ok(id) => state.task_manager.get(&id).await,
+                Err(_) => None,
+            };
+            let workspace = match task {
+                Some(task) => workspace_by_path(state, &task.workspace_path).await,
+                None => None,
+            };
+            workspaces.push(workspace);
+        }
+        _ => {}
+    }
+    workspaces
+}
+
+async fn workspace_by_path(state: &AppState, path: &Path) -> Option<Uuid> {
+    let canonical = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    state.workspace_registry.find_by_path(&canonical).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scopes_for_routes() {
+        let scope = |method: Method, path: &str| required_scope(&method, path);
+
+        assert_eq!(
+            scope(Method::GET, "/api/v1/fs/read"),
+            Some(TokenScope::ReadOnly)
+        );
+        assert_eq!(
+            scope(Method::GET, "/api/v1/agents/sessions"),
+            Some(TokenScope::ReadOnly)
+        );
+        assert_eq!(
+            scope(Method::POST, "/api/v1/fs/read_many"),
+            Some(TokenScope::ReadOnly)
+        );
+        assert_eq!(
+            scope(Method::POST, "/api/v1/fs/write"),
+            Some(TokenScope::FsWrite)
+        );
+        assert_eq!(
+            scope(Method::POST, "/api/v1/git/commit"),
+            Some(TokenScope::GitWrite)
+        );
+        assert_eq!(
+            scope(Method::POST, "/api/v1/agents/sessions/abc/input"),
+            Some(TokenScope::Agents)
+        );
+        assert_eq!(
+            scope(Method::POST, "/api/v1/tasks"),
+            Some(TokenScope::Agents)
+        );
+        assert_eq!(
+            scope(Method::POST, "/api/v1/workspace-tasks/run"),
+            Some(TokenScope::Agents)
+        );
+        assert_eq!(
+            scope(Method::POST, "/api/v1/remote/share/start"),
+            Some(TokenScope::RemoteShare)
+        );
+
+        assert_eq!(scope(Method::GET, "/api/v1/auth/tokens"), None);
+        assert_eq!(scope(Method::POST, "/api/v1/auth/rotate"), None);
+        assert_eq!(scope(Method::POST, "/api/v1/workspaces/register"), None);
+        assert_eq!(scope(Method::DELETE, "/api/v1/fs/write"), None);
+    }
+
+    #[tokio::test]
+    async fn workspace_from_query_body_or_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = lw_config::DaemonConfig::default();
+        config.set_paths(lw_config::ConfigPaths::with_base(dir.path().join("data")));
+        let state = AppState::new(config, crate::auth::TokenStore::hash_token("test")).unwrap();
+        let workspace_id = Uuid::new_v4();
+        state
+            .workspace_registry
+            .register(workspace_id, dir.path().to_path_buf())
+            .await
+            .unwrap();
+
+        let uri: Uri = format!("/api/v1/git/status?workspace_id={workspace_id}")
+            .parse()
+            .unwrap();
+        assert_eq!(
+            request_workspaces(&state, &uri, b"").await,
+            vec![Some(workspace_id)]
+        );
+
+        let uri: Uri = "/api/v1/shells/sessions".parse().unwrap();
+        let body = serde_json::json!({ "workspace_path": dir.path() }).to_string();
+        assert_eq!(
+            request_workspaces(&state, &uri, body.as_bytes()).await,
+            vec![Some(workspace_id)]
+        );
+
+        let other = Uuid::new_v4();
+        let uri: Uri = format!("/api/v1/fs/write?workspace_id={workspace_id}")
+            .parse()
+            .unwrap();
+        let body = serde_json::json!({ "workspace_id": other }).to_string();
+        let found = request_workspaces(&state, &uri, body.as_bytes()).await;
+        assert_eq!(found.len(), 2);
+        assert!(found.contains(&Some(workspace_id)) && found.contains(&Some(other)));
+
+        let uri: Uri = format!("/api/v1/agents/sessions/{}/stop", Uuid::new_v4())
+            .parse()
+            .unwrap();
+        assert_eq!(request_workspaces(&state, &uri, b"").await, vec![None]);
+        let uri: Uri = "/api/v1/bootstrap".parse().unwrap();
+        assert!(request_workspaces(&state, &uri, b"").await.is_empty());
+    }
+}
@@ -0,0 +1,331 @@
+//! Named API tokens for automation. Each one is limited to a set of scopes,
+//! optionally to some workspaces, and may expire.
+//!
+//! They live in `~/.loopwire/api_tokens.json` so `loopwired token create`
+//! works whether or not the daemon is running; the daemon notices changes to
+//! the file on the next request.
+
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use lw_config::ConfigPaths;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::{generate_token, TokenStore};
+use crate::rest::workspace::write_json_atomic;
+
+/// What an API token may do. Scopes don't imply each other, so a bot that
+/// writes files and reads them back needs both `fs-write` and `read-only`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TokenScope {
+    /// Any `GET` endpoint, the event WebSocket and dev server previews.
+    ReadOnly,
+    /// Writing, creating, moving and deleting workspace files.
+    FsWrite,
+    /// Starting, driving and stopping agents, shells and tasks.
+    Agents,
+    /// Staging, discarding and committing changes.
+    GitWrite,
+    /// Starting and stopping remote share tunnels.
+    RemoteShare,
+}
+
+impl TokenScope {
+    pub const ALL: [TokenScope; 5] = [
+        TokenScope::ReadOnly,
+        TokenScope::FsWrite,
+        TokenScope::Agents,
+        TokenScope::GitWrite,
+        TokenScope::RemoteShare,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TokenScope::ReadOnly => "read-only",
+            TokenScope::FsWrite => "fs-write",
+            TokenScope::Agents => "agents",
+            TokenScope::GitWrite => "git-write",
+            TokenScope::RemoteShare => "remote-share",
+        }
+    }
+}
+
+impl fmt::Display for TokenScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for TokenScope {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|scope| scope.as_str() == s)
+            .ok_or_else(|| {
+                let known: Vec<&str> = Self::ALL.iter().map(|scope| scope.as_str()).collect();
+                format!("unknown scope '{}' (expected {})", s, known.join(", "))
+            })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApiToken {
+    pub id: Uuid,
+    pub name: String,
+    pub scopes: Vec<TokenScope>,
+    /// Workspaces the token is limited to; empty for all of them.
+    #[serde(default)]
+    pub workspace_ids: Vec<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl ApiToken {
+    pub fn has_scope(&self, scope: TokenScope) -> bool {
+        self.scopes.contains(&scope)
+    }
+
+    pub fn is_workspace_restricted(&self) -> bool {
+        !self.workspace_ids.is_empty()
+    }
+
+    pub fn allows_workspace(&self, workspace_id: &Uuid) -> bool {
+        !self.is_workspace_restricted() || self.workspace_ids.contains(workspace_id)
+    }
+
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct NewApiToken {
+    pub name: String,
+    pub scopes: Vec<TokenScope>,
+    pub workspace_ids: Vec<Uuid>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ApiTokenError {
+    #[error("Token name must not be empty")]
+    EmptyName,
+    #[error("A token named '{0}' already exists")]
+    DuplicateName(String),
+    #[error("At least one scope is required")]
+    NoScopes,
+    #[error("Expiry must be in the future")]
+    AlreadyExpired,
+    #[error("Failed to access API tokens: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("API token file is corrupt: {0}")]
+    Corrupt(#[from] serde_json::Error),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(super) struct StoredApiToken {
+    #[serde(flatten)]
+    pub token: ApiToken,
+    pub token_hash: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ApiTokenFile {
+    #[serde(default)]
+    tokens: Vec<StoredApiToken>,
+}
+
+fn read_file(paths: &ConfigPaths) -> Result<ApiTokenFile, ApiTokenError> {
+    match std::fs::read_to_string(paths.api_tokens_path()) {
+        Ok(content) => Ok(serde_json::from_str(&content)?),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(ApiTokenFile::default()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+fn write_file(paths: &ConfigPaths, file: &ApiTokenFile) -> Result<(), ApiTokenError> {
+    write_json_atomic(&paths.api_tokens_path(), file)?;
+    Ok(())
+}
+
+pub(super) fn load_stored(paths: &ConfigPaths) -> Result<Vec<StoredApiToken>, ApiTokenError> {
+    Ok(read_file(paths)?.tokens)
+}
+
+/// All API tokens, oldest first.
+pub fn list_api_tokens(paths: &ConfigPaths) -> Result<Vec<ApiToken>, ApiTokenError> {
+    Ok(read_file(paths)?
+        .tokens
+        .into_iter()
+        .map(|stored| stored.token)
+        .collect())
+}
+
+/// Creates a token. Returns its plaintext, which isn't stored anywhere.
+pub fn create_api_token(
+    paths: &ConfigPaths,
+    new: NewApiToken,
+) -> Result<(String, ApiToken), ApiTokenError> {
+    let name = new.name.trim().to_string();
+    if name.is_empty() {
+        return Err(ApiTokenError::EmptyName);
+    }
+    if new.scopes.is_empty() {
+        return Err(ApiTokenError::NoScopes);
+    }
+    let now = Utc::now();
+    if new.expires_at.is_some_and(|expires_at| expires_at <= now) {
+        return Err(ApiTokenError::AlreadyExpired);
+    }
+
+    let mut file = read_file(paths)?;
+    if file.tokens.iter().any(|stored| stored.token.name == name) {
+        return Err(ApiTokenError::DuplicateName(name));
+    }
+
+    let mut scopes = new.scopes;
+    scopes.sort();
+    scopes.dedup();
+    let mut workspace_ids = new.workspace_ids;
+    workspace_ids.sort();
+    workspace_ids.dedup();
+
+    let secret = generate_token();
+    let token = ApiToken {
+        id: Uuid::new_v4(),
+        name,
+        scopes,
+        workspace_ids,
+        created_at: now,
+        expires_at: new.expires_at,
+    };
+    file.tokens.push(StoredApiToken {
+        token: token.clone(),
+        token_hash: TokenStore::hash_token(&secret),
+    });
+    write_file(paths, &file)?;
+    Ok((secret, token))
+}
+
+/// Revokes the token with this id or name, returning it if there was one.
+pub fn revoke_api_token(
+    paths: &ConfigPaths,
+    id_or_name: &str,
+) -> Result<Option<ApiToken>, ApiTokenError> {
+    let mut file = read_file(paths)?;
+    let Some(index) = file.tokens.iter().position(|stored| {
+        stored.token.id.to_string() == id_or_name || stored.token.name == id_or_name
+    }) else {
+        return Ok(None);
+    };
+    let removed = file.tokens.remove(index);
+    write_file(paths, &file)?;
+    Ok(Some(removed.token))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_token(name: &str, scopes: &[TokenScope]) -> NewApiToken {
+        NewApiToken {
+            name: name.to_string(),
+            scopes: scopes.to_vec(),
+            workspace_ids: Vec::new(),
+            expires_at: None,
+        }
+    }
+
+    #[test]
+    fn scopes_round_trip_through_their_names() {
+        for scope in TokenScope::ALL {
+            assert_eq!(scope.as_str().parse::<TokenScope>(), Ok(scope));
+            assert_eq!(
+                serde_json::to_value(scope).unwrap(),
+                serde_json::json!(scope.as_str())
+            );
+        }
+        assert!("admin"
+            .parse::<TokenScope>()
+            .unwrap_err()
+            .contains("read-only"));
+    }
+
+    #[test]
+    fn create_list_and_revoke() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = ConfigPaths::with_base(dir.path().to_path_buf());
+
+        let (secret, token) = create_api_token(
+            &paths,
+            new_token("ci", &[TokenScope::FsWrite, TokenScope::ReadOnly]),
+        )
+        .unwrap();
+        assert_eq!(secret.len(), 64);
+        assert_eq!(
+            token.scopes,
+            vec![TokenScope::ReadOnly, TokenScope::FsWrite]
+        );
+
+        let on_disk = std::fs::read_to_string(paths.api_tokens_path()).unwrap();
+        assert!(!on_disk.contains(&secret));
+        assert!(on_disk.contains(&TokenStore::hash_token(&secret)));
+
+        assert!(matches!(
+            create_api_token(&paths, new_token("ci", &[TokenScope::ReadOnly])),
+            Err(ApiTokenError::DuplicateName(_))
+        ));
+        assert_eq!(list_api_tokens(&paths).unwrap(), vec![token.clone()]);
+
+        assert_eq!(revoke_api_token(&paths, "ci").unwrap(), Some(token));
+        assert!(list_api_tokens(&paths).unwrap().is_empty());
+        assert_eq!(revoke_api_token(&paths, "ci").unwrap(), None);
+    }
+
+    #[test]
+    fn create_rejects_bad_input() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = ConfigPaths::with_base(dir.path().to_path_buf());
+
+        assert!(matches!(
+            create_api_token(&paths, new_token("  ", &[TokenScope::ReadOnly])),
+            Err(ApiTokenError::EmptyName)
+        ));
+        assert!(matches!(
+            create_api_token(&paths, new_token("bot", &[])),
+            Err(ApiTokenError::NoScopes)
+        ));
+        let mut expired = new_token("bot", &[TokenScope::ReadOnly]);
+        expired.expires_at = Some(Utc::now() - chrono::Duration::seconds(1));
+        assert!(matches!(
+            create_api_token(&paths, expired),
+            Err(ApiTokenError::AlreadyExpired)
+        ));
+    }
+
+    #[test]
+    fn workspace_restrictions_and_expiry() {
+        let workspace = Uuid::new_v4();
+        let mut token = ApiToken {
+            id: Uuid::new_v4(),
+            name: "bot".to_string(),
+            scopes: vec![TokenScope::ReadOnly],
+            workspace_ids: Vec::new(),
+            created_at: Utc::now(),
+            expires_at: None,
+        };
+        assert!(token.allows_workspace(&workspace));
+        token.workspace_ids = vec![Uuid::new_v4()];
+        assert!(!token.allows_workspace(&workspace));
+
+        let now = Utc::now();
+        assert!(!token.is_expired(now));
+        token.expires_at = Some(now);
+        assert!(token.is_expired(now));
+    }
+}
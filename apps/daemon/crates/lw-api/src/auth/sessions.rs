@@ -0,0 +1,276 @@
+//! Session tokens issued to browsers and phones, with enough metadata to
+//! tell devices apart and revoke one of them.
+//!
+//! They live in `~/.loopwire/sessions.json`; the bare hash list older
+//! versions kept in `session_hashes` is migrated on first load. Like API
+//! tokens, `loopwired sessions` edits the file directly and the daemon
+//! notices on the next request.
+
+use chrono::{DateTime, Duration, Utc};
+use lw_config::ConfigPaths;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::rest::workspace::write_json_atomic;
+
+/// A session expires after this many days without use.
+pub const SESSION_IDLE_DAYS: i64 = 30;
+
+/// How stale `last_seen_at` may get before a request refreshes it, so busy
+/// clients don't rewrite the file on every call.
+const LAST_SEEN_RESOLUTION_SECS: i64 = 60;
+
+/// How a session token was issued.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SessionOrigin {
+    /// Exchanged for the bootstrap token.
+    #[default]
+    Local,
+    /// Exchanged for a remote share invite.
+    Remote,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionInfo {
+    pub id: Uuid,
+    pub label: Option<String>,
+    pub user_agent: Option<String>,
+    /// Unknown for sessions migrated from the old hash list.
+    pub origin: Option<SessionOrigin>,
+    pub created_at: DateTime<Utc>,
+    pub last_seen_at: DateTime<Utc>,
+    /// Pushed back by every use.
+    pub expires_at: DateTime<Utc>,
+}
+
+impl SessionInfo {
+    fn new(new: NewSession, now: DateTime<Utc>) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            label: new.label.filter(|label| !label.trim().is_empty()),
+            user_agent: new.user_agent,
+            origin: Some(new.origin),
+            created_at: now,
+            last_seen_at: now,
+            expires_at: now + Duration::days(SESSION_IDLE_DAYS),
+        }
+    }
+
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at <= now
+    }
+
+    /// Whether a use at `now` should be recorded.
+    pub(super) fn is_stale(&self, now: DateTime<Utc>) -> bool {
+        now - self.last_seen_at >= Duration::seconds(LAST_SEEN_RESOLUTION_SECS)
+    }
+
+    pub(super) fn touch(&mut self, now: DateTime<Utc>) {
+        self.last_seen_at = now;
+        self.expires_at = now + Duration::days(SESSION_IDLE_DAYS);
+    }
+}
+
+/// What's known about a client when it gets a session token.
+#[derive(Debug, Clone, Default)]
+pub struct NewSession {
+    pub label: Option<String>,
+    pub user_agent: Option<String>,
+    pub origin: SessionOrigin,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(super) struct StoredSession {
+    #[serde(flatten)]
+    pub info: SessionInfo,
+    pub token_hash: String,
+}
+
+impl StoredSession {
+    pub(super) fn new(token_hash: String, new: NewSession) -> Self {
+        Self {
+            info: SessionInfo::new(new, Utc::now()),
+            token_hash,
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SessionFile {
+    #[serde(default)]
+    sessions: Vec<StoredSession>,
+}
+
+/// Stored sessions that haven't expired, migrating `session_hashes` if
+/// there's no `sessions.json` yet. Fails if the file doesn't parse.
+pub(super) fn load_stored(paths: &ConfigPaths) -> std::io::Result<Vec<StoredSession>> {
+    let path = paths.session_tokens_path();
+    let sessions = match std::fs::read_to_string(&path) {
+        Ok(content) => {
+            serde_json::from_str::<SessionFile>(&content)
+                .map_err(|err| {
+                    std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        format!("{} is corrupt: {err}", path.display()),
+                    )
+                })?
+                .sessions
+        }
+        Err(_) => return Ok(migrate_session_hashes(paths)),
+    };
+    let now = Utc::now();
+    Ok(sessions
+        .into_iter()
+        .filter(|stored| !stored.info.is_expired(now))
+        .collect())
+}
+
+pub(super) fn write_stored(paths: &ConfigPaths, sessions: &[StoredSession]) -> std::io::Result<()> {
+    let file = SessionFile {
+        sessions: sessions.to_vec(),
+    };
+    write_json_atomic(&paths.session_tokens_path(), &file)
+}
+
+fn migrate_session_hashes(paths: &ConfigPaths) -> Vec<StoredSession> {
+    let hashes = super::load_session_hashes(paths);
+    if hashes.is_empty() {
+        return Vec::new();
+    }
+    let now = Utc::now();
+    let sessions: Vec<StoredSession> = hashes
+        .into_iter()
+        .map(|token_hash| {
+            let mut info = SessionInfo::new(NewSession::default(), now);
+            info.origin = None;
+            StoredSession { info, token_hash }
+        })
+        .collect();
+    match write_stored(paths, &sessions) {
+        Ok(()) => {
+            let _ = std::fs::remove_file(paths.sessions_path());
+            tracing::info!(
+                "Migrated {} session tokens to {}",
+                sessions.len(),
+                paths.session_tokens_path().display()
+            );
+        }
+        Err(err) => tracing::warn!("Failed to migrate session hashes: {}", err),
+    }
+    sessions
+}
+
+/// Unexpired sessions, most recently seen first.
+pub fn list_sessions(paths: &ConfigPaths) -> std::io::Result<Vec<SessionInfo>> {
+    let mut sessions: Vec<SessionInfo> = load_stored(paths)?
+        .into_iter()
+        .map(|stored| stored.info)
+        .collect();
+    sessions.sort_by(|a, b| b.last_seen_at.cmp(&a.last_seen_at));
+    Ok(sessions)
+}
+
+/// Revokes the session with this id, returning it if there was one.
+pub fn revoke_session_by_id(
+    paths: &ConfigPaths,
+    id: &Uuid,
+) -> std::io::Result<Option<SessionInfo>> {
+    let mut sessions = load_stored(paths)?;
+    let Some(index) = sessions.iter().position(|stored| stored.info.id == *id) else {
+        return Ok(None);
+    };
+    let removed = sessions.remove(index);
+    write_stored(paths, &sessions)?;
+    Ok(Some(removed.info))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::TokenStore;
+
+    #[test]
+    fn migrates_the_hash_list() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = ConfigPaths::with_base(dir.path().to_path_buf());
+        let hash = TokenStore::hash_token("old-token");
+        std::fs::write(paths.sessions_path(), format!("{hash}\n")).unwrap();
+
+        let sessions = load_stored(&paths).unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].token_hash, hash);
+        assert_eq!(sessions[0].info.origin, None);
+        assert!(!paths.sessions_path().exists());
+        assert!(paths.session_tokens_path().exists());
+
+        // Loads from the new file from now on.
+        assert_eq!(load_stored(&paths).unwrap()[0].info.id, sessions[0].info.id);
+    }
+
+    #[test]
+    fn expired_sessions_are_dropped_on_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = ConfigPaths::with_base(dir.path().to_path_buf());
+        let fresh = StoredSession::new("a".to_string(), NewSession::default());
+        let mut stale = StoredSession::new("b".to_string(), NewSession::default());
+        stale.info.expires_at = Utc::now() - Duration::seconds(1);
+        write_stored(&paths, &[fresh.clone(), stale]).unwrap();
+
+        let sessions = load_stored(&paths).unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].info.id, fresh.info.id);
+    }
+
+    #[test]
+    fn corrupt_file_is_an_error_not_an_empty_list() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = ConfigPaths::with_base(dir.path().to_path_buf());
+        let phone = StoredSession::new("phone".to_string(), NewSession::default());
+        write_stored(&paths, std::slice::from_ref(&phone)).unwrap();
+        std::fs::write(paths.session_tokens_path(), "{\"sessions\": [").unwrap();
+
+        assert!(load_stored(&paths).is_err());
+        assert!(list_sessions(&paths).is_err());
+        assert!(revoke_session_by_id(&paths, &phone.info.id).is_err());
+        // Nothing was written over the file.
+        assert_eq!(
+            std::fs::read_to_string(paths.session_tokens_path()).unwrap(),
+            "{\"sessions\": ["
+        );
+    }
+
+    #[test]
+    fn list_and_revoke_by_id() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = ConfigPaths::with_base(dir.path().to_path_buf());
+        let phone = StoredSession::new(
+            "phone".to_string(),
+            NewSession {
+                label: Some("Phone".to_string()),
+                user_agent: Some("Mobile Safari".to_string()),
+                origin: SessionOrigin::Remote,
+            },
+        );
+        let laptop = StoredSession::new("laptop".to_string(), NewSession::default());
+        write_stored(&paths, &[phone.clone(), laptop.clone()]).unwrap();
+
+        assert_eq!(list_sessions(&paths).unwrap().len(), 2);
+        assert_eq!(
+            revoke_session_by_id(&paths, &phone.info.id).unwrap(),
+            Some(phone.info.clone())
+        );
+        assert_eq!(list_sessions(&paths).unwrap(), vec![laptop.info]);
+        assert_eq!(revoke_session_by_id(&paths, &phone.info.id).unwrap(), None);
+    }
+
+    #[test]
+    fn touch_slides_the_expiry() {
+        let mut info = SessionInfo::new(NewSession::default(), Utc::now() - Duration::days(10));
+        let now = Utc::now();
+        assert!(info.is_stale(now));
+        info.touch(now);
+        assert!(!info.is_stale(now));
+        assert_eq!(info.expires_at, now + Duration::days(SESSION_IDLE_DAYS));
+    }
+}
@@ -66,12 +66,41 @@ impl ApiError {
                 StatusCode::PAYLOAD_TOO_LARGE,
                 Self::new(err.error_code(), err.to_string()),
             ),
+            lw_fs::FsError::Conflict { current_etag } => {
+                let mut error = Self::new(err.error_code(), err.to_string());
+                error.details = Some(serde_json::json!({ "current_etag": current_etag }));
+                (StatusCode::CONFLICT, error)
+            }
+            lw_fs::FsError::AlreadyExists => (
+                StatusCode::CONFLICT,
+                Self::new(err.error_code(), err.to_string()),
+            ),
+            lw_fs::FsError::NotFound => (
+                StatusCode::NOT_FOUND,
+                Self::new(err.error_code(), err.to_string()),
+            ),
+            lw_fs::FsError::MoveIntoItself => (
+                StatusCode::BAD_REQUEST,
+                Self::new(err.error_code(), err.to_string()),
+            ),
             lw_fs::FsError::Io(e) => (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 Self::new("FS_IO_ERROR", e.to_string()),
             ),
         }
     }
+
+    pub fn prompt_queue_error(err: &lw_agent::PromptQueueError) -> (StatusCode, Self) {
+        let status = match err {
+            lw_agent::PromptQueueError::SessionNotFound
+            | lw_agent::PromptQueueError::PromptNotFound => StatusCode::NOT_FOUND,
+            lw_agent::PromptQueueError::AlreadyDelivered => StatusCode::CONFLICT,
+            lw_agent::PromptQueueError::EmptyPrompt | lw_agent::PromptQueueError::InvalidOrder => {
+                StatusCode::BAD_REQUEST
+            }
+        };
+        (status, Self::new(err.error_code(), err.to_string()))
+    }
 }
 
 #[derive(Debug)]
@@ -175,6 +204,27 @@ mod tests {
         assert_eq!(status, StatusCode::NOT_FOUND);
     }
 
+    #[test]
+    fn fs_error_conflict_includes_current_etag() {
+        let fs_err = lw_fs::FsError::Conflict {
+            current_etag: Some("abc-1".to_string()),
+        };
+        let (status, error) = ApiError::fs_error(&fs_err);
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(error.code, "FS_CONFLICT");
+        assert_eq!(error.details.unwrap()["current_etag"], "abc-1");
+    }
+
+    #[test]
+    fn fs_error_already_exists_and_not_found() {
+        let (status, _) = ApiError::fs_error(&lw_fs::FsError::AlreadyExists);
+        assert_eq!(status, StatusCode::CONFLICT);
+        let (status, _) = ApiError::fs_error(&lw_fs::FsError::NotFound);
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = ApiError::fs_error(&lw_fs::FsError::MoveIntoItself);
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
     #[test]
     fn fs_error_io() {
         let fs_err = lw_fs::FsError::Io(std::io::Error::new(std::io::ErrorKind::NotFound, "gone"));
@@ -221,4 +271,14 @@ mod tests {
         assert_eq!(api_err.code, "FS_IO_ERROR");
         assert!(api_err.message.contains("denied"));
     }
+
+    #[test]
+    fn prompt_queue_error_maps_status() {
+        let (status, api_err) =
+            ApiError::prompt_queue_error(&lw_agent::PromptQueueError::AlreadyDelivered);
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(api_err.code, "PROMPT_ALREADY_DELIVERED");
+        let (status, _) = ApiError::prompt_queue_error(&lw_agent::PromptQueueError::InvalidOrder);
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
 }
@@ -1,10 +1,15 @@
 pub mod auth;
 pub mod error;
+pub mod notifications;
+pub mod preview;
+pub mod project;
+pub mod prompt_queue;
 pub mod remote;
 pub mod rest;
 pub mod router;
 pub mod state;
+pub mod web;
 pub mod ws;
 
-pub use router::build_router;
+pub use router::{build_preview_router, build_router};
 pub use state::AppState;
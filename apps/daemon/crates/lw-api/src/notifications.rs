@@ -0,0 +1,465 @@
+mod sinks;
+
+use chrono::{DateTime, Utc};
+use lw_agent::{AgentActivity, AgentActivityPhase, ApprovalRequest};
+use lw_config::{NotificationEvent, NotificationRule, NotificationSinkKind};
+use lw_pty::PtySession;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Weak};
+use tokio::sync::{broadcast, mpsc};
+use tokio::task::JoinHandle;
+use tokio::time::{Duration, Instant};
+use uuid::Uuid;
+
+use crate::state::AppState;
+
+/// An agent activity transition worth telling someone about. Sent as the
+/// webhook body, on the command's stdin and as the payload of the WS
+/// `notification` message.
+#[derive(Debug, Clone, Serialize)]
+pub struct Notification {
+    pub id: Uuid,
+    pub event: NotificationEvent,
+    pub session_id: Uuid,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub workspace_id: Option<Uuid>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub workspace_path: Option<String>,
+    pub agent_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub approval: Option<ApprovalRequest>,
+    pub message: String,
+    pub created_at: DateTime<Utc>,
+}
+
+fn phase_event(phase: AgentActivityPhase) -> Option<NotificationEvent> {
+    match phase {
+        AgentActivityPhase::AwaitingUser => Some(NotificationEvent::AwaitingUser),
+        AgentActivityPhase::AwaitingApproval => Some(NotificationEvent::AwaitingApproval),
+        _ => None,
+    }
+}
+
+fn describe(
+    event: NotificationEvent,
+    name: &str,
+    approval: Option<&ApprovalRequest>,
+    exit_code: Option<u32>,
+) -> String {
+    match event {
+        NotificationEvent::AwaitingUser => format!("{name} is waiting for input"),
+        NotificationEvent::AwaitingApproval => match approval {
+            Some(approval) => format!("{name} needs approval to use {}", approval.tool),
+            None => format!("{name} needs approval"),
+        },
+        NotificationEvent::Exited => match exit_code {
+            Some(code) => format!("{name} exited with code {code}"),
+            None => format!("{name} exited"),
+        },
+    }
+}
+
+struct Pending {
+    rule: usize,
+    notification: Notification,
+    due: Instant,
+}
+
+/// Rule matching and debouncing, kept free of I/O.
+struct Dispatcher {
+    rules: Vec<NotificationRule>,
+    /// Notifiable phase each session is currently in.
+    phases: HashMap<Uuid, NotificationEvent>,
+    pending: Vec<Pending>,
+}
+
+impl Dispatcher {
+    fn new(rules: Vec<NotificationRule>) -> Self {
+        Self {
+            rules,
+            phases: HashMap::new(),
+            pending: Vec::new(),
+        }
+    }
+
+    /// Records the phase `session_id` is in now and drops pending
+    /// notifications for phases it has already left. Returns whether
+    /// `current` was newly entered.
+    fn set_phase(&mut self, session_id: Uuid, current: Option<NotificationEvent>) -> bool {
+        self.pending.retain(|pending| {
+            pending.notification.session_id != session_id
+                || pending.notification.event == NotificationEvent::Exited
+                || Some(pending.notification.event) == current
+        });
+        match current {
+            Some(event) => self.phases.insert(session_id, event) != Some(event),
+            None => {
+                self.phases.remove(&session_id);
+                false
+            }
+        }
+    }
+
+    /// Queues `notification` for every matching rule. Phase notifications
+    /// wait out the rule's debounce; exits go out on the next poll.
+    fn schedule(&mut self, notification: Notification, now: Instant) {
+        let session_id = notification.session_id.to_string();
+        let workspace_id = notification.workspace_id.map(|id| id.to_string());
+        for (index, rule) in self.rules.iter().enumerate() {
+            if !rule.matches(
+                notification.event,
+                workspace_id.as_deref(),
+                notification.workspace_path.as_deref(),
+                &session_id,
+            ) {
+                continue;
+            }
+            let due = if notification.event == NotificationEvent::Exited {
+                now
+            } else {
+                now + Duration::from_millis(rule.debounce_ms)
+            };
+            self.pending.retain(|pending| {
+                pending.rule != index
+                    || pending.notification.session_id != notification.session_id
+                    || pending.notification.event != notification.event
+            });
+            self.pending.push(Pending {
+                rule: index,
+                notification: notification.clone(),
+                due,
+            });
+        }
+    }
+
+    fn next_due(&self) -> Option<Instant> {
+        self.pending.iter().map(|pending| pending.due).min()
+    }
+
+    fn take_due(&mut self, now: Instant) -> Vec<(usize, Notification)> {
+        let (due, waiting) = std::mem::take(&mut self.pending)
+            .into_iter()
+            .partition(|pending| pending.due <= now);
+        self.pending = waiting;
+        due.into_iter()
+            .map(|pending: Pending| (pending.rule, pending.notification))
+            .collect()
+    }
+}
+
+/// Starts delivering notifications for the configured `[[notifications]]`
+/// rules. Returns `None` when there are none.
+pub fn spawn_notifier(state: AppState) -> Option<JoinHandle<()>> {
+    if state.config.notifications.is_empty() {
+        return None;
+    }
+    Some(tokio::spawn(run_notifier(state)))
+}
+
+async fn run_notifier(state: AppState) {
+    let mut dispatcher = Dispatcher::new(state.config.notifications.clone());
+    let client = reqwest::Client::new();
+    let mut activity_rx = state.agent_manager.subscribe_activity();
+    let (exit_tx, mut exit_rx) = mpsc::unbounded_channel::<(Uuid, Option<u32>)>();
+    // PTY whose exit is being watched, per session. Restarts replace it.
+    let mut exit_watches: HashMap<Uuid, Weak<PtySession>> = HashMap::new();
+
+    loop {
+        let next_due = dispatcher.next_due();
+        tokio::select! {
+            event = activity_rx.recv() => match event {
+                Ok(event) => {
+                    let session_id = event.session_id;
+                    watch_exit(&state, session_id, &mut exit_watches, &exit_tx).await;
+                    if event.activity.reason == "session_exit" {
+                        // Normally reported (with its exit code) by the
+                        // watcher; this covers exits it never saw.
+                        if !exit_watches.contains_key(&session_id) {
+                            dispatcher.set_phase(session_id, None);
+                            if let Some(notification) = build_notification(
+                                &state,
+                                session_id,
+                                NotificationEvent::Exited,
+                                None,
+                            )
+                            .await
+                            {
+                                dispatcher.schedule(notification, Instant::now());
+                            }
+                        }
+                        continue;
+                    }
+                    let current = phase_event(event.activity.phase);
+                    if !dispatcher.set_phase(session_id, current) {
+                        continue;
+                    }
+                    let Some(event_kind) = current else { continue };
+                    if let Some(notification) =
+                        build_notification(&state, session_id, event_kind, Some(&event.activity)).await
+                    {
+                        dispatcher.schedule(notification, Instant::now());
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    tracing::warn!(skipped, "notifier lagged behind activity events");
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+            Some((session_id, exit_code)) = exit_rx.recv() => {
+                dispatcher.set_phase(session_id, None);
+                if let Some(mut notification) =
+                    build_notification(&state, session_id, NotificationEvent::Exited, None).await
+                {
+                    notification.exit_code = exit_code;
+                    notification.message = describe(
+                        NotificationEvent::Exited,
+                        notification.session_name.as_deref().unwrap_or(&notification.agent_type),
+                        None,
+                        exit_code,
+                    );
+                    dispatcher.schedule(notification, Instant::now());
+                }
+            }
+            _ = sleep_until(next_due) => {}
+        }
+
+        for (rule, notification) in dispatcher.take_due(Instant::now()) {
+            deliver(&state, &client, &dispatcher.rules[rule], notification);
+        }
+    }
+}
+
+async fn sleep_until(deadline: Option<Instant>) {
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(deadline).await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Subscribes to the exit of the session's current PTY, once per PTY.
+async fn watch_exit(
+    state: &AppState,
+    session_id: Uuid,
+    exit_watches: &mut HashMap<Uuid, Weak<PtySession>>,
+    exit_tx: &mpsc::UnboundedSender<(Uuid, Option<u32>)>,
+) {
+    let Ok(session) = state.pty_manager.get(&session_id).await else {
+        return;
+    };
+    let current = Arc::downgrade(&session);
+    if exit_watches
+        .get(&session_id)
+        .is_some_and(|watched| watched.ptr_eq(&current))
+    {
+        return;
+    }
+    let mut exit_rx = session.subscribe_exit();
+    // Checked after subscribing: an exit racing with it is left to the
+    // activity fallback rather than reported twice.
+    if session.is_stopped() {
+        return;
+    }
+    exit_watches.insert(session_id, current);
+    let exit_tx = exit_tx.clone();
+    tokio::spawn(async move {
+        let exit_code = exit_rx.recv().await.ok().flatten();
+        let _ = exit_tx.send((session_id, exit_code));
+    });
+}
+
+/// Builds the notification for an agent session; `None` for sessions the
+/// agent manager doesn't know (e.g. shells).
+async fn build_notification(
+    state: &AppState,
+    session_id: Uuid,
+    event: NotificationEvent,
+    activity: Option<&AgentActivity>,
+) -> Option<Notification> {
+    let handle = state.agent_manager.get_handle(&session_id).await?;
+    let workspace_id = state
+        .workspace_registry
+        .find_by_path(&handle.workspace_path)
+        .await;
+    let agent_type = handle.agent_type.to_string();
+    let approval = activity.and_then(|activity| activity.approval.clone());
+    let name = handle.custom_name.as_deref().unwrap_or(&agent_type);
+    Some(Notification {
+        id: Uuid::new_v4(),
+        event,
+        session_id,
+        workspace_id,
+        workspace_path: Some(handle.workspace_path.to_string_lossy().into_owned()),
+        message: describe(event, name, approval.as_ref(), None),
+        session_name: handle.custom_name.clone(),
+        agent_type,
+        exit_code: None,
+        approval,
+        created_at: Utc::now(),
+    })
+}
+
+fn deliver(
+    state: &AppState,
+    client: &reqwest::Client,
+    rule: &NotificationRule,
+    notification: Notification,
+) {
+    match rule.sink {
+        NotificationSinkKind::Websocket => {
+            let _ = state.notifications.send(notification);
+        }
+        NotificationSinkKind::Webhook => {
+            let Some(url) = rule.url.clone() else { return };
+            let secret = rule.secret.clone();
+            let client = client.clone();
+            tokio::spawn(async move {
+                if let Err(err) =
+                    sinks::deliver_webhook(&client, &url, secret.as_deref(), &notification).await
+                {
+                    tracing::warn!(url, "notification webhook failed: {err:#}");
+                }
+            });
+        }
+        NotificationSinkKind::Command => {
+            let Some(command) = rule.command.clone() else {
+                return;
+            };
+            let args = rule.args.clone();
+            tokio::spawn(async move {
+                if let Err(err) = sinks::run_command(&command, &args, &notification).await {
+                    tracing::warn!(command, "notification command failed: {err:#}");
+                }
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    pub(super) fn notification(event: NotificationEvent) -> Notification {
+        Notification {
+            id: Uuid::new_v4(),
+            event,
+            session_id: Uuid::new_v4(),
+            workspace_id: None,
+            workspace_path: Some("/repo".to_string()),
+            agent_type: "claude_code".to_string(),
+            session_name: None,
+            exit_code: None,
+            approval: None,
+            message: describe(event, "claude_code", None, None),
+            created_at: Utc::now(),
+        }
+    }
+
+    fn rule(value: serde_json::Value) -> NotificationRule {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn phase_held_past_debounce_is_delivered() {
+        let mut dispatcher = Dispatcher::new(vec![rule(
+            json!({ "sink": "websocket", "debounce_ms": 1000 }),
+        )]);
+        let now = Instant::now();
+        let waiting = notification(NotificationEvent::AwaitingUser);
+        let session_id = waiting.session_id;
+
+        assert!(dispatcher.set_phase(session_id, Some(NotificationEvent::AwaitingUser)));
+        dispatcher.schedule(waiting, now);
+        assert!(dispatcher.take_due(now).is_empty());
+        assert_eq!(dispatcher.next_due(), Some(now + Duration::from_secs(1)));
+
+        let due = dispatcher.take_due(now + Duration::from_secs(1));
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].1.session_id, session_id);
+        assert!(dispatcher.next_due().is_none());
+    }
+
+    #[test]
+    fn flapping_phase_is_dropped() {
+        let mut dispatcher = Dispatcher::new(vec![rule(json!({ "sink": "websocket" }))]);
+        let now = Instant::now();
+        let waiting = notification(NotificationEvent::AwaitingUser);
+        let session_id = waiting.session_id;
+
+        dispatcher.set_phase(session_id, Some(NotificationEvent::AwaitingUser));
+        dispatcher.schedule(waiting, now);
+        // Back to work before the debounce elapsed.
+        assert!(!dispatcher.set_phase(session_id, None));
+        assert!(dispatcher
+            .take_due(now + Duration::from_secs(60))
+            .is_empty());
+    }
+
+    #[test]
+    fn repeated_phase_is_not_newly_entered() {
+        let mut dispatcher = Dispatcher::new(Vec::new());
+        let id = Uuid::new_v4();
+        assert!(dispatcher.set_phase(id, Some(NotificationEvent::AwaitingApproval)));
+        assert!(!dispatcher.set_phase(id, Some(NotificationEvent::AwaitingApproval)));
+        assert!(dispatcher.set_phase(id, Some(NotificationEvent::AwaitingUser)));
+    }
+
+    #[test]
+    fn exit_is_delivered_immediately_and_survives_phase_changes() {
+        let mut dispatcher = Dispatcher::new(vec![rule(
+            json!({ "sink": "websocket", "debounce_ms": 60000 }),
+        )]);
+        let now = Instant::now();
+        let exited = notification(NotificationEvent::Exited);
+        dispatcher.schedule(exited.clone(), now);
+        dispatcher.set_phase(exited.session_id, None);
+        assert_eq!(dispatcher.take_due(now).len(), 1);
+    }
+
+    #[test]
+    fn rules_filter_by_event_and_workspace() {
+        let mut dispatcher = Dispatcher::new(vec![
+            rule(json!({ "sink": "websocket", "events": ["exited"], "debounce_ms": 0 })),
+            rule(json!({ "sink": "websocket", "workspaces": ["/other"], "debounce_ms": 0 })),
+            rule(json!({ "sink": "websocket", "workspaces": ["/repo"], "debounce_ms": 0 })),
+        ]);
+        let now = Instant::now();
+        let mut waiting = notification(NotificationEvent::AwaitingUser);
+        waiting.workspace_id = Some(Uuid::new_v4());
+        dispatcher.schedule(waiting, now);
+        let rules: Vec<usize> = dispatcher
+            .take_due(now)
+            .into_iter()
+            .map(|(rule, _)| rule)
+            .collect();
+        assert_eq!(rules, vec![2]);
+    }
+
+    #[test]
+    fn describe_mentions_tool_and_exit_code() {
+        let approval = ApprovalRequest {
+            tool: "Bash".to_string(),
+            command: Some("rm -rf target".to_string()),
+            file: None,
+            prompt: String::new(),
+        };
+        assert_eq!(
+            describe(
+                NotificationEvent::AwaitingApproval,
+                "fixer",
+                Some(&approval),
+                None
+            ),
+            "fixer needs approval to use Bash"
+        );
+        assert_eq!(
+            describe(NotificationEvent::Exited, "fixer", None, Some(1)),
+            "fixer exited with code 1"
+        );
+    }
+}
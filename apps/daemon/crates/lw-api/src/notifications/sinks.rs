@@ -0,0 +1,110 @@
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+use super::Notification;
+
+pub(crate) const SIGNATURE_HEADER: &str = "X-Loopwire-Signature";
+pub(crate) const EVENT_HEADER: &str = "X-Loopwire-Event";
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// `sha256=<hex>` HMAC of `body` keyed with `secret`, in the format GitHub
+/// webhooks use so existing receivers can verify it.
+pub(crate) fn signature(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+pub(crate) async fn deliver_webhook(
+    client: &reqwest::Client,
+    url: &str,
+    secret: Option<&str>,
+    notification: &Notification,
+) -> anyhow::Result<()> {
+    let body = serde_json::to_vec(notification)?;
+    let mut request = client
+        .post(url)
+        .timeout(DELIVERY_TIMEOUT)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .header(EVENT_HEADER, notification.event.as_str());
+    if let Some(secret) = secret {
+        request = request.header(SIGNATURE_HEADER, signature(secret, &body));
+    }
+    request.body(body).send().await?.error_for_status()?;
+    Ok(())
+}
+
+/// Runs `command` with the notification as JSON on stdin and its main fields
+/// in `LOOPWIRE_*` environment variables.
+pub(crate) async fn run_command(
+    command: &str,
+    args: &[String],
+    notification: &Notification,
+) -> anyhow::Result<()> {
+    let body = serde_json::to_vec(notification)?;
+    let mut child = Command::new(command)
+        .args(args)
+        .env("LOOPWIRE_EVENT", notification.event.as_str())
+        .env("LOOPWIRE_SESSION_ID", notification.session_id.to_string())
+        .env("LOOPWIRE_MESSAGE", &notification.message)
+        .env(
+            "LOOPWIRE_WORKSPACE",
+            notification.workspace_path.as_deref().unwrap_or_default(),
+        )
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // Commands that ignore stdin may close it early.
+        let _ = stdin.write_all(&body).await;
+    }
+    let status = tokio::time::timeout(DELIVERY_TIMEOUT, child.wait()).await??;
+    if !status.success() {
+        anyhow::bail!("{command} exited with {status}");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signature_matches_known_vector() {
+        // RFC 4231 test case 2.
+        assert_eq!(
+            signature("Jefe", b"what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn run_command_passes_payload_on_stdin() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("out.json");
+        let script = format!("cat > '{}'", out.display());
+        let notification = super::super::tests::notification(lw_config::NotificationEvent::Exited);
+        run_command("sh", &["-c".to_string(), script], &notification)
+            .await
+            .unwrap();
+        let written: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(&out).unwrap()).unwrap();
+        assert_eq!(written["event"], "exited");
+        assert_eq!(written["session_id"], notification.session_id.to_string());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn run_command_reports_failure() {
+        let notification = super::super::tests::notification(lw_config::NotificationEvent::Exited);
+        assert!(run_command("false", &[], &notification).await.is_err());
+    }
+}
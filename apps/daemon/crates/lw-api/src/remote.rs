@@ -12,9 +12,9 @@ use std::process::Stdio;
 use tokio::process::{Child, Command};
 use tokio::sync::{Mutex, RwLock};
 
-use crate::auth::{generate_token, TokenStore};
+use crate::auth::{generate_token, NewSession, TokenStore};
 use crypto::{constant_time_eq, hash_pin, sign_payload, verify_pin, TrustedDevicePayload};
-use invite::{build_connect_url, validate_invite};
+use invite::{build_connect_url, connect_page_url, validate_invite};
 #[cfg(test)]
 use invite::{obfuscate_backend_target, parse_hex_or_bytes, stream_key_byte};
 use tunnel::{
@@ -112,6 +112,7 @@ pub struct RemoteAccessManager {
     trusted_device_key: Vec<u8>,
     active_share: RwLock<Option<ActiveShare>>,
     tunnel_child: Mutex<Option<Child>>,
+    serves_web_ui: bool,
 }
 
 impl RemoteAccessManager {
@@ -132,9 +133,16 @@ impl RemoteAccessManager {
             trusted_device_key,
             active_share: RwLock::new(None),
             tunnel_child: Mutex::new(None),
+            serves_web_ui: false,
         })
     }
 
+    /// Points connect links at the daemon's own UI instead of
+    /// `remote.frontend_connect_url`.
+    pub fn set_serves_web_ui(&mut self, serves: bool) {
+        self.serves_web_ui = serves;
+    }
+
     pub async fn start_share(
         &self,
         options: ShareStartOptions,
@@ -158,7 +166,11 @@ impl RemoteAccessManager {
         };
 
         let connect_url = build_connect_url(
-            &self.config.remote.frontend_connect_url,
+            &connect_page_url(
+                &self.config.remote.frontend_connect_url,
+                &public_backend_url,
+                self.serves_web_ui,
+            ),
             &public_backend_url,
             &invite_token,
         );
@@ -261,6 +273,7 @@ impl RemoteAccessManager {
         invite_token: &str,
         pin: Option<&str>,
         trusted_device_token: Option<&str>,
+        session: NewSession,
     ) -> Result<InviteExchangeResult, RemoteError> {
         self.sync_share_from_process().await;
 
@@ -300,7 +313,9 @@ impl RemoteAccessManager {
 
         let session_token = generate_token();
         let session_hash = TokenStore::hash_token(&session_token);
-        self.token_store.add_session_token(session_hash).await;
+        self.token_store
+            .add_session_token(session_hash, session)
+            .await;
 
         Ok(InviteExchangeResult {
             session_token,
@@ -585,6 +600,21 @@ mod tests {
         assert!(url.contains("&invite=abc123"));
     }
 
+    #[test]
+    fn connect_page_is_the_tunnel_when_serving_web_ui() {
+        let hosted = "https://app.example.com/connect";
+        let tunnel = "https://abc.trycloudflare.com/";
+        assert_eq!(connect_page_url(hosted, tunnel, false), hosted);
+        assert_eq!(
+            connect_page_url(hosted, tunnel, true),
+            "https://abc.trycloudflare.com/connect"
+        );
+        assert_eq!(
+            connect_page_url("", tunnel, false),
+            "https://abc.trycloudflare.com/connect"
+        );
+    }
+
     #[test]
     fn build_connect_url_trims_trailing_slash() {
         let url = build_connect_url(
@@ -15,6 +15,20 @@ pub(super) fn validate_invite(share: &ActiveShare, invite_hash: &str) -> Result<
     Ok(())
 }
 
+/// Page invitees open. A daemon serving its own UI is reached through the
+/// tunnel, so the connect page sits at the public backend URL.
+pub(super) fn connect_page_url(
+    frontend_connect_url: &str,
+    public_backend_url: &str,
+    serves_web_ui: bool,
+) -> String {
+    if serves_web_ui || frontend_connect_url.is_empty() {
+        format!("{}/connect", public_backend_url.trim_end_matches('/'))
+    } else {
+        frontend_connect_url.to_string()
+    }
+}
+
 pub(super) fn build_connect_url(base: &str, backend_url: &str, invite_token: &str) -> String {
     let trimmed = base.trim_end_matches('/');
     let separator = if trimmed.contains('?') { '&' } else { '?' };
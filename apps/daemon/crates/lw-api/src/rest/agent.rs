@@ -1,6 +1,7 @@
 use std::cmp::Ordering;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::Path as StdPath;
+use std::time::Duration;
 
 use axum::extract::{Path, Query, State};
 use axum::http::StatusCode;
@@ -10,14 +11,71 @@ use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::error::{ApiError, ApiErrorResponse};
-use crate::rest::workspace::{load_workspace_agents, save_workspace_agents, WorkspaceAgentEntry};
+use crate::project::{run_setup_commands, workspace_project_config};
+use crate::rest::git::worktree;
+use crate::rest::workspace::{
+    load_workspace_agents, save_workspace_agents, scrollback_dir, WorkspaceAgentEntry,
+};
 use crate::state::AppState;
 
 #[derive(Deserialize)]
 pub struct CreateSessionRequest {
-    pub agent_type: lw_agent::AgentType,
+    /// Defaults to the `agent.default` of the workspace's `.loopwire.toml`.
+    #[serde(default)]
+    pub agent_type: Option<lw_agent::AgentType>,
     pub custom_name: Option<String>,
     pub workspace_path: String,
+    /// Run the agent in a fresh `git worktree` on a new branch instead of
+    /// the workspace checkout.
+    #[serde(default)]
+    pub worktree: bool,
+    /// Branch for the worktree; defaults to `loopwire/<short id>`.
+    pub worktree_branch: Option<String>,
+    #[serde(flatten)]
+    pub launch: SessionLaunchRequest,
+}
+
+/// Launch settings of a new session, kept for its restores except for
+/// `initial_prompt`.
+#[derive(Deserialize, Default)]
+pub struct SessionLaunchRequest {
+    /// Extra CLI arguments for the agent, e.g. `["--model", "opus"]`.
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    /// Directory inside the workspace to run the agent in.
+    pub subdirectory: Option<String>,
+    /// Prompt typed in once the agent first waits for input.
+    pub initial_prompt: Option<String>,
+    /// The client's terminal size.
+    pub cols: Option<u16>,
+    pub rows: Option<u16>,
+}
+
+#[derive(Deserialize, Default)]
+pub struct ForkSessionRequest {
+    /// Name of the fork; defaults to the source's name with " (fork)".
+    pub custom_name: Option<String>,
+    /// Run the fork in a fresh `git worktree` branched off the source
+    /// session's checkout.
+    #[serde(default)]
+    pub worktree: bool,
+    /// Branch for the worktree; defaults to `loopwire/<short id>`.
+    pub worktree_branch: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct StopSessionQuery {
+    /// Remove the session's worktree; it is kept on disk by default.
+    #[serde(default)]
+    pub remove_worktree: bool,
+    /// Remove the worktree even if it has uncommitted changes.
+    #[serde(default)]
+    pub force: bool,
+    /// Also delete the worktree branch when removing the worktree.
+    #[serde(default)]
+    pub delete_branch: bool,
 }
 
 #[derive(Serialize)]
@@ -30,6 +88,7 @@ pub struct CreateSessionResponse {
     pub pinned: bool,
     pub icon: Option<String>,
     pub sort_order: Option<i32>,
+    pub worktree: Option<lw_agent::AgentWorktree>,
     pub status: lw_agent::AgentStatus,
     pub resumability_status: lw_agent::ResumabilityStatus,
     pub resume_failure_reason: Option<String>,
@@ -52,6 +111,8 @@ pub struct AgentSessionResponse {
     pub icon: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub sort_order: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub worktree: Option<lw_agent::AgentWorktree>,
     pub status: lw_agent::AgentStatus,
     pub resumability_status: lw_agent::ResumabilityStatus,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -104,7 +165,7 @@ async fn running_sessions_for_workspace(
     sort_workspace_sessions(sessions)
 }
 
-async fn persist_workspace_agents_snapshot(
+pub(crate) async fn persist_workspace_agents_snapshot(
     state: &AppState,
     workspace_path: &StdPath,
     skip_sort_fallback_for: Option<Uuid>,
@@ -139,6 +200,9 @@ async fn persist_workspace_agents_snapshot(
                     resume_failure_reason: session.resume_failure_reason,
                     created_at: Some(session.created_at.to_rfc3339()),
                     pid: session.process_id,
+                    worktree: session.worktree,
+                    launch_options: session.launch_options,
+                    prompt_queue: state.agent_manager.prompt_queue(&session.session_id),
                 },
             )
         })
@@ -168,6 +232,7 @@ fn to_api_session(session: lw_agent::AgentHandle, workspace_id: Uuid) -> AgentSe
         pinned: session.pinned,
         icon: session.icon,
         sort_order: session.sort_order,
+        worktree: session.worktree,
         status: session.status,
         resumability_status: session.resumability_status,
         resume_failure_reason: session.resume_failure_reason,
@@ -199,6 +264,129 @@ pub async fn list_sessions(State(state): State<AppState>) -> Json<Vec<AgentSessi
     Json(response)
 }
 
+/// Creates a worktree for a new session under the workspace's data dir,
+/// starting at the `HEAD` of `checkout`.
+fn create_session_worktree(
+    state: &AppState,
+    workspace_id: Uuid,
+    checkout: &StdPath,
+    branch: Option<&str>,
+) -> Result<lw_agent::AgentWorktree, ApiErrorResponse> {
+    let worktree_id = Uuid::new_v4();
+    let branch = branch
+        .map(str::trim)
+        .filter(|branch| !branch.is_empty())
+        .map(ToOwned::to_owned)
+        .unwrap_or_else(|| worktree::default_branch_name(worktree_id));
+    let path = state
+        .paths
+        .workspace_data_dir(workspace_id)
+        .join("worktrees")
+        .join(worktree_id.to_string());
+    worktree::create_worktree(checkout, &path, &branch)?;
+    Ok(lw_agent::AgentWorktree { path, branch })
+}
+
+fn invalid_launch_option(code: &str, message: impl Into<String>) -> ApiErrorResponse {
+    ApiErrorResponse {
+        status: StatusCode::BAD_REQUEST,
+        error: ApiError::new(code, message),
+    }
+}
+
+/// The agent a new session runs: the requested one, else the project's
+/// default.
+fn session_agent_type(
+    requested: Option<lw_agent::AgentType>,
+    project: &lw_config::ProjectConfig,
+) -> Result<lw_agent::AgentType, ApiErrorResponse> {
+    if let Some(agent_type) = requested {
+        return Ok(agent_type);
+    }
+    let Some(default) = project.agent.default.as_deref() else {
+        return Err(invalid_launch_option(
+            "AGENT_TYPE_REQUIRED",
+            "agent_type is required when the workspace declares no default agent",
+        ));
+    };
+    default.parse().map_err(|e: String| {
+        invalid_launch_option(
+            "INVALID_PROJECT_CONFIG",
+            format!("agent.default in {}: {e}", lw_config::PROJECT_CONFIG_FILE),
+        )
+    })
+}
+
+/// Validates the launch settings of a request against the workspace.
+async fn launch_options(
+    state: &AppState,
+    workspace_id: Uuid,
+    workspace_path: &StdPath,
+    launch: &SessionLaunchRequest,
+) -> Result<lw_agent::SessionLaunchOptions, ApiErrorResponse> {
+    if let Some(key) = launch
+        .env
+        .keys()
+        .find(|key| key.is_empty() || key.contains(['=', '\0']))
+    {
+        return Err(invalid_launch_option(
+            "INVALID_ENV",
+            format!("Invalid environment variable name: {key:?}"),
+        ));
+    }
+
+    let size = match (launch.cols, launch.rows) {
+        (None, None) => None,
+        (Some(cols), Some(rows)) if cols > 0 && rows > 0 => {
+            Some(lw_agent::TerminalSize { cols, rows })
+        }
+        _ => {
+            return Err(invalid_launch_option(
+                "INVALID_SIZE",
+                "cols and rows must both be given and non-zero",
+            ))
+        }
+    };
+
+    let subdirectory = match launch
+        .subdirectory
+        .as_deref()
+        .map(str::trim)
+        .filter(|dir| !dir.is_empty())
+    {
+        Some(dir) => {
+            let resolved = state
+                .workspace_registry
+                .resolve(&workspace_id, dir)
+                .await
+                .map_err(|e| -> ApiErrorResponse { ApiError::fs_error(&e).into() })?;
+            if !resolved.is_dir() {
+                return Err(invalid_launch_option(
+                    "INVALID_SUBDIRECTORY",
+                    format!("{dir} is not a directory in the workspace"),
+                ));
+            }
+            // Kept relative so that it applies inside a worktree as well.
+            let root = tokio::fs::canonicalize(workspace_path)
+                .await
+                .unwrap_or_else(|_| workspace_path.to_path_buf());
+            resolved
+                .strip_prefix(&root)
+                .ok()
+                .filter(|relative| !relative.as_os_str().is_empty())
+                .map(StdPath::to_path_buf)
+        }
+        None => None,
+    };
+
+    Ok(lw_agent::SessionLaunchOptions {
+        extra_args: launch.args.clone(),
+        env: launch.env.clone(),
+        subdirectory,
+        size,
+    })
+}
+
 pub async fn create_session(
     State(state): State<AppState>,
     Json(body): Json<CreateSessionRequest>,
@@ -230,18 +418,73 @@ pub async fn create_session(
             error: ApiError::internal(e.to_string()),
         })?;
 
-    let (session_id, _session) = state
+    let project = workspace_project_config(&state, workspace_id)
+        .await
+        .trusted_config()
+        .cloned()
+        .unwrap_or_default();
+    let agent_type = session_agent_type(body.agent_type, &project)?;
+    let mut options = launch_options(&state, workspace_id, &workspace_path, &body.launch).await?;
+    // Project settings go first so that the request can override them.
+    let project_default = project.agent.default.as_deref();
+    if project_default.is_none_or(|default| default.parse().ok().as_ref() == Some(&agent_type)) {
+        options.extra_args = [project.agent.args.clone(), options.extra_args].concat();
+    }
+    options.env = project.env.clone().into_iter().chain(options.env).collect();
+
+    let worktree = if body.worktree {
+        Some(create_session_worktree(
+            &state,
+            workspace_id,
+            &workspace_path,
+            body.worktree_branch.as_deref(),
+        )?)
+    } else {
+        None
+    };
+
+    let mut launch_dir = worktree
+        .as_ref()
+        .map_or_else(|| workspace_path.clone(), |worktree| worktree.path.clone());
+    if let Some(subdirectory) = &options.subdirectory {
+        launch_dir.push(subdirectory);
+    }
+    if let Err(e) = run_setup_commands(&project.setup, &launch_dir, &options.env).await {
+        if let Some(worktree) = &worktree {
+            let _ =
+                worktree::remove_worktree(&workspace_path, &worktree.path, &worktree.branch, true);
+        }
+        return Err(e);
+    }
+
+    let (session_id, _session) = match state
         .agent_manager
         .start_session(
-            body.agent_type,
+            agent_type.clone(),
             workspace_path.clone(),
             body.custom_name.clone(),
+            worktree.clone(),
+            options,
+            Some(scrollback_dir(&state.paths, workspace_id)),
         )
         .await
-        .map_err(|e| ApiErrorResponse {
-            status: StatusCode::INTERNAL_SERVER_ERROR,
-            error: ApiError::internal(e.to_string()),
-        })?;
+    {
+        Ok(started) => started,
+        Err(e) => {
+            if let Some(worktree) = &worktree {
+                let _ = worktree::remove_worktree(
+                    &workspace_path,
+                    &worktree.path,
+                    &worktree.branch,
+                    true,
+                );
+            }
+            return Err(ApiErrorResponse {
+                status: StatusCode::INTERNAL_SERVER_ERROR,
+                error: ApiError::internal(e.to_string()),
+            });
+        }
+    };
 
     let handle = state.agent_manager.get_handle(&session_id).await;
     let created_at = handle
@@ -260,6 +503,20 @@ pub async fn create_session(
         .as_ref()
         .map(|h| h.recovered_from_previous)
         .unwrap_or(false);
+    if let Some(prompt) = body
+        .launch
+        .initial_prompt
+        .filter(|prompt| !prompt.trim().is_empty())
+    {
+        // Queued, so it is typed in as soon as the agent is ready.
+        if let Err(err) = state
+            .agent_manager
+            .enqueue_prompt(&session_id, prompt)
+            .await
+        {
+            tracing::warn!(session_id = %session_id, "failed to queue initial prompt: {err}");
+        }
+    }
     persist_workspace_agents_snapshot(&state, &workspace_path, None).await?;
 
     Ok((
@@ -267,12 +524,13 @@ pub async fn create_session(
         Json(CreateSessionResponse {
             session_id,
             workspace_id,
-            agent_type: body.agent_type,
+            agent_type,
             conversation_id,
             custom_name: body.custom_name,
             pinned: false,
             icon: None,
             sort_order: None,
+            worktree,
             status: lw_agent::AgentStatus::Running,
             resumability_status,
             resume_failure_reason,
@@ -306,15 +564,114 @@ pub async fn get_session(
     Ok(Json(to_api_session(handle, workspace_id)))
 }
 
-pub async fn stop_session(
+pub async fn fork_session(
     State(state): State<AppState>,
     Path(id): Path<Uuid>,
-) -> Result<StatusCode, ApiErrorResponse> {
-    let workspace_path = state
+    Json(body): Json<ForkSessionRequest>,
+) -> Result<(StatusCode, Json<AgentSessionResponse>), ApiErrorResponse> {
+    let source = state
         .agent_manager
         .get_handle(&id)
         .await
-        .map(|handle| handle.workspace_path);
+        .ok_or_else(|| ApiErrorResponse {
+            status: StatusCode::NOT_FOUND,
+            error: ApiError::not_found("Session"),
+        })?;
+    if !state.agent_manager.supports_fork(&source.agent_type) {
+        return Err(ApiErrorResponse {
+            status: StatusCode::BAD_REQUEST,
+            error: ApiError::new(
+                "FORK_UNSUPPORTED",
+                format!("{} sessions cannot be forked", source.agent_type),
+            ),
+        });
+    }
+    let workspace_id = state
+        .workspace_registry
+        .find_by_path(&source.workspace_path)
+        .await
+        .ok_or_else(|| ApiErrorResponse {
+            status: StatusCode::NOT_FOUND,
+            error: ApiError::not_found("Workspace"),
+        })?;
+
+    let worktree = if body.worktree {
+        Some(create_session_worktree(
+            &state,
+            workspace_id,
+            source.working_dir(),
+            body.worktree_branch.as_deref(),
+        )?)
+    } else {
+        None
+    };
+
+    let (session_id, _session) = match state
+        .agent_manager
+        .fork_session(
+            &id,
+            body.custom_name,
+            worktree.clone(),
+            Some(scrollback_dir(&state.paths, workspace_id)),
+        )
+        .await
+    {
+        Ok(started) => started,
+        Err(e) => {
+            if let Some(worktree) = &worktree {
+                let _ = worktree::remove_worktree(
+                    &source.workspace_path,
+                    &worktree.path,
+                    &worktree.branch,
+                    true,
+                );
+            }
+            return Err(ApiErrorResponse {
+                status: StatusCode::INTERNAL_SERVER_ERROR,
+                error: ApiError::internal(e.to_string()),
+            });
+        }
+    };
+    persist_workspace_agents_snapshot(&state, &source.workspace_path, None).await?;
+
+    let handle = state
+        .agent_manager
+        .get_handle(&session_id)
+        .await
+        .ok_or_else(|| ApiErrorResponse {
+            status: StatusCode::INTERNAL_SERVER_ERROR,
+            error: ApiError::internal("Forked session disappeared"),
+        })?;
+    Ok((
+        StatusCode::CREATED,
+        Json(to_api_session(handle, workspace_id)),
+    ))
+}
+
+pub async fn stop_session(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<StopSessionQuery>,
+) -> Result<StatusCode, ApiErrorResponse> {
+    let handle = state.agent_manager.get_handle(&id).await;
+    let workspace_path = handle.as_ref().map(|handle| handle.workspace_path.clone());
+    let worktree = handle
+        .and_then(|handle| handle.worktree)
+        .filter(|_| query.remove_worktree);
+    if let Some(worktree) = &worktree {
+        if !query.force && worktree::worktree_is_dirty(&worktree.path)? {
+            return Err(ApiErrorResponse {
+                status: StatusCode::CONFLICT,
+                error: ApiError::new(
+                    "WORKTREE_DIRTY",
+                    format!(
+                        "Worktree {} has uncommitted changes; pass force=true to discard them",
+                        worktree.path.display()
+                    ),
+                ),
+            });
+        }
+    }
     state
         .agent_manager
         .stop_session(&id)
@@ -332,6 +689,22 @@ pub async fn stop_session(
                 .join(id.to_string());
             let _ = tokio::fs::remove_dir_all(dir).await;
         }
+        state.pty_manager.remove_output_log(&id);
+        if let Some(worktree) = worktree {
+            if let Err(err) = worktree::remove_worktree(
+                workspace_path,
+                &worktree.path,
+                &worktree.branch,
+                query.delete_branch,
+            ) {
+                tracing::warn!(
+                    session_id = %id,
+                    worktree = %worktree.path.display(),
+                    "failed to remove agent worktree: {}",
+                    err.error.message
+                );
+            }
+        }
         persist_workspace_agents_snapshot(&state, workspace_path, None).await?;
     }
     Ok(StatusCode::NO_CONTENT)
@@ -390,6 +763,178 @@ pub async fn update_session_settings(
     Ok(StatusCode::NO_CONTENT)
 }
 
+const DEFAULT_INPUT_WAIT_SECS: u64 = 300;
+const MAX_INPUT_WAIT_SECS: u64 = 3600;
+
+#[derive(Deserialize)]
+pub struct SessionInputRequest {
+    pub text: Option<String>,
+    /// Send `text` as a bracketed paste, so multi-line prompts are not
+    /// submitted line by line.
+    #[serde(default)]
+    pub paste: bool,
+    /// Press Enter after `text`.
+    #[serde(default)]
+    pub submit: bool,
+    /// Named keys pressed after `text`, e.g. `["Down", "Enter"]`.
+    #[serde(default)]
+    pub keys: Vec<lw_agent::InputKey>,
+    /// Respond only once the agent waits for input (or approval) again.
+    /// Needs `submit` or `keys`.
+    #[serde(default)]
+    pub wait: bool,
+    /// How long `wait` may take; defaults to 5 minutes.
+    pub timeout_secs: Option<u64>,
+}
+
+#[derive(Serialize)]
+pub struct SessionInputResponse {
+    pub activity: lw_agent::AgentActivity,
+    /// `wait` gave up before the agent handed the turn back.
+    pub timed_out: bool,
+}
+
+pub async fn session_input(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(body): Json<SessionInputRequest>,
+) -> Result<Json<SessionInputResponse>, ApiErrorResponse> {
+    let input = lw_agent::SessionInput {
+        text: body.text,
+        bracketed_paste: body.paste,
+        submit: body.submit,
+        keys: body.keys,
+    };
+    if input.is_empty() {
+        return Err(ApiErrorResponse {
+            status: StatusCode::BAD_REQUEST,
+            error: ApiError::new("INVALID_INPUT", "Provide text, submit or keys"),
+        });
+    }
+    // Typing without submitting leaves the agent in `user_input`, which
+    // never hands the turn back.
+    if body.wait && !input.submit && input.keys.is_empty() {
+        return Err(ApiErrorResponse {
+            status: StatusCode::BAD_REQUEST,
+            error: ApiError::new("INVALID_INPUT", "wait needs submit or keys"),
+        });
+    }
+    if state.agent_manager.get_handle(&id).await.is_none() {
+        return Err(ApiErrorResponse {
+            status: StatusCode::NOT_FOUND,
+            error: ApiError::not_found("Session"),
+        });
+    }
+
+    state
+        .agent_manager
+        .send_input(&id, &input)
+        .await
+        .map_err(|e| ApiErrorResponse {
+            status: StatusCode::INTERNAL_SERVER_ERROR,
+            error: ApiError::new("PTY_WRITE_ERROR", e.to_string()).retryable(),
+        })?;
+
+    if !body.wait {
+        return Ok(Json(SessionInputResponse {
+            activity: state.agent_manager.get_activity(&id).await,
+            timed_out: false,
+        }));
+    }
+    let timeout = Duration::from_secs(
+        body.timeout_secs
+            .unwrap_or(DEFAULT_INPUT_WAIT_SECS)
+            .min(MAX_INPUT_WAIT_SECS),
+    );
+    let (activity, timed_out) = match state.agent_manager.wait_for_turn(&id, timeout).await {
+        Some(activity) => (activity, false),
+        None => (state.agent_manager.get_activity(&id).await, true),
+    };
+    Ok(Json(SessionInputResponse {
+        activity,
+        timed_out,
+    }))
+}
+
+#[derive(Deserialize)]
+pub struct QueuePromptRequest {
+    pub text: String,
+}
+
+#[derive(Deserialize)]
+pub struct ReorderQueueRequest {
+    /// Every pending prompt id, in the new delivery order.
+    pub prompt_ids: Vec<Uuid>,
+}
+
+fn prompt_queue_error(err: lw_agent::PromptQueueError) -> ApiErrorResponse {
+    ApiError::prompt_queue_error(&err).into()
+}
+
+pub async fn list_prompt_queue(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<lw_agent::QueuedPrompt>>, ApiErrorResponse> {
+    if state.agent_manager.get_handle(&id).await.is_none() {
+        return Err(ApiErrorResponse {
+            status: StatusCode::NOT_FOUND,
+            error: ApiError::not_found("Session"),
+        });
+    }
+    Ok(Json(state.agent_manager.prompt_queue(&id)))
+}
+
+pub async fn enqueue_prompt(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(body): Json<QueuePromptRequest>,
+) -> Result<(StatusCode, Json<lw_agent::QueuedPrompt>), ApiErrorResponse> {
+    let prompt = state
+        .agent_manager
+        .enqueue_prompt(&id, body.text)
+        .await
+        .map_err(prompt_queue_error)?;
+    Ok((StatusCode::CREATED, Json(prompt)))
+}
+
+pub async fn edit_queued_prompt(
+    State(state): State<AppState>,
+    Path((id, prompt_id)): Path<(Uuid, Uuid)>,
+    Json(body): Json<QueuePromptRequest>,
+) -> Result<Json<lw_agent::QueuedPrompt>, ApiErrorResponse> {
+    state
+        .agent_manager
+        .edit_queued_prompt(&id, &prompt_id, body.text)
+        .await
+        .map(Json)
+        .map_err(prompt_queue_error)
+}
+
+pub async fn cancel_queued_prompt(
+    State(state): State<AppState>,
+    Path((id, prompt_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode, ApiErrorResponse> {
+    state
+        .agent_manager
+        .cancel_queued_prompt(&id, &prompt_id)
+        .await
+        .map_err(prompt_queue_error)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn reorder_prompt_queue(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(body): Json<ReorderQueueRequest>,
+) -> Result<Json<Vec<lw_agent::QueuedPrompt>>, ApiErrorResponse> {
+    state
+        .agent_manager
+        .reorder_prompt_queue(&id, &body.prompt_ids)
+        .await
+        .map(Json)
+        .map_err(prompt_queue_error)
+}
+
 const MAX_ATTACHMENT_BYTES: usize = 10 * 1024 * 1024; // 10 MB
 
 #[derive(Deserialize)]
@@ -468,10 +1013,23 @@ pub async fn attach_to_session(
     }))
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScrollbackFormat {
+    /// Base64 of the raw PTY bytes.
+    #[default]
+    Raw,
+    /// Normalized text with escape sequences stripped and spinner redraws
+    /// filtered. Offsets still count raw bytes.
+    Text,
+}
+
 #[derive(Deserialize)]
 pub struct ScrollbackQuery {
     pub before_offset: Option<usize>,
     pub max_bytes: Option<usize>,
+    #[serde(default)]
+    pub format: ScrollbackFormat,
 }
 
 #[derive(Serialize)]
@@ -482,6 +1040,28 @@ pub struct ScrollbackResponse {
     pub has_more: bool,
 }
 
+impl ScrollbackResponse {
+    pub(crate) fn new(
+        data: &[u8],
+        (start_offset, end_offset): (usize, usize),
+        has_more: bool,
+        format: ScrollbackFormat,
+    ) -> Self {
+        let data = match format {
+            ScrollbackFormat::Raw => base64::engine::general_purpose::STANDARD.encode(data),
+            ScrollbackFormat::Text => {
+                lw_agent::terminal_text::normalize_terminal_bytes_deduped(data)
+            }
+        };
+        Self {
+            data,
+            start_offset,
+            end_offset,
+            has_more,
+        }
+    }
+}
+
 pub async fn session_scrollback(
     State(state): State<AppState>,
     Path(id): Path<Uuid>,
@@ -498,11 +1078,49 @@ pub async fn session_scrollback(
             error: ApiError::new("SCROLLBACK_UNAVAILABLE", e.to_string()),
         })?;
 
-    Ok(Json(ScrollbackResponse {
-        data: base64::Engine::encode(&base64::engine::general_purpose::STANDARD, &result.data),
-        start_offset: result.start_offset,
-        end_offset: result.end_offset,
-        has_more: result.has_more,
+    Ok(Json(ScrollbackResponse::new(
+        &result.data,
+        (result.start_offset, result.end_offset),
+        result.has_more,
+        query.format,
+    )))
+}
+
+/// What a session's terminal shows right now, as plain text.
+#[derive(Serialize)]
+pub struct ScreenResponse {
+    pub cols: u16,
+    pub rows: u16,
+    pub cursor_row: u16,
+    pub cursor_col: u16,
+    pub cursor_hidden: bool,
+    pub alternate_screen: bool,
+    /// One line per row, trailing blanks trimmed.
+    pub text: String,
+}
+
+pub async fn session_screen(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ScreenResponse>, ApiErrorResponse> {
+    // Only a live PTY has a screen; don't spawn one to read it.
+    let session = state
+        .pty_manager
+        .get(&id)
+        .await
+        .map_err(|e| ApiErrorResponse {
+            status: StatusCode::NOT_FOUND,
+            error: ApiError::new("SCREEN_UNAVAILABLE", e.to_string()),
+        })?;
+    let capture = session.screen_capture();
+    Ok(Json(ScreenResponse {
+        cols: capture.cols,
+        rows: capture.rows,
+        cursor_row: capture.cursor_row,
+        cursor_col: capture.cursor_col,
+        cursor_hidden: capture.cursor_hidden,
+        alternate_screen: capture.alternate_screen,
+        text: capture.lines.join("\n"),
     }))
 }
 
@@ -525,6 +1143,8 @@ mod tests {
             icon: None,
             sort_order,
             workspace_path: std::path::PathBuf::from("/tmp"),
+            worktree: None,
+            launch_options: Default::default(),
             status: lw_agent::AgentStatus::Running,
             process_id: None,
             resumability_status: lw_agent::ResumabilityStatus::Resumable,
@@ -615,6 +1235,42 @@ mod tests {
         assert_eq!(agents.len(), 3);
     }
 
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn session_screen_handler_captures_live_pty() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = lw_config::DaemonConfig::default();
+        config.set_paths(lw_config::ConfigPaths::with_base(dir.path().to_path_buf()));
+        let hash = crate::auth::TokenStore::hash_token("test");
+        let state = crate::state::AppState::new(config, hash).unwrap();
+
+        let Err(err) = session_screen(State(state.clone()), Path(Uuid::new_v4())).await else {
+            panic!("expected no screen without a PTY");
+        };
+        assert_eq!(err.error.code, "SCREEN_UNAVAILABLE");
+
+        let id = Uuid::new_v4();
+        let session = state
+            .pty_manager
+            .create(id, "printf", &["one\\ntwo"], dir.path(), vec![], (40, 5))
+            .await
+            .unwrap();
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while session.exit_code().is_none() {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        })
+        .await
+        .unwrap();
+
+        let Json(screen) = session_screen(State(state), Path(id))
+            .await
+            .unwrap_or_else(|_| panic!("screen failed"));
+        assert_eq!((screen.cols, screen.rows), (40, 5));
+        assert_eq!(screen.text, "one\ntwo\n\n\n");
+        assert!(!screen.alternate_screen);
+    }
+
     #[tokio::test]
     async fn list_sessions_handler_returns_empty_with_no_active_agents() {
         let dir = tempfile::tempdir().unwrap();
@@ -626,4 +1282,620 @@ mod tests {
         let Json(sessions) = list_sessions(State(state)).await;
         assert!(sessions.is_empty());
     }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn create_session_in_worktree_and_remove_on_stop() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path().join("repo");
+        std::fs::create_dir_all(&repo).unwrap();
+        for args in [
+            vec!["init", "-q"],
+            vec![
+                "-c",
+                "user.email=t@example.com",
+                "-c",
+                "user.name=T",
+                "commit",
+                "-q",
+                "--allow-empty",
+                "-m",
+                "init",
+            ],
+        ] {
+            assert!(std::process::Command::new("git")
+                .args(&args)
+                .current_dir(&repo)
+                .status()
+                .unwrap()
+                .success());
+        }
+
+        let mut config = lw_config::DaemonConfig::default();
+        config.set_paths(lw_config::ConfigPaths::with_base(dir.path().join("data")));
+        config.agents = vec![serde_json::from_value(serde_json::json!({
+            "id": "sleeper",
+            "command": "sh",
+            "args": ["-c", "sleep 30"],
+        }))
+        .unwrap()];
+        let hash = crate::auth::TokenStore::hash_token("test");
+        let state = crate::state::AppState::new(config, hash).unwrap();
+
+        let (_, Json(created)) = create_session(
+            State(state.clone()),
+            Json(CreateSessionRequest {
+                agent_type: Some(lw_agent::AgentType::Custom("sleeper".to_string())),
+                custom_name: None,
+                workspace_path: repo.to_string_lossy().to_string(),
+                worktree: true,
+                worktree_branch: Some("loopwire/test-branch".to_string()),
+                launch: Default::default(),
+            }),
+        )
+        .await
+        .unwrap_or_else(|e| panic!("create failed: {}", e.error.message));
+        let worktree = created.worktree.clone().expect("worktree");
+        assert_eq!(worktree.branch, "loopwire/test-branch");
+        assert!(worktree.path.join(".git").exists());
+
+        let persisted = load_workspace_agents(&state.paths, &repo);
+        assert_eq!(
+            persisted[&created.session_id].worktree.as_ref(),
+            Some(&worktree)
+        );
+
+        stop_session(
+            State(state.clone()),
+            Path(created.session_id),
+            Query(StopSessionQuery {
+                remove_worktree: true,
+                force: false,
+                delete_branch: true,
+            }),
+        )
+        .await
+        .unwrap_or_else(|e| panic!("stop failed: {}", e.error.message));
+        assert!(!worktree.path.exists());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn fork_session_branches_conversation_into_worktree() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path().join("repo");
+        std::fs::create_dir_all(&repo).unwrap();
+        for args in [
+            vec!["init", "-q"],
+            vec![
+                "-c",
+                "user.email=t@example.com",
+                "-c",
+                "user.name=T",
+                "commit",
+                "-q",
+                "--allow-empty",
+                "-m",
+                "init",
+            ],
+        ] {
+            assert!(std::process::Command::new("git")
+                .args(&args)
+                .current_dir(&repo)
+                .status()
+                .unwrap()
+                .success());
+        }
+
+        let mut config = lw_config::DaemonConfig::default();
+        config.set_paths(lw_config::ConfigPaths::with_base(dir.path().join("data")));
+        config.agents = vec![
+            serde_json::from_value(serde_json::json!({
+                "id": "brancher",
+                "command": "sh",
+                "session_id_args": ["-c", "echo start {conversation_id}; sleep 30"],
+                "fork_args": ["-c", "echo fork {conversation_id} {new_conversation_id}; sleep 30"],
+            }))
+            .unwrap(),
+            serde_json::from_value(serde_json::json!({
+                "id": "sleeper",
+                "command": "sh",
+                "args": ["-c", "sleep 30"],
+            }))
+            .unwrap(),
+        ];
+        let hash = crate::auth::TokenStore::hash_token("test");
+        let state = crate::state::AppState::new(config, hash).unwrap();
+
+        let mut created = Vec::new();
+        for agent in ["brancher", "sleeper"] {
+            let (_, Json(session)) = create_session(
+                State(state.clone()),
+                Json(CreateSessionRequest {
+                    agent_type: Some(lw_agent::AgentType::Custom(agent.to_string())),
+                    custom_name: Some("Main".to_string()),
+                    workspace_path: repo.to_string_lossy().to_string(),
+                    worktree: false,
+                    worktree_branch: None,
+                    launch: Default::default(),
+                }),
+            )
+            .await
+            .unwrap_or_else(|e| panic!("create failed: {}", e.error.message));
+            created.push(session);
+        }
+        let source = &created[0];
+
+        let err = fork_session(
+            State(state.clone()),
+            Path(created[1].session_id),
+            Json(ForkSessionRequest::default()),
+        )
+        .await
+        .err()
+        .expect("sleeper cannot fork");
+        assert_eq!(err.error.code, "FORK_UNSUPPORTED");
+        let err = fork_session(
+            State(state.clone()),
+            Path(Uuid::new_v4()),
+            Json(ForkSessionRequest::default()),
+        )
+        .await
+        .err()
+        .expect("unknown session");
+        assert_eq!(err.status, StatusCode::NOT_FOUND);
+
+        let (status, Json(fork)) = fork_session(
+            State(state.clone()),
+            Path(source.session_id),
+            Json(ForkSessionRequest {
+                worktree: true,
+                ..Default::default()
+            }),
+        )
+        .await
+        .unwrap_or_else(|e| panic!("fork failed: {}", e.error.message));
+        assert_eq!(status, StatusCode::CREATED);
+        assert_ne!(fork.session_id, source.session_id);
+        assert_ne!(fork.conversation_id, source.conversation_id);
+        assert_eq!(fork.custom_name.as_deref(), Some("Main (fork)"));
+        let worktree = fork.worktree.clone().expect("worktree");
+        assert!(worktree.path.join(".git").exists());
+
+        let expected = format!(
+            "fork {} {}",
+            source.conversation_id.as_deref().unwrap(),
+            fork.conversation_id.as_deref().unwrap()
+        );
+        tokio::time::timeout(Duration::from_secs(10), async {
+            loop {
+                let scrollback = state
+                    .agent_manager
+                    .capture_scrollback_raw(&fork.session_id, None, 64 * 1024)
+                    .await
+                    .unwrap();
+                if String::from_utf8_lossy(&scrollback.data).contains(&expected) {
+                    break;
+                }
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+        })
+        .await
+        .expect("fork did not branch off the source conversation");
+
+        let source_handle = state
+            .agent_manager
+            .get_handle(&source.session_id)
+            .await
+            .unwrap();
+        assert_eq!(source_handle.status, lw_agent::AgentStatus::Running);
+        assert_eq!(source_handle.conversation_id, source.conversation_id);
+        let persisted = load_workspace_agents(&state.paths, &repo);
+        assert_eq!(
+            persisted[&fork.session_id].conversation_id,
+            fork.conversation_id
+        );
+        assert!(persisted.contains_key(&source.session_id));
+
+        // Uncommitted work blocks removal unless forced.
+        std::fs::write(worktree.path.join("wip.txt"), "draft").unwrap();
+        let err = stop_session(
+            State(state.clone()),
+            Path(fork.session_id),
+            Query(StopSessionQuery {
+                remove_worktree: true,
+                force: false,
+                delete_branch: false,
+            }),
+        )
+        .await
+        .expect_err("dirty worktree should block removal");
+        assert_eq!(err.status, StatusCode::CONFLICT);
+        assert_eq!(err.error.code, "WORKTREE_DIRTY");
+        assert!(worktree.path.join("wip.txt").exists());
+        std::fs::remove_file(worktree.path.join("wip.txt")).unwrap();
+
+        for id in [source.session_id, created[1].session_id, fork.session_id] {
+            stop_session(
+                State(state.clone()),
+                Path(id),
+                Query(StopSessionQuery {
+                    remove_worktree: true,
+                    force: false,
+                    delete_branch: true,
+                }),
+            )
+            .await
+            .unwrap_or_else(|e| panic!("stop failed: {}", e.error.message));
+        }
+        assert!(!worktree.path.exists());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn create_session_uses_project_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = lw_config::DaemonConfig::default();
+        config.set_paths(lw_config::ConfigPaths::with_base(dir.path().join("data")));
+        config.agents = vec![serde_json::from_value(serde_json::json!({
+            "id": "shell",
+            "command": "sh",
+        }))
+        .unwrap()];
+        let hash = crate::auth::TokenStore::hash_token("test");
+        let state = crate::state::AppState::new(config, hash).unwrap();
+        let workspace = |name: &str, project: Option<&str>| {
+            let path = dir.path().join(name);
+            std::fs::create_dir_all(&path).unwrap();
+            if let Some(project) = project {
+                std::fs::write(path.join(lw_config::PROJECT_CONFIG_FILE), project).unwrap();
+            }
+            path
+        };
+        let trust = |workspace: &StdPath| {
+            let state = state.clone();
+            let workspace = workspace.to_path_buf();
+            async move {
+                let id = match state.workspace_registry.find_by_path(&workspace).await {
+                    Some(id) => id,
+                    None => {
+                        let id = Uuid::new_v4();
+                        state
+                            .workspace_registry
+                            .register(id, workspace)
+                            .await
+                            .unwrap();
+                        id
+                    }
+                };
+                crate::project::trust_workspace_config(&state, id).await;
+            }
+        };
+        let request = |workspace: &StdPath, env: BTreeMap<String, String>| CreateSessionRequest {
+            agent_type: None,
+            custom_name: None,
+            workspace_path: workspace.to_string_lossy().to_string(),
+            worktree: false,
+            worktree_branch: None,
+            launch: SessionLaunchRequest {
+                env,
+                ..Default::default()
+            },
+        };
+
+        let plain = workspace("plain", None);
+        let err = create_session(State(state.clone()), Json(request(&plain, BTreeMap::new())))
+            .await
+            .err()
+            .expect("no agent to start");
+        assert_eq!(err.error.code, "AGENT_TYPE_REQUIRED");
+
+        let broken = workspace(
+            "broken",
+            Some("setup = [\"echo missing deps; exit 1\"]\n[agent]\ndefault = \"custom:shell\""),
+        );
+        let err = create_session(
+            State(state.clone()),
+            Json(request(&broken, BTreeMap::new())),
+        )
+        .await
+        .err()
+        .expect("an unapproved config is ignored");
+        assert_eq!(err.error.code, "AGENT_TYPE_REQUIRED");
+        trust(&broken).await;
+        let err = create_session(
+            State(state.clone()),
+            Json(request(&broken, BTreeMap::new())),
+        )
+        .await
+        .err()
+        .expect("setup failure starts no agent");
+        assert_eq!(err.error.code, "SETUP_FAILED");
+        assert!(state.agent_manager.list_sessions().await.is_empty());
+
+        let project = workspace(
+            "project",
+            Some(
+                r#"
+setup = ["echo $TEAM > setup.txt"]
+
+[agent]
+default = "custom:shell"
+args = ["-c", "exec sh"]
+
+[env]
+TEAM = "core"
+ORIGIN = "project"
+"#,
+            ),
+        );
+        trust(&project).await;
+        let (_, Json(created)) = create_session(
+            State(state.clone()),
+            Json(request(
+                &project,
+                BTreeMap::from([("ORIGIN".to_string(), "request".to_string())]),
+            )),
+        )
+        .await
+        .unwrap_or_else(|e| panic!("create failed: {}", e.error.message));
+        assert_eq!(
+            created.agent_type,
+            lw_agent::AgentType::Custom("shell".to_string())
+        );
+        assert_eq!(
+            std::fs::read_to_string(project.join("setup.txt")).unwrap(),
+            "core\n"
+        );
+        let persisted = load_workspace_agents(&state.paths, &project);
+        let options = &persisted[&created.session_id].launch_options;
+        assert_eq!(options.extra_args, vec!["-c", "exec sh"]);
+        assert_eq!(options.env["TEAM"], "core");
+        assert_eq!(options.env["ORIGIN"], "request");
+
+        state
+            .agent_manager
+            .stop_session(&created.session_id)
+            .await
+            .unwrap();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn create_session_applies_and_persists_launch_options() {
+        let dir = tempfile::tempdir().unwrap();
+        let workspace = dir.path().join("ws");
+        std::fs::create_dir_all(workspace.join("sub")).unwrap();
+        let mut config = lw_config::DaemonConfig::default();
+        config.set_paths(lw_config::ConfigPaths::with_base(dir.path().join("data")));
+        config.agents = vec![serde_json::from_value(serde_json::json!({
+            "id": "shell",
+            "command": "sh",
+        }))
+        .unwrap()];
+        let hash = crate::auth::TokenStore::hash_token("test");
+        let state = crate::state::AppState::new(config, hash).unwrap();
+        let request = |launch: SessionLaunchRequest| CreateSessionRequest {
+            agent_type: Some(lw_agent::AgentType::Custom("shell".to_string())),
+            custom_name: None,
+            workspace_path: workspace.to_string_lossy().to_string(),
+            worktree: false,
+            worktree_branch: None,
+            launch,
+        };
+
+        for (launch, code) in [
+            (
+                SessionLaunchRequest {
+                    env: BTreeMap::from([("A=B".to_string(), "x".to_string())]),
+                    ..Default::default()
+                },
+                "INVALID_ENV",
+            ),
+            (
+                SessionLaunchRequest {
+                    cols: Some(80),
+                    ..Default::default()
+                },
+                "INVALID_SIZE",
+            ),
+            (
+                SessionLaunchRequest {
+                    subdirectory: Some("missing".to_string()),
+                    ..Default::default()
+                },
+                "INVALID_SUBDIRECTORY",
+            ),
+            (
+                SessionLaunchRequest {
+                    subdirectory: Some("../elsewhere".to_string()),
+                    ..Default::default()
+                },
+                "WORKSPACE_PATH_TRAVERSAL",
+            ),
+        ] {
+            let err = create_session(State(state.clone()), Json(request(launch)))
+                .await
+                .err()
+                .expect("invalid launch options");
+            assert_eq!(err.error.code, code);
+        }
+
+        // Delivers the initial prompt.
+        let queue = crate::prompt_queue::spawn_prompt_queue(state.clone());
+        let (_, Json(created)) = create_session(
+            State(state.clone()),
+            Json(request(SessionLaunchRequest {
+                args: vec![
+                    "-c".to_string(),
+                    "echo \"$GREETING in ${PWD##*/} at $(stty size)\"; exec sh".to_string(),
+                ],
+                env: BTreeMap::from([("GREETING".to_string(), "hello".to_string())]),
+                subdirectory: Some("sub".to_string()),
+                initial_prompt: Some("echo first-$((1 + 1))".to_string()),
+                cols: Some(100),
+                rows: Some(30),
+            })),
+        )
+        .await
+        .unwrap_or_else(|e| panic!("create failed: {}", e.error.message));
+        let id = created.session_id;
+
+        let persisted = load_workspace_agents(&state.paths, &workspace);
+        let options = &persisted[&id].launch_options;
+        assert_eq!(options.subdirectory.as_deref(), Some(StdPath::new("sub")));
+        assert_eq!(
+            options.size,
+            Some(lw_agent::TerminalSize {
+                cols: 100,
+                rows: 30
+            })
+        );
+        assert_eq!(options.env["GREETING"], "hello");
+        assert_eq!(persisted[&id].prompt_queue.len(), 1);
+
+        tokio::time::timeout(Duration::from_secs(20), async {
+            loop {
+                let scrollback = state
+                    .agent_manager
+                    .capture_scrollback_raw(&id, None, 64 * 1024)
+                    .await
+                    .unwrap();
+                let output = String::from_utf8_lossy(&scrollback.data);
+                if output.contains("hello in sub at 30 100") && output.contains("first-2") {
+                    break;
+                }
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+        })
+        .await
+        .expect("launch options were not applied");
+
+        queue.abort();
+        state.agent_manager.stop_session(&id).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn session_input_rejects_empty_input_and_unknown_sessions() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = lw_config::DaemonConfig::default();
+        config.set_paths(lw_config::ConfigPaths::with_base(dir.path().to_path_buf()));
+        let hash = crate::auth::TokenStore::hash_token("test");
+        let state = crate::state::AppState::new(config, hash).unwrap();
+        let request = |text: Option<&str>| SessionInputRequest {
+            text: text.map(str::to_string),
+            paste: false,
+            submit: false,
+            keys: vec![],
+            wait: false,
+            timeout_secs: None,
+        };
+
+        let err = session_input(
+            State(state.clone()),
+            Path(Uuid::new_v4()),
+            Json(request(None)),
+        )
+        .await
+        .err()
+        .expect("input should fail");
+        assert_eq!(err.error.code, "INVALID_INPUT");
+        let err = session_input(
+            State(state),
+            Path(Uuid::new_v4()),
+            Json(request(Some("hi"))),
+        )
+        .await
+        .err()
+        .expect("input should fail");
+        assert_eq!(err.status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn session_input_rejects_wait_without_submit_or_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = lw_config::DaemonConfig::default();
+        config.set_paths(lw_config::ConfigPaths::with_base(dir.path().to_path_buf()));
+        let hash = crate::auth::TokenStore::hash_token("test");
+        let state = crate::state::AppState::new(config, hash).unwrap();
+
+        let err = session_input(
+            State(state),
+            Path(Uuid::new_v4()),
+            Json(SessionInputRequest {
+                text: Some("half a prompt".to_string()),
+                paste: false,
+                submit: false,
+                keys: vec![],
+                wait: true,
+                timeout_secs: Some(3600),
+            }),
+        )
+        .await
+        .err()
+        .expect("input should fail");
+        assert_eq!(err.status, StatusCode::BAD_REQUEST);
+        assert_eq!(err.error.code, "INVALID_INPUT");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn session_input_submits_and_waits_for_turn() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = lw_config::DaemonConfig::default();
+        config.set_paths(lw_config::ConfigPaths::with_base(dir.path().join("data")));
+        config.agents = vec![serde_json::from_value(serde_json::json!({
+            "id": "shell",
+            "command": "sh",
+        }))
+        .unwrap()];
+        let hash = crate::auth::TokenStore::hash_token("test");
+        let state = crate::state::AppState::new(config, hash).unwrap();
+
+        let (_, Json(created)) = create_session(
+            State(state.clone()),
+            Json(CreateSessionRequest {
+                agent_type: Some(lw_agent::AgentType::Custom("shell".to_string())),
+                custom_name: None,
+                workspace_path: dir.path().to_string_lossy().to_string(),
+                worktree: false,
+                worktree_branch: None,
+                launch: Default::default(),
+            }),
+        )
+        .await
+        .unwrap_or_else(|e| panic!("create failed: {}", e.error.message));
+
+        let Json(response) = session_input(
+            State(state.clone()),
+            Path(created.session_id),
+            Json(SessionInputRequest {
+                text: Some("echo lw-$((40 + 2))".to_string()),
+                paste: false,
+                submit: true,
+                keys: vec![],
+                wait: true,
+                timeout_secs: Some(20),
+            }),
+        )
+        .await
+        .unwrap_or_else(|e| panic!("input failed: {}", e.error.message));
+        assert!(!response.timed_out);
+        assert_eq!(
+            response.activity.phase,
+            lw_agent::AgentActivityPhase::AwaitingUser
+        );
+
+        let scrollback = state
+            .agent_manager
+            .capture_scrollback_raw(&created.session_id, None, 64 * 1024)
+            .await
+            .unwrap();
+        assert!(String::from_utf8_lossy(&scrollback.data).contains("lw-42"));
+        state
+            .agent_manager
+            .stop_session(&created.session_id)
+            .await
+            .unwrap();
+    }
 }
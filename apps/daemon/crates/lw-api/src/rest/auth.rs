@@ -1,16 +1,22 @@
-use axum::extract::State;
+use axum::extract::{Path, State};
 use axum::http::{HeaderMap, StatusCode};
 use axum::Json;
 
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
-use crate::auth::{extract_bearer_from_headers, generate_token, TokenStore};
+use crate::auth::{
+    extract_bearer_from_headers, generate_token, ApiToken, ApiTokenError, NewApiToken, NewSession,
+    SessionInfo, SessionOrigin, TokenScope, TokenStore,
+};
 use crate::error::{ApiError, ApiErrorResponse};
 use crate::state::AppState;
 
 #[derive(Deserialize)]
 pub struct ExchangeRequest {
     pub bootstrap_token: String,
+    /// Name for the device in the session list, e.g. "Work laptop".
+    pub label: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -29,6 +35,32 @@ pub struct RotateResponse {
     pub session_token: String,
 }
 
+#[derive(Serialize)]
+pub struct SessionResponse {
+    #[serde(flatten)]
+    pub session: SessionInfo,
+    /// Whether this is the session making the request.
+    pub current: bool,
+}
+
+#[derive(Deserialize)]
+pub struct CreateApiTokenRequest {
+    pub name: String,
+    pub scopes: Vec<TokenScope>,
+    /// Limit the token to these workspaces; all of them when empty.
+    #[serde(default)]
+    pub workspace_ids: Vec<Uuid>,
+    pub expires_in_seconds: Option<u64>,
+}
+
+#[derive(Serialize)]
+pub struct CreateApiTokenResponse {
+    /// Shown once; only its hash is kept.
+    pub token: String,
+    #[serde(flatten)]
+    pub api_token: ApiToken,
+}
+
 pub async fn bootstrap(State(state): State<AppState>) -> Json<BootstrapResponse> {
     Json(BootstrapResponse {
         status: "ready".to_string(),
@@ -36,8 +68,17 @@ pub async fn bootstrap(State(state): State<AppState>) -> Json<BootstrapResponse>
     })
 }
 
+/// Longest user agent kept with a session.
+const MAX_USER_AGENT_LEN: usize = 256;
+
+pub(crate) fn user_agent(headers: &HeaderMap) -> Option<String> {
+    let user_agent = headers.get(axum::http::header::USER_AGENT)?.to_str().ok()?;
+    Some(user_agent.chars().take(MAX_USER_AGENT_LEN).collect())
+}
+
 pub async fn exchange(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Json(body): Json<ExchangeRequest>,
 ) -> Result<Json<ExchangeResponse>, ApiErrorResponse> {
     // Validate bootstrap token
@@ -63,7 +104,17 @@ pub async fn exchange(
     // Generate session token
     let session_token = generate_token();
     let session_hash = TokenStore::hash_token(&session_token);
-    state.token_store.add_session_token(session_hash).await;
+    state
+        .token_store
+        .add_session_token(
+            session_hash,
+            NewSession {
+                label: body.label,
+                user_agent: user_agent(&headers),
+                origin: SessionOrigin::Local,
+            },
+        )
+        .await;
 
     Ok(Json(ExchangeResponse { session_token }))
 }
@@ -77,21 +128,18 @@ pub async fn rotate(
         error: ApiError::unauthorized(),
     })?;
 
-    // Revoke old token
+    // Swap in a new token for the same session
+    let new_token = generate_token();
+    let new_hash = TokenStore::hash_token(&new_token);
     state
         .token_store
-        .rotate_session(&token)
+        .rotate_session(&token, new_hash)
         .await
         .ok_or_else(|| ApiErrorResponse {
             status: StatusCode::UNAUTHORIZED,
             error: ApiError::invalid_token(),
         })?;
 
-    // Issue new one
-    let new_token = generate_token();
-    let new_hash = TokenStore::hash_token(&new_token);
-    state.token_store.add_session_token(new_hash).await;
-
     Ok(Json(RotateResponse {
         session_token: new_token,
     }))
@@ -109,3 +157,132 @@ pub async fn revoke(
     state.token_store.revoke_session(&token).await;
     Ok(StatusCode::NO_CONTENT)
 }
+
+pub async fn list_sessions(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Json<Vec<SessionResponse>> {
+    let current = match extract_bearer_from_headers(&headers) {
+        Some(token) => state.token_store.session(&token).await.map(|s| s.id),
+        None => None,
+    };
+    let sessions = state
+        .token_store
+        .list_sessions()
+        .await
+        .into_iter()
+        .map(|session| SessionResponse {
+            current: Some(session.id) == current,
+            session,
+        })
+        .collect();
+    Json(sessions)
+}
+
+pub async fn revoke_session(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, ApiErrorResponse> {
+    state
+        .token_store
+        .revoke_session_by_id(&id)
+        .await
+        .ok_or_else(|| ApiErrorResponse {
+            status: StatusCode::NOT_FOUND,
+            error: ApiError::not_found("Session"),
+        })?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+fn api_token_error(err: ApiTokenError) -> ApiErrorResponse {
+    let (status, code) = match &err {
+        ApiTokenError::EmptyName | ApiTokenError::NoScopes | ApiTokenError::AlreadyExpired => {
+            (StatusCode::BAD_REQUEST, "INVALID_REQUEST")
+        }
+        ApiTokenError::DuplicateName(_) => (StatusCode::CONFLICT, "TOKEN_NAME_TAKEN"),
+        ApiTokenError::Io(_) | ApiTokenError::Corrupt(_) => {
+            return ApiErrorResponse {
+                status: StatusCode::INTERNAL_SERVER_ERROR,
+                error: ApiError::internal(err.to_string()),
+            };
+        }
+    };
+    ApiErrorResponse {
+        status,
+        error: ApiError::new(code, err.to_string()),
+    }
+}
+
+pub async fn list_api_tokens(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<ApiToken>>, ApiErrorResponse> {
+    let tokens = state
+        .token_store
+        .list_api_tokens()
+        .await
+        .map_err(api_token_error)?;
+    Ok(Json(tokens))
+}
+
+pub async fn create_api_token(
+    State(state): State<AppState>,
+    Json(body): Json<CreateApiTokenRequest>,
+) -> Result<Json<CreateApiTokenResponse>, ApiErrorResponse> {
+    for workspace_id in &body.workspace_ids {
+        if state
+            .workspace_registry
+            .get_root(workspace_id)
+            .await
+            .is_err()
+        {
+            return Err(ApiErrorResponse {
+                status: StatusCode::BAD_REQUEST,
+                error: ApiError::new(
+                    "WORKSPACE_NOT_REGISTERED",
+                    format!("Workspace {} is not registered", workspace_id),
+                ),
+            });
+        }
+    }
+    let expires_at = match body.expires_in_seconds {
+        Some(seconds) => Some(
+            i64::try_from(seconds)
+                .ok()
+                .and_then(chrono::Duration::try_seconds)
+                .and_then(|ttl| chrono::Utc::now().checked_add_signed(ttl))
+                .ok_or_else(|| ApiErrorResponse {
+                    status: StatusCode::BAD_REQUEST,
+                    error: ApiError::new("INVALID_REQUEST", "Expiry is out of range"),
+                })?,
+        ),
+        None => None,
+    };
+
+    let (token, api_token) = state
+        .token_store
+        .create_api_token(NewApiToken {
+            name: body.name,
+            scopes: body.scopes,
+            workspace_ids: body.workspace_ids,
+            expires_at,
+        })
+        .await
+        .map_err(api_token_error)?;
+    Ok(Json(CreateApiTokenResponse { token, api_token }))
+}
+
+pub async fn revoke_api_token(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, ApiErrorResponse> {
+    state
+        .token_store
+        .revoke_api_token(&id.to_string())
+        .await
+        .map_err(api_token_error)?
+        .ok_or_else(|| ApiErrorResponse {
+            status: StatusCode::NOT_FOUND,
+            error: ApiError::not_found("API token"),
+        })?;
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -7,8 +7,10 @@ use serde::Serialize;
 
 use lw_agent::PersistedAgentInfo;
 
+use crate::project::{workspace_project_config, LoadedProjectConfig};
+use crate::rest::shell::active_shells_for_workspace;
 use crate::rest::workspace::{
-    load_workspace_agents, load_workspaces, WorkspaceAgentEntry, WorkspaceEntry,
+    load_workspace_agents, load_workspaces, scrollback_dir, WorkspaceAgentEntry, WorkspaceEntry,
 };
 use crate::state::AppState;
 
@@ -23,6 +25,9 @@ pub struct BootstrapWorkspaceEntry {
     #[serde(flatten)]
     pub workspace: WorkspaceEntry,
     pub sessions: Vec<BootstrapSession>,
+    pub shells: Vec<lw_agent::ShellHandle>,
+    /// The workspace's `.loopwire.toml`.
+    pub project_config: LoadedProjectConfig,
 }
 
 #[derive(Serialize)]
@@ -38,6 +43,8 @@ pub struct BootstrapSession {
     pub icon: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub sort_order: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub worktree: Option<lw_agent::AgentWorktree>,
     pub status: lw_agent::AgentStatus,
     pub resumability_status: lw_agent::ResumabilityStatus,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -57,6 +64,7 @@ impl BootstrapSession {
             pinned: handle.pinned,
             icon: handle.icon,
             sort_order: handle.sort_order,
+            worktree: handle.worktree,
             status: handle.status,
             resumability_status: handle.resumability_status,
             resume_failure_reason: handle.resume_failure_reason,
@@ -90,9 +98,15 @@ pub async fn bootstrap(State(state): State<AppState>) -> Json<BootstrapResponse>
                 continue;
             }
             if let Ok(agent_type) = entry.agent_type.parse::<lw_agent::AgentType>() {
+                state.pty_manager.set_output_log_dir(
+                    session_id,
+                    scrollback_dir(&state.paths, workspace.id).join(session_id.to_string()),
+                );
                 all_persisted.push(PersistedAgentInfo {
                     session_id,
                     workspace_path: workspace_path.clone(),
+                    worktree: entry.worktree,
+                    launch_options: entry.launch_options,
                     agent_type,
                     conversation_id: entry.conversation_id,
                     custom_name: entry.custom_name,
@@ -188,18 +202,25 @@ pub async fn bootstrap(State(state): State<AppState>) -> Json<BootstrapResponse>
                 .push(BootstrapSession::from_handle(session));
         }
     }
-    let mut workspaces: Vec<_> = workspaces
-        .into_iter()
-        .map(|workspace| BootstrapWorkspaceEntry {
+    let mut bootstrap_workspaces = Vec::with_capacity(workspaces.len());
+    for workspace in workspaces {
+        let shells = active_shells_for_workspace(&state, Path::new(&workspace.path)).await;
+        let project_config = workspace_project_config(&state, workspace.id).await;
+        bootstrap_workspaces.push(BootstrapWorkspaceEntry {
             sessions: sessions_by_workspace
                 .remove(&workspace.id)
                 .map(|(_, sessions)| sessions)
                 .unwrap_or_default(),
+            shells,
+            project_config,
             workspace,
-        })
-        .collect();
+        });
+    }
+    let mut workspaces = bootstrap_workspaces;
     for (workspace_id, (workspace_path, sessions)) in sessions_by_workspace {
+        let shells = active_shells_for_workspace(&state, &workspace_path).await;
         let path = workspace_path.to_string_lossy().to_string();
+        let project_config = workspace_project_config(&state, workspace_id).await;
         workspaces.push(BootstrapWorkspaceEntry {
             workspace: WorkspaceEntry {
                 id: workspace_id,
@@ -209,6 +230,8 @@ pub async fn bootstrap(State(state): State<AppState>) -> Json<BootstrapResponse>
                 icon: None,
             },
             sessions,
+            shells,
+            project_config,
         });
     }
 
@@ -0,0 +1,216 @@
+use axum::extract::{Path, Query, State};
+use axum::http::{header, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use lw_agent::terminal_export::export_recording;
+use lw_config::ConfigPaths;
+use lw_pty::OutputRecording;
+use serde::Deserialize;
+use uuid::Uuid;
+
+pub use lw_agent::terminal_export::ExportFormat;
+
+use crate::error::{ApiError, ApiErrorResponse};
+use crate::rest::workspace::{load_workspaces, scrollback_dir};
+use crate::state::AppState;
+
+/// Output exported per session: everything an on-disk log keeps.
+const EXPORT_MAX_BYTES: usize = 32 * 1024 * 1024;
+
+#[derive(Deserialize)]
+pub struct ExportQuery {
+    #[serde(default)]
+    pub format: ExportFormat,
+}
+
+async fn session_title(state: &AppState, id: &Uuid) -> String {
+    let custom_name = match state.agent_manager.get_handle(id).await {
+        Some(handle) => handle.custom_name,
+        None => state
+            .shell_manager
+            .get_handle(id)
+            .await
+            .and_then(|handle| handle.custom_name),
+    };
+    custom_name.unwrap_or_else(|| format!("Session {id}"))
+}
+
+fn export_file_name(id: &Uuid, format: ExportFormat) -> String {
+    format!("session-{id}.{}", format.extension())
+}
+
+/// Downloads a session's output as an asciinema recording, HTML or text.
+pub async fn export_session(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<ExportQuery>,
+) -> Result<Response, ApiErrorResponse> {
+    let recording = state
+        .pty_manager
+        .output_recording(&id, EXPORT_MAX_BYTES)
+        .await
+        .map_err(|e| ApiErrorResponse {
+            status: StatusCode::NOT_FOUND,
+            error: ApiError::new("SESSION_NOT_FOUND", e.to_string()),
+        })?;
+    let title = session_title(&state, &id).await;
+    let format = query.format;
+    let body = tokio::task::spawn_blocking(move || export_recording(&recording, format, &title))
+        .await
+        .map_err(|err| ApiErrorResponse {
+            status: StatusCode::INTERNAL_SERVER_ERROR,
+            error: ApiError::internal(err.to_string()),
+        })?;
+    let disposition = format!("attachment; filename=\"{}\"", export_file_name(&id, format));
+    Ok((
+        [
+            (
+                header::CONTENT_TYPE,
+                HeaderValue::from_static(format.content_type()),
+            ),
+            (
+                header::CONTENT_DISPOSITION,
+                HeaderValue::from_str(&disposition).expect("file name is ASCII"),
+            ),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+/// Exports a session straight from its on-disk output log, for the CLI.
+/// Without a PTY the terminal size isn't known, so `size` or the default
+/// is used. `Ok(None)` if no workspace or task run has a log for `id`.
+pub fn export_from_disk(
+    paths: &ConfigPaths,
+    id: &Uuid,
+    format: ExportFormat,
+    size: Option<(u16, u16)>,
+) -> std::io::Result<Option<Vec<u8>>> {
+    let candidates = load_workspaces(paths)
+        .into_iter()
+        .map(|workspace| scrollback_dir(paths, workspace.id))
+        .chain(std::iter::once(paths.task_runs_dir()))
+        .map(|dir| dir.join(id.to_string()));
+    for dir in candidates {
+        if !dir.is_dir() {
+            continue;
+        }
+        let mut recording = OutputRecording::from_log_dir(&dir, EXPORT_MAX_BYTES)?;
+        recording.size = size;
+        let title = format!("Session {id}");
+        return Ok(Some(export_recording(&recording, format, &title)));
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    async fn body_text(response: Response) -> String {
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn exports_live_session_in_every_format() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = AppState::for_test(dir.path(), |_| {});
+        let export = |id, format| {
+            export_session(
+                State(state.clone()),
+                Path(id),
+                Query(ExportQuery { format }),
+            )
+        };
+
+        let Err(err) = export(Uuid::new_v4(), ExportFormat::Cast).await else {
+            panic!("expected unknown session error");
+        };
+        assert_eq!(err.status, StatusCode::NOT_FOUND);
+
+        let id = Uuid::new_v4();
+        let session = state
+            .pty_manager
+            .create(
+                id,
+                "printf",
+                &["\\033[32mgreen\\033[0m\\n"],
+                dir.path(),
+                vec![],
+                (60, 8),
+            )
+            .await
+            .unwrap();
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while session.exit_code().is_none() {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        })
+        .await
+        .unwrap();
+
+        let response = export(id, ExportFormat::Cast)
+            .await
+            .unwrap_or_else(|_| panic!("cast failed"));
+        assert_eq!(
+            response.headers()[header::CONTENT_DISPOSITION],
+            format!("attachment; filename=\"session-{id}.cast\"")
+        );
+        let cast = body_text(response).await;
+        let mut lines = cast.lines();
+        let header: serde_json::Value = serde_json::from_str(lines.next().unwrap()).unwrap();
+        assert_eq!(
+            (header["width"].as_u64(), header["height"].as_u64()),
+            (Some(60), Some(8))
+        );
+        assert!(header["timestamp"].is_u64());
+        let event: serde_json::Value = serde_json::from_str(lines.next().unwrap()).unwrap();
+        assert_eq!(event[0], 0.0);
+        assert_eq!(event[1], "o");
+
+        let response = export(id, ExportFormat::Html)
+            .await
+            .unwrap_or_else(|_| panic!("html failed"));
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "text/html; charset=utf-8"
+        );
+        assert!(body_text(response)
+            .await
+            .contains("<span style=\"color:#0dbc79;\">green</span>"));
+
+        let response = export(id, ExportFormat::Text)
+            .await
+            .unwrap_or_else(|_| panic!("text failed"));
+        assert_eq!(body_text(response).await, "green\n");
+    }
+
+    #[test]
+    fn exports_from_disk_by_session_id() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = ConfigPaths::with_base(dir.path().to_path_buf());
+        let id = Uuid::new_v4();
+        let log_dir = paths.task_runs_dir().join(id.to_string());
+        std::fs::create_dir_all(&log_dir).unwrap();
+        std::fs::write(log_dir.join(format!("{:020}.log", 0)), "done\r\n").unwrap();
+
+        let text = export_from_disk(&paths, &id, ExportFormat::Text, None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(text, b"done\n");
+        let cast = export_from_disk(&paths, &id, ExportFormat::Cast, Some((100, 30)))
+            .unwrap()
+            .unwrap();
+        assert!(String::from_utf8(cast).unwrap().contains("\"width\":100"));
+        assert!(
+            export_from_disk(&paths, &Uuid::new_v4(), ExportFormat::Text, None)
+                .unwrap()
+                .is_none()
+        );
+    }
+}
@@ -0,0 +1,420 @@
+use std::path::{Component, Path, PathBuf};
+
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use base64::{engine::general_purpose, Engine as _};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::error::{ApiError, ApiErrorResponse};
+use crate::project::HiddenPaths;
+use crate::state::AppState;
+
+/// Request body limit for write/create: room for a 10 MB file sent as base64.
+pub const MAX_WRITE_BODY_BYTES: usize = 16 * 1024 * 1024;
+
+#[derive(Deserialize)]
+pub struct WriteRequest {
+    pub workspace_id: Uuid,
+    pub relative_path: String,
+    #[serde(default)]
+    pub content: String,
+    pub content_base64: Option<String>,
+    /// `etag` from the last read or write of this file.
+    pub expected_etag: Option<String>,
+    /// `modified_ms` from the last read or write, used when no etag is sent.
+    pub expected_modified_ms: Option<u64>,
+}
+
+#[derive(Deserialize)]
+pub struct CreateFileRequest {
+    pub workspace_id: Uuid,
+    pub relative_path: String,
+    #[serde(default)]
+    pub content: String,
+    pub content_base64: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct MkdirRequest {
+    pub workspace_id: Uuid,
+    pub relative_path: String,
+    #[serde(default)]
+    pub recursive: bool,
+}
+
+#[derive(Deserialize)]
+pub struct RenameRequest {
+    pub workspace_id: Uuid,
+    pub relative_path: String,
+    pub new_name: String,
+}
+
+#[derive(Deserialize)]
+pub struct MoveRequest {
+    pub workspace_id: Uuid,
+    pub relative_path: String,
+    pub destination_path: String,
+}
+
+#[derive(Deserialize)]
+pub struct DeleteRequest {
+    pub workspace_id: Uuid,
+    pub relative_path: String,
+}
+
+fn fs_error_response(e: lw_fs::FsError) -> ApiErrorResponse {
+    ApiError::fs_error(&e).into()
+}
+
+fn decode_content(
+    content: String,
+    content_base64: Option<String>,
+) -> Result<Vec<u8>, ApiErrorResponse> {
+    match content_base64 {
+        Some(encoded) => general_purpose::STANDARD
+            .decode(encoded)
+            .map_err(|e| ApiErrorResponse {
+                status: StatusCode::BAD_REQUEST,
+                error: ApiError::new("INVALID_CONTENT", format!("Invalid base64 content: {e}")),
+            }),
+        None => Ok(content.into_bytes()),
+    }
+}
+
+/// Resolve a path whose contents are read or written, following symlinks
+/// that stay inside the workspace. Paths hidden by `.loopwire.toml` are
+/// reported as missing.
+async fn resolve_target(
+    state: &AppState,
+    workspace_id: &Uuid,
+    relative_path: &str,
+) -> Result<PathBuf, ApiErrorResponse> {
+    let path = state
+        .workspace_registry
+        .resolve(workspace_id, relative_path)
+        .await
+        .map_err(fs_error_response)?;
+    HiddenPaths::of(state, workspace_id).await.check(&path)?;
+    Ok(path)
+}
+
+/// Resolve a directory entry itself (for rename, move and delete): the
+/// parent is resolved through the registry but the final component is not
+/// followed, so acting on a symlink never touches what it points at.
+async fn resolve_entry(
+    state: &AppState,
+    workspace_id: &Uuid,
+    relative_path: &str,
+) -> Result<PathBuf, ApiErrorResponse> {
+    let relative = Path::new(relative_path);
+    let file_name = match relative.components().next_back() {
+        Some(Component::Normal(name)) => name.to_owned(),
+        Some(Component::ParentDir) => {
+            return Err(fs_error_response(lw_fs::FsError::PathTraversal));
+        }
+        _ => {
+            return Err(ApiErrorResponse {
+                status: StatusCode::BAD_REQUEST,
+                error: ApiError::new(
+                    "WORKSPACE_ROOT",
+                    "The workspace root cannot be renamed, moved or deleted",
+                ),
+            });
+        }
+    };
+    let parent = relative
+        .parent()
+        .and_then(Path::to_str)
+        .filter(|parent| !parent.is_empty())
+        .unwrap_or(".");
+    let parent = resolve_target(state, workspace_id, parent).await?;
+    let entry = parent.join(file_name);
+    HiddenPaths::of(state, workspace_id).await.check(&entry)?;
+    Ok(entry)
+}
+
+fn validate_new_name(new_name: &str) -> Result<(), ApiErrorResponse> {
+    let mut components = Path::new(new_name).components();
+    match (components.next(), components.next()) {
+        (Some(Component::Normal(name)), None) if name == new_name => Ok(()),
+        _ => Err(ApiErrorResponse {
+            status: StatusCode::BAD_REQUEST,
+            error: ApiError::new(
+                "INVALID_NAME",
+                "New name must be a single file or directory name",
+            ),
+        }),
+    }
+}
+
+pub async fn write(
+    State(state): State<AppState>,
+    Json(body): Json<WriteRequest>,
+) -> Result<Json<lw_fs::FileStat>, ApiErrorResponse> {
+    let precondition = match (body.expected_etag, body.expected_modified_ms) {
+        (Some(etag), _) => lw_fs::Precondition::Etag(etag),
+        (None, Some(modified_ms)) => lw_fs::Precondition::ModifiedMs(modified_ms),
+        (None, None) => {
+            return Err(ApiErrorResponse {
+                status: StatusCode::PRECONDITION_REQUIRED,
+                error: ApiError::new(
+                    "PRECONDITION_REQUIRED",
+                    "Writes must include expected_etag or expected_modified_ms",
+                ),
+            });
+        }
+    };
+    let contents = decode_content(body.content, body.content_base64)?;
+    let path = resolve_target(&state, &body.workspace_id, &body.relative_path).await?;
+
+    let stat = lw_fs::write_file(&path, &contents, &precondition).map_err(fs_error_response)?;
+    Ok(Json(stat))
+}
+
+pub async fn create(
+    State(state): State<AppState>,
+    Json(body): Json<CreateFileRequest>,
+) -> Result<(StatusCode, Json<lw_fs::FileStat>), ApiErrorResponse> {
+    let contents = decode_content(body.content, body.content_base64)?;
+    let path = resolve_target(&state, &body.workspace_id, &body.relative_path).await?;
+
+    let stat = lw_fs::create_file(&path, &contents).map_err(fs_error_response)?;
+    Ok((StatusCode::CREATED, Json(stat)))
+}
+
+pub async fn mkdir(
+    State(state): State<AppState>,
+    Json(body): Json<MkdirRequest>,
+) -> Result<StatusCode, ApiErrorResponse> {
+    let path = resolve_target(&state, &body.workspace_id, &body.relative_path).await?;
+
+    lw_fs::create_dir(&path, body.recursive).map_err(fs_error_response)?;
+    Ok(StatusCode::CREATED)
+}
+
+pub async fn rename(
+    State(state): State<AppState>,
+    Json(body): Json<RenameRequest>,
+) -> Result<StatusCode, ApiErrorResponse> {
+    validate_new_name(&body.new_name)?;
+    let from = resolve_entry(&state, &body.workspace_id, &body.relative_path).await?;
+    let destination = Path::new(&body.relative_path).with_file_name(&body.new_name);
+    let to = resolve_entry(&state, &body.workspace_id, &destination.to_string_lossy()).await?;
+
+    lw_fs::move_path(&from, &to).map_err(fs_error_response)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn move_entry(
+    State(state): State<AppState>,
+    Json(body): Json<MoveRequest>,
+) -> Result<StatusCode, ApiErrorResponse> {
+    let from = resolve_entry(&state, &body.workspace_id, &body.relative_path).await?;
+    let to = resolve_entry(&state, &body.workspace_id, &body.destination_path).await?;
+
+    lw_fs::move_path(&from, &to).map_err(fs_error_response)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn delete(
+    State(state): State<AppState>,
+    Json(body): Json<DeleteRequest>,
+) -> Result<StatusCode, ApiErrorResponse> {
+    let path = resolve_entry(&state, &body.workspace_id, &body.relative_path).await?;
+    let trash_dir = lw_fs::default_trash_dir().ok_or_else(|| ApiErrorResponse {
+        status: StatusCode::INTERNAL_SERVER_ERROR,
+        error: ApiError::internal("No trash directory available for this user"),
+    })?;
+
+    lw_fs::move_to_trash(&path, &trash_dir).map_err(fs_error_response)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn make_test_state() -> (tempfile::TempDir, AppState, Uuid) {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = lw_config::DaemonConfig::default();
+        config.set_paths(lw_config::ConfigPaths::with_base(dir.path().join("config")));
+        let state = AppState::new(config, crate::auth::TokenStore::hash_token("test")).unwrap();
+        let workspace = dir.path().join("project");
+        std::fs::create_dir_all(&workspace).unwrap();
+        let workspace_id = Uuid::new_v4();
+        state
+            .workspace_registry
+            .register(workspace_id, workspace)
+            .await
+            .unwrap();
+        (dir, state, workspace_id)
+    }
+
+    fn write_request(workspace_id: Uuid, content: &str, etag: Option<String>) -> WriteRequest {
+        WriteRequest {
+            workspace_id,
+            relative_path: "main.rs".to_string(),
+            content: content.to_string(),
+            content_base64: None,
+            expected_etag: etag,
+            expected_modified_ms: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn create_then_write_with_etag_round_trip() {
+        let (dir, state, workspace_id) = make_test_state().await;
+        let (status, Json(created)) = create(
+            State(state.clone()),
+            Json(CreateFileRequest {
+                workspace_id,
+                relative_path: "main.rs".to_string(),
+                content: "fn main() {}".to_string(),
+                content_base64: None,
+            }),
+        )
+        .await
+        .unwrap_or_else(|_| panic!("create failed"));
+        assert_eq!(status, StatusCode::CREATED);
+
+        let Json(written) = write(
+            State(state.clone()),
+            Json(write_request(
+                workspace_id,
+                "fn main() { run() }",
+                Some(created.etag),
+            )),
+        )
+        .await
+        .unwrap_or_else(|_| panic!("write failed"));
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("project/main.rs")).unwrap(),
+            "fn main() { run() }"
+        );
+        assert_eq!(written.size, 19);
+    }
+
+    #[tokio::test]
+    async fn write_with_stale_etag_returns_conflict() {
+        let (dir, state, workspace_id) = make_test_state().await;
+        let path = dir.path().join("project/main.rs");
+        std::fs::write(&path, "v1").unwrap();
+        let stale = lw_fs::read_file(&path).unwrap().etag;
+        std::fs::write(&path, "agent edit").unwrap();
+
+        let result = write(
+            State(state),
+            Json(write_request(workspace_id, "mine", Some(stale))),
+        )
+        .await;
+        let Err(err) = result else {
+            panic!("expected conflict");
+        };
+        assert_eq!(err.status, StatusCode::CONFLICT);
+        assert_eq!(err.error.code, "FS_CONFLICT");
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "agent edit");
+    }
+
+    #[tokio::test]
+    async fn write_without_precondition_is_rejected() {
+        let (_dir, state, workspace_id) = make_test_state().await;
+        let result = write(State(state), Json(write_request(workspace_id, "x", None))).await;
+        let Err(err) = result else {
+            panic!("expected precondition required");
+        };
+        assert_eq!(err.status, StatusCode::PRECONDITION_REQUIRED);
+    }
+
+    #[tokio::test]
+    async fn mkdir_rename_and_move() {
+        let (dir, state, workspace_id) = make_test_state().await;
+        let root = dir.path().join("project");
+        mkdir(
+            State(state.clone()),
+            Json(MkdirRequest {
+                workspace_id,
+                relative_path: "src/lib".to_string(),
+                recursive: true,
+            }),
+        )
+        .await
+        .unwrap_or_else(|_| panic!("mkdir failed"));
+        std::fs::write(root.join("src/lib/a.rs"), "a").unwrap();
+
+        rename(
+            State(state.clone()),
+            Json(RenameRequest {
+                workspace_id,
+                relative_path: "src/lib/a.rs".to_string(),
+                new_name: "b.rs".to_string(),
+            }),
+        )
+        .await
+        .unwrap_or_else(|_| panic!("rename failed"));
+        assert!(root.join("src/lib/b.rs").is_file());
+
+        move_entry(
+            State(state),
+            Json(MoveRequest {
+                workspace_id,
+                relative_path: "src/lib/b.rs".to_string(),
+                destination_path: "tests/b.rs".to_string(),
+            }),
+        )
+        .await
+        .unwrap_or_else(|_| panic!("move failed"));
+        assert!(root.join("tests/b.rs").is_file());
+        assert!(!root.join("src/lib/b.rs").exists());
+    }
+
+    #[tokio::test]
+    async fn rename_rejects_path_in_new_name() {
+        let (_dir, state, workspace_id) = make_test_state().await;
+        let result = rename(
+            State(state),
+            Json(RenameRequest {
+                workspace_id,
+                relative_path: "a.txt".to_string(),
+                new_name: "../escape.txt".to_string(),
+            }),
+        )
+        .await;
+        let Err(err) = result else {
+            panic!("expected invalid name");
+        };
+        assert_eq!(err.status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn move_rejects_traversal_and_workspace_root() {
+        let (_dir, state, workspace_id) = make_test_state().await;
+        let result = move_entry(
+            State(state.clone()),
+            Json(MoveRequest {
+                workspace_id,
+                relative_path: "a.txt".to_string(),
+                destination_path: "../outside.txt".to_string(),
+            }),
+        )
+        .await;
+        let Err(err) = result else {
+            panic!("expected traversal error");
+        };
+        assert_eq!(err.status, StatusCode::FORBIDDEN);
+
+        let result = delete(
+            State(state),
+            Json(DeleteRequest {
+                workspace_id,
+                relative_path: ".".to_string(),
+            }),
+        )
+        .await;
+        let Err(err) = result else {
+            panic!("expected workspace root error");
+        };
+        assert_eq!(err.error.code, "WORKSPACE_ROOT");
+    }
+}
@@ -1,4 +1,6 @@
+mod changes;
 mod git_helpers;
+pub(crate) mod worktree;
 
 use std::collections::{BTreeMap, HashMap};
 use std::path::Path;
@@ -13,10 +15,15 @@ use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::error::{ApiError, ApiErrorResponse};
+use crate::project::HiddenPaths;
 use crate::state::AppState;
 #[cfg(test)]
 use git_helpers::porcelain_code_to_status;
-use git_helpers::{append_patch_segment, collect_ignored_dirs, parse_numstat, parse_porcelain};
+use git_helpers::{
+    append_patch_segment, collect_ignored_dirs, drop_patch_files, parse_numstat, parse_porcelain,
+};
+
+pub use changes::{commit, discard, stage, unstage, GitChangesRequest, GitCommitRequest};
 
 #[derive(Deserialize)]
 pub struct GitDiffQuery {
@@ -53,6 +60,28 @@ fn run_git(cwd: &Path, args: &[&str]) -> Result<Output, ApiErrorResponse> {
         })
 }
 
+fn run_git_with_stdin(cwd: &Path, args: &[&str], stdin: &[u8]) -> Result<Output, ApiErrorResponse> {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let spawn_error = |e: std::io::Error| ApiErrorResponse {
+        status: StatusCode::INTERNAL_SERVER_ERROR,
+        error: ApiError::internal(format!("Failed to run git: {e}")),
+    };
+    let mut child = Command::new("git")
+        .args(args)
+        .current_dir(cwd)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(spawn_error)?;
+    if let Some(mut child_stdin) = child.stdin.take() {
+        child_stdin.write_all(stdin).map_err(spawn_error)?;
+    }
+    child.wait_with_output().map_err(spawn_error)
+}
+
 fn command_stderr(output: &Output) -> String {
     String::from_utf8_lossy(&output.stderr).trim().to_string()
 }
@@ -102,14 +131,31 @@ fn ensure_git_repo(cwd: &Path) -> Result<(), ApiErrorResponse> {
     }
 }
 
-fn collect_untracked_patches(cwd: &Path) -> Result<String, ApiErrorResponse> {
+/// Path of the workspace inside its repository, with a trailing `/`; empty
+/// when the workspace is the repository root.
+fn workspace_prefix(workspace_root: &Path) -> Result<String, ApiErrorResponse> {
+    let toplevel_out = run_git_ok(workspace_root, &["rev-parse", "--show-toplevel"])?;
+    let toplevel = String::from_utf8_lossy(&toplevel_out.stdout)
+        .trim()
+        .to_string();
+    let rel = workspace_root
+        .strip_prefix(Path::new(&toplevel))
+        .unwrap_or(Path::new(""));
+    let s = rel.to_string_lossy().to_string();
+    Ok(if s.is_empty() { s } else { format!("{s}/") })
+}
+
+fn collect_untracked_patches(cwd: &Path, hidden: &HiddenPaths) -> Result<String, ApiErrorResponse> {
     let output = run_git_ok(cwd, &["ls-files", "--others", "--exclude-standard", "-z"])?;
     let mut untracked_paths: Vec<String> = Vec::new();
     for bytes in output.stdout.split(|byte| *byte == 0) {
         if bytes.is_empty() {
             continue;
         }
-        untracked_paths.push(String::from_utf8_lossy(bytes).to_string());
+        let path = String::from_utf8_lossy(bytes).to_string();
+        if !hidden.is_hidden_relative(&path) {
+            untracked_paths.push(path);
+        }
     }
 
     if untracked_paths.is_empty() {
@@ -215,12 +261,13 @@ pub async fn diff(
         })?;
 
     ensure_git_repo(&workspace_root)?;
+    let hidden = HiddenPaths::of(&state, &query.workspace_id).await;
 
     let has_head = run_git(&workspace_root, &["rev-parse", "--verify", "HEAD"])?
         .status
         .success();
 
-    let mut patch = if has_head {
+    let patch = if has_head {
         run_git_diff(
             &workspace_root,
             &[
@@ -258,8 +305,14 @@ pub async fn diff(
         )?;
         format!("{staged}{unstaged}")
     };
-
-    let untracked = collect_untracked_patches(&workspace_root)?;
+    // Tracked files are named from the repository root.
+    let prefix = workspace_prefix(&workspace_root)?;
+    let mut patch = drop_patch_files(&patch, |path| {
+        path.strip_prefix(&prefix)
+            .is_some_and(|relative| hidden.is_hidden_relative(relative))
+    });
+
+    let untracked = collect_untracked_patches(&workspace_root, &hidden)?;
     if !untracked.is_empty() {
         if !patch.is_empty() && !patch.ends_with('\n') {
             patch.push('\n');
@@ -280,6 +333,18 @@ pub async fn diff(
     Ok(Json(GitDiffResponse { patch }))
 }
 
+/// Drop cached diff/status for a workspace and wake its `git:status`
+/// subscribers. Call after anything that changes the index or HEAD.
+fn invalidate_git_caches(state: &AppState, workspace_id: Uuid) {
+    if let Ok(mut cache) = git_diff_cache().lock() {
+        cache.remove(&workspace_id);
+    }
+    if let Ok(mut cache) = git_status_cache().lock() {
+        cache.remove(&workspace_id);
+    }
+    let _ = state.git_changes.send(workspace_id);
+}
+
 // ── Git Status endpoint ──────────────────────────────────────────────
 
 #[derive(Deserialize)]
@@ -295,6 +360,10 @@ pub struct GitFileStatus {
     pub additions: Option<u64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub deletions: Option<u64>,
+    /// Has changes recorded in the index.
+    pub staged: bool,
+    /// Has working-tree changes not yet in the index (including untracked).
+    pub unstaged: bool,
 }
 
 #[derive(Serialize, Clone)]
@@ -303,6 +372,17 @@ pub struct GitStatusResponse {
     pub ignored_dirs: Vec<String>,
 }
 
+impl GitStatusResponse {
+    /// Leaves out the workspace's `hidden` paths.
+    pub(crate) fn without_hidden(mut self, hidden: &HiddenPaths) -> Self {
+        self.files
+            .retain(|path, _| !hidden.is_hidden_relative(path));
+        self.ignored_dirs
+            .retain(|path| !hidden.is_hidden_relative(path));
+        self
+    }
+}
+
 #[derive(Clone)]
 struct CachedStatus {
     response: GitStatusResponse,
@@ -323,22 +403,7 @@ pub fn compute_git_status(workspace_root: &Path) -> Result<GitStatusResponse, Ap
 
     // Determine workspace path relative to git toplevel so we can
     // filter and strip paths (git reports paths from the repo root).
-    let prefix = {
-        let toplevel_out = run_git_ok(workspace_root, &["rev-parse", "--show-toplevel"])?;
-        let toplevel = String::from_utf8_lossy(&toplevel_out.stdout)
-            .trim()
-            .to_string();
-        let toplevel_path = Path::new(&toplevel);
-        let rel = workspace_root
-            .strip_prefix(toplevel_path)
-            .unwrap_or(Path::new(""));
-        let s = rel.to_string_lossy().to_string();
-        if s.is_empty() {
-            s
-        } else {
-            format!("{s}/")
-        }
-    };
+    let prefix = workspace_prefix(workspace_root)?;
 
     // 1. File statuses via porcelain
     let porcelain_output = run_git_ok(workspace_root, &["status", "--porcelain=v1", "-z"])?;
@@ -428,7 +493,8 @@ pub async fn status(
             ApiErrorResponse { status, error }
         })?;
 
-    let response = compute_git_status(&workspace_root)?;
+    let hidden = HiddenPaths::of(&state, &query.workspace_id).await;
+    let response = compute_git_status(&workspace_root)?.without_hidden(&hidden);
 
     if let Ok(mut cache) = git_status_cache().lock() {
         cache.insert(
@@ -533,6 +599,16 @@ mod tests {
         assert_eq!(files["gone.rs"].status, "deleted");
     }
 
+    #[test]
+    fn parse_porcelain_staged_and_unstaged_flags() {
+        let raw = b"M  staged.rs\0 M unstaged.rs\0MM both.rs\0?? new.rs\0";
+        let files = parse_porcelain(raw);
+        assert!(files["staged.rs"].staged && !files["staged.rs"].unstaged);
+        assert!(!files["unstaged.rs"].staged && files["unstaged.rs"].unstaged);
+        assert!(files["both.rs"].staged && files["both.rs"].unstaged);
+        assert!(!files["new.rs"].staged && files["new.rs"].unstaged);
+    }
+
     #[test]
     fn parse_porcelain_rename_consumes_extra_field() {
         // Renames produce: "R  new_name\0old_name\0"
@@ -580,6 +656,8 @@ mod tests {
                 status: "modified".to_string(),
                 additions: None,
                 deletions: None,
+                staged: false,
+                unstaged: true,
             },
         );
         parse_numstat("10\t5\tfile.rs\n", &mut files, "");
@@ -596,6 +674,8 @@ mod tests {
                 status: "modified".to_string(),
                 additions: None,
                 deletions: None,
+                staged: false,
+                unstaged: true,
             },
         );
         parse_numstat("3\t1\tunknown.rs\n", &mut files, "");
@@ -611,6 +691,8 @@ mod tests {
                 status: "modified".to_string(),
                 additions: None,
                 deletions: None,
+                staged: false,
+                unstaged: true,
             },
         );
         parse_numstat("7\t2\tsrc/file.rs\n", &mut files, "src/");
@@ -626,6 +708,8 @@ mod tests {
                 status: "added".to_string(),
                 additions: None,
                 deletions: None,
+                staged: false,
+                unstaged: true,
             },
         );
         parse_numstat("-\t-\timage.png\n", &mut files, "");
@@ -642,6 +726,8 @@ mod tests {
                 status: "modified".to_string(),
                 additions: None,
                 deletions: None,
+                staged: false,
+                unstaged: true,
             },
         );
         files.insert(
@@ -650,6 +736,8 @@ mod tests {
                 status: "modified".to_string(),
                 additions: None,
                 deletions: None,
+                staged: false,
+                unstaged: true,
             },
         );
         parse_numstat("1\t2\ta.rs\n3\t4\tb.rs\n", &mut files, "");
@@ -666,6 +754,8 @@ mod tests {
                 status: "modified".to_string(),
                 additions: None,
                 deletions: None,
+                staged: false,
+                unstaged: true,
             },
         );
         parse_numstat("bad_line\n1\t2\tok.rs\n", &mut files, "");
@@ -719,6 +809,8 @@ mod tests {
                 status: "modified".to_string(),
                 additions: None,
                 deletions: None,
+                staged: false,
+                unstaged: true,
             },
         );
         parse_numstat("4\t2\toutside/inside.rs\n", &mut files, "src/");
@@ -747,4 +839,78 @@ mod tests {
         append_patch_segment(&mut target, "second");
         assert_eq!(target, "first\nsecond\n");
     }
+
+    // ── hidden paths ──────────────────────────────────────────────────
+
+    fn git(dir: &Path, args: &[&str]) {
+        assert!(Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .status()
+            .unwrap()
+            .success());
+    }
+
+    #[tokio::test]
+    async fn diff_and_status_leave_out_hidden_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path().join("repo");
+        std::fs::create_dir(&repo).unwrap();
+        git(&repo, &["init", "-q"]);
+        git(&repo, &["config", "user.email", "test@example.com"]);
+        git(&repo, &["config", "user.name", "Test"]);
+        std::fs::write(repo.join(".env"), "TOKEN=old\n").unwrap();
+        std::fs::write(repo.join("app.txt"), "old\n").unwrap();
+        git(&repo, &["add", "."]);
+        git(&repo, &["commit", "-q", "-m", "init"]);
+        std::fs::write(repo.join(".env"), "TOKEN=secret\n").unwrap();
+        std::fs::write(repo.join("app.txt"), "new\n").unwrap();
+        std::fs::create_dir(repo.join("secrets")).unwrap();
+        std::fs::write(repo.join("secrets").join("key"), "secret\n").unwrap();
+        std::fs::write(repo.join("notes.txt"), "new\n").unwrap();
+        std::fs::write(
+            repo.join(lw_config::PROJECT_CONFIG_FILE),
+            "hidden = [\".env\", \"secrets/\"]",
+        )
+        .unwrap();
+
+        let state = AppState::for_test(dir.path(), |_| {});
+        let workspace_id = Uuid::new_v4();
+        state
+            .workspace_registry
+            .register(workspace_id, repo.clone())
+            .await
+            .unwrap();
+
+        let Json(response) = diff(
+            State(state.clone()),
+            Query(GitDiffQuery {
+                workspace_id,
+                force: Some(true),
+            }),
+        )
+        .await
+        .unwrap();
+        assert!(response.patch.contains("app.txt"));
+        assert!(response.patch.contains("notes.txt"));
+        // The untracked `.loopwire.toml` names the patterns, so check contents.
+        assert!(!response.patch.contains("TOKEN"));
+        assert!(!response.patch.contains("secrets/key"));
+
+        let Json(response) = status(
+            State(state),
+            Query(GitStatusQuery {
+                workspace_id,
+                force: Some(true),
+            }),
+        )
+        .await
+        .unwrap();
+        assert!(response.files.contains_key("app.txt"));
+        assert!(!response.files.contains_key(".env"));
+        assert!(!response
+            .files
+            .keys()
+            .any(|path| path.starts_with("secrets")));
+    }
 }
@@ -0,0 +1,500 @@
+use std::path::{Path, PathBuf};
+
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::{ensure_git_repo, invalidate_git_caches, run_git, run_git_ok, run_git_with_stdin};
+use crate::error::{ApiError, ApiErrorResponse};
+use crate::state::AppState;
+
+/// Body for stage, unstage and discard. Exactly one of `paths` or `patch`
+/// must be given.
+#[derive(Deserialize)]
+pub struct GitChangesRequest {
+    pub workspace_id: Uuid,
+    /// Files or directories relative to the workspace root.
+    #[serde(default)]
+    pub paths: Vec<String>,
+    /// A unified diff containing only the hunks to act on. Staging and
+    /// discarding expect hunks from the unstaged diff (`git diff`),
+    /// unstaging expects hunks from the staged diff (`git diff --cached`).
+    pub patch: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct GitAuthor {
+    pub name: String,
+    pub email: String,
+}
+
+#[derive(Deserialize)]
+pub struct GitCommitRequest {
+    pub workspace_id: Uuid,
+    pub message: String,
+    /// Defaults to the repository's configured `user.name`/`user.email`.
+    pub author: Option<GitAuthor>,
+}
+
+#[derive(Serialize)]
+pub struct GitCommitResponse {
+    pub commit: String,
+}
+
+enum ChangeTarget {
+    Paths(Vec<String>),
+    Patch(String),
+}
+
+async fn workspace_repo(
+    state: &AppState,
+    workspace_id: &Uuid,
+) -> Result<PathBuf, ApiErrorResponse> {
+    let root = state
+        .workspace_registry
+        .resolve(workspace_id, ".")
+        .await
+        .map_err(|e| {
+            let (status, error) = ApiError::fs_error(&e);
+            ApiErrorResponse { status, error }
+        })?;
+    ensure_git_repo(&root)?;
+    Ok(root)
+}
+
+async fn change_target(
+    state: &AppState,
+    body: GitChangesRequest,
+) -> Result<ChangeTarget, ApiErrorResponse> {
+    match (body.paths.is_empty(), body.patch) {
+        (false, None) => {
+            let mut paths = Vec::with_capacity(body.paths.len());
+            for path in body.paths {
+                // Same traversal/symlink checks as the fs endpoints.
+                state
+                    .workspace_registry
+                    .resolve(&body.workspace_id, &path)
+                    .await
+                    .map_err(|e| {
+                        let (status, error) = ApiError::fs_error(&e);
+                        ApiErrorResponse { status, error }
+                    })?;
+                paths.push(if path.is_empty() {
+                    ".".to_string()
+                } else {
+                    path
+                });
+            }
+            Ok(ChangeTarget::Paths(paths))
+        }
+        (true, Some(patch)) if !patch.trim().is_empty() => Ok(ChangeTarget::Patch(patch)),
+        _ => Err(ApiErrorResponse {
+            status: StatusCode::BAD_REQUEST,
+            error: ApiError::new(
+                "INVALID_GIT_CHANGES",
+                "Provide either a non-empty paths list or a patch",
+            ),
+        }),
+    }
+}
+
+/// `git --literal-pathspecs <args> -- <paths>` so file names are never
+/// interpreted as globs or pathspec magic.
+fn run_git_on_paths(cwd: &Path, args: &[&str], paths: &[String]) -> Result<(), ApiErrorResponse> {
+    let mut full_args = vec!["--literal-pathspecs"];
+    full_args.extend_from_slice(args);
+    full_args.push("--");
+    full_args.extend(paths.iter().map(String::as_str));
+    run_git_ok(cwd, &full_args)?;
+    Ok(())
+}
+
+fn list_files(
+    cwd: &Path,
+    args: &[&str],
+    paths: &[String],
+) -> Result<Vec<String>, ApiErrorResponse> {
+    let mut full_args = vec!["--literal-pathspecs", "ls-files", "-z"];
+    full_args.extend_from_slice(args);
+    full_args.push("--");
+    full_args.extend(paths.iter().map(String::as_str));
+    let output = run_git_ok(cwd, &full_args)?;
+    Ok(output
+        .stdout
+        .split(|byte| *byte == 0)
+        .filter(|bytes| !bytes.is_empty())
+        .map(|bytes| String::from_utf8_lossy(bytes).to_string())
+        .collect())
+}
+
+fn apply_patch(cwd: &Path, args: &[&str], patch: &str) -> Result<(), ApiErrorResponse> {
+    let mut full_args = vec!["apply", "--recount"];
+    full_args.extend_from_slice(args);
+    full_args.push("-");
+    let mut patch = patch.to_string();
+    if !patch.ends_with('\n') {
+        patch.push('\n');
+    }
+    let output = run_git_with_stdin(cwd, &full_args, patch.as_bytes())?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(ApiErrorResponse {
+            status: StatusCode::CONFLICT,
+            error: ApiError::new(
+                "PATCH_DOES_NOT_APPLY",
+                format!("Patch does not apply: {}", super::command_stderr(&output)),
+            ),
+        })
+    }
+}
+
+fn has_head(cwd: &Path) -> Result<bool, ApiErrorResponse> {
+    Ok(run_git(cwd, &["rev-parse", "--verify", "--quiet", "HEAD"])?
+        .status
+        .success())
+}
+
+pub async fn stage(
+    State(state): State<AppState>,
+    Json(body): Json<GitChangesRequest>,
+) -> Result<StatusCode, ApiErrorResponse> {
+    let workspace_id = body.workspace_id;
+    let root = workspace_repo(&state, &workspace_id).await?;
+    match change_target(&state, body).await? {
+        ChangeTarget::Paths(paths) => run_git_on_paths(&root, &["add", "-A"], &paths)?,
+        ChangeTarget::Patch(patch) => apply_patch(&root, &["--cached"], &patch)?,
+    }
+    invalidate_git_caches(&state, workspace_id);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn unstage(
+    State(state): State<AppState>,
+    Json(body): Json<GitChangesRequest>,
+) -> Result<StatusCode, ApiErrorResponse> {
+    let workspace_id = body.workspace_id;
+    let root = workspace_repo(&state, &workspace_id).await?;
+    match change_target(&state, body).await? {
+        ChangeTarget::Paths(paths) => {
+            if has_head(&root)? {
+                run_git_on_paths(&root, &["reset", "-q", "HEAD"], &paths)?;
+            } else {
+                run_git_on_paths(
+                    &root,
+                    &["rm", "-r", "-q", "--cached", "--ignore-unmatch"],
+                    &paths,
+                )?;
+            }
+        }
+        ChangeTarget::Patch(patch) => apply_patch(&root, &["--cached", "--reverse"], &patch)?,
+    }
+    invalidate_git_caches(&state, workspace_id);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Revert working-tree changes to what is in the index. Untracked files
+/// under `paths` are moved to the trash rather than deleted.
+pub async fn discard(
+    State(state): State<AppState>,
+    Json(body): Json<GitChangesRequest>,
+) -> Result<StatusCode, ApiErrorResponse> {
+    let workspace_id = body.workspace_id;
+    let root = workspace_repo(&state, &workspace_id).await?;
+    match change_target(&state, body).await? {
+        ChangeTarget::Paths(paths) => {
+            let tracked = list_files(&root, &[], &paths)?;
+            if !tracked.is_empty() {
+                run_git_on_paths(&root, &["checkout", "-q"], &tracked)?;
+            }
+            let untracked = list_files(&root, &["--others", "--exclude-standard"], &paths)?;
+            if !untracked.is_empty() {
+                let trash_dir = lw_fs::default_trash_dir().ok_or_else(|| ApiErrorResponse {
+                    status: StatusCode::INTERNAL_SERVER_ERROR,
+                    error: ApiError::internal("No trash directory available for this user"),
+                })?;
+                for file in untracked {
+                    lw_fs::move_to_trash(&root.join(file), &trash_dir).map_err(|e| {
+                        let (status, error) = ApiError::fs_error(&e);
+                        ApiErrorResponse { status, error }
+                    })?;
+                }
+            }
+        }
+        ChangeTarget::Patch(patch) => apply_patch(&root, &["--reverse"], &patch)?,
+    }
+    invalidate_git_caches(&state, workspace_id);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+fn author_arg(author: &GitAuthor) -> Result<String, ApiErrorResponse> {
+    let name = author.name.trim();
+    let email = author.email.trim();
+    let invalid = |value: &str| value.is_empty() || value.contains(['<', '>', '\n']);
+    if invalid(name) || invalid(email) {
+        return Err(ApiErrorResponse {
+            status: StatusCode::BAD_REQUEST,
+            error: ApiError::new("INVALID_AUTHOR", "Author name and email are required"),
+        });
+    }
+    Ok(format!("--author={name} <{email}>"))
+}
+
+/// Commit what is currently staged.
+pub async fn commit(
+    State(state): State<AppState>,
+    Json(body): Json<GitCommitRequest>,
+) -> Result<(StatusCode, Json<GitCommitResponse>), ApiErrorResponse> {
+    if body.message.trim().is_empty() {
+        return Err(ApiErrorResponse {
+            status: StatusCode::BAD_REQUEST,
+            error: ApiError::new("INVALID_COMMIT_MESSAGE", "Commit message is required"),
+        });
+    }
+    let author = body.author.as_ref().map(author_arg).transpose()?;
+    let root = workspace_repo(&state, &body.workspace_id).await?;
+
+    let nothing_staged = run_git(&root, &["diff", "--cached", "--quiet"])?
+        .status
+        .success();
+    if nothing_staged {
+        return Err(ApiErrorResponse {
+            status: StatusCode::BAD_REQUEST,
+            error: ApiError::new("NOTHING_TO_COMMIT", "No staged changes to commit"),
+        });
+    }
+
+    let mut args = vec!["commit", "-q", "-m", body.message.as_str()];
+    if let Some(author) = author.as_deref() {
+        args.push(author);
+    }
+    run_git_ok(&root, &args)?;
+    let head = run_git_ok(&root, &["rev-parse", "HEAD"])?;
+    invalidate_git_caches(&state, body.workspace_id);
+
+    Ok((
+        StatusCode::CREATED,
+        Json(GitCommitResponse {
+            commit: String::from_utf8_lossy(&head.stdout).trim().to_string(),
+        }),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rest::git::compute_git_status;
+    use std::process::Command;
+
+    fn git(dir: &Path, args: &[&str]) -> String {
+        let output = Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "git {args:?} failed");
+        String::from_utf8_lossy(&output.stdout).to_string()
+    }
+
+    async fn make_repo_state() -> (tempfile::TempDir, AppState, Uuid, PathBuf) {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = lw_config::DaemonConfig::default();
+        config.set_paths(lw_config::ConfigPaths::with_base(dir.path().join("config")));
+        let state = AppState::new(config, crate::auth::TokenStore::hash_token("test")).unwrap();
+
+        let repo = dir.path().join("repo");
+        std::fs::create_dir_all(&repo).unwrap();
+        git(&repo, &["init", "-q"]);
+        git(&repo, &["config", "user.email", "test@example.com"]);
+        git(&repo, &["config", "user.name", "Test"]);
+        std::fs::write(repo.join("a.txt"), "one\ntwo\nthree\n").unwrap();
+        git(&repo, &["add", "a.txt"]);
+        git(&repo, &["commit", "-q", "-m", "init"]);
+
+        let workspace_id = Uuid::new_v4();
+        state
+            .workspace_registry
+            .register(workspace_id, repo.clone())
+            .await
+            .unwrap();
+        (dir, state, workspace_id, repo)
+    }
+
+    fn paths_request(workspace_id: Uuid, paths: &[&str]) -> GitChangesRequest {
+        GitChangesRequest {
+            workspace_id,
+            paths: paths.iter().map(|p| p.to_string()).collect(),
+            patch: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn stage_unstage_and_commit_paths() {
+        let (_dir, state, workspace_id, repo) = make_repo_state().await;
+        std::fs::write(repo.join("a.txt"), "one\ntwo\nthree\nfour\n").unwrap();
+        std::fs::write(repo.join("b.txt"), "new\n").unwrap();
+
+        stage(
+            State(state.clone()),
+            Json(paths_request(workspace_id, &["a.txt", "b.txt"])),
+        )
+        .await
+        .unwrap_or_else(|_| panic!("stage failed"));
+        let status = compute_git_status(&repo).unwrap_or_else(|_| panic!("status failed"));
+        assert!(status.files["a.txt"].staged);
+        assert!(status.files["b.txt"].staged);
+
+        unstage(
+            State(state.clone()),
+            Json(paths_request(workspace_id, &["b.txt"])),
+        )
+        .await
+        .unwrap_or_else(|_| panic!("unstage failed"));
+        let status = compute_git_status(&repo).unwrap_or_else(|_| panic!("status failed"));
+        assert_eq!(status.files["b.txt"].status, "untracked");
+
+        let (code, Json(response)) = commit(
+            State(state.clone()),
+            Json(GitCommitRequest {
+                workspace_id,
+                message: "Add four".to_string(),
+                author: Some(GitAuthor {
+                    name: "Reviewer".to_string(),
+                    email: "reviewer@example.com".to_string(),
+                }),
+            }),
+        )
+        .await
+        .unwrap_or_else(|_| panic!("commit failed"));
+        assert_eq!(code, StatusCode::CREATED);
+        assert_eq!(git(&repo, &["rev-parse", "HEAD"]).trim(), response.commit);
+        assert_eq!(
+            git(&repo, &["log", "-1", "--format=%an <%ae>|%s"]).trim(),
+            "Reviewer <reviewer@example.com>|Add four"
+        );
+    }
+
+    #[tokio::test]
+    async fn stage_and_unstage_single_hunk() {
+        let (_dir, state, workspace_id, repo) = make_repo_state().await;
+        std::fs::write(repo.join("a.txt"), "ONE\ntwo\nthree\n").unwrap();
+        let patch = git(&repo, &["diff", "--no-color", "--", "a.txt"]);
+
+        stage(
+            State(state.clone()),
+            Json(GitChangesRequest {
+                workspace_id,
+                paths: vec![],
+                patch: Some(patch),
+            }),
+        )
+        .await
+        .unwrap_or_else(|_| panic!("stage hunk failed"));
+        assert_eq!(
+            git(&repo, &["diff", "--cached", "--name-only"]).trim(),
+            "a.txt"
+        );
+
+        let staged_patch = git(&repo, &["diff", "--cached", "--no-color"]);
+        unstage(
+            State(state),
+            Json(GitChangesRequest {
+                workspace_id,
+                paths: vec![],
+                patch: Some(staged_patch),
+            }),
+        )
+        .await
+        .unwrap_or_else(|_| panic!("unstage hunk failed"));
+        assert!(git(&repo, &["diff", "--cached", "--name-only"]).is_empty());
+    }
+
+    #[tokio::test]
+    async fn stale_patch_returns_conflict() {
+        let (_dir, state, workspace_id, _repo) = make_repo_state().await;
+        let patch = "--- a/a.txt\n+++ b/a.txt\n@@ -1,1 +1,1 @@\n-missing\n+other\n";
+        let result = stage(
+            State(state),
+            Json(GitChangesRequest {
+                workspace_id,
+                paths: vec![],
+                patch: Some(patch.to_string()),
+            }),
+        )
+        .await;
+        let Err(err) = result else {
+            panic!("expected patch conflict");
+        };
+        assert_eq!(err.status, StatusCode::CONFLICT);
+        assert_eq!(err.error.code, "PATCH_DOES_NOT_APPLY");
+    }
+
+    #[tokio::test]
+    async fn discard_restores_tracked_file() {
+        let (_dir, state, workspace_id, repo) = make_repo_state().await;
+        std::fs::write(repo.join("a.txt"), "clobbered\n").unwrap();
+
+        discard(State(state), Json(paths_request(workspace_id, &["a.txt"])))
+            .await
+            .unwrap_or_else(|_| panic!("discard failed"));
+        assert_eq!(
+            std::fs::read_to_string(repo.join("a.txt")).unwrap(),
+            "one\ntwo\nthree\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_traversal_and_empty_requests() {
+        let (_dir, state, workspace_id, _repo) = make_repo_state().await;
+        let result = stage(
+            State(state.clone()),
+            Json(paths_request(workspace_id, &["../outside"])),
+        )
+        .await;
+        let Err(err) = result else {
+            panic!("expected traversal error");
+        };
+        assert_eq!(err.status, StatusCode::FORBIDDEN);
+
+        let result = stage(State(state), Json(paths_request(workspace_id, &[]))).await;
+        let Err(err) = result else {
+            panic!("expected invalid request");
+        };
+        assert_eq!(err.error.code, "INVALID_GIT_CHANGES");
+    }
+
+    #[tokio::test]
+    async fn commit_requires_staged_changes() {
+        let (_dir, state, workspace_id, _repo) = make_repo_state().await;
+        let result = commit(
+            State(state),
+            Json(GitCommitRequest {
+                workspace_id,
+                message: "Empty".to_string(),
+                author: None,
+            }),
+        )
+        .await;
+        let Err(err) = result else {
+            panic!("expected nothing to commit");
+        };
+        assert_eq!(err.error.code, "NOTHING_TO_COMMIT");
+    }
+
+    #[tokio::test]
+    async fn mutations_notify_git_status_subscribers() {
+        let (_dir, state, workspace_id, repo) = make_repo_state().await;
+        let mut changes = state.git_changes.subscribe();
+        std::fs::write(repo.join("a.txt"), "changed\n").unwrap();
+
+        stage(
+            State(state.clone()),
+            Json(paths_request(workspace_id, &["a.txt"])),
+        )
+        .await
+        .unwrap_or_else(|_| panic!("stage failed"));
+        assert_eq!(changes.try_recv().unwrap(), workspace_id);
+    }
+}
@@ -30,6 +30,8 @@ pub(super) fn parse_porcelain(raw: &[u8]) -> BTreeMap<String, GitFileStatus> {
                 status: status.to_string(),
                 additions: None,
                 deletions: None,
+                staged: x != b' ' && x != b'?',
+                unstaged: y != b' ',
             },
         );
         if x == b'R' || y == b'R' {
@@ -100,3 +102,30 @@ pub(super) fn append_patch_segment(target: &mut String, segment: &str) {
         target.push('\n');
     }
 }
+
+/// Path of the file a `diff --git a/<path> b/<path>` header introduces,
+/// still C-quoted if git quoted it. Renames are off, so both sides match.
+fn patch_header_path(header: &str) -> Option<&str> {
+    let rest = header.strip_prefix("diff --git ")?;
+    let (open, sides) = if rest.starts_with('"') {
+        (3, rest.len().checked_sub(9)? / 2)
+    } else {
+        (2, rest.len().checked_sub(5)? / 2)
+    };
+    rest.get(open..open + sides)
+}
+
+/// Drops the files of `patch` whose path `is_hidden` accepts.
+pub(super) fn drop_patch_files(patch: &str, is_hidden: impl Fn(&str) -> bool) -> String {
+    let mut kept = String::with_capacity(patch.len());
+    let mut keep = true;
+    for line in patch.split_inclusive('\n') {
+        if line.starts_with("diff --git ") {
+            keep = patch_header_path(line.trim_end()).is_none_or(|path| !is_hidden(path));
+        }
+        if keep {
+            kept.push_str(line);
+        }
+    }
+    kept
+}
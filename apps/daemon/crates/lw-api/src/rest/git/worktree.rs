@@ -0,0 +1,156 @@
+use std::path::Path;
+
+use axum::http::StatusCode;
+
+use super::{ensure_git_repo, run_git, run_git_ok};
+use crate::error::{ApiError, ApiErrorResponse};
+
+/// Default branch name for a session worktree: `loopwire/<first 8 of id>`.
+pub(crate) fn default_branch_name(worktree_id: uuid::Uuid) -> String {
+    let id = worktree_id.simple().to_string();
+    format!("loopwire/{}", &id[..8])
+}
+
+fn ensure_valid_branch(repo: &Path, branch: &str) -> Result<(), ApiErrorResponse> {
+    let output = run_git(repo, &["check-ref-format", "--branch", branch])?;
+    if output.status.success() && !branch.starts_with('-') {
+        Ok(())
+    } else {
+        Err(ApiErrorResponse {
+            status: StatusCode::BAD_REQUEST,
+            error: ApiError::new("INVALID_BRANCH", format!("Invalid branch name: {branch}")),
+        })
+    }
+}
+
+/// Creates `worktree_path` as a new worktree of `repo` on a new `branch`
+/// starting at the repo's current `HEAD`.
+pub(crate) fn create_worktree(
+    repo: &Path,
+    worktree_path: &Path,
+    branch: &str,
+) -> Result<(), ApiErrorResponse> {
+    ensure_git_repo(repo)?;
+    ensure_valid_branch(repo, branch)?;
+    if let Some(parent) = worktree_path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| ApiErrorResponse {
+            status: StatusCode::INTERNAL_SERVER_ERROR,
+            error: ApiError::internal(format!("Failed to create worktree directory: {e}")),
+        })?;
+    }
+    let path = worktree_path.to_string_lossy();
+    run_git_ok(repo, &["worktree", "add", "-b", branch, &path, "HEAD"])?;
+    Ok(())
+}
+
+/// Whether the worktree has uncommitted changes or untracked files. A
+/// worktree that is already gone has none.
+pub(crate) fn worktree_is_dirty(worktree_path: &Path) -> Result<bool, ApiErrorResponse> {
+    if !worktree_path.exists() {
+        return Ok(false);
+    }
+    let output = run_git_ok(worktree_path, &["status", "--porcelain"])?;
+    Ok(!output.stdout.is_empty())
+}
+
+/// Removes a session worktree, discarding uncommitted changes in it.
+/// Commits stay reachable from `branch` unless `delete_branch` is set.
+pub(crate) fn remove_worktree(
+    repo: &Path,
+    worktree_path: &Path,
+    branch: &str,
+    delete_branch: bool,
+) -> Result<(), ApiErrorResponse> {
+    let path = worktree_path.to_string_lossy();
+    if worktree_path.exists() {
+        run_git_ok(repo, &["worktree", "remove", "--force", &path])?;
+    } else {
+        run_git_ok(repo, &["worktree", "prune"])?;
+    }
+    if delete_branch {
+        run_git_ok(repo, &["branch", "-D", branch])?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    fn init_repo(dir: &Path) {
+        for args in [
+            vec!["init", "-q"],
+            vec!["config", "user.email", "test@example.com"],
+            vec!["config", "user.name", "Test"],
+            vec!["commit", "-q", "--allow-empty", "-m", "init"],
+        ] {
+            assert!(Command::new("git")
+                .args(&args)
+                .current_dir(dir)
+                .status()
+                .unwrap()
+                .success());
+        }
+    }
+
+    fn branch_exists(repo: &Path, branch: &str) -> bool {
+        run_git(repo, &["rev-parse", "--verify", "--quiet", branch])
+            .unwrap()
+            .status
+            .success()
+    }
+
+    #[test]
+    fn default_branch_name_uses_short_id() {
+        let id = uuid::Uuid::parse_str("12345678-1234-1234-1234-123456789abc").unwrap();
+        assert_eq!(default_branch_name(id), "loopwire/12345678");
+    }
+
+    #[test]
+    fn create_and_remove_worktree() {
+        let repo = tempfile::tempdir().unwrap();
+        init_repo(repo.path());
+        let data = tempfile::tempdir().unwrap();
+        let worktree = data.path().join("worktrees").join("wt");
+
+        create_worktree(repo.path(), &worktree, "loopwire/test").unwrap();
+        assert!(worktree.join(".git").exists());
+        assert!(branch_exists(repo.path(), "loopwire/test"));
+        assert!(!worktree_is_dirty(&worktree).unwrap());
+        std::fs::write(worktree.join("notes.txt"), "wip").unwrap();
+        assert!(worktree_is_dirty(&worktree).unwrap());
+
+        remove_worktree(repo.path(), &worktree, "loopwire/test", false).unwrap();
+        assert!(!worktree.exists());
+        assert!(branch_exists(repo.path(), "loopwire/test"));
+    }
+
+    #[test]
+    fn remove_worktree_can_delete_branch() {
+        let repo = tempfile::tempdir().unwrap();
+        init_repo(repo.path());
+        let data = tempfile::tempdir().unwrap();
+        let worktree = data.path().join("wt");
+
+        create_worktree(repo.path(), &worktree, "loopwire/gone").unwrap();
+        remove_worktree(repo.path(), &worktree, "loopwire/gone", true).unwrap();
+        assert!(!branch_exists(repo.path(), "loopwire/gone"));
+    }
+
+    #[test]
+    fn create_worktree_rejects_invalid_branch() {
+        let repo = tempfile::tempdir().unwrap();
+        init_repo(repo.path());
+        let worktree = repo.path().join("wt");
+        let err = create_worktree(repo.path(), &worktree, "bad..name").unwrap_err();
+        assert_eq!(err.status, StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn create_worktree_requires_git_repo() {
+        let dir = tempfile::tempdir().unwrap();
+        let err = create_worktree(dir.path(), &dir.path().join("wt"), "loopwire/x").unwrap_err();
+        assert_eq!(err.status, StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,118 @@
+use axum::extract::{ConnectInfo, Path, Query, State};
+use axum::http::StatusCode;
+use axum::Json;
+use lw_config::DaemonConfig;
+use serde::Deserialize;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use uuid::Uuid;
+
+use crate::error::{ApiError, ApiErrorResponse};
+use crate::state::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct HookQuery {
+    pub token: String,
+}
+
+/// URL agents call back on, reachable from the daemon's own machine.
+pub fn hooks_base_url(config: &DaemonConfig) -> String {
+    let host = match config.host {
+        IpAddr::V4(ip) if ip.is_unspecified() => IpAddr::V4(Ipv4Addr::LOCALHOST),
+        IpAddr::V6(ip) if ip.is_unspecified() => IpAddr::V6(Ipv6Addr::LOCALHOST),
+        host => host,
+    };
+    format!("http://{}/api/v1/hooks", SocketAddr::new(host, config.port))
+}
+
+/// Receives lifecycle hook callbacks (Claude Code hooks, Codex `notify`)
+/// from agent processes. Authenticated by the per-session token handed to
+/// the agent at launch rather than a user session.
+pub async fn ingest(
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    State(state): State<AppState>,
+    Path(session_id): Path<Uuid>,
+    Query(query): Query<HookQuery>,
+    Json(payload): Json<serde_json::Value>,
+) -> Result<StatusCode, ApiErrorResponse> {
+    if !addr.ip().is_loopback() && addr.ip() != state.config.host {
+        return Err(ApiErrorResponse {
+            status: StatusCode::FORBIDDEN,
+            error: ApiError::new(
+                "FORBIDDEN",
+                "This endpoint is only available from the local machine",
+            ),
+        });
+    }
+    if !state
+        .agent_manager
+        .ingest_hook(&session_id, &query.token, &payload)
+        .await
+    {
+        return Err(ApiErrorResponse {
+            status: StatusCode::FORBIDDEN,
+            error: ApiError::new("INVALID_HOOK_TOKEN", "Unknown session or hook token"),
+        });
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base_url_uses_loopback_for_unspecified_host() {
+        let mut config = DaemonConfig::default();
+        config.host = IpAddr::V4(Ipv4Addr::UNSPECIFIED);
+        config.port = 9400;
+        assert_eq!(
+            hooks_base_url(&config),
+            "http://127.0.0.1:9400/api/v1/hooks"
+        );
+        config.host = IpAddr::V6(Ipv6Addr::UNSPECIFIED);
+        assert_eq!(hooks_base_url(&config), "http://[::1]:9400/api/v1/hooks");
+        config.host = "192.168.1.20".parse().unwrap();
+        assert_eq!(
+            hooks_base_url(&config),
+            "http://192.168.1.20:9400/api/v1/hooks"
+        );
+    }
+
+    #[tokio::test]
+    async fn ingest_rejects_unknown_token_and_remote_peers() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = AppState::for_test(dir.path(), |_| {});
+        let payload = serde_json::json!({ "hook_event_name": "Stop" });
+
+        let result = ingest(
+            ConnectInfo("127.0.0.1:5000".parse().unwrap()),
+            State(state.clone()),
+            Path(Uuid::new_v4()),
+            Query(HookQuery {
+                token: "nope".to_string(),
+            }),
+            Json(payload.clone()),
+        )
+        .await;
+        let Err(err) = result else {
+            panic!("expected unknown token to be rejected");
+        };
+        assert_eq!(err.status, StatusCode::FORBIDDEN);
+        assert_eq!(err.error.code, "INVALID_HOOK_TOKEN");
+
+        let result = ingest(
+            ConnectInfo("10.0.0.7:5000".parse().unwrap()),
+            State(state),
+            Path(Uuid::new_v4()),
+            Query(HookQuery {
+                token: "nope".to_string(),
+            }),
+            Json(payload),
+        )
+        .await;
+        let Err(err) = result else {
+            panic!("expected remote peer to be rejected");
+        };
+        assert_eq!(err.error.code, "FORBIDDEN");
+    }
+}
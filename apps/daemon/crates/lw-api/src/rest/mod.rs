@@ -1,8 +1,16 @@
 pub mod agent;
 pub mod auth;
 pub mod bootstrap;
+pub mod export;
+pub mod fs;
 pub mod git;
 pub mod health;
+pub mod hooks;
+pub mod preview;
 
 pub mod remote;
+pub mod search;
+pub mod shell;
+pub mod task;
 pub mod workspace;
+pub mod workspace_task;
@@ -0,0 +1,10 @@
+use axum::extract::State;
+use axum::Json;
+
+use crate::preview::OpenPort;
+use crate::state::AppState;
+
+/// Ports that can be opened through `/api/v1/preview/{port}/`.
+pub async fn list_ports(State(state): State<AppState>) -> Json<Vec<OpenPort>> {
+    Json(state.port_tracker.list().await)
+}
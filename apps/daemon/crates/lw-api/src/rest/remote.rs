@@ -1,11 +1,13 @@
 use axum::extract::{ConnectInfo, State};
-use axum::http::StatusCode;
+use axum::http::{HeaderMap, StatusCode};
 use axum::Json;
 use serde::Deserialize;
 use std::net::SocketAddr;
 
+use crate::auth::{NewSession, SessionOrigin};
 use crate::error::{ApiError, ApiErrorResponse};
 use crate::remote::{RemoteError, ShareStartOptions};
+use crate::rest::auth::user_agent;
 use crate::state::AppState;
 
 #[derive(Debug, Deserialize)]
@@ -24,6 +26,8 @@ pub struct InviteExchangeRequest {
     pub invite_token: String,
     pub pin: Option<String>,
     pub trusted_device_token: Option<String>,
+    /// Name for the device in the session list.
+    pub label: Option<String>,
 }
 
 pub async fn share_start(
@@ -70,6 +74,7 @@ pub async fn invite_bootstrap(
 
 pub async fn invite_exchange(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Json(body): Json<InviteExchangeRequest>,
 ) -> Result<Json<crate::remote::InviteExchangeResult>, ApiErrorResponse> {
     let response = state
@@ -78,6 +83,11 @@ pub async fn invite_exchange(
             &body.invite_token,
             body.pin.as_deref(),
             body.trusted_device_token.as_deref(),
+            NewSession {
+                label: body.label,
+                user_agent: user_agent(&headers),
+                origin: SessionOrigin::Remote,
+            },
         )
         .await
         .map_err(remote_error_to_response)?;
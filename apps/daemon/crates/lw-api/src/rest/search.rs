@@ -0,0 +1,317 @@
+use std::ops::ControlFlow;
+use std::path::PathBuf;
+
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::error::{ApiError, ApiErrorResponse};
+use crate::state::AppState;
+
+/// Output searched per session: everything an on-disk log keeps.
+const SEARCH_MAX_BYTES_PER_SESSION: usize = 32 * 1024 * 1024;
+const DEFAULT_MATCH_LIMIT: usize = 200;
+const MAX_MATCH_LIMIT: usize = 1000;
+
+#[derive(Deserialize)]
+pub struct ScrollbackSearchQuery {
+    pub q: String,
+    /// Treat `q` as a regular expression instead of literal text.
+    #[serde(default)]
+    pub regex: bool,
+    #[serde(default)]
+    pub case_sensitive: bool,
+    pub workspace_id: Option<Uuid>,
+    pub session_id: Option<Uuid>,
+    pub limit: Option<usize>,
+}
+
+/// Which scrollback endpoint serves a session: `/agents/sessions/{id}` for
+/// agents and shells, `/workspace-tasks/runs/{id}` for task runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchSessionKind {
+    Agent,
+    Shell,
+    TaskRun,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ScrollbackMatch {
+    pub session_id: Uuid,
+    pub session_kind: SearchSessionKind,
+    pub workspace_id: Option<Uuid>,
+    /// The matching line of normalized text.
+    pub line: String,
+    /// Byte offsets of the line in the session's raw output. Passing
+    /// `end_offset` as `before_offset` loads a page ending with the line.
+    pub start_offset: usize,
+    pub end_offset: usize,
+    /// Byte range of the first match within `line`.
+    pub match_start: usize,
+    pub match_end: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ScrollbackSearchResponse {
+    pub matches: Vec<ScrollbackMatch>,
+    pub sessions_searched: usize,
+    /// More matches exist beyond `limit`.
+    pub truncated: bool,
+}
+
+struct SearchTarget {
+    session_id: Uuid,
+    kind: SearchSessionKind,
+    workspace_path: PathBuf,
+}
+
+fn invalid_query(message: impl Into<String>) -> ApiErrorResponse {
+    ApiErrorResponse {
+        status: StatusCode::BAD_REQUEST,
+        error: ApiError::new("INVALID_QUERY", message),
+    }
+}
+
+async fn search_targets(state: &AppState) -> Vec<SearchTarget> {
+    let agents = state
+        .agent_manager
+        .list_sessions()
+        .await
+        .into_iter()
+        .map(|handle| SearchTarget {
+            session_id: handle.session_id,
+            kind: SearchSessionKind::Agent,
+            workspace_path: handle.workspace_path,
+        });
+    let shells = state
+        .shell_manager
+        .list_sessions()
+        .await
+        .into_iter()
+        .map(|handle| SearchTarget {
+            session_id: handle.session_id,
+            kind: SearchSessionKind::Shell,
+            workspace_path: handle.workspace_path,
+        });
+    let runs = state
+        .workspace_task_manager
+        .list_all()
+        .await
+        .into_iter()
+        .map(|run| SearchTarget {
+            session_id: run.run_id,
+            kind: SearchSessionKind::TaskRun,
+            workspace_path: run.workspace_path,
+        });
+    agents.chain(shells).chain(runs).collect()
+}
+
+/// Searches the normalized output of every session, live or only on disk.
+pub async fn search_scrollback(
+    State(state): State<AppState>,
+    Query(query): Query<ScrollbackSearchQuery>,
+) -> Result<Json<ScrollbackSearchResponse>, ApiErrorResponse> {
+    if query.q.is_empty() {
+        return Err(invalid_query("Query must not be empty"));
+    }
+    let pattern = if query.regex {
+        query.q.clone()
+    } else {
+        regex::escape(&query.q)
+    };
+    let matcher = regex::RegexBuilder::new(&pattern)
+        .case_insensitive(!query.case_sensitive)
+        .size_limit(1024 * 1024)
+        .build()
+        .map_err(|err| invalid_query(format!("Invalid regex: {err}")))?;
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_MATCH_LIMIT)
+        .clamp(1, MAX_MATCH_LIMIT);
+
+    let mut matches = Vec::new();
+    let mut sessions_searched = 0;
+    let mut truncated = false;
+    for target in search_targets(&state).await {
+        if query
+            .session_id
+            .is_some_and(|session_id| session_id != target.session_id)
+        {
+            continue;
+        }
+        let workspace_id = state
+            .workspace_registry
+            .find_by_path(&target.workspace_path)
+            .await;
+        if query.workspace_id.is_some() && query.workspace_id != workspace_id {
+            continue;
+        }
+        let Ok((data, start_offset, _, _)) = state
+            .pty_manager
+            .output_slice_before(&target.session_id, None, SEARCH_MAX_BYTES_PER_SESSION)
+            .await
+        else {
+            continue;
+        };
+        sessions_searched += 1;
+
+        let matcher = matcher.clone();
+        let remaining = limit - matches.len();
+        let (found, more) = tokio::task::spawn_blocking(move || {
+            let mut found = Vec::new();
+            let mut more = false;
+            lw_agent::terminal_text::visit_terminal_lines(&data, start_offset, |line, range| {
+                let Some(hit) = matcher.find(line) else {
+                    return ControlFlow::Continue(());
+                };
+                if found.len() == remaining {
+                    more = true;
+                    return ControlFlow::Break(());
+                }
+                found.push(ScrollbackMatch {
+                    session_id: target.session_id,
+                    session_kind: target.kind,
+                    workspace_id,
+                    line: line.to_string(),
+                    start_offset: range.start,
+                    end_offset: range.end,
+                    match_start: hit.start(),
+                    match_end: hit.end(),
+                });
+                ControlFlow::Continue(())
+            });
+            (found, more)
+        })
+        .await
+        .map_err(|err| ApiErrorResponse {
+            status: StatusCode::INTERNAL_SERVER_ERROR,
+            error: ApiError::internal(err.to_string()),
+        })?;
+        matches.extend(found);
+        if more {
+            truncated = true;
+            break;
+        }
+    }
+
+    Ok(Json(ScrollbackSearchResponse {
+        matches,
+        sessions_searched,
+        truncated,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query(q: &str) -> ScrollbackSearchQuery {
+        ScrollbackSearchQuery {
+            q: q.to_string(),
+            regex: false,
+            case_sensitive: false,
+            workspace_id: None,
+            session_id: None,
+            limit: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn rejects_empty_and_invalid_queries() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = AppState::for_test(dir.path(), |_| {});
+
+        let Err(err) = search_scrollback(State(state.clone()), Query(query(""))).await else {
+            panic!("expected empty query error");
+        };
+        assert_eq!(err.status, StatusCode::BAD_REQUEST);
+
+        let mut bad = query("(unclosed");
+        bad.regex = true;
+        let Err(err) = search_scrollback(State(state.clone()), Query(bad)).await else {
+            panic!("expected invalid regex error");
+        };
+        assert_eq!(err.error.code, "INVALID_QUERY");
+
+        // Without `regex` the same text is searched literally.
+        let Json(response) = search_scrollback(State(state), Query(query("(unclosed")))
+            .await
+            .unwrap_or_else(|_| panic!("literal search failed"));
+        assert!(response.matches.is_empty());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn finds_lines_in_task_run_output() {
+        let dir = tempfile::tempdir().unwrap();
+        let workspace = dir.path().join("repo");
+        std::fs::create_dir(&workspace).unwrap();
+        std::fs::write(
+            workspace.join(lw_config::PROJECT_CONFIG_FILE),
+            "[tasks.migrate]\ncommand = \"printf 'applying 0001\\\\nmigration 0002 FAILED: locked\\\\n'\"\n",
+        )
+        .unwrap();
+        let state = AppState::for_test(dir.path(), |_| {});
+        let workspace_id = Uuid::new_v4();
+        state
+            .workspace_registry
+            .register(workspace_id, workspace.clone())
+            .await
+            .unwrap();
+        crate::project::trust_workspace_config(&state, workspace_id).await;
+        let mut events = state.workspace_task_manager.subscribe();
+        let (_, Json(run)) = crate::rest::workspace_task::run_task(
+            State(state.clone()),
+            Json(crate::rest::workspace_task::RunWorkspaceTaskRequest {
+                workspace_id,
+                name: "migrate".to_string(),
+            }),
+        )
+        .await
+        .unwrap_or_else(|_| panic!("run failed"));
+        tokio::time::timeout(std::time::Duration::from_secs(10), async {
+            while !matches!(
+                events.recv().await,
+                Ok(lw_agent::TaskRunEvent::Finished { .. })
+            ) {}
+        })
+        .await
+        .unwrap();
+
+        let mut search = query("migration \\d+ failed");
+        search.regex = true;
+        search.workspace_id = Some(workspace_id);
+        let Json(response) = search_scrollback(State(state.clone()), Query(search))
+            .await
+            .unwrap_or_else(|_| panic!("search failed"));
+        assert_eq!(response.sessions_searched, 1);
+        assert_eq!(response.matches.len(), 1);
+        let hit = &response.matches[0];
+        assert_eq!(hit.session_id, run.run_id);
+        assert_eq!(hit.session_kind, SearchSessionKind::TaskRun);
+        assert_eq!(hit.line, "migration 0002 FAILED: locked");
+        assert_eq!(
+            &hit.line[hit.match_start..hit.match_end],
+            "migration 0002 FAILED"
+        );
+
+        let (page, _, end, _) = state
+            .pty_manager
+            .output_slice_before(&run.run_id, Some(hit.end_offset), 1024)
+            .await
+            .unwrap();
+        assert_eq!(end, hit.end_offset);
+        assert!(String::from_utf8_lossy(&page).ends_with("FAILED: locked\r\n"));
+
+        let mut search = query("FAILED");
+        search.case_sensitive = true;
+        search.workspace_id = Some(Uuid::new_v4());
+        let Json(response) = search_scrollback(State(state), Query(search))
+            .await
+            .unwrap_or_else(|_| panic!("search failed"));
+        assert_eq!(response.sessions_searched, 0);
+    }
+}
@@ -0,0 +1,355 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::path::Path as StdPath;
+
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::error::{ApiError, ApiErrorResponse};
+use crate::rest::workspace::{save_workspace_shells, scrollback_dir, WorkspaceShellEntry};
+use crate::state::AppState;
+
+#[derive(Deserialize)]
+pub struct CreateShellRequest {
+    pub workspace_path: String,
+    pub shell: Option<String>,
+    pub custom_name: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct ShellSessionResponse {
+    pub workspace_id: Uuid,
+    #[serde(flatten)]
+    pub shell: lw_agent::ShellHandle,
+}
+
+#[derive(Deserialize)]
+pub struct RenameShellRequest {
+    pub custom_name: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct UpdateShellSettingsRequest {
+    pub pinned: Option<bool>,
+    pub sort_order: Option<Option<i32>>,
+}
+
+fn is_active_status(status: lw_agent::ShellStatus) -> bool {
+    status == lw_agent::ShellStatus::Running || status == lw_agent::ShellStatus::Restored
+}
+
+fn sort_workspace_shells(mut shells: Vec<lw_agent::ShellHandle>) -> Vec<lw_agent::ShellHandle> {
+    shells.sort_by(|a, b| match (a.sort_order, b.sort_order) {
+        (Some(left), Some(right)) => left.cmp(&right),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => a.created_at.cmp(&b.created_at),
+    });
+    shells
+}
+
+pub(crate) async fn active_shells_for_workspace(
+    state: &AppState,
+    workspace_path: &StdPath,
+) -> Vec<lw_agent::ShellHandle> {
+    let shells: Vec<_> = state
+        .shell_manager
+        .list_sessions()
+        .await
+        .into_iter()
+        .filter(|shell| shell.workspace_path == workspace_path && is_active_status(shell.status))
+        .collect();
+    sort_workspace_shells(shells)
+}
+
+async fn persist_workspace_shells_snapshot(
+    state: &AppState,
+    workspace_path: &StdPath,
+) -> Result<(), ApiErrorResponse> {
+    let shells: HashMap<Uuid, WorkspaceShellEntry> =
+        active_shells_for_workspace(state, workspace_path)
+            .await
+            .into_iter()
+            .map(|shell| {
+                (
+                    shell.session_id,
+                    WorkspaceShellEntry {
+                        shell: shell.shell,
+                        custom_name: shell.custom_name,
+                        pinned: shell.pinned,
+                        sort_order: shell.sort_order,
+                        created_at: Some(shell.created_at.to_rfc3339()),
+                        pid: shell.process_id,
+                    },
+                )
+            })
+            .collect();
+
+    save_workspace_shells(&state.paths, workspace_path, &shells).map_err(|e| ApiErrorResponse {
+        status: StatusCode::INTERNAL_SERVER_ERROR,
+        error: ApiError::internal(format!(
+            "Failed to persist workspace shells for {}: {}",
+            workspace_path.display(),
+            e
+        )),
+    })
+}
+
+async fn get_shell_handle(
+    state: &AppState,
+    id: &Uuid,
+) -> Result<lw_agent::ShellHandle, ApiErrorResponse> {
+    state
+        .shell_manager
+        .get_handle(id)
+        .await
+        .ok_or_else(|| ApiErrorResponse {
+            status: StatusCode::NOT_FOUND,
+            error: ApiError::not_found("Shell session"),
+        })
+}
+
+pub async fn list_sessions(State(state): State<AppState>) -> Json<Vec<ShellSessionResponse>> {
+    let shells: Vec<_> = state
+        .shell_manager
+        .list_sessions()
+        .await
+        .into_iter()
+        .filter(|shell| is_active_status(shell.status))
+        .collect();
+    let mut response = Vec::with_capacity(shells.len());
+    for shell in sort_workspace_shells(shells) {
+        let Some(workspace_id) = state
+            .workspace_registry
+            .find_by_path(&shell.workspace_path)
+            .await
+        else {
+            continue;
+        };
+        response.push(ShellSessionResponse {
+            workspace_id,
+            shell,
+        });
+    }
+    Json(response)
+}
+
+pub async fn create_session(
+    State(state): State<AppState>,
+    Json(body): Json<CreateShellRequest>,
+) -> Result<(StatusCode, Json<ShellSessionResponse>), ApiErrorResponse> {
+    let workspace_path = std::path::PathBuf::from(&body.workspace_path);
+
+    if !workspace_path.is_dir() {
+        return Err(ApiErrorResponse {
+            status: StatusCode::BAD_REQUEST,
+            error: ApiError::new(
+                "INVALID_WORKSPACE",
+                "Workspace path is not a valid directory",
+            ),
+        });
+    }
+
+    let workspace_id = state
+        .workspace_registry
+        .find_by_path(&workspace_path)
+        .await
+        .unwrap_or_else(Uuid::new_v4);
+    state
+        .workspace_registry
+        .register(workspace_id, workspace_path.clone())
+        .await
+        .map_err(|e| ApiErrorResponse {
+            status: StatusCode::INTERNAL_SERVER_ERROR,
+            error: ApiError::internal(e.to_string()),
+        })?;
+
+    let (session_id, _session) = state
+        .shell_manager
+        .start_session(
+            workspace_path.clone(),
+            body.shell,
+            body.custom_name,
+            Some(scrollback_dir(&state.paths, workspace_id)),
+        )
+        .await
+        .map_err(|e| ApiErrorResponse {
+            status: StatusCode::INTERNAL_SERVER_ERROR,
+            error: ApiError::internal(e.to_string()),
+        })?;
+
+    persist_workspace_shells_snapshot(&state, &workspace_path).await?;
+    let shell = get_shell_handle(&state, &session_id).await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(ShellSessionResponse {
+            workspace_id,
+            shell,
+        }),
+    ))
+}
+
+pub async fn get_session(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ShellSessionResponse>, ApiErrorResponse> {
+    let shell = get_shell_handle(&state, &id).await?;
+    let workspace_id = state
+        .workspace_registry
+        .find_by_path(&shell.workspace_path)
+        .await
+        .ok_or_else(|| ApiErrorResponse {
+            status: StatusCode::NOT_FOUND,
+            error: ApiError::not_found("Workspace"),
+        })?;
+    Ok(Json(ShellSessionResponse {
+        workspace_id,
+        shell,
+    }))
+}
+
+pub async fn stop_session(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, ApiErrorResponse> {
+    let shell = get_shell_handle(&state, &id).await?;
+    state
+        .shell_manager
+        .stop_session(&id)
+        .await
+        .map_err(|e| ApiErrorResponse {
+            status: StatusCode::INTERNAL_SERVER_ERROR,
+            error: ApiError::internal(e.to_string()),
+        })?;
+    persist_workspace_shells_snapshot(&state, &shell.workspace_path).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn rename_session(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(body): Json<RenameShellRequest>,
+) -> Result<StatusCode, ApiErrorResponse> {
+    if !state
+        .shell_manager
+        .rename_session(&id, body.custom_name)
+        .await
+    {
+        return Err(ApiErrorResponse {
+            status: StatusCode::NOT_FOUND,
+            error: ApiError::not_found("Shell session"),
+        });
+    }
+    let shell = get_shell_handle(&state, &id).await?;
+    persist_workspace_shells_snapshot(&state, &shell.workspace_path).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn update_session_settings(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(body): Json<UpdateShellSettingsRequest>,
+) -> Result<StatusCode, ApiErrorResponse> {
+    if !state
+        .shell_manager
+        .update_session_settings(&id, body.pinned, body.sort_order)
+        .await
+    {
+        return Err(ApiErrorResponse {
+            status: StatusCode::NOT_FOUND,
+            error: ApiError::not_found("Shell session"),
+        });
+    }
+    let shell = get_shell_handle(&state, &id).await?;
+    persist_workspace_shells_snapshot(&state, &shell.workspace_path).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rest::workspace::load_workspace_shells;
+
+    async fn make_test_state() -> (tempfile::TempDir, AppState) {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = lw_config::DaemonConfig::default();
+        config.set_paths(lw_config::ConfigPaths::with_base(dir.path().to_path_buf()));
+        let state = AppState::new(config, crate::auth::TokenStore::hash_token("test")).unwrap();
+        (dir, state)
+    }
+
+    #[tokio::test]
+    async fn create_session_rejects_missing_workspace() {
+        let (_dir, state) = make_test_state().await;
+        let result = create_session(
+            State(state),
+            Json(CreateShellRequest {
+                workspace_path: "/nonexistent/workspace/path".to_string(),
+                shell: None,
+                custom_name: None,
+            }),
+        )
+        .await;
+        let Err(err) = result else {
+            panic!("expected invalid workspace error");
+        };
+        assert_eq!(err.status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn get_session_unknown_returns_not_found() {
+        let (_dir, state) = make_test_state().await;
+        let result = get_session(State(state), Path(Uuid::new_v4())).await;
+        let Err(err) = result else {
+            panic!("expected not found");
+        };
+        assert_eq!(err.status, StatusCode::NOT_FOUND);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn create_rename_and_stop_persist_shell_entries() {
+        let (dir, state) = make_test_state().await;
+        let workspace = dir.path().join("project");
+        std::fs::create_dir_all(&workspace).unwrap();
+
+        let (status, Json(created)) = create_session(
+            State(state.clone()),
+            Json(CreateShellRequest {
+                workspace_path: workspace.to_string_lossy().to_string(),
+                shell: Some("/bin/sh".to_string()),
+                custom_name: None,
+            }),
+        )
+        .await
+        .unwrap_or_else(|_| panic!("create failed"));
+        assert_eq!(status, StatusCode::CREATED);
+        let id = created.shell.session_id;
+        assert_eq!(created.shell.status, lw_agent::ShellStatus::Running);
+
+        rename_session(
+            State(state.clone()),
+            Path(id),
+            Json(RenameShellRequest {
+                custom_name: Some("tests".to_string()),
+            }),
+        )
+        .await
+        .unwrap_or_else(|_| panic!("rename failed"));
+        let persisted = load_workspace_shells(&state.paths, &workspace);
+        assert_eq!(persisted[&id].shell, "/bin/sh");
+        assert_eq!(persisted[&id].custom_name.as_deref(), Some("tests"));
+
+        let Json(listed) = list_sessions(State(state.clone())).await;
+        assert!(listed.iter().any(|s| s.shell.session_id == id));
+
+        stop_session(State(state.clone()), Path(id))
+            .await
+            .unwrap_or_else(|_| panic!("stop failed"));
+        assert!(load_workspace_shells(&state.paths, &workspace).is_empty());
+    }
+}
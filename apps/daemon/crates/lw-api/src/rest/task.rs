@@ -0,0 +1,272 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::Json;
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::error::{ApiError, ApiErrorResponse};
+use crate::state::AppState;
+
+#[derive(Deserialize)]
+pub struct CreateTaskRequest {
+    pub agent_type: lw_agent::AgentType,
+    pub workspace_path: String,
+    pub prompt: String,
+    /// Kill the agent if it is still running after this many seconds.
+    pub timeout_secs: Option<u64>,
+}
+
+#[derive(Deserialize)]
+pub struct ListTasksQuery {
+    /// Only tasks run in this workspace path.
+    pub workspace_path: Option<String>,
+}
+
+fn task_not_found() -> ApiErrorResponse {
+    ApiErrorResponse {
+        status: StatusCode::NOT_FOUND,
+        error: ApiError::not_found("Task"),
+    }
+}
+
+pub async fn create_task(
+    State(state): State<AppState>,
+    Json(body): Json<CreateTaskRequest>,
+) -> Result<(StatusCode, Json<lw_agent::TaskRecord>), ApiErrorResponse> {
+    let workspace_path = PathBuf::from(&body.workspace_path);
+    if !workspace_path.is_dir() {
+        return Err(ApiErrorResponse {
+            status: StatusCode::BAD_REQUEST,
+            error: ApiError::new(
+                "INVALID_WORKSPACE",
+                "Workspace path is not a valid directory",
+            ),
+        });
+    }
+    if body.prompt.trim().is_empty() {
+        return Err(ApiErrorResponse {
+            status: StatusCode::BAD_REQUEST,
+            error: ApiError::new("INVALID_PROMPT", "Prompt must not be empty"),
+        });
+    }
+
+    // Register the workspace so its git and fs endpoints work for the
+    // task's diff, as they do for interactive sessions.
+    let workspace_id = state
+        .workspace_registry
+        .find_by_path(&workspace_path)
+        .await
+        .unwrap_or_else(Uuid::new_v4);
+    state
+        .workspace_registry
+        .register(workspace_id, workspace_path.clone())
+        .await
+        .map_err(|e| ApiErrorResponse {
+            status: StatusCode::INTERNAL_SERVER_ERROR,
+            error: ApiError::internal(e.to_string()),
+        })?;
+
+    let record = state
+        .task_manager
+        .start_task(
+            body.agent_type,
+            workspace_path,
+            body.prompt,
+            body.timeout_secs.map(Duration::from_secs),
+        )
+        .await
+        .map_err(|e| ApiErrorResponse {
+            status: StatusCode::INTERNAL_SERVER_ERROR,
+            error: ApiError::internal(e.to_string()),
+        })?;
+    Ok((StatusCode::CREATED, Json(record)))
+}
+
+pub async fn list_tasks(
+    State(state): State<AppState>,
+    Query(query): Query<ListTasksQuery>,
+) -> Json<Vec<lw_agent::TaskSummary>> {
+    let mut tasks = state.task_manager.list().await;
+    if let Some(workspace_path) = query.workspace_path.as_deref() {
+        let workspace_path = std::path::Path::new(workspace_path);
+        tasks.retain(|task| task.workspace_path == workspace_path);
+    }
+    Json(tasks)
+}
+
+pub async fn get_task(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<lw_agent::TaskRecord>, ApiErrorResponse> {
+    state
+        .task_manager
+        .get(&id)
+        .await
+        .map(Json)
+        .ok_or_else(task_not_found)
+}
+
+pub async fn cancel_task(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, ApiErrorResponse> {
+    if state.task_manager.get(&id).await.is_none() {
+        return Err(task_not_found());
+    }
+    if !state.task_manager.cancel(&id) {
+        return Err(ApiErrorResponse {
+            status: StatusCode::CONFLICT,
+            error: ApiError::new("TASK_NOT_RUNNING", "Task is not running"),
+        });
+    }
+    Ok(StatusCode::ACCEPTED)
+}
+
+pub async fn delete_task(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, ApiErrorResponse> {
+    match state.task_manager.remove(&id).await {
+        Ok(true) => Ok(StatusCode::NO_CONTENT),
+        Ok(false) => Err(task_not_found()),
+        Err(e) => Err(ApiErrorResponse {
+            status: StatusCode::CONFLICT,
+            error: ApiError::new("TASK_RUNNING", e.to_string()),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_state(dir: &std::path::Path) -> AppState {
+        AppState::for_test(dir, |config| {
+            config.agents = vec![sh_agent()];
+        })
+    }
+
+    fn sh_agent() -> lw_config::CustomAgentConfig {
+        lw_config::CustomAgentConfig {
+            id: "sh".to_string(),
+            name: None,
+            command: "sh".to_string(),
+            args: vec![],
+            env: Default::default(),
+            version_args: vec!["--version".to_string()],
+            session_id_args: vec![],
+            resume_args: vec![],
+            fork_args: vec![],
+            headless_args: vec!["-c".to_string(), "{prompt}".to_string()],
+        }
+    }
+
+    fn request(workspace: &std::path::Path, prompt: &str) -> CreateTaskRequest {
+        CreateTaskRequest {
+            agent_type: lw_agent::AgentType::Custom("sh".to_string()),
+            workspace_path: workspace.to_string_lossy().into_owned(),
+            prompt: prompt.to_string(),
+            timeout_secs: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn create_task_rejects_invalid_workspace_and_empty_prompt() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = make_state(dir.path());
+
+        let err = create_task(
+            State(state.clone()),
+            Json(request(&dir.path().join("missing"), "echo hi")),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.status, StatusCode::BAD_REQUEST);
+        assert_eq!(err.error.code, "INVALID_WORKSPACE");
+
+        let err = create_task(State(state), Json(request(dir.path(), "  ")))
+            .await
+            .unwrap_err();
+        assert_eq!(err.error.code, "INVALID_PROMPT");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn task_lifecycle_through_handlers() {
+        let dir = tempfile::tempdir().unwrap();
+        let workspace = tempfile::tempdir().unwrap();
+        let state = make_state(dir.path());
+        let mut events = state.task_manager.subscribe();
+
+        let (status, Json(record)) = create_task(
+            State(state.clone()),
+            Json(request(workspace.path(), "sleep 30")),
+        )
+        .await
+        .unwrap();
+        assert_eq!(status, StatusCode::CREATED);
+        assert!(state
+            .workspace_registry
+            .find_by_path(workspace.path())
+            .await
+            .is_some());
+
+        let err = delete_task(State(state.clone()), Path(record.task_id))
+            .await
+            .unwrap_err();
+        assert_eq!(err.error.code, "TASK_RUNNING");
+        assert_eq!(
+            cancel_task(State(state.clone()), Path(record.task_id))
+                .await
+                .unwrap(),
+            StatusCode::ACCEPTED
+        );
+        tokio::time::timeout(Duration::from_secs(10), async {
+            while !matches!(
+                events.recv().await,
+                Ok(lw_agent::TaskEvent::Finished { .. })
+            ) {}
+        })
+        .await
+        .unwrap();
+
+        let Json(task) = get_task(State(state.clone()), Path(record.task_id))
+            .await
+            .unwrap();
+        assert_eq!(task.status, lw_agent::TaskStatus::Cancelled);
+        let Json(tasks) = list_tasks(
+            State(state.clone()),
+            Query(ListTasksQuery {
+                workspace_path: Some(workspace.path().to_string_lossy().into_owned()),
+            }),
+        )
+        .await;
+        assert_eq!(tasks.len(), 1);
+        let Json(tasks) = list_tasks(
+            State(state.clone()),
+            Query(ListTasksQuery {
+                workspace_path: Some("/elsewhere".to_string()),
+            }),
+        )
+        .await;
+        assert!(tasks.is_empty());
+
+        let err = cancel_task(State(state.clone()), Path(record.task_id))
+            .await
+            .unwrap_err();
+        assert_eq!(err.error.code, "TASK_NOT_RUNNING");
+        assert_eq!(
+            delete_task(State(state.clone()), Path(record.task_id))
+                .await
+                .unwrap(),
+            StatusCode::NO_CONTENT
+        );
+        let err = get_task(State(state), Path(record.task_id))
+            .await
+            .unwrap_err();
+        assert_eq!(err.status, StatusCode::NOT_FOUND);
+    }
+}
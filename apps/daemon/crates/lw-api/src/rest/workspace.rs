@@ -9,6 +9,7 @@ use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::error::{ApiError, ApiErrorResponse};
+use crate::project::{workspace_project_config, HiddenPaths, LoadedProjectConfig};
 use crate::state::AppState;
 
 // ── Workspace persistence ──────────────────────────────────────────────
@@ -37,6 +38,8 @@ struct WorkspacePersistence {
     icon: Option<String>,
     #[serde(default)]
     agents: HashMap<String, WorkspaceAgentEntry>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    shells: HashMap<String, WorkspaceShellEntry>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Default)]
@@ -54,6 +57,28 @@ pub struct WorkspaceAgentEntry {
     pub created_at: Option<String>,
     #[serde(default)]
     pub pid: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub worktree: Option<lw_agent::AgentWorktree>,
+    #[serde(
+        default,
+        skip_serializing_if = "lw_agent::SessionLaunchOptions::is_empty"
+    )]
+    pub launch_options: lw_agent::SessionLaunchOptions,
+    /// Pending prompts and the recently delivered ones.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub prompt_queue: Vec<lw_agent::QueuedPrompt>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct WorkspaceShellEntry {
+    pub shell: String,
+    pub custom_name: Option<String>,
+    #[serde(default)]
+    pub pinned: bool,
+    pub sort_order: Option<i32>,
+    pub created_at: Option<String>,
+    #[serde(default)]
+    pub pid: Option<u32>,
 }
 
 fn workspace_persistence_path(paths: &ConfigPaths, workspace_id: Uuid) -> PathBuf {
@@ -62,6 +87,11 @@ fn workspace_persistence_path(paths: &ConfigPaths, workspace_id: Uuid) -> PathBu
         .join("workspace.json")
 }
 
+/// Root of the per-session PTY output logs of a workspace.
+pub fn scrollback_dir(paths: &ConfigPaths, workspace_id: Uuid) -> PathBuf {
+    paths.workspace_data_dir(workspace_id).join("scrollback")
+}
+
 fn default_workspace_name(path: &str) -> String {
     Path::new(path)
         .file_name()
@@ -71,7 +101,12 @@ fn default_workspace_name(path: &str) -> String {
         .to_string()
 }
 
-fn write_json_atomic<T: Serialize>(path: &Path, value: &T) -> Result<(), std::io::Error> {
+/// Writes `value` to a temporary file beside `path` and renames it over
+/// `path`, so that readers never see a partial file.
+pub(crate) fn write_json_atomic<T: Serialize>(
+    path: &Path,
+    value: &T,
+) -> Result<(), std::io::Error> {
     if let Some(parent) = path.parent() {
         std::fs::create_dir_all(parent)?;
     }
@@ -127,8 +162,8 @@ pub fn save_workspaces(
             continue;
         }
         let path = workspace_persistence_path(paths, entry.id);
-        let existing_agents = load_single_workspace_persistence(&path)
-            .map(|p| p.agents)
+        let (existing_agents, existing_shells) = load_single_workspace_persistence(&path)
+            .map(|p| (p.agents, p.shells))
             .unwrap_or_default();
         let persistence = WorkspacePersistence {
             id: entry.id,
@@ -137,50 +172,70 @@ pub fn save_workspaces(
             pinned: entry.pinned,
             icon: entry.icon.clone(),
             agents: existing_agents,
+            shells: existing_shells,
         };
         write_json_atomic(&path, &persistence)?;
     }
     Ok(())
 }
 
-pub fn load_workspace_agents(
+fn load_workspace_persistence_for_path(
     paths: &ConfigPaths,
     workspace_path: &Path,
-) -> HashMap<Uuid, WorkspaceAgentEntry> {
+) -> Option<WorkspacePersistence> {
     let workspace_path_str = workspace_path.to_string_lossy();
     let dir = paths.workspaces_data_dir();
     if !dir.is_dir() {
-        return HashMap::new();
+        return None;
     }
-    let read_dir = match std::fs::read_dir(&dir) {
-        Ok(rd) => rd,
-        Err(_) => return HashMap::new(),
-    };
+    let read_dir = std::fs::read_dir(&dir).ok()?;
     for item in read_dir.flatten() {
         let workspace_json = item.path().join("workspace.json");
         let Some(persistence) = load_single_workspace_persistence(&workspace_json) else {
             continue;
         };
-        if persistence.path != workspace_path_str.as_ref() {
-            continue;
+        if persistence.path == workspace_path_str.as_ref() {
+            return Some(persistence);
         }
-        return persistence
-            .agents
-            .into_iter()
-            .filter_map(|(session_id, entry)| {
-                Uuid::parse_str(&session_id)
-                    .ok()
-                    .map(|parsed| (parsed, entry))
-            })
-            .collect();
     }
-    HashMap::new()
+    None
 }
 
-pub fn save_workspace_agents(
+fn parse_session_keyed<T>(entries: HashMap<String, T>) -> HashMap<Uuid, T> {
+    entries
+        .into_iter()
+        .filter_map(|(session_id, entry)| {
+            Uuid::parse_str(&session_id)
+                .ok()
+                .map(|parsed| (parsed, entry))
+        })
+        .collect()
+}
+
+pub fn load_workspace_agents(
     paths: &ConfigPaths,
     workspace_path: &Path,
-    agents: &HashMap<Uuid, WorkspaceAgentEntry>,
+) -> HashMap<Uuid, WorkspaceAgentEntry> {
+    load_workspace_persistence_for_path(paths, workspace_path)
+        .map(|persistence| parse_session_keyed(persistence.agents))
+        .unwrap_or_default()
+}
+
+pub fn load_workspace_shells(
+    paths: &ConfigPaths,
+    workspace_path: &Path,
+) -> HashMap<Uuid, WorkspaceShellEntry> {
+    load_workspace_persistence_for_path(paths, workspace_path)
+        .map(|persistence| parse_session_keyed(persistence.shells))
+        .unwrap_or_default()
+}
+
+/// Loads (or creates) the `workspace.json` for `workspace_path`, applies
+/// `update` and writes it back atomically.
+fn update_workspace_persistence(
+    paths: &ConfigPaths,
+    workspace_path: &Path,
+    update: impl FnOnce(&mut WorkspacePersistence),
 ) -> Result<(), std::io::Error> {
     let workspace_path_str = workspace_path.to_string_lossy().to_string();
     let existing_entry = load_workspaces(paths)
@@ -195,12 +250,8 @@ pub fn save_workspace_agents(
     };
 
     let existing_persistence = load_single_workspace_persistence(&persistence_path);
-    let persisted_agents: HashMap<String, WorkspaceAgentEntry> = agents
-        .iter()
-        .map(|(session_id, entry)| (session_id.to_string(), entry.clone()))
-        .collect();
 
-    let persistence = WorkspacePersistence {
+    let mut persistence = WorkspacePersistence {
         id: workspace_id,
         path: workspace_path_str.clone(),
         name: existing_entry
@@ -217,12 +268,44 @@ pub fn save_workspace_agents(
             .as_ref()
             .and_then(|e| e.icon.clone())
             .or_else(|| existing_persistence.as_ref().and_then(|p| p.icon.clone())),
-        agents: persisted_agents,
+        agents: HashMap::new(),
+        shells: HashMap::new(),
     };
+    if let Some(existing) = existing_persistence {
+        persistence.agents = existing.agents;
+        persistence.shells = existing.shells;
+    }
+    update(&mut persistence);
 
     write_json_atomic(&persistence_path, &persistence)
 }
 
+pub fn save_workspace_agents(
+    paths: &ConfigPaths,
+    workspace_path: &Path,
+    agents: &HashMap<Uuid, WorkspaceAgentEntry>,
+) -> Result<(), std::io::Error> {
+    update_workspace_persistence(paths, workspace_path, |persistence| {
+        persistence.agents = agents
+            .iter()
+            .map(|(session_id, entry)| (session_id.to_string(), entry.clone()))
+            .collect();
+    })
+}
+
+pub fn save_workspace_shells(
+    paths: &ConfigPaths,
+    workspace_path: &Path,
+    shells: &HashMap<Uuid, WorkspaceShellEntry>,
+) -> Result<(), std::io::Error> {
+    update_workspace_persistence(paths, workspace_path, |persistence| {
+        persistence.shells = shells
+            .iter()
+            .map(|(session_id, entry)| (session_id.to_string(), entry.clone()))
+            .collect();
+    })
+}
+
 pub fn load_workspace_agent_sort_orders(
     paths: &ConfigPaths,
     workspace_path: &Path,
@@ -303,6 +386,7 @@ pub async fn roots() -> Json<RootsResponse> {
 }
 
 pub async fn browse(
+    State(state): State<AppState>,
     Query(query): Query<BrowseQuery>,
 ) -> Result<Json<Vec<lw_fs::DirEntry>>, ApiErrorResponse> {
     let path = std::path::Path::new(&query.path);
@@ -312,10 +396,20 @@ pub async fn browse(
             error: ApiError::new("INVALID_PATH", "Path must be absolute"),
         });
     }
-    let entries = lw_fs::list_directory(path).map_err(|e| {
+    let path = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    // Paths inside registered workspaces keep their `hidden` patterns.
+    let hidden = HiddenPaths::containing(&state, &path).await;
+    for workspace in &hidden {
+        workspace.check(&path)?;
+    }
+    let mut entries = lw_fs::list_directory(&path).map_err(|e| {
         let (status, error) = ApiError::fs_error(&e);
         ApiErrorResponse { status, error }
     })?;
+    entries.retain(|entry| {
+        let entry = path.join(&entry.name);
+        !hidden.iter().any(|workspace| workspace.is_hidden(&entry))
+    });
     Ok(Json(entries))
 }
 
@@ -333,10 +427,14 @@ pub async fn list(
             ApiErrorResponse { status, error }
         })?;
 
-    let entries = lw_fs::list_directory(&path).map_err(|e| {
+    let hidden = HiddenPaths::of(&state, &query.workspace_id).await;
+    hidden.check(&path)?;
+
+    let mut entries = lw_fs::list_directory(&path).map_err(|e| {
         let (status, error) = ApiError::fs_error(&e);
         ApiErrorResponse { status, error }
     })?;
+    entries.retain(|entry| !hidden.is_hidden(&path.join(&entry.name)));
 
     Ok(Json(entries))
 }
@@ -354,6 +452,10 @@ pub async fn read(
             ApiErrorResponse { status, error }
         })?;
 
+    HiddenPaths::of(&state, &query.workspace_id)
+        .await
+        .check(&path)?;
+
     let include_binary = query.include_binary.unwrap_or(false);
     let content = (if include_binary {
         lw_fs::read_file_with_binary(&path)
@@ -385,6 +487,7 @@ pub async fn read_many(
 
     let mut files = HashMap::new();
     let mut seen = HashSet::new();
+    let hidden = HiddenPaths::of(&state, &body.workspace_id).await;
 
     for relative_path in body.relative_paths {
         if !seen.insert(relative_path.clone()) {
@@ -400,6 +503,8 @@ pub async fn read_many(
                 ApiErrorResponse { status, error }
             })?;
 
+        hidden.check(&path)?;
+
         let content = lw_fs::read_file(&path).map_err(|e| {
             let (status, error) = ApiError::fs_error(&e);
             ApiErrorResponse { status, error }
@@ -526,6 +631,27 @@ pub async fn update_workspace_settings(
     }
 }
 
+// ── Approve project config ─────────────────────────────────────────────
+
+#[derive(Deserialize)]
+pub struct TrustProjectConfigRequest {
+    pub workspace_id: Uuid,
+    /// `digest` of the config being approved; `null` withdraws approval.
+    pub digest: Option<String>,
+}
+
+pub async fn trust_project_config(
+    State(state): State<AppState>,
+    Json(body): Json<TrustProjectConfigRequest>,
+) -> Result<Json<LoadedProjectConfig>, ApiErrorResponse> {
+    // Reads the config if nothing has asked for it yet.
+    workspace_project_config(&state, body.workspace_id).await;
+    state
+        .project_configs
+        .set_trusted(body.workspace_id, body.digest.as_deref())
+        .map(Json)
+}
+
 // ── Remove workspace ───────────────────────────────────────────────────
 
 #[derive(Deserialize)]
@@ -545,6 +671,7 @@ pub async fn remove_workspace(
     }
 
     if let Some(id) = workspace_id {
+        state.project_configs.forget(&id);
         let data_dir = state.paths.workspace_data_dir(id);
         let _ = std::fs::remove_dir_all(&data_dir);
     }
@@ -861,6 +988,9 @@ mod tests {
                     resume_failure_reason: None,
                     created_at: Some("2026-02-17T00:00:00Z".to_string()),
                     pid: None,
+                    worktree: None,
+                    launch_options: Default::default(),
+                    prompt_queue: vec![],
                 },
             ),
             (
@@ -876,6 +1006,9 @@ mod tests {
                     resume_failure_reason: Some("restore failed".to_string()),
                     created_at: Some("2026-02-17T00:00:01Z".to_string()),
                     pid: None,
+                    worktree: None,
+                    launch_options: Default::default(),
+                    prompt_queue: vec![],
                 },
             ),
         ]);
@@ -954,9 +1087,13 @@ mod tests {
 
     #[tokio::test]
     async fn browse_handler_rejects_relative_path() {
-        let result = browse(Query(BrowseQuery {
-            path: "relative/path".to_string(),
-        }))
+        let (_dir, state) = make_test_state().await;
+        let result = browse(
+            State(state),
+            Query(BrowseQuery {
+                path: "relative/path".to_string(),
+            }),
+        )
         .await;
         assert!(result.is_err());
         let err = result.unwrap_err();
@@ -965,14 +1102,61 @@ mod tests {
 
     #[tokio::test]
     async fn browse_handler_valid_absolute_path() {
+        let (_state_dir, state) = make_test_state().await;
         let dir = tempfile::tempdir().unwrap();
-        let result = browse(Query(BrowseQuery {
-            path: dir.path().to_string_lossy().to_string(),
-        }))
+        let result = browse(
+            State(state),
+            Query(BrowseQuery {
+                path: dir.path().to_string_lossy().to_string(),
+            }),
+        )
         .await;
         assert!(result.is_ok());
     }
 
+    #[tokio::test]
+    async fn browse_handler_leaves_out_hidden_paths() {
+        let (_state_dir, state) = make_test_state().await;
+        let dir = tempfile::tempdir().unwrap();
+        let root = std::fs::canonicalize(dir.path()).unwrap();
+        std::fs::write(
+            root.join(".loopwire.toml"),
+            "hidden = [\".env\", \"secrets/\"]",
+        )
+        .unwrap();
+        std::fs::write(root.join(".env"), "TOKEN=secret").unwrap();
+        std::fs::create_dir(root.join("secrets")).unwrap();
+        std::fs::write(root.join("app.txt"), "").unwrap();
+        state
+            .workspace_registry
+            .register(Uuid::new_v4(), root.clone())
+            .await
+            .unwrap();
+
+        let Json(entries) = browse(
+            State(state.clone()),
+            Query(BrowseQuery {
+                path: root.to_string_lossy().to_string(),
+            }),
+        )
+        .await
+        .unwrap();
+        let names: Vec<_> = entries.iter().map(|entry| entry.name.as_str()).collect();
+        assert!(names.contains(&"app.txt"));
+        assert!(!names.contains(&".env"));
+        assert!(!names.contains(&"secrets"));
+
+        let err = browse(
+            State(state),
+            Query(BrowseQuery {
+                path: root.join("secrets").to_string_lossy().to_string(),
+            }),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.status, StatusCode::NOT_FOUND);
+    }
+
     #[tokio::test]
     async fn list_workspaces_handler_returns_empty_initially() {
         let (_dir, state) = make_test_state().await;
@@ -1027,4 +1211,58 @@ mod tests {
         assert_eq!(resp.path, ws_dir.path().to_string_lossy().to_string());
         drop(config_dir);
     }
+
+    #[tokio::test]
+    async fn list_and_read_skip_hidden_paths() {
+        let (_config_dir, state) = make_test_state().await;
+        let ws_dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            ws_dir.path().join(lw_config::PROJECT_CONFIG_FILE),
+            "hidden = [\".env\"]",
+        )
+        .unwrap();
+        std::fs::write(ws_dir.path().join(".env"), "TOKEN=secret").unwrap();
+        std::fs::write(ws_dir.path().join("README.md"), "hello").unwrap();
+        let workspace_id = Uuid::new_v4();
+        state
+            .workspace_registry
+            .register(workspace_id, ws_dir.path().to_path_buf())
+            .await
+            .unwrap();
+
+        let Json(entries) = list(
+            State(state.clone()),
+            Query(ListQuery {
+                workspace_id,
+                relative_path: None,
+            }),
+        )
+        .await
+        .unwrap_or_else(|e| panic!("list failed: {}", e.error.message));
+        let names: Vec<_> = entries.iter().map(|entry| entry.name.as_str()).collect();
+        assert_eq!(names, vec![".loopwire.toml", "README.md"]);
+
+        let err = read(
+            State(state.clone()),
+            Query(ReadQuery {
+                workspace_id,
+                relative_path: ".env".to_string(),
+                include_binary: None,
+            }),
+        )
+        .await
+        .expect_err("hidden file was served");
+        assert_eq!(err.status, StatusCode::NOT_FOUND);
+        let err = read_many(
+            State(state),
+            Json(ReadManyRequest {
+                workspace_id,
+                relative_paths: vec!["README.md".to_string(), "./.env".to_string()],
+            }),
+        )
+        .await
+        .err()
+        .expect("hidden file was served");
+        assert_eq!(err.error.code, "FS_NOT_FOUND");
+    }
 }
@@ -0,0 +1,339 @@
+use std::path::PathBuf;
+
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::error::{ApiError, ApiErrorResponse};
+use crate::project::workspace_project_config;
+use crate::rest::agent::{ScrollbackQuery, ScrollbackResponse};
+use crate::state::AppState;
+
+#[derive(Deserialize)]
+pub struct WorkspaceTasksQuery {
+    pub workspace_id: Uuid,
+}
+
+#[derive(Serialize)]
+pub struct WorkspaceTaskResponse {
+    #[serde(flatten)]
+    pub task: lw_agent::WorkspaceTask,
+    /// Most recent run, for pass/fail badges.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_run: Option<lw_agent::TaskRun>,
+}
+
+#[derive(Deserialize)]
+pub struct RunWorkspaceTaskRequest {
+    pub workspace_id: Uuid,
+    pub name: String,
+}
+
+#[derive(Deserialize)]
+pub struct ListTaskRunsQuery {
+    pub workspace_id: Uuid,
+    /// Only runs of this task.
+    pub name: Option<String>,
+}
+
+fn run_not_found() -> ApiErrorResponse {
+    ApiErrorResponse {
+        status: StatusCode::NOT_FOUND,
+        error: ApiError::not_found("Task run"),
+    }
+}
+
+async fn workspace_root(
+    state: &AppState,
+    workspace_id: &Uuid,
+) -> Result<PathBuf, ApiErrorResponse> {
+    state
+        .workspace_registry
+        .get_root(workspace_id)
+        .await
+        .map_err(|e| ApiError::fs_error(&e).into())
+}
+
+pub async fn list_tasks(
+    State(state): State<AppState>,
+    Query(query): Query<WorkspaceTasksQuery>,
+) -> Result<Json<Vec<WorkspaceTaskResponse>>, ApiErrorResponse> {
+    let root = workspace_root(&state, &query.workspace_id).await?;
+    let config = workspace_project_config(&state, query.workspace_id)
+        .await
+        .trusted_config()
+        .cloned();
+    let runs = state.workspace_task_manager.list(&root, None).await;
+    let tasks = lw_agent::discover_tasks(&root, config.as_ref())
+        .into_iter()
+        .map(|task| WorkspaceTaskResponse {
+            last_run: runs.iter().find(|run| run.task.name == task.name).cloned(),
+            task,
+        })
+        .collect();
+    Ok(Json(tasks))
+}
+
+pub async fn run_task(
+    State(state): State<AppState>,
+    Json(body): Json<RunWorkspaceTaskRequest>,
+) -> Result<(StatusCode, Json<lw_agent::TaskRun>), ApiErrorResponse> {
+    let root = workspace_root(&state, &body.workspace_id).await?;
+    let config = workspace_project_config(&state, body.workspace_id)
+        .await
+        .trusted_config()
+        .cloned()
+        .unwrap_or_default();
+    let task = lw_agent::discover_tasks(&root, Some(&config))
+        .into_iter()
+        .find(|task| task.name == body.name)
+        .ok_or_else(|| ApiErrorResponse {
+            status: StatusCode::NOT_FOUND,
+            error: ApiError::not_found("Workspace task"),
+        })?;
+    let run = state
+        .workspace_task_manager
+        .start_run(root, task, config.env.into_iter().collect())
+        .await
+        .map_err(|e| ApiErrorResponse {
+            status: StatusCode::INTERNAL_SERVER_ERROR,
+            error: ApiError::internal(e.to_string()),
+        })?;
+    Ok((StatusCode::CREATED, Json(run)))
+}
+
+pub async fn list_runs(
+    State(state): State<AppState>,
+    Query(query): Query<ListTaskRunsQuery>,
+) -> Result<Json<Vec<lw_agent::TaskRun>>, ApiErrorResponse> {
+    let root = workspace_root(&state, &query.workspace_id).await?;
+    Ok(Json(
+        state
+            .workspace_task_manager
+            .list(&root, query.name.as_deref())
+            .await,
+    ))
+}
+
+pub async fn get_run(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<lw_agent::TaskRun>, ApiErrorResponse> {
+    state
+        .workspace_task_manager
+        .get(&id)
+        .await
+        .map(Json)
+        .ok_or_else(run_not_found)
+}
+
+pub async fn cancel_run(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, ApiErrorResponse> {
+    if state.workspace_task_manager.cancel(&id).await {
+        return Ok(StatusCode::ACCEPTED);
+    }
+    if state.workspace_task_manager.get(&id).await.is_none() {
+        return Err(run_not_found());
+    }
+    Err(ApiErrorResponse {
+        status: StatusCode::CONFLICT,
+        error: ApiError::new("TASK_RUN_NOT_RUNNING", "Task run is not running"),
+    })
+}
+
+pub async fn delete_run(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, ApiErrorResponse> {
+    match state.workspace_task_manager.remove(&id).await {
+        Ok(true) => Ok(StatusCode::NO_CONTENT),
+        Ok(false) => Err(run_not_found()),
+        Err(e) => Err(ApiErrorResponse {
+            status: StatusCode::CONFLICT,
+            error: ApiError::new("TASK_RUN_RUNNING", e.to_string()),
+        }),
+    }
+}
+
+pub async fn run_scrollback(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<ScrollbackQuery>,
+) -> Result<Json<ScrollbackResponse>, ApiErrorResponse> {
+    if !state.workspace_task_manager.is_task_run(&id).await {
+        return Err(run_not_found());
+    }
+    let max_bytes = query.max_bytes.unwrap_or(512 * 1024).min(2 * 1024 * 1024);
+    let (data, start_offset, end_offset, has_more) = state
+        .pty_manager
+        .output_slice_before(&id, query.before_offset, max_bytes)
+        .await
+        .map_err(|e| ApiErrorResponse {
+            status: StatusCode::NOT_FOUND,
+            error: ApiError::new("SCROLLBACK_UNAVAILABLE", e.to_string()),
+        })?;
+    Ok(Json(ScrollbackResponse::new(
+        &data,
+        (start_offset, end_offset),
+        has_more,
+        query.format,
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rest::agent::ScrollbackFormat;
+
+    async fn register(state: &AppState, root: &std::path::Path) -> Uuid {
+        let id = Uuid::new_v4();
+        state
+            .workspace_registry
+            .register(id, root.to_path_buf())
+            .await
+            .unwrap();
+        id
+    }
+
+    #[tokio::test]
+    async fn unknown_workspace_and_task_are_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = AppState::for_test(dir.path(), |_| {});
+        let Err(err) = list_tasks(
+            State(state.clone()),
+            Query(WorkspaceTasksQuery {
+                workspace_id: Uuid::new_v4(),
+            }),
+        )
+        .await
+        else {
+            panic!("expected unregistered workspace error");
+        };
+        assert_eq!(err.status, StatusCode::NOT_FOUND);
+
+        let workspace_id = register(&state, dir.path()).await;
+        let Err(err) = run_task(
+            State(state),
+            Json(RunWorkspaceTaskRequest {
+                workspace_id,
+                name: "missing".to_string(),
+            }),
+        )
+        .await
+        else {
+            panic!("expected unknown task error");
+        };
+        assert_eq!(err.status, StatusCode::NOT_FOUND);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn run_lifecycle_through_handlers() {
+        let dir = tempfile::tempdir().unwrap();
+        let workspace = dir.path().join("repo");
+        std::fs::create_dir(&workspace).unwrap();
+        std::fs::write(
+            workspace.join(lw_config::PROJECT_CONFIG_FILE),
+            "[env]\nWHO = \"loopwire\"\n\n[tasks.hello]\ncommand = \"echo hello $WHO\"\n",
+        )
+        .unwrap();
+        let state = AppState::for_test(dir.path(), |_| {});
+        let workspace_id = register(&state, &workspace).await;
+        crate::project::trust_workspace_config(&state, workspace_id).await;
+        let mut events = state.workspace_task_manager.subscribe();
+
+        let Json(tasks) = list_tasks(
+            State(state.clone()),
+            Query(WorkspaceTasksQuery { workspace_id }),
+        )
+        .await
+        .unwrap_or_else(|_| panic!("list failed"));
+        assert_eq!(tasks.len(), 1);
+        assert!(tasks[0].last_run.is_none());
+
+        let (status, Json(run)) = run_task(
+            State(state.clone()),
+            Json(RunWorkspaceTaskRequest {
+                workspace_id,
+                name: "hello".to_string(),
+            }),
+        )
+        .await
+        .unwrap_or_else(|_| panic!("run failed"));
+        assert_eq!(status, StatusCode::CREATED);
+        tokio::time::timeout(std::time::Duration::from_secs(10), async {
+            while !matches!(
+                events.recv().await,
+                Ok(lw_agent::TaskRunEvent::Finished { .. })
+            ) {}
+        })
+        .await
+        .unwrap();
+
+        let Json(tasks) = list_tasks(
+            State(state.clone()),
+            Query(WorkspaceTasksQuery { workspace_id }),
+        )
+        .await
+        .unwrap_or_else(|_| panic!("list failed"));
+        let last_run = tasks[0].last_run.as_ref().unwrap();
+        assert_eq!(last_run.status, lw_agent::TaskRunStatus::Succeeded);
+        assert_eq!(last_run.exit_code, Some(0));
+
+        let Json(scrollback) = run_scrollback(
+            State(state.clone()),
+            Path(run.run_id),
+            Query(ScrollbackQuery {
+                before_offset: None,
+                max_bytes: None,
+                format: ScrollbackFormat::Raw,
+            }),
+        )
+        .await
+        .unwrap_or_else(|_| panic!("scrollback failed"));
+        let data =
+            base64::Engine::decode(&base64::engine::general_purpose::STANDARD, scrollback.data)
+                .unwrap();
+        assert!(String::from_utf8_lossy(&data).contains("hello loopwire"));
+        let Json(text) = run_scrollback(
+            State(state.clone()),
+            Path(run.run_id),
+            Query(ScrollbackQuery {
+                before_offset: None,
+                max_bytes: None,
+                format: ScrollbackFormat::Text,
+            }),
+        )
+        .await
+        .unwrap_or_else(|_| panic!("text scrollback failed"));
+        assert!(text.data.contains("hello loopwire\n"));
+        assert_eq!(text.end_offset, scrollback.end_offset);
+
+        let err = cancel_run(State(state.clone()), Path(run.run_id))
+            .await
+            .unwrap_err();
+        assert_eq!(err.error.code, "TASK_RUN_NOT_RUNNING");
+        let Json(runs) = list_runs(
+            State(state.clone()),
+            Query(ListTaskRunsQuery {
+                workspace_id,
+                name: Some("hello".to_string()),
+            }),
+        )
+        .await
+        .unwrap_or_else(|_| panic!("list runs failed"));
+        assert_eq!(runs.len(), 1);
+        assert_eq!(
+            delete_run(State(state.clone()), Path(run.run_id))
+                .await
+                .unwrap(),
+            StatusCode::NO_CONTENT
+        );
+        let err = get_run(State(state), Path(run.run_id)).await.unwrap_err();
+        assert_eq!(err.status, StatusCode::NOT_FOUND);
+    }
+}
@@ -1,12 +1,16 @@
+use axum::extract::DefaultBodyLimit;
 use axum::http::HeaderValue;
 use axum::middleware;
-use axum::routing::{get, post};
+use axum::routing::{any, get, post};
 use axum::Router;
 use tower_http::cors::{AllowOrigin, CorsLayer};
 use tower_http::trace::TraceLayer;
 
 use crate::auth::auth_middleware;
-use crate::rest::{agent, auth, bootstrap, git, health, remote, workspace};
+use crate::rest::{
+    agent, auth, bootstrap, export, fs, git, health, hooks, preview, remote, search, shell, task,
+    workspace, workspace_task,
+};
 use crate::state::AppState;
 use crate::ws::handler::ws_upgrade;
 use crate::ws::terminal::term_ws_upgrade;
@@ -48,6 +52,7 @@ pub fn build_router(state: AppState) -> Router {
         }
     }
 
+    // A UI served by the daemon itself is same-origin and needs no entry.
     if static_origins.is_empty() {
         if let Ok(fallback) = state.config.frontend_url.parse() {
             static_origins.push(fallback);
@@ -86,6 +91,7 @@ pub fn build_router(state: AppState) -> Router {
         .route("/api/v1/health", get(health::health))
         .route("/api/v1/auth/bootstrap", post(auth::bootstrap))
         .route("/api/v1/auth/exchange", post(auth::exchange))
+        .route("/api/v1/hooks/{session_id}", post(hooks::ingest))
         .route(
             "/api/v1/remote/invite/bootstrap",
             post(remote::invite_bootstrap),
@@ -112,6 +118,17 @@ pub fn build_router(state: AppState) -> Router {
         .route("/api/v1/bootstrap", get(bootstrap::bootstrap))
         .route("/api/v1/auth/rotate", post(auth::rotate))
         .route("/api/v1/auth/revoke", post(auth::revoke))
+        .route("/api/v1/auth/sessions", get(auth::list_sessions))
+        .route(
+            "/api/v1/auth/sessions/{id}/revoke",
+            post(auth::revoke_session),
+        )
+        .route("/api/v1/auth/tokens", get(auth::list_api_tokens))
+        .route("/api/v1/auth/tokens", post(auth::create_api_token))
+        .route(
+            "/api/v1/auth/tokens/{id}/revoke",
+            post(auth::revoke_api_token),
+        )
         .route("/api/v1/remote/share/start", post(remote::share_start))
         .route("/api/v1/remote/share/stop", post(remote::share_stop))
         .route("/api/v1/remote/share/status", get(remote::share_status))
@@ -120,14 +137,34 @@ pub fn build_router(state: AppState) -> Router {
         .route("/api/v1/fs/list", get(workspace::list))
         .route("/api/v1/fs/read", get(workspace::read))
         .route("/api/v1/fs/read_many", post(workspace::read_many))
+        .route(
+            "/api/v1/fs/write",
+            post(fs::write).layer(DefaultBodyLimit::max(fs::MAX_WRITE_BODY_BYTES)),
+        )
+        .route(
+            "/api/v1/fs/create",
+            post(fs::create).layer(DefaultBodyLimit::max(fs::MAX_WRITE_BODY_BYTES)),
+        )
+        .route("/api/v1/fs/mkdir", post(fs::mkdir))
+        .route("/api/v1/fs/rename", post(fs::rename))
+        .route("/api/v1/fs/move", post(fs::move_entry))
+        .route("/api/v1/fs/delete", post(fs::delete))
         .route("/api/v1/git/diff", get(git::diff))
         .route("/api/v1/git/status", get(git::status))
+        .route("/api/v1/git/stage", post(git::stage))
+        .route("/api/v1/git/unstage", post(git::unstage))
+        .route("/api/v1/git/discard", post(git::discard))
+        .route("/api/v1/git/commit", post(git::commit))
         .route("/api/v1/workspaces", get(workspace::list_workspaces))
         .route("/api/v1/workspaces/register", post(workspace::register))
         .route(
             "/api/v1/workspaces/settings",
             post(workspace::update_workspace_settings),
         )
+        .route(
+            "/api/v1/workspaces/trust",
+            post(workspace::trust_project_config),
+        )
         .route(
             "/api/v1/workspaces/remove",
             post(workspace::remove_workspace),
@@ -136,6 +173,10 @@ pub fn build_router(state: AppState) -> Router {
         .route("/api/v1/agents/sessions", get(agent::list_sessions))
         .route("/api/v1/agents/sessions", post(agent::create_session))
         .route("/api/v1/agents/sessions/{id}", get(agent::get_session))
+        .route(
+            "/api/v1/agents/sessions/{id}/fork",
+            post(agent::fork_session),
+        )
         .route(
             "/api/v1/agents/sessions/{id}/stop",
             post(agent::stop_session),
@@ -152,10 +193,89 @@ pub fn build_router(state: AppState) -> Router {
             "/api/v1/agents/sessions/{id}/attach",
             post(agent::attach_to_session),
         )
+        .route(
+            "/api/v1/agents/sessions/{id}/input",
+            post(agent::session_input),
+        )
+        .route(
+            "/api/v1/agents/sessions/{id}/queue",
+            get(agent::list_prompt_queue),
+        )
+        .route(
+            "/api/v1/agents/sessions/{id}/queue",
+            post(agent::enqueue_prompt),
+        )
+        .route(
+            "/api/v1/agents/sessions/{id}/queue/reorder",
+            post(agent::reorder_prompt_queue),
+        )
+        .route(
+            "/api/v1/agents/sessions/{id}/queue/{prompt_id}",
+            post(agent::edit_queued_prompt),
+        )
+        .route(
+            "/api/v1/agents/sessions/{id}/queue/{prompt_id}/cancel",
+            post(agent::cancel_queued_prompt),
+        )
         .route(
             "/api/v1/agents/sessions/{id}/scrollback",
             get(agent::session_scrollback),
         )
+        .route(
+            "/api/v1/agents/sessions/{id}/screen",
+            get(agent::session_screen),
+        )
+        .route(
+            "/api/v1/agents/sessions/{id}/export",
+            get(export::export_session),
+        )
+        .route("/api/v1/shells/sessions", get(shell::list_sessions))
+        .route("/api/v1/shells/sessions", post(shell::create_session))
+        .route("/api/v1/shells/sessions/{id}", get(shell::get_session))
+        .route(
+            "/api/v1/shells/sessions/{id}/stop",
+            post(shell::stop_session),
+        )
+        .route(
+            "/api/v1/shells/sessions/{id}/rename",
+            post(shell::rename_session),
+        )
+        .route(
+            "/api/v1/shells/sessions/{id}/settings",
+            post(shell::update_session_settings),
+        )
+        .route("/api/v1/search/scrollback", get(search::search_scrollback))
+        .route("/api/v1/tasks", get(task::list_tasks))
+        .route("/api/v1/tasks", post(task::create_task))
+        .route("/api/v1/tasks/{id}", get(task::get_task))
+        .route("/api/v1/tasks/{id}/cancel", post(task::cancel_task))
+        .route("/api/v1/tasks/{id}/delete", post(task::delete_task))
+        .route("/api/v1/workspace-tasks", get(workspace_task::list_tasks))
+        .route(
+            "/api/v1/workspace-tasks/run",
+            post(workspace_task::run_task),
+        )
+        .route(
+            "/api/v1/workspace-tasks/runs",
+            get(workspace_task::list_runs),
+        )
+        .route(
+            "/api/v1/workspace-tasks/runs/{id}",
+            get(workspace_task::get_run),
+        )
+        .route(
+            "/api/v1/workspace-tasks/runs/{id}/cancel",
+            post(workspace_task::cancel_run),
+        )
+        .route(
+            "/api/v1/workspace-tasks/runs/{id}/delete",
+            post(workspace_task::delete_run),
+        )
+        .route(
+            "/api/v1/workspace-tasks/runs/{id}/scrollback",
+            get(workspace_task::run_scrollback),
+        )
+        .route("/api/v1/ports", get(preview::list_ports))
         .layer(middleware::from_fn_with_state(
             state.clone(),
             auth_middleware,
@@ -166,10 +286,28 @@ pub fn build_router(state: AppState) -> Router {
         .route("/api/v1/ws", get(ws_upgrade))
         .route("/api/v1/term/{session_id}", get(term_ws_upgrade));
 
+    // A UI served by the daemon keeps its token in the page's storage, so
+    // previews move to their own listener (`build_preview_router`).
+    let preview_routes = if state.web_ui.is_some() {
+        Router::new()
+            .route(
+                "/api/v1/preview/{port}",
+                any(crate::preview::proxy::redirect_to_preview_listener),
+            )
+            .route(
+                "/api/v1/preview/{port}/{*path}",
+                any(crate::preview::proxy::redirect_to_preview_listener),
+            )
+    } else {
+        preview_proxy_routes()
+    };
+
     Router::new()
         .merge(public_routes)
         .merge(protected_routes)
         .merge(ws_routes)
+        .merge(preview_routes)
+        .fallback(crate::web::serve)
         .layer(cors)
         .layer(TraceLayer::new_for_http().make_span_with(
             |request: &axum::http::Request<axum::body::Body>| {
@@ -183,6 +321,35 @@ pub fn build_router(state: AppState) -> Router {
         .with_state(state)
 }
 
+/// Dev server preview (auth via header, query param or cookie).
+fn preview_proxy_routes() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/api/v1/preview/{port}",
+            any(crate::preview::proxy::proxy_root),
+        )
+        .route(
+            "/api/v1/preview/{port}/{*path}",
+            any(crate::preview::proxy::proxy_path),
+        )
+}
+
+/// The preview proxy alone, served on `config.preview_port()` while the
+/// daemon serves the web UI.
+pub fn build_preview_router(state: AppState) -> Router {
+    preview_proxy_routes()
+        .layer(TraceLayer::new_for_http().make_span_with(
+            |request: &axum::http::Request<axum::body::Body>| {
+                tracing::info_span!(
+                    "preview_request",
+                    method = %request.method(),
+                    uri = %request.uri()
+                )
+            },
+        ))
+        .with_state(state)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
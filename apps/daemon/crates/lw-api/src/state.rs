@@ -1,4 +1,7 @@
-use lw_agent::{AgentManager, AgentType, PersistedAgentInfo};
+use lw_agent::{
+    AgentManager, AgentType, PersistedAgentInfo, PersistedShellInfo, ShellManager, TaskManager,
+    WorkspaceTaskManager,
+};
 use lw_config::{ConfigPaths, DaemonConfig};
 use lw_fs::{FsWatcher, WorkspaceRegistry};
 use lw_pty::PtyManager;
@@ -7,8 +10,16 @@ use std::path::PathBuf;
 use std::sync::Arc;
 
 use crate::auth::TokenStore;
+use crate::notifications::Notification;
+use crate::preview::PortTracker;
+use crate::project::ProjectConfigs;
 use crate::remote::RemoteAccessManager;
-use crate::rest::workspace::{load_workspace_agents, load_workspaces, save_workspaces};
+use crate::rest::hooks::hooks_base_url;
+use crate::rest::workspace::{
+    load_workspace_agents, load_workspace_shells, load_workspaces, save_workspaces, scrollback_dir,
+};
+use crate::web::WebUi;
+use crate::ws::input_lock::TermInputLocks;
 
 #[derive(Clone)]
 pub struct AppState {
@@ -18,8 +29,25 @@ pub struct AppState {
     pub remote_access: Arc<RemoteAccessManager>,
     pub pty_manager: Arc<PtyManager>,
     pub agent_manager: Arc<AgentManager>,
+    pub shell_manager: Arc<ShellManager>,
+    pub task_manager: Arc<TaskManager>,
+    pub workspace_task_manager: Arc<WorkspaceTaskManager>,
     pub workspace_registry: WorkspaceRegistry,
     pub fs_watcher: Arc<FsWatcher>,
+    /// `.loopwire.toml` of each workspace, kept current by `fs_watcher`.
+    pub project_configs: Arc<ProjectConfigs>,
+    /// Workspaces whose git state was changed through the API; wakes
+    /// `git:status` subscribers without waiting for the fs debounce.
+    pub git_changes: tokio::sync::broadcast::Sender<uuid::Uuid>,
+    /// Notifications for `sink = "websocket"` rules, forwarded to every
+    /// connected client.
+    pub notifications: tokio::sync::broadcast::Sender<Notification>,
+    /// Ports session processes listen on, for the preview proxy.
+    pub port_tracker: Arc<PortTracker>,
+    /// Which terminal client, if any, is the sole writer of each session.
+    pub term_input_locks: Arc<TermInputLocks>,
+    /// The web UI build served at `/`, when `[web]` is enabled.
+    pub web_ui: Option<Arc<WebUi>>,
 
     pub version: &'static str,
 }
@@ -36,10 +64,15 @@ impl AppState {
 
         // Build persisted agent info from all workspaces for recovery.
         let mut persisted_agents = Vec::new();
+        let mut persisted_shells = Vec::new();
+        let mut prompt_queues = Vec::new();
         for ws in &ws_entries {
             let ws_path = PathBuf::from(&ws.path);
+            let ws_scrollback_dir = scrollback_dir(&paths, ws.id);
             let agents = load_workspace_agents(&paths, &ws_path);
             for (session_id, entry) in agents {
+                pty_manager
+                    .set_output_log_dir(session_id, ws_scrollback_dir.join(session_id.to_string()));
                 let agent_type = entry
                     .agent_type
                     .parse::<AgentType>()
@@ -49,9 +82,12 @@ impl AppState {
                     .as_ref()
                     .and_then(|s| chrono::DateTime::parse_from_rfc3339(s).ok())
                     .map(|dt| dt.with_timezone(&chrono::Utc));
+                prompt_queues.push((session_id, entry.prompt_queue));
                 persisted_agents.push(PersistedAgentInfo {
                     session_id,
                     workspace_path: ws_path.clone(),
+                    worktree: entry.worktree,
+                    launch_options: entry.launch_options,
                     agent_type,
                     conversation_id: entry.conversation_id,
                     custom_name: entry.custom_name,
@@ -70,9 +106,39 @@ impl AppState {
                     pid: entry.pid,
                 });
             }
+            for (session_id, entry) in load_workspace_shells(&paths, &ws_path) {
+                pty_manager
+                    .set_output_log_dir(session_id, ws_scrollback_dir.join(session_id.to_string()));
+                persisted_shells.push(PersistedShellInfo {
+                    session_id,
+                    workspace_path: ws_path.clone(),
+                    shell: entry.shell,
+                    custom_name: entry.custom_name,
+                    pinned: entry.pinned,
+                    sort_order: entry.sort_order,
+                    created_at: entry
+                        .created_at
+                        .as_ref()
+                        .and_then(|s| chrono::DateTime::parse_from_rfc3339(s).ok())
+                        .map(|dt| dt.with_timezone(&chrono::Utc)),
+                    pid: entry.pid,
+                });
+            }
         }
 
-        let agent_manager = Arc::new(AgentManager::new(pty_manager.clone(), persisted_agents));
+        let mut agent_manager =
+            AgentManager::new(pty_manager.clone(), persisted_agents, &config.agents);
+        agent_manager.set_hooks_url(hooks_base_url(&config));
+        for (session_id, prompts) in prompt_queues {
+            agent_manager.restore_prompt_queue(session_id, prompts);
+        }
+        let agent_manager = Arc::new(agent_manager);
+        let shell_manager = Arc::new(ShellManager::new(pty_manager.clone(), persisted_shells));
+        let task_manager = Arc::new(TaskManager::new(&config.agents, Some(paths.tasks_dir())));
+        let workspace_task_manager = Arc::new(WorkspaceTaskManager::new(
+            pty_manager.clone(),
+            Some(paths.task_runs_dir()),
+        ));
         let registry_entries: Vec<(uuid::Uuid, PathBuf)> = ws_entries
             .iter()
             .filter(|e| PathBuf::from(&e.path).is_dir())
@@ -86,11 +152,12 @@ impl AppState {
         }
 
         let token_store = Arc::new(TokenStore::new(bootstrap_token_hash, paths.clone()));
-        let remote_access = Arc::new(RemoteAccessManager::new(
-            config.clone(),
-            token_store.clone(),
-            paths.clone(),
-        )?);
+        let web_ui = WebUi::load(&config, &paths).map(Arc::new);
+        let mut remote_access =
+            RemoteAccessManager::new(config.clone(), token_store.clone(), paths.clone())?;
+        remote_access.set_serves_web_ui(web_ui.is_some());
+        let remote_access = Arc::new(remote_access);
+        let project_configs = Arc::new(ProjectConfigs::new(paths.clone()));
 
         Ok(Self {
             config,
@@ -99,14 +166,38 @@ impl AppState {
             remote_access,
             pty_manager,
             agent_manager,
+            shell_manager,
+            task_manager,
+            workspace_task_manager,
             workspace_registry,
             fs_watcher,
+            project_configs,
+            git_changes: tokio::sync::broadcast::channel(64).0,
+            notifications: tokio::sync::broadcast::channel(64).0,
+            port_tracker: Arc::new(PortTracker::new()),
+            term_input_locks: Arc::new(TermInputLocks::new()),
+            web_ui,
 
             version: option_env!("LOOPWIRED_VERSION").unwrap_or(env!("CARGO_PKG_VERSION")),
         })
     }
 }
 
+#[cfg(test)]
+impl AppState {
+    /// State for handler tests, keeping its data under `dir/data`.
+    /// `configure` adjusts the config first.
+    pub(crate) fn for_test(
+        dir: &std::path::Path,
+        configure: impl FnOnce(&mut DaemonConfig),
+    ) -> Self {
+        let mut config = DaemonConfig::default();
+        config.set_paths(ConfigPaths::with_base(dir.join("data")));
+        configure(&mut config);
+        Self::new(config, TokenStore::hash_token("test")).unwrap()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
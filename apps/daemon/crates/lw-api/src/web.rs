@@ -0,0 +1,330 @@
+//! The web UI served by the daemon itself (`[web] enabled = true`), for
+//! setups that can't load the hosted frontend.
+//!
+//! Assets come from a build of `apps/web` on disk, or from one compiled in
+//! with the `bundled-web` feature. Unknown paths get `index.html` so client
+//! routes like `/connect` load the app. The page is marked as served by the
+//! daemon, which makes the client use its own origin as the daemon URL.
+//! Dev server previews then move to a port of their own, so they never share
+//! that origin (see `preview::proxy`).
+
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+
+use axum::extract::State;
+use axum::http::{header, HeaderValue, Method, StatusCode, Uri};
+use axum::response::{IntoResponse, Response};
+use lw_config::{ConfigPaths, DaemonConfig};
+
+use crate::error::{ApiError, ApiErrorResponse};
+use crate::state::AppState;
+
+/// Tells the client it was loaded from the daemon it should talk to.
+const SAME_ORIGIN_MARKER: &str = r#"<meta name="loopwire-daemon" content="same-origin" />"#;
+
+/// Hashed build output, safe to cache for good.
+const IMMUTABLE_PREFIX: &str = "assets/";
+
+#[cfg(feature = "bundled-web")]
+#[derive(rust_embed::RustEmbed)]
+#[folder = "../../../web/dist"]
+struct BundledAssets;
+
+#[derive(Debug, Clone)]
+enum Assets {
+    Dir(PathBuf),
+    #[cfg(feature = "bundled-web")]
+    Bundled,
+}
+
+#[derive(Debug, Clone)]
+pub struct WebUi {
+    assets: Assets,
+}
+
+impl WebUi {
+    /// The build to serve, if the UI is enabled and one is found: `web.dir`,
+    /// then `~/.loopwire/web`, then the bundled one.
+    pub fn load(config: &DaemonConfig, paths: &ConfigPaths) -> Option<Self> {
+        if !config.web.enabled {
+            return None;
+        }
+        let dirs: Vec<PathBuf> = config
+            .web
+            .dir
+            .iter()
+            .cloned()
+            .chain([paths.web_dir()])
+            .collect();
+        if let Some(dir) = dirs.iter().find(|dir| dir.join("index.html").is_file()) {
+            return Some(Self {
+                assets: Assets::Dir(dir.clone()),
+            });
+        }
+        #[cfg(feature = "bundled-web")]
+        if BundledAssets::get("index.html").is_some() {
+            return Some(Self {
+                assets: Assets::Bundled,
+            });
+        }
+        tracing::warn!(
+            "web UI enabled but no build found in {}; build apps/web and point web.dir at its dist/",
+            dirs.iter()
+                .map(|dir| dir.display().to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        None
+    }
+
+    /// Where the assets come from, for the startup log.
+    pub fn source(&self) -> String {
+        match &self.assets {
+            Assets::Dir(dir) => dir.display().to_string(),
+            #[cfg(feature = "bundled-web")]
+            Assets::Bundled => "bundled build".to_string(),
+        }
+    }
+
+    async fn asset(&self, path: &str) -> Option<Cow<'static, [u8]>> {
+        match &self.assets {
+            Assets::Dir(dir) => read_file(dir, path).await.map(Cow::Owned),
+            #[cfg(feature = "bundled-web")]
+            Assets::Bundled => BundledAssets::get(path).map(|file| file.data),
+        }
+    }
+
+    async fn index(&self) -> Option<Vec<u8>> {
+        let html = self.asset("index.html").await?;
+        Some(mark_same_origin(&String::from_utf8_lossy(&html)).into_bytes())
+    }
+}
+
+async fn read_file(dir: &Path, path: &str) -> Option<Vec<u8>> {
+    let file = dir.join(path);
+    if !tokio::fs::metadata(&file).await.ok()?.is_file() {
+        return None;
+    }
+    tokio::fs::read(file).await.ok()
+}
+
+/// Where people open the UI: the daemon itself when it serves one (or no
+/// hosted frontend is configured), `frontend_url` otherwise.
+pub fn frontend_url(config: &DaemonConfig, serves_web_ui: bool) -> String {
+    if serves_web_ui || config.frontend_url.is_empty() {
+        config.local_url()
+    } else {
+        config.frontend_url.trim_end_matches('/').to_string()
+    }
+}
+
+fn mark_same_origin(html: &str) -> String {
+    match html.find("</head>") {
+        Some(at) => format!("{}{SAME_ORIGIN_MARKER}\n{}", &html[..at], &html[at..]),
+        None => format!("{SAME_ORIGIN_MARKER}\n{html}"),
+    }
+}
+
+/// Asset path relative to the build root; `None` for anything that could
+/// leave it.
+fn asset_path(uri_path: &str) -> Option<String> {
+    let path = uri_path.trim_start_matches('/');
+    let valid = path.split('/').all(|segment| {
+        !matches!(segment, "." | "..") && !segment.contains('\\') && !segment.contains(':')
+    });
+    valid.then(|| path.to_string())
+}
+
+fn content_type(path: &str) -> &'static str {
+    let extension = path
+        .rsplit_once('.')
+        .map(|(_, ext)| ext)
+        .unwrap_or_default();
+    match extension.to_ascii_lowercase().as_str() {
+        "html" => "text/html; charset=utf-8",
+        "js" | "mjs" => "text/javascript; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "json" | "map" => "application/json",
+        "webmanifest" => "application/manifest+json",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "ico" => "image/x-icon",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "ttf" => "font/ttf",
+        "wasm" => "application/wasm",
+        "txt" | "sh" => "text/plain; charset=utf-8",
+        _ => "application/octet-stream",
+    }
+}
+
+fn not_found() -> Response {
+    ApiErrorResponse {
+        status: StatusCode::NOT_FOUND,
+        error: ApiError::not_found("Route"),
+    }
+    .into_response()
+}
+
+fn respond(body: Vec<u8>, content_type: &'static str, immutable: bool) -> Response {
+    let cache = if immutable {
+        "public, max-age=31536000, immutable"
+    } else {
+        "no-cache"
+    };
+    (
+        [
+            (header::CONTENT_TYPE, HeaderValue::from_static(content_type)),
+            (header::CACHE_CONTROL, HeaderValue::from_static(cache)),
+        ],
+        body,
+    )
+        .into_response()
+}
+
+/// Router fallback: static assets, then `index.html` for client routes.
+/// Unknown API paths stay 404s.
+pub async fn serve(State(state): State<AppState>, method: Method, uri: Uri) -> Response {
+    let Some(web_ui) = state.web_ui.as_deref() else {
+        return not_found();
+    };
+    if !matches!(method, Method::GET | Method::HEAD) || uri.path().starts_with("/api/") {
+        return not_found();
+    }
+    let Some(path) = asset_path(uri.path()) else {
+        return not_found();
+    };
+
+    if !path.is_empty() && path != "index.html" {
+        if let Some(body) = web_ui.asset(&path).await {
+            return respond(
+                body.into_owned(),
+                content_type(&path),
+                path.starts_with(IMMUTABLE_PREFIX),
+            );
+        }
+        // A missing file isn't a client route.
+        let file_name = path.rsplit('/').next().unwrap_or_default();
+        if file_name.contains('.') {
+            return not_found();
+        }
+    }
+    match web_ui.index().await {
+        Some(index) => respond(index, "text/html; charset=utf-8", false),
+        None => not_found(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_state(dir: &Path, web_dir: Option<&Path>) -> AppState {
+        AppState::for_test(dir, |config| {
+            config.web.enabled = web_dir.is_some();
+            config.web.dir = web_dir.map(Path::to_path_buf);
+        })
+    }
+
+    async fn get(state: &AppState, path: &str) -> Response {
+        serve(State(state.clone()), Method::GET, path.parse().unwrap()).await
+    }
+
+    async fn body(response: Response) -> String {
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    #[test]
+    fn asset_paths_cannot_escape_the_build() {
+        assert_eq!(
+            asset_path("/assets/app.js").as_deref(),
+            Some("assets/app.js")
+        );
+        assert_eq!(asset_path("/").as_deref(), Some(""));
+        assert_eq!(asset_path("/../secret"), None);
+        assert_eq!(asset_path("/assets/../../x"), None);
+        assert_eq!(asset_path("/C:/x"), None);
+    }
+
+    #[test]
+    fn marker_goes_into_the_head() {
+        let html = mark_same_origin("<html><head><title>x</title></head><body></body></html>");
+        assert!(html.contains(&format!("{SAME_ORIGIN_MARKER}\n</head>")));
+    }
+
+    #[test]
+    fn frontend_url_prefers_the_daemon_when_it_serves_the_ui() {
+        let mut config = DaemonConfig::default();
+        config.port = 9400;
+        config.frontend_url = "https://app.example.com/".to_string();
+        assert_eq!(frontend_url(&config, false), "https://app.example.com");
+        assert_eq!(frontend_url(&config, true), "http://localhost:9400");
+        config.frontend_url = String::new();
+        assert_eq!(frontend_url(&config, false), "http://localhost:9400");
+    }
+
+    #[tokio::test]
+    async fn serves_assets_with_spa_fallback() {
+        let dir = tempfile::tempdir().unwrap();
+        let web = dir.path().join("dist");
+        std::fs::create_dir_all(web.join("assets")).unwrap();
+        std::fs::write(
+            web.join("index.html"),
+            "<html><head><title>Loopwire</title></head><body></body></html>",
+        )
+        .unwrap();
+        std::fs::write(web.join("assets/index-abc.js"), "console.log(1)").unwrap();
+        std::fs::write(web.join("favicon.svg"), "<svg/>").unwrap();
+        let state = make_state(dir.path(), Some(&web));
+        assert!(state.web_ui.is_some());
+
+        let response = get(&state, "/assets/index-abc.js").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "text/javascript; charset=utf-8"
+        );
+        assert!(response.headers()[header::CACHE_CONTROL]
+            .to_str()
+            .unwrap()
+            .contains("immutable"));
+        assert_eq!(body(response).await, "console.log(1)");
+
+        let response = get(&state, "/favicon.svg").await;
+        assert_eq!(response.headers()[header::CACHE_CONTROL], "no-cache");
+
+        for route in ["/", "/connect?target=x", "/workspace/abc"] {
+            let response = get(&state, route).await;
+            assert_eq!(response.status(), StatusCode::OK, "{route}");
+            assert!(body(response).await.contains(SAME_ORIGIN_MARKER));
+        }
+
+        assert_eq!(
+            get(&state, "/assets/missing.js").await.status(),
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(
+            get(&state, "/api/v1/nope").await.status(),
+            StatusCode::NOT_FOUND
+        );
+        let response = serve(State(state), Method::POST, "/".parse().unwrap()).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn disabled_or_missing_build_serves_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = make_state(dir.path(), None);
+        assert!(state.web_ui.is_none());
+        assert_eq!(get(&state, "/").await.status(), StatusCode::NOT_FOUND);
+
+        let state = make_state(dir.path(), Some(&dir.path().join("missing")));
+        assert!(state.web_ui.is_none());
+    }
+}
@@ -8,6 +8,8 @@ use tokio::task::JoinHandle;
 use tokio::time::{Duration, MissedTickBehavior};
 use uuid::Uuid;
 
+use crate::auth::{Access, TokenScope};
+use crate::project::HiddenPaths;
 use crate::rest::git::compute_git_status;
 use crate::state::AppState;
 use crate::ws::messages::WsEnvelope;
@@ -25,22 +27,28 @@ pub async fn ws_upgrade(
 ) -> Result<Response, axum::http::StatusCode> {
     tracing::info!("WebSocket upgrade request received");
 
-    let authenticated = if let Some(token) = query.token {
-        if !state.token_store.validate_session(&token).await {
+    let access = if let Some(token) = query.token {
+        let Some(access) = state.token_store.authenticate(&token).await else {
             let prefix: String = token.chars().take(8).collect();
             tracing::warn!(
                 "WebSocket upgrade rejected: invalid token (prefix: {}...)",
                 prefix
             );
             return Err(axum::http::StatusCode::UNAUTHORIZED);
+        };
+        // Events cover every workspace, so API tokens limited to some can't
+        // subscribe.
+        if !access.allows(TokenScope::ReadOnly) || access.is_workspace_restricted() {
+            tracing::warn!("WebSocket upgrade rejected: token lacks access to all events");
+            return Err(axum::http::StatusCode::FORBIDDEN);
         }
-        true
+        Some(access)
     } else if query
         .probe
         .as_deref()
         .is_some_and(|value| value == "1" || value.eq_ignore_ascii_case("true"))
     {
-        false
+        None
     } else {
         tracing::warn!("WebSocket upgrade rejected: no token provided");
         return Err(axum::http::StatusCode::UNAUTHORIZED);
@@ -48,14 +56,17 @@ pub async fn ws_upgrade(
 
     tracing::info!(
         "WebSocket upgrade accepted (authenticated={})",
-        authenticated
+        access.is_some()
     );
-    Ok(ws.on_upgrade(move |socket| handle_socket(socket, state, authenticated)))
+    Ok(ws.on_upgrade(move |socket| handle_socket(socket, state, access)))
 }
 
-async fn handle_socket(socket: WebSocket, state: AppState, authenticated: bool) {
+/// `access` is `None` for unauthenticated probe connections.
+async fn handle_socket(socket: WebSocket, state: AppState, access: Option<Access>) {
     use futures::{SinkExt, StreamExt};
 
+    let authenticated = access.is_some();
+
     tracing::info!("WebSocket connection established");
 
     let (mut ws_tx, mut ws_rx) = socket.split();
@@ -71,6 +82,12 @@ async fn handle_socket(socket: WebSocket, state: AppState, authenticated: bool)
     });
 
     let mut activity_rx = state.agent_manager.subscribe_activity();
+    let mut notification_rx = state.notifications.subscribe();
+    let mut task_rx = state.task_manager.subscribe();
+    let mut task_run_rx = state.workspace_task_manager.subscribe();
+    let mut queue_rx = state.agent_manager.subscribe_prompt_queue();
+    let mut project_config_rx = state.project_configs.subscribe();
+    let mut port_rx = state.port_tracker.subscribe();
     let mut alive_tick = tokio::time::interval(Duration::from_secs(3));
     alive_tick.set_missed_tick_behavior(MissedTickBehavior::Skip);
 
@@ -93,7 +110,7 @@ async fn handle_socket(socket: WebSocket, state: AppState, authenticated: bool)
                             }
                         };
 
-                        handle_client_message(&msg_tx, &state, authenticated, &envelope, &mut git_subs).await;
+                        handle_client_message(&msg_tx, &state, access.as_ref(), &envelope, &mut git_subs).await;
                     }
                     Some(Ok(Message::Close(_))) | None => {
                         tracing::info!("WebSocket connection closed");
@@ -122,6 +139,166 @@ async fn handle_socket(socket: WebSocket, state: AppState, authenticated: bool)
                     }
                 }
             }
+            notification = notification_rx.recv() => {
+                match notification {
+                    Ok(notification) => {
+                        if !authenticated {
+                            continue;
+                        }
+                        if let Ok(payload) = serde_json::to_value(notification) {
+                            let message = WsEnvelope::notification(payload);
+                            let text = serde_json::to_string(&message).unwrap();
+                            if msg_tx.send(Message::Text(text.into())).await.is_err() {
+                                break;
+                            }
+                        }
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(n)) => {
+                        tracing::warn!("Notification subscriber lagged by {} messages", n);
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => {
+                        break;
+                    }
+                }
+            }
+            task_event = task_rx.recv() => {
+                match task_event {
+                    Ok(event) => {
+                        if !authenticated {
+                            continue;
+                        }
+                        let message = match event {
+                            lw_agent::TaskEvent::Started { task } => {
+                                serde_json::to_value(task).ok().map(WsEnvelope::task_started)
+                            }
+                            lw_agent::TaskEvent::Output { task_id, stream, data } => {
+                                let stream = match stream {
+                                    lw_agent::TaskStream::Stdout => "stdout",
+                                    lw_agent::TaskStream::Stderr => "stderr",
+                                };
+                                Some(WsEnvelope::task_output(task_id, stream, &data))
+                            }
+                            lw_agent::TaskEvent::Finished { task } => {
+                                serde_json::to_value(task).ok().map(WsEnvelope::task_finished)
+                            }
+                        };
+                        if let Some(message) = message {
+                            let text = serde_json::to_string(&message).unwrap();
+                            if msg_tx.send(Message::Text(text.into())).await.is_err() {
+                                break;
+                            }
+                        }
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(n)) => {
+                        tracing::warn!("Task subscriber lagged by {} messages", n);
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => {
+                        break;
+                    }
+                }
+            }
+            task_run_event = task_run_rx.recv() => {
+                match task_run_event {
+                    Ok(event) => {
+                        if !authenticated {
+                            continue;
+                        }
+                        let message = match event {
+                            lw_agent::TaskRunEvent::Started { run } => {
+                                serde_json::to_value(run).ok().map(WsEnvelope::task_run_started)
+                            }
+                            lw_agent::TaskRunEvent::Finished { run } => {
+                                serde_json::to_value(run).ok().map(WsEnvelope::task_run_finished)
+                            }
+                        };
+                        if let Some(message) = message {
+                            let text = serde_json::to_string(&message).unwrap();
+                            if msg_tx.send(Message::Text(text.into())).await.is_err() {
+                                break;
+                            }
+                        }
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(n)) => {
+                        tracing::warn!("Task run subscriber lagged by {} messages", n);
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => {
+                        break;
+                    }
+                }
+            }
+            queue_event = queue_rx.recv() => {
+                match queue_event {
+                    Ok(event) => {
+                        if !authenticated {
+                            continue;
+                        }
+                        if let Ok(prompts) = serde_json::to_value(event.prompts) {
+                            let message = WsEnvelope::agent_queue(event.session_id, prompts);
+                            let text = serde_json::to_string(&message).unwrap();
+                            if msg_tx.send(Message::Text(text.into())).await.is_err() {
+                                break;
+                            }
+                        }
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(n)) => {
+                        tracing::warn!("Prompt queue subscriber lagged by {} messages", n);
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => {
+                        break;
+                    }
+                }
+            }
+            port_event = port_rx.recv() => {
+                match port_event {
+                    Ok(event) => {
+                        if !authenticated {
+                            continue;
+                        }
+                        let message = match event {
+                            crate::preview::PortEvent::Opened(port) => {
+                                serde_json::to_value(port).ok().map(WsEnvelope::port_opened)
+                            }
+                            crate::preview::PortEvent::Closed(port) => {
+                                serde_json::to_value(port).ok().map(WsEnvelope::port_closed)
+                            }
+                        };
+                        if let Some(message) = message {
+                            let text = serde_json::to_string(&message).unwrap();
+                            if msg_tx.send(Message::Text(text.into())).await.is_err() {
+                                break;
+                            }
+                        }
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(n)) => {
+                        tracing::warn!("Port subscriber lagged by {} messages", n);
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => {
+                        break;
+                    }
+                }
+            }
+            config_event = project_config_rx.recv() => {
+                match config_event {
+                    Ok(event) => {
+                        if !authenticated {
+                            continue;
+                        }
+                        if let Ok(payload) = serde_json::to_value(event) {
+                            let message = WsEnvelope::workspace_config(payload);
+                            let text = serde_json::to_string(&message).unwrap();
+                            if msg_tx.send(Message::Text(text.into())).await.is_err() {
+                                break;
+                            }
+                        }
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(n)) => {
+                        tracing::warn!("Project config subscriber lagged by {} messages", n);
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => {
+                        break;
+                    }
+                }
+            }
             _ = alive_tick.tick() => {
                 let alive = WsEnvelope::daemon_alive();
                 let text = serde_json::to_string(&alive).unwrap();
@@ -143,15 +320,15 @@ async fn handle_socket(socket: WebSocket, state: AppState, authenticated: bool)
 async fn handle_client_message(
     tx: &tokio::sync::mpsc::Sender<Message>,
     state: &AppState,
-    authenticated: bool,
+    access: Option<&Access>,
     envelope: &WsEnvelope,
     git_subs: &mut HashMap<Uuid, JoinHandle<()>>,
 ) {
     let request_id = envelope.request_id.clone();
-    if !authenticated {
+    let Some(access) = access else {
         send_error(tx, request_id, "UNAUTHORIZED", "Authentication required").await;
         return;
-    }
+    };
 
     match envelope.msg_type.as_str() {
         "fs:watch" => {
@@ -162,6 +339,14 @@ async fn handle_client_message(
 
             if let Some(wid) = workspace_id {
                 if let Ok(root) = state.workspace_registry.get_root(&wid).await {
+                    if HiddenPaths::of(state, &wid)
+                        .await
+                        .is_hidden_relative(relative_path)
+                    {
+                        let err = lw_fs::FsError::NotFound;
+                        send_error(tx, request_id, err.error_code(), &err.to_string()).await;
+                        return;
+                    }
                     match state.fs_watcher.watch(wid, &root, relative_path).await {
                         Ok(_rx) => {
                             tracing::info!("Watching {}/{}", wid, relative_path);
@@ -230,20 +415,38 @@ async fn handle_client_message(
                 }
             };
 
+            let mut git_rx = state.git_changes.subscribe();
             let tx_clone = tx.clone();
             let root_clone = root.clone();
+            let state = state.clone();
             let handle = tokio::spawn(async move {
+                use tokio::sync::broadcast::error::RecvError;
                 const DEBOUNCE: Duration = Duration::from_millis(500);
                 loop {
-                    // Wait for the first fs event
-                    match fs_rx.recv().await {
-                        Ok(_) => {}
-                        Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
-                        Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => {}
-                    }
+                    // Wait for the first fs event, or an API-driven git change
+                    // which skips the debounce.
+                    let debounce = tokio::select! {
+                        event = fs_rx.recv() => match event {
+                            // Changes to hidden files must not show up as
+                            // status pushes either.
+                            Ok(event) if HiddenPaths::of(&state, &wid)
+                                .await
+                                .is_hidden_relative(&event.path) => continue,
+                            Ok(_) | Err(RecvError::Lagged(_)) => true,
+                            Err(RecvError::Closed) => break,
+                        },
+                        changed = git_rx.recv() => match changed {
+                            Ok(id) if id == wid => false,
+                            Ok(_) => continue,
+                            Err(RecvError::Lagged(_)) => false,
+                            Err(RecvError::Closed) => break,
+                        },
+                    };
 
                     // Debounce: drain further events for 500ms
-                    tokio::time::sleep(DEBOUNCE).await;
+                    if debounce {
+                        tokio::time::sleep(DEBOUNCE).await;
+                    }
                     while fs_rx.try_recv().is_ok() {}
 
                     // Compute git status on a blocking thread
@@ -252,7 +455,7 @@ async fn handle_client_message(
                         tokio::task::spawn_blocking(move || compute_git_status(&root)).await;
 
                     let response = match result {
-                        Ok(Ok(resp)) => resp,
+                        Ok(Ok(resp)) => resp.without_hidden(&HiddenPaths::of(&state, &wid).await),
                         Ok(Err(e)) => {
                             tracing::debug!("git status computation failed: {}", e.error.message);
                             continue;
@@ -289,6 +492,14 @@ async fn handle_client_message(
             }
         }
 
+        "agent:queue:list"
+        | "agent:queue:add"
+        | "agent:queue:edit"
+        | "agent:queue:cancel"
+        | "agent:queue:reorder" => {
+            handle_queue_message(tx, state, access, envelope).await;
+        }
+
         _ => {
             send_error(
                 tx,
@@ -301,6 +512,116 @@ async fn handle_client_message(
     }
 }
 
+/// Prompt queue requests. Each one is answered with the session's whole
+/// queue, tagged with the request id. Queued prompts are typed into the
+/// agent, so API tokens need the `agents` scope to change the queue.
+async fn handle_queue_message(
+    tx: &tokio::sync::mpsc::Sender<Message>,
+    state: &AppState,
+    access: &Access,
+    envelope: &WsEnvelope,
+) {
+    let request_id = envelope.request_id.clone();
+    let payload = &envelope.payload;
+    let uuid_field = |field: &str| {
+        payload[field]
+            .as_str()
+            .and_then(|s| Uuid::parse_str(s).ok())
+    };
+    let Some(session_id) = uuid_field("session_id") else {
+        send_error(tx, request_id, "INVALID_PAYLOAD", "Missing session_id").await;
+        return;
+    };
+    if envelope.msg_type != "agent:queue:list" && !access.allows(TokenScope::Agents) {
+        send_error(
+            tx,
+            request_id,
+            "INSUFFICIENT_SCOPE",
+            &format!("Token lacks the '{}' scope", TokenScope::Agents),
+        )
+        .await;
+        return;
+    }
+    if access.is_workspace_restricted() {
+        let workspace = crate::preview::session_workspace_id(state, session_id).await;
+        if !workspace.is_some_and(|id| access.allows_workspace(&id)) {
+            send_error(
+                tx,
+                request_id,
+                "WORKSPACE_FORBIDDEN",
+                "Token is limited to other workspaces",
+            )
+            .await;
+            return;
+        }
+    }
+    let text = payload["text"].as_str().map(str::to_string);
+    let manager = &state.agent_manager;
+
+    let result = match envelope.msg_type.as_str() {
+        "agent:queue:list" => {
+            if manager.get_handle(&session_id).await.is_some() {
+                Ok(())
+            } else {
+                Err(lw_agent::PromptQueueError::SessionNotFound)
+            }
+        }
+        "agent:queue:add" => {
+            let Some(text) = text else {
+                send_error(tx, request_id, "INVALID_PAYLOAD", "Missing text").await;
+                return;
+            };
+            manager.enqueue_prompt(&session_id, text).await.map(|_| ())
+        }
+        "agent:queue:edit" => {
+            let (Some(prompt_id), Some(text)) = (uuid_field("prompt_id"), text) else {
+                send_error(
+                    tx,
+                    request_id,
+                    "INVALID_PAYLOAD",
+                    "Missing prompt_id or text",
+                )
+                .await;
+                return;
+            };
+            manager
+                .edit_queued_prompt(&session_id, &prompt_id, text)
+                .await
+                .map(|_| ())
+        }
+        "agent:queue:cancel" => {
+            let Some(prompt_id) = uuid_field("prompt_id") else {
+                send_error(tx, request_id, "INVALID_PAYLOAD", "Missing prompt_id").await;
+                return;
+            };
+            manager.cancel_queued_prompt(&session_id, &prompt_id).await
+        }
+        _ => {
+            let Ok(prompt_ids) = serde_json::from_value::<Vec<Uuid>>(payload["prompt_ids"].clone())
+            else {
+                send_error(tx, request_id, "INVALID_PAYLOAD", "Missing prompt_ids").await;
+                return;
+            };
+            manager
+                .reorder_prompt_queue(&session_id, &prompt_ids)
+                .await
+                .map(|_| ())
+        }
+    };
+    if let Err(err) = result {
+        send_error(tx, request_id, err.error_code(), &err.to_string()).await;
+        return;
+    }
+
+    let prompts = serde_json::to_value(manager.prompt_queue(&session_id)).unwrap_or_default();
+    let response = WsEnvelope::agent_queue(session_id, prompts).with_request_id(request_id);
+    let _ = tx
+        .send(Message::Text(
+            serde_json::to_string(&response).unwrap().into(),
+        ))
+        .await;
+}
+
 async fn send_error(
     tx: &tokio::sync::mpsc::Sender<Message>,
     request_id: Option<String>,
@@ -370,4 +691,127 @@ mod tests {
             .as_deref()
             .is_some_and(|v| v == "1" || v.eq_ignore_ascii_case("true")));
     }
+
+    async fn queue_request(
+        state: &AppState,
+        access: &Access,
+        msg_type: &str,
+        payload: serde_json::Value,
+    ) -> WsEnvelope {
+        let (tx, mut rx) = tokio::sync::mpsc::channel(4);
+        let envelope = WsEnvelope::new(msg_type, payload).with_request_id(Some("r1".to_string()));
+        handle_queue_message(&tx, state, access, &envelope).await;
+        let Some(Message::Text(text)) = rx.recv().await else {
+            panic!("no reply");
+        };
+        serde_json::from_str(&text).unwrap()
+    }
+
+    async fn state_with_session() -> (tempfile::TempDir, AppState, Uuid) {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = lw_config::DaemonConfig::default();
+        config.set_paths(lw_config::ConfigPaths::with_base(dir.path().to_path_buf()));
+        let state = AppState::new(config, crate::auth::TokenStore::hash_token("test")).unwrap();
+        let session_id = Uuid::new_v4();
+        state
+            .agent_manager
+            .ensure_persisted_handles(&[lw_agent::PersistedAgentInfo {
+                session_id,
+                workspace_path: dir.path().to_path_buf(),
+                worktree: None,
+                launch_options: Default::default(),
+                agent_type: lw_agent::AgentType::ClaudeCode,
+                conversation_id: None,
+                custom_name: None,
+                pinned: false,
+                icon: None,
+                sort_order: None,
+                resumability_status: None,
+                resume_failure_reason: None,
+                created_at: None,
+                pid: None,
+            }])
+            .await;
+        (dir, state, session_id)
+    }
+
+    #[tokio::test]
+    async fn queue_messages_reply_with_the_whole_queue() {
+        let (_dir, state, session_id) = state_with_session().await;
+
+        let reply = queue_request(
+            &state,
+            &Access::Session,
+            "agent:queue:add",
+            serde_json::json!({"session_id": session_id, "text": "later"}),
+        )
+        .await;
+        assert_eq!(reply.msg_type, "agent:queue");
+        assert_eq!(reply.request_id.as_deref(), Some("r1"));
+        let prompt_id = reply.payload["prompts"][0]["id"]
+            .as_str()
+            .unwrap()
+            .to_string();
+
+        let reply = queue_request(
+            &state,
+            &Access::Session,
+            "agent:queue:edit",
+            serde_json::json!({"session_id": session_id, "prompt_id": prompt_id, "text": "sooner"}),
+        )
+        .await;
+        assert_eq!(reply.payload["prompts"][0]["text"], "sooner");
+
+        let reply = queue_request(
+            &state,
+            &Access::Session,
+            "agent:queue:cancel",
+            serde_json::json!({"session_id": session_id, "prompt_id": prompt_id}),
+        )
+        .await;
+        assert_eq!(reply.payload["prompts"], serde_json::json!([]));
+
+        let reply = queue_request(
+            &state,
+            &Access::Session,
+            "agent:queue:list",
+            serde_json::json!({"session_id": Uuid::new_v4()}),
+        )
+        .await;
+        assert_eq!(reply.msg_type, "error");
+        assert_eq!(reply.error.unwrap().code, "NOT_FOUND");
+    }
+
+    #[tokio::test]
+    async fn read_only_token_cannot_change_the_queue() {
+        let (_dir, state, session_id) = state_with_session().await;
+        let read_only = Access::ApiToken(crate::auth::ApiToken {
+            id: Uuid::new_v4(),
+            name: "viewer".to_string(),
+            scopes: vec![TokenScope::ReadOnly],
+            workspace_ids: Vec::new(),
+            created_at: chrono::Utc::now(),
+            expires_at: None,
+        });
+
+        let reply = queue_request(
+            &state,
+            &read_only,
+            "agent:queue:add",
+            serde_json::json!({"session_id": session_id, "text": "rm -rf"}),
+        )
+        .await;
+        assert_eq!(reply.msg_type, "error");
+        assert_eq!(reply.error.unwrap().code, "INSUFFICIENT_SCOPE");
+        assert!(state.agent_manager.prompt_queue(&session_id).is_empty());
+
+        let reply = queue_request(
+            &state,
+            &read_only,
+            "agent:queue:list",
+            serde_json::json!({"session_id": session_id}),
+        )
+        .await;
+        assert_eq!(reply.msg_type, "agent:queue");
+    }
 }
@@ -0,0 +1,131 @@
+//! Per-session input locks for terminal sockets. A client holding a
+//! session's lock is the only one whose keystrokes and resizes reach the
+//! PTY, so two people watching the same agent don't type over each other.
+//!
+//! Locks only gate `/api/v1/term` sockets; REST input and the prompt queue
+//! still write to the session.
+
+use std::collections::HashMap;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+/// The lock of `session_id` is now held by `holder`, or released.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InputLockEvent {
+    pub session_id: Uuid,
+    pub holder: Option<Uuid>,
+}
+
+pub struct TermInputLocks {
+    /// Session id to the terminal client id holding its lock.
+    holders: std::sync::Mutex<HashMap<Uuid, Uuid>>,
+    events: broadcast::Sender<InputLockEvent>,
+}
+
+impl Default for TermInputLocks {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TermInputLocks {
+    pub fn new() -> Self {
+        let (events, _) = broadcast::channel(64);
+        Self {
+            holders: std::sync::Mutex::new(HashMap::new()),
+            events,
+        }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<InputLockEvent> {
+        self.events.subscribe()
+    }
+
+    pub fn holder(&self, session_id: &Uuid) -> Option<Uuid> {
+        self.lock_holders().get(session_id).copied()
+    }
+
+    /// Whether `client_id` may write to the session: nobody holds the lock
+    /// or it does.
+    pub fn may_write(&self, session_id: &Uuid, client_id: &Uuid) -> bool {
+        self.holder(session_id)
+            .is_none_or(|holder| holder == *client_id)
+    }
+
+    /// Gives the lock to `client_id`, or returns the client holding it.
+    pub fn acquire(&self, session_id: Uuid, client_id: Uuid) -> Result<(), Uuid> {
+        let mut holders = self.lock_holders();
+        match holders.get(&session_id) {
+            Some(holder) if *holder == client_id => return Ok(()),
+            Some(holder) => return Err(*holder),
+            None => {}
+        }
+        holders.insert(session_id, client_id);
+        let _ = self.events.send(InputLockEvent {
+            session_id,
+            holder: Some(client_id),
+        });
+        Ok(())
+    }
+
+    /// Releases the lock if `client_id` holds it; returns whether it did.
+    pub fn release(&self, session_id: Uuid, client_id: Uuid) -> bool {
+        let mut holders = self.lock_holders();
+        if holders.get(&session_id) != Some(&client_id) {
+            return false;
+        }
+        holders.remove(&session_id);
+        let _ = self.events.send(InputLockEvent {
+            session_id,
+            holder: None,
+        });
+        true
+    }
+
+    fn lock_holders(&self) -> std::sync::MutexGuard<'_, HashMap<Uuid, Uuid>> {
+        self.holders.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn one_client_holds_the_lock_at_a_time() {
+        let locks = TermInputLocks::new();
+        let mut events = locks.subscribe();
+        let (session, alice, bob) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        assert!(locks.may_write(&session, &alice));
+        assert!(locks.may_write(&session, &bob));
+
+        locks.acquire(session, alice).unwrap();
+        assert_eq!(locks.acquire(session, bob), Err(alice));
+        // Re-acquiring is a no-op and isn't broadcast again.
+        locks.acquire(session, alice).unwrap();
+        assert!(locks.may_write(&session, &alice));
+        assert!(!locks.may_write(&session, &bob));
+        // Other sessions are unaffected.
+        assert!(locks.may_write(&Uuid::new_v4(), &bob));
+
+        assert!(!locks.release(session, bob));
+        assert!(locks.release(session, alice));
+        assert!(locks.may_write(&session, &bob));
+
+        assert_eq!(
+            events.try_recv().unwrap(),
+            InputLockEvent {
+                session_id: session,
+                holder: Some(alice),
+            }
+        );
+        assert_eq!(
+            events.try_recv().unwrap(),
+            InputLockEvent {
+                session_id: session,
+                holder: None,
+            }
+        );
+        assert!(events.try_recv().is_err());
+    }
+}
@@ -153,6 +153,59 @@ impl WsEnvelope {
         )
     }
 
+    pub fn agent_queue(session_id: Uuid, prompts: serde_json::Value) -> Self {
+        Self::new(
+            "agent:queue",
+            serde_json::json!({
+                "session_id": session_id.to_string(),
+                "prompts": prompts,
+            }),
+        )
+    }
+
+    pub fn workspace_config(event: serde_json::Value) -> Self {
+        Self::new("workspace:config", event)
+    }
+
+    pub fn notification(notification: serde_json::Value) -> Self {
+        Self::new("notification", notification)
+    }
+
+    pub fn task_started(task: serde_json::Value) -> Self {
+        Self::new("task:started", task)
+    }
+
+    pub fn task_output(task_id: Uuid, stream: &str, data: &str) -> Self {
+        Self::new(
+            "task:output",
+            serde_json::json!({
+                "task_id": task_id.to_string(),
+                "stream": stream,
+                "data": data,
+            }),
+        )
+    }
+
+    pub fn task_finished(task: serde_json::Value) -> Self {
+        Self::new("task:finished", task)
+    }
+
+    pub fn task_run_started(run: serde_json::Value) -> Self {
+        Self::new("task_run:started", run)
+    }
+
+    pub fn task_run_finished(run: serde_json::Value) -> Self {
+        Self::new("task_run:finished", run)
+    }
+
+    pub fn port_opened(port: serde_json::Value) -> Self {
+        Self::new("port:opened", port)
+    }
+
+    pub fn port_closed(port: serde_json::Value) -> Self {
+        Self::new("port:closed", port)
+    }
+
     pub fn git_status(workspace_id: Uuid, response: serde_json::Value) -> Self {
         Self::new(
             "git:status",
@@ -288,6 +341,13 @@ mod tests {
         assert_eq!(env.msg_type, "agent:activity");
     }
 
+    #[test]
+    fn notification() {
+        let env = WsEnvelope::notification(serde_json::json!({"event": "exited"}));
+        assert_eq!(env.msg_type, "notification");
+        assert_eq!(env.payload["event"], "exited");
+    }
+
     #[test]
     fn daemon_alive() {
         let env = WsEnvelope::daemon_alive();
@@ -330,4 +390,65 @@ mod tests {
         assert_eq!(env.payload["files"], response["files"]);
         assert_eq!(env.payload["ignored_dirs"], response["ignored_dirs"]);
     }
+
+    #[test]
+    fn task_messages() {
+        let id = Uuid::new_v4();
+        let env = WsEnvelope::task_output(id, "stderr", "warning\n");
+        assert_eq!(env.msg_type, "task:output");
+        assert_eq!(env.payload["task_id"], id.to_string());
+        assert_eq!(env.payload["stream"], "stderr");
+        assert_eq!(env.payload["data"], "warning\n");
+
+        let task = serde_json::json!({"task_id": id.to_string(), "status": "running"});
+        assert_eq!(
+            WsEnvelope::task_started(task.clone()).msg_type,
+            "task:started"
+        );
+        let env = WsEnvelope::task_finished(task.clone());
+        assert_eq!(env.msg_type, "task:finished");
+        assert_eq!(env.payload, task);
+    }
+
+    #[test]
+    fn task_run_messages() {
+        let run = serde_json::json!({"run_id": "r", "name": "test", "status": "failed"});
+        assert_eq!(
+            WsEnvelope::task_run_started(run.clone()).msg_type,
+            "task_run:started"
+        );
+        let env = WsEnvelope::task_run_finished(run.clone());
+        assert_eq!(env.msg_type, "task_run:finished");
+        assert_eq!(env.payload, run);
+    }
+
+    #[test]
+    fn port_messages() {
+        let port = serde_json::json!({"port": 5173, "address": "127.0.0.1"});
+        assert_eq!(
+            WsEnvelope::port_opened(port.clone()).msg_type,
+            "port:opened"
+        );
+        let env = WsEnvelope::port_closed(port.clone());
+        assert_eq!(env.msg_type, "port:closed");
+        assert_eq!(env.payload, port);
+    }
+
+    #[test]
+    fn agent_queue_message() {
+        let id = Uuid::new_v4();
+        let prompts = serde_json::json!([{"text": "next", "status": "pending"}]);
+        let env = WsEnvelope::agent_queue(id, prompts.clone());
+        assert_eq!(env.msg_type, "agent:queue");
+        assert_eq!(env.payload["session_id"], id.to_string());
+        assert_eq!(env.payload["prompts"], prompts);
+    }
+
+    #[test]
+    fn workspace_config_message() {
+        let event = serde_json::json!({"workspace_id": "w", "config": {"setup": ["make"]}});
+        let env = WsEnvelope::workspace_config(event.clone());
+        assert_eq!(env.msg_type, "workspace:config");
+        assert_eq!(env.payload, event);
+    }
 }
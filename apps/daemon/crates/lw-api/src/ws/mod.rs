@@ -1,3 +1,4 @@
 pub mod handler;
+pub mod input_lock;
 pub mod messages;
 pub mod terminal;
@@ -2,28 +2,105 @@ use axum::extract::ws::{Message, WebSocket};
 use axum::extract::{Path, Query, State, WebSocketUpgrade};
 use axum::http::StatusCode;
 use axum::response::Response;
+use lw_agent::terminal_text::TerminalTextStream;
 use serde::Deserialize;
 use uuid::Uuid;
 
+use crate::auth::TokenScope;
 use crate::state::AppState;
 
 const TERM_WIRE_VERSION: u8 = 1;
 const TERM_FRAME_HISTORY: u8 = 1;
 const TERM_FRAME_LIVE: u8 = 2;
+const TERM_FRAME_SNAPSHOT: u8 = 3;
 const TERM_INPUT_BYTES_OPCODE: u8 = 1;
+/// How much of the output history a text-mode socket starts with.
+const TEXT_REPLAY_MAX_BYTES: usize = 256 * 1024;
+/// How much of a finished task run's log a raw socket is sent.
+const RUN_LOG_REPLAY_MAX_BYTES: usize = 8 * 1024 * 1024;
+
+/// Which manager owns the PTY behind a terminal socket. Shell sessions skip
+/// the agent activity recorder and resume logic; task runs are never
+/// re-spawned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TermSessionKind {
+    Agent,
+    Shell,
+    TaskRun,
+}
+
+/// What a newly attached client is sent before live output.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TermReplay {
+    /// The raw output history, in chunks.
+    #[default]
+    History,
+    /// One frame redrawing the current screen; older output is left to the
+    /// scrollback endpoint.
+    Screen,
+}
+
+/// How output is delivered.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TermFormat {
+    /// Raw PTY bytes in binary frames.
+    #[default]
+    Raw,
+    /// Normalized lines in `text` messages, with escape sequences stripped
+    /// and spinner redraws filtered. Always replays the tail of the history,
+    /// whatever `replay` says.
+    Text,
+}
+
+/// Whether a socket may drive the PTY.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TermMode {
+    /// Input and resizes, subject to the session's input lock.
+    #[default]
+    Interactive,
+    /// Output only. Needs just the read-only scope and never starts or
+    /// resumes the session's process.
+    Spectator,
+}
+
+impl TermMode {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Interactive => "interactive",
+            Self::Spectator => "spectator",
+        }
+    }
+}
 
 #[derive(Debug, Deserialize)]
 pub struct TermWsQuery {
     pub token: Option<String>,
     pub cols: Option<u16>,
     pub rows: Option<u16>,
+    #[serde(default)]
+    pub replay: TermReplay,
+    #[serde(default)]
+    pub format: TermFormat,
+    #[serde(default)]
+    pub mode: TermMode,
 }
 
 #[derive(Debug, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 enum TermClientCommand {
-    Resize { cols: u16, rows: u16 },
-    InputUtf8 { data: String },
+    Resize {
+        cols: u16,
+        rows: u16,
+    },
+    InputUtf8 {
+        data: String,
+    },
+    /// Become the session's only writer until unlocked or disconnected.
+    LockInput,
+    UnlockInput,
 }
 
 pub async fn term_ws_upgrade(
@@ -33,17 +110,61 @@ pub async fn term_ws_upgrade(
     ws: WebSocketUpgrade,
 ) -> Result<Response, StatusCode> {
     let token = query.token.ok_or(StatusCode::UNAUTHORIZED)?;
-    if !state.token_store.validate_session(&token).await {
-        return Err(StatusCode::UNAUTHORIZED);
+    let access = state
+        .token_store
+        .authenticate(&token)
+        .await
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+    // Terminals take input, so API tokens need the agents scope unless
+    // they only watch.
+    let scope = match query.mode {
+        TermMode::Interactive => TokenScope::Agents,
+        TermMode::Spectator => TokenScope::ReadOnly,
+    };
+    if !access.allows(scope) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    if access.is_workspace_restricted() {
+        let workspace = crate::preview::session_workspace_id(&state, session_id).await;
+        if !workspace.is_some_and(|id| access.allows_workspace(&id)) {
+            return Err(StatusCode::FORBIDDEN);
+        }
     }
 
-    let session = state
-        .agent_manager
-        .ensure_pty_attached(&session_id)
-        .await
-        .map_err(|_| StatusCode::NOT_FOUND)?;
+    let kind = if state.shell_manager.is_shell_session(&session_id).await {
+        TermSessionKind::Shell
+    } else if state.workspace_task_manager.is_task_run(&session_id).await {
+        TermSessionKind::TaskRun
+    } else {
+        TermSessionKind::Agent
+    };
+    let session = match (query.mode, kind) {
+        (TermMode::Spectator, _) => state.pty_manager.get(&session_id).await.map_err(Into::into),
+        (_, TermSessionKind::Agent) => state.agent_manager.ensure_pty_attached(&session_id).await,
+        (_, TermSessionKind::Shell) => state.shell_manager.ensure_pty_attached(&session_id).await,
+        (_, TermSessionKind::TaskRun) => {
+            state.workspace_task_manager.pty_session(&session_id).await
+        }
+    };
+    let session = match session {
+        Ok(session) => session,
+        Err(_) if kind == TermSessionKind::TaskRun => {
+            // A finished run has no PTY left, only its output log.
+            let run = state
+                .workspace_task_manager
+                .get(&session_id)
+                .await
+                .ok_or(StatusCode::NOT_FOUND)?;
+            let format = query.format;
+            return Ok(ws.on_upgrade(move |socket| replay_finished_run(socket, state, run, format)));
+        }
+        Err(_) => return Err(StatusCode::NOT_FOUND),
+    };
 
-    if let (Some(cols), Some(rows)) = (query.cols, query.rows) {
+    // Joining doesn't resize the PTY under a spectator or lock holder.
+    let may_resize =
+        query.mode == TermMode::Interactive && state.term_input_locks.holder(&session_id).is_none();
+    if let (true, Some(cols), Some(rows)) = (may_resize, query.cols, query.rows) {
         if cols > 0 && rows > 0 {
             if let Err(err) = session.resize(cols, rows).await {
                 tracing::warn!(
@@ -57,16 +178,46 @@ pub async fn term_ws_upgrade(
         }
     }
 
-    Ok(ws.on_upgrade(move |socket| handle_terminal_socket(socket, state, session_id, session)))
+    let options = TermSocketOptions {
+        replay: query.replay,
+        format: query.format,
+        mode: query.mode,
+    };
+    Ok(ws.on_upgrade(move |socket| {
+        handle_terminal_socket(socket, state, session_id, kind, session, options)
+    }))
+}
+
+#[derive(Debug, Clone, Copy)]
+struct TermSocketOptions {
+    replay: TermReplay,
+    format: TermFormat,
+    mode: TermMode,
 }
 
 async fn handle_terminal_socket(
     mut socket: WebSocket,
     state: AppState,
     session_id: Uuid,
+    kind: TermSessionKind,
     session: std::sync::Arc<lw_pty::PtySession>,
+    options: TermSocketOptions,
 ) {
-    let mut output_rx = session.subscribe();
+    let TermSocketOptions {
+        replay,
+        format,
+        mode,
+    } = options;
+    // Identifies this socket as an input lock holder.
+    let client_id = Uuid::new_v4();
+    let mut lock_rx = state.term_input_locks.subscribe();
+    let (snapshot, mut output_rx) = match (format, replay) {
+        (TermFormat::Text, _) | (_, TermReplay::History) => (None, session.subscribe()),
+        (TermFormat::Raw, TermReplay::Screen) => {
+            let (snapshot, output_rx) = session.subscribe_with_screen();
+            (Some(snapshot), output_rx)
+        }
+    };
     let mut exit_rx = session.subscribe_exit();
     let mut seq: u64 = 0;
 
@@ -75,6 +226,8 @@ async fn handle_terminal_socket(
         serde_json::json!({
             "type": "ready",
             "session_id": session_id.to_string(),
+            "client_id": client_id.to_string(),
+            "mode": mode.as_str(),
         }),
     )
     .await
@@ -82,12 +235,40 @@ async fn handle_terminal_socket(
     {
         return;
     }
+    let holder = state.term_input_locks.holder(&session_id);
+    if send_input_lock(&mut socket, session_id, holder)
+        .await
+        .is_err()
+    {
+        return;
+    }
 
-    let chunks = session.output_snapshot_chunked(64 * 1024);
-    for chunk in &chunks {
+    let mut text_stream = (format == TermFormat::Text).then(TerminalTextStream::new);
+    let mut last_partial = String::new();
+    let frames = match (&mut text_stream, snapshot) {
+        (Some(stream), _) => {
+            let (history, ..) = session.output_slice_before(None, TEXT_REPLAY_MAX_BYTES);
+            let text = stream.push(&history);
+            last_partial = stream.partial_line();
+            if send_text_output(&mut socket, "history", &text, &last_partial)
+                .await
+                .is_err()
+            {
+                return;
+            }
+            Vec::new()
+        }
+        (None, Some(snapshot)) => vec![(TERM_FRAME_SNAPSHOT, snapshot)],
+        (None, None) => session
+            .output_snapshot_chunked(64 * 1024)
+            .into_iter()
+            .map(|chunk| (TERM_FRAME_HISTORY, chunk))
+            .collect(),
+    };
+    for (frame_kind, payload) in &frames {
         if send_binary_frame(
             &mut socket,
-            encode_binary_frame(session_id, TERM_FRAME_HISTORY, seq, chunk),
+            encode_binary_frame(session_id, *frame_kind, seq, payload),
         )
         .await
         .is_err()
@@ -97,6 +278,21 @@ async fn handle_terminal_socket(
         seq = seq.saturating_add(1);
     }
 
+    // A run that finished while this socket was being set up: its exit
+    // was announced before the socket subscribed.
+    if let (TermSessionKind::TaskRun, Some(exit_code)) = (kind, session.exit_code()) {
+        let _ = send_json(
+            &mut socket,
+            serde_json::json!({
+                "type": "exit",
+                "session_id": session_id.to_string(),
+                "exit_code": exit_code,
+            }),
+        )
+        .await;
+        return;
+    }
+
     loop {
         tokio::select! {
             incoming = socket.recv() => {
@@ -105,7 +301,11 @@ async fn handle_terminal_socket(
                         let cmd = serde_json::from_str::<TermClientCommand>(&text);
                         match cmd {
                             Ok(TermClientCommand::Resize { cols, rows }) => {
-                                if cols > 0 && rows > 0 {
+                                if let Err((code, message)) = check_writable(&state, session_id, client_id, mode) {
+                                    if send_protocol_error(&mut socket, code, message, false).await.is_err() {
+                                        break;
+                                    }
+                                } else if cols > 0 && rows > 0 {
                                     if let Err(err) = session.resize(cols, rows).await {
                                         if send_protocol_error(
                                             &mut socket,
@@ -122,10 +322,27 @@ async fn handle_terminal_socket(
                                 }
                             }
                             Ok(TermClientCommand::InputUtf8 { data }) => {
-                                if write_input_bytes(&state, session_id, data.as_bytes(), &mut socket).await.is_err() {
+                                if write_input_bytes(&state, session_id, client_id, mode, kind, data.as_bytes(), &mut socket).await.is_err() {
                                     break;
                                 }
                             }
+                            Ok(TermClientCommand::LockInput) => {
+                                let result = match mode {
+                                    TermMode::Spectator => Err(("READ_ONLY", "spectators can't take the input lock")),
+                                    TermMode::Interactive => state
+                                        .term_input_locks
+                                        .acquire(session_id, client_id)
+                                        .map_err(|_| ("INPUT_LOCKED", "another client holds the input lock")),
+                                };
+                                if let Err((code, message)) = result {
+                                    if send_protocol_error(&mut socket, code, message, false).await.is_err() {
+                                        break;
+                                    }
+                                }
+                            }
+                            Ok(TermClientCommand::UnlockInput) => {
+                                state.term_input_locks.release(session_id, client_id);
+                            }
                             Err(err) => {
                                 if send_protocol_error(
                                     &mut socket,
@@ -159,7 +376,7 @@ async fn handle_terminal_socket(
                         let opcode = bytes[0];
                         match opcode {
                             TERM_INPUT_BYTES_OPCODE => {
-                                if write_input_bytes(&state, session_id, &bytes[1..], &mut socket).await.is_err() {
+                                if write_input_bytes(&state, session_id, client_id, mode, kind, &bytes[1..], &mut socket).await.is_err() {
                                     break;
                                 }
                             }
@@ -186,16 +403,26 @@ async fn handle_terminal_socket(
             output = output_rx.recv() => {
                 match output {
                     Ok(data) => {
-                        if send_binary_frame(
-                            &mut socket,
-                            encode_binary_frame(session_id, TERM_FRAME_LIVE, seq, &data),
-                        )
-                        .await
-                        .is_err()
-                        {
+                        let sent = match &mut text_stream {
+                            Some(stream) => {
+                                let text = stream.push(&data);
+                                let partial = stream.partial_line();
+                                if text.is_empty() && partial == last_partial {
+                                    Ok(())
+                                } else {
+                                    last_partial = partial;
+                                    send_text_output(&mut socket, "live", &text, &last_partial).await
+                                }
+                            }
+                            None => {
+                                let frame = encode_binary_frame(session_id, TERM_FRAME_LIVE, seq, &data);
+                                seq = seq.saturating_add(1);
+                                send_binary_frame(&mut socket, frame).await
+                            }
+                        };
+                        if sent.is_err() {
                             break;
                         }
-                        seq = seq.saturating_add(1);
                     }
                     Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
                         tracing::warn!(
@@ -222,6 +449,20 @@ async fn handle_terminal_socket(
                     }
                 }
             }
+            lock = lock_rx.recv() => {
+                let holder = match lock {
+                    Ok(event) if event.session_id == session_id => event.holder,
+                    Ok(_) => continue,
+                    // Missed events: resend the current state instead.
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => {
+                        state.term_input_locks.holder(&session_id)
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => continue,
+                };
+                if send_input_lock(&mut socket, session_id, holder).await.is_err() {
+                    break;
+                }
+            }
             exit = exit_rx.recv() => {
                 match exit {
                     Ok(exit_code) => {
@@ -250,15 +491,52 @@ async fn handle_terminal_socket(
             }
         }
     }
+
+    state.term_input_locks.release(session_id, client_id);
+}
+
+/// Why this socket may not write to the PTY right now, as an error code
+/// and message.
+fn check_writable(
+    state: &AppState,
+    session_id: Uuid,
+    client_id: Uuid,
+    mode: TermMode,
+) -> Result<(), (&'static str, &'static str)> {
+    if mode == TermMode::Spectator {
+        return Err(("READ_ONLY", "spectators can't send input or resize"));
+    }
+    if !state.term_input_locks.may_write(&session_id, &client_id) {
+        return Err(("INPUT_LOCKED", "another client holds the input lock"));
+    }
+    Ok(())
 }
 
 async fn write_input_bytes(
     state: &AppState,
     session_id: Uuid,
+    client_id: Uuid,
+    mode: TermMode,
+    kind: TermSessionKind,
     bytes: &[u8],
     socket: &mut WebSocket,
 ) -> Result<(), ()> {
-    match state.agent_manager.input_session(&session_id, bytes).await {
+    if let Err((code, message)) = check_writable(state, session_id, client_id, mode) {
+        return send_protocol_error(socket, code, message, false)
+            .await
+            .map_err(|_| ());
+    }
+    let result = match kind {
+        TermSessionKind::Agent => state.agent_manager.input_session(&session_id, bytes).await,
+        TermSessionKind::Shell => state.shell_manager.input_session(&session_id, bytes).await,
+        TermSessionKind::TaskRun => {
+            state
+                .workspace_task_manager
+                .input_run(&session_id, bytes)
+                .await
+        }
+    };
+    match result {
         Ok(()) => Ok(()),
         Err(err) => {
             let message = err.to_string();
@@ -292,6 +570,109 @@ async fn send_protocol_error(
     .await
 }
 
+/// `holder` is the client id of the socket holding the input lock; clients
+/// compare it to the `client_id` they were sent in `ready`.
+async fn send_input_lock(
+    socket: &mut WebSocket,
+    session_id: Uuid,
+    holder: Option<Uuid>,
+) -> Result<(), axum::Error> {
+    send_json(
+        socket,
+        serde_json::json!({
+            "type": "input_lock",
+            "session_id": session_id.to_string(),
+            "locked": holder.is_some(),
+            "holder": holder.map(|holder| holder.to_string()),
+        }),
+    )
+    .await
+}
+
+/// Sends a finished task run's output from its log, then its exit, and
+/// closes.
+async fn replay_finished_run(
+    mut socket: WebSocket,
+    state: AppState,
+    run: lw_agent::TaskRun,
+    format: TermFormat,
+) {
+    let session_id = run.run_id;
+    let ready = serde_json::json!({
+        "type": "ready",
+        "session_id": session_id.to_string(),
+        "client_id": Uuid::new_v4().to_string(),
+        "mode": TermMode::Spectator.as_str(),
+    });
+    if send_json(&mut socket, ready).await.is_err()
+        || send_input_lock(&mut socket, session_id, None)
+            .await
+            .is_err()
+    {
+        return;
+    }
+    let max_bytes = match format {
+        TermFormat::Text => TEXT_REPLAY_MAX_BYTES,
+        TermFormat::Raw => RUN_LOG_REPLAY_MAX_BYTES,
+    };
+    let history = state
+        .pty_manager
+        .output_slice_before(&session_id, None, max_bytes)
+        .await
+        .map(|(data, ..)| data)
+        .unwrap_or_default();
+    let sent = match format {
+        TermFormat::Text => {
+            let mut stream = TerminalTextStream::new();
+            let text = stream.push(&history);
+            send_text_output(&mut socket, "history", &text, &stream.partial_line()).await
+        }
+        TermFormat::Raw => {
+            let mut sent = Ok(());
+            for (seq, chunk) in (0u64..).zip(history.chunks(64 * 1024)) {
+                let frame = encode_binary_frame(session_id, TERM_FRAME_HISTORY, seq, chunk);
+                sent = send_binary_frame(&mut socket, frame).await;
+                if sent.is_err() {
+                    break;
+                }
+            }
+            sent
+        }
+    };
+    if sent.is_err() {
+        return;
+    }
+    let _ = send_json(
+        &mut socket,
+        serde_json::json!({
+            "type": "exit",
+            "session_id": session_id.to_string(),
+            "exit_code": run.exit_code,
+        }),
+    )
+    .await;
+}
+
+/// `text` holds completed lines; `partial` the line still being written,
+/// which replaces the previous `partial` rather than appending to it.
+async fn send_text_output(
+    socket: &mut WebSocket,
+    kind: &str,
+    text: &str,
+    partial: &str,
+) -> Result<(), axum::Error> {
+    send_json(
+        socket,
+        serde_json::json!({
+            "type": "text",
+            "kind": kind,
+            "data": text,
+            "partial": partial,
+        }),
+    )
+    .await
+}
+
 async fn send_json(socket: &mut WebSocket, payload: serde_json::Value) -> Result<(), axum::Error> {
     socket.send(Message::Text(payload.to_string().into())).await
 }
@@ -346,7 +727,11 @@ mod tests {
     #[test]
     fn encode_binary_frame_kinds() {
         let id = Uuid::nil();
-        for (kind, expected) in [(TERM_FRAME_HISTORY, 1u8), (TERM_FRAME_LIVE, 2u8)] {
+        for (kind, expected) in [
+            (TERM_FRAME_HISTORY, 1u8),
+            (TERM_FRAME_LIVE, 2u8),
+            (TERM_FRAME_SNAPSHOT, 3u8),
+        ] {
             let frame = encode_binary_frame(id, kind, 0, b"");
             assert_eq!(frame[1], expected);
         }
@@ -430,6 +815,42 @@ mod tests {
         }
     }
 
+    #[test]
+    fn term_client_command_input_lock_deserializes() {
+        let lock: TermClientCommand = serde_json::from_str(r#"{"type": "lock_input"}"#).unwrap();
+        assert!(matches!(lock, TermClientCommand::LockInput));
+        let unlock: TermClientCommand =
+            serde_json::from_str(r#"{"type": "unlock_input"}"#).unwrap();
+        assert!(matches!(unlock, TermClientCommand::UnlockInput));
+    }
+
+    #[tokio::test]
+    async fn check_writable_honours_mode_and_lock() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = lw_config::DaemonConfig::default();
+        config.set_paths(lw_config::ConfigPaths::with_base(dir.path().to_path_buf()));
+        let hash = crate::auth::TokenStore::hash_token("test");
+        let state = AppState::new(config, hash).unwrap();
+        let (session, holder, other) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+
+        assert!(check_writable(&state, session, other, TermMode::Interactive).is_ok());
+        assert_eq!(
+            check_writable(&state, session, other, TermMode::Spectator)
+                .unwrap_err()
+                .0,
+            "READ_ONLY"
+        );
+
+        state.term_input_locks.acquire(session, holder).unwrap();
+        assert!(check_writable(&state, session, holder, TermMode::Interactive).is_ok());
+        assert_eq!(
+            check_writable(&state, session, other, TermMode::Interactive)
+                .unwrap_err()
+                .0,
+            "INPUT_LOCKED"
+        );
+    }
+
     // ── TermWsQuery deserialization ────────────────────────────────────
 
     #[test]
@@ -439,6 +860,33 @@ mod tests {
         assert_eq!(query.token, Some("mytoken".to_string()));
         assert_eq!(query.cols, Some(120));
         assert_eq!(query.rows, Some(40));
+        assert_eq!(query.replay, TermReplay::History);
+        assert_eq!(query.format, TermFormat::Raw);
+        assert_eq!(query.mode, TermMode::Interactive);
+    }
+
+    #[test]
+    fn term_ws_query_spectator_mode() {
+        let json = r#"{"token": "mytoken", "mode": "spectator"}"#;
+        let query: TermWsQuery = serde_json::from_str(json).unwrap();
+        assert_eq!(query.mode, TermMode::Spectator);
+        assert!(serde_json::from_str::<TermWsQuery>(r#"{"mode": "admin"}"#).is_err());
+    }
+
+    #[test]
+    fn term_ws_query_text_format() {
+        let json = r#"{"token": "mytoken", "format": "text"}"#;
+        let query: TermWsQuery = serde_json::from_str(json).unwrap();
+        assert_eq!(query.format, TermFormat::Text);
+        assert!(serde_json::from_str::<TermWsQuery>(r#"{"format": "html"}"#).is_err());
+    }
+
+    #[test]
+    fn term_ws_query_screen_replay() {
+        let json = r#"{"token": "mytoken", "replay": "screen"}"#;
+        let query: TermWsQuery = serde_json::from_str(json).unwrap();
+        assert_eq!(query.replay, TermReplay::Screen);
+        assert!(serde_json::from_str::<TermWsQuery>(r#"{"replay": "frames"}"#).is_err());
     }
 
     #[test]
@@ -0,0 +1,199 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// Placeholder substituted with the workspace path in `args`.
+pub const WORKSPACE_PLACEHOLDER: &str = "{workspace}";
+
+/// Placeholder substituted with the conversation id in `session_id_args`,
+/// `resume_args` and `fork_args`.
+pub const CONVERSATION_ID_PLACEHOLDER: &str = "{conversation_id}";
+
+/// Placeholder substituted with the forked conversation's id in `fork_args`.
+pub const NEW_CONVERSATION_ID_PLACEHOLDER: &str = "{new_conversation_id}";
+
+/// Placeholder substituted with the task prompt in `headless_args`.
+pub const PROMPT_PLACEHOLDER: &str = "{prompt}";
+
+const RESERVED_AGENT_IDS: &[&str] = &["claude_code", "codex", "gemini"];
+
+/// A user-defined agent CLI declared in `config.toml`:
+///
+/// ```toml
+/// [[agents]]
+/// id = "aider"
+/// name = "Aider"
+/// command = "aider"
+/// args = ["--no-auto-commits"]
+/// resume_args = ["--restore-chat-history"]
+/// headless_args = ["--yes", "--message", "{prompt}"]
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CustomAgentConfig {
+    /// Stable identifier, persisted with sessions as `custom:<id>`.
+    pub id: String,
+    /// Display name; defaults to `id`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Binary name or absolute path.
+    pub command: String,
+    /// Arguments for a fresh session. `{workspace}` is substituted.
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    /// Arguments used to probe the installed version.
+    #[serde(default = "default_version_args")]
+    pub version_args: Vec<String>,
+    /// Arguments prepended to `args` on a fresh session to pin the
+    /// conversation id (e.g. `["--session-id", "{conversation_id}"]`).
+    #[serde(default)]
+    pub session_id_args: Vec<String>,
+    /// Arguments used instead of `args` when restoring a session. Empty
+    /// means the agent cannot resume and restores start fresh.
+    #[serde(default)]
+    pub resume_args: Vec<String>,
+    /// Arguments that start a new conversation branched off
+    /// `{conversation_id}` under the id `{new_conversation_id}`. Empty
+    /// means sessions of the agent cannot be forked.
+    #[serde(default)]
+    pub fork_args: Vec<String>,
+    /// Arguments for a non-interactive one-shot run; `{prompt}` is
+    /// substituted. Empty means the agent cannot run headless tasks.
+    #[serde(default)]
+    pub headless_args: Vec<String>,
+}
+
+fn default_version_args() -> Vec<String> {
+    vec!["--version".to_string()]
+}
+
+impl CustomAgentConfig {
+    pub fn display_name(&self) -> &str {
+        self.name
+            .as_deref()
+            .filter(|name| !name.trim().is_empty())
+            .unwrap_or(&self.id)
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        if self.id.is_empty()
+            || !self
+                .id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        {
+            anyhow::bail!(
+                "agents.id {:?} must be non-empty and contain only ASCII letters, digits, '_' or '-'",
+                self.id
+            );
+        }
+        if RESERVED_AGENT_IDS.contains(&self.id.as_str()) {
+            anyhow::bail!("agents.id {:?} is reserved for a built-in agent", self.id);
+        }
+        if self.command.trim().is_empty() {
+            anyhow::bail!("agents.command must not be empty (agent {:?})", self.id);
+        }
+        Ok(())
+    }
+}
+
+/// Validate a list of custom agents: each entry must be valid and ids unique.
+pub(crate) fn validate_custom_agents(agents: &[CustomAgentConfig]) -> anyhow::Result<()> {
+    let mut seen = HashSet::new();
+    for agent in agents {
+        agent.validate()?;
+        if !seen.insert(agent.id.as_str()) {
+            anyhow::bail!("agents.id {:?} is declared more than once", agent.id);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Deserialize)]
+    struct Wrapper {
+        agents: Vec<CustomAgentConfig>,
+    }
+
+    fn agent(id: &str) -> CustomAgentConfig {
+        CustomAgentConfig {
+            id: id.to_string(),
+            name: None,
+            command: "tool".to_string(),
+            args: vec![],
+            env: HashMap::new(),
+            version_args: default_version_args(),
+            session_id_args: vec![],
+            resume_args: vec![],
+            fork_args: vec![],
+            headless_args: vec![],
+        }
+    }
+
+    #[test]
+    fn parses_minimal_entry_with_defaults() {
+        let parsed: Wrapper =
+            toml::from_str("[[agents]]\nid = \"aider\"\ncommand = \"aider\"\n").unwrap();
+        let agent = &parsed.agents[0];
+        assert_eq!(agent.id, "aider");
+        assert_eq!(agent.display_name(), "aider");
+        assert_eq!(agent.version_args, vec!["--version"]);
+        assert!(agent.args.is_empty());
+        assert!(agent.resume_args.is_empty());
+        assert!(agent.fork_args.is_empty());
+        assert!(agent.headless_args.is_empty());
+    }
+
+    #[test]
+    fn parses_full_entry() {
+        let parsed: Wrapper = toml::from_str(
+            r#"
+[[agents]]
+id = "opencode"
+name = "OpenCode"
+command = "/usr/local/bin/opencode"
+args = ["--cwd", "{workspace}"]
+version_args = ["version"]
+session_id_args = ["--session", "{conversation_id}"]
+resume_args = ["--continue", "{conversation_id}"]
+fork_args = ["--continue", "{conversation_id}", "--fork", "{new_conversation_id}"]
+headless_args = ["run", "{prompt}"]
+
+[agents.env]
+OPENCODE_THEME = "dark"
+"#,
+        )
+        .unwrap();
+        let agent = &parsed.agents[0];
+        assert_eq!(agent.display_name(), "OpenCode");
+        assert_eq!(agent.args, vec!["--cwd", "{workspace}"]);
+        assert_eq!(agent.version_args, vec!["version"]);
+        assert_eq!(agent.resume_args, vec!["--continue", "{conversation_id}"]);
+        assert_eq!(agent.fork_args[3], "{new_conversation_id}");
+        assert_eq!(agent.headless_args, vec!["run", "{prompt}"]);
+        assert_eq!(
+            agent.env.get("OPENCODE_THEME").map(String::as_str),
+            Some("dark")
+        );
+    }
+
+    #[test]
+    fn validate_rejects_bad_ids() {
+        assert!(validate_custom_agents(&[agent("")]).is_err());
+        assert!(validate_custom_agents(&[agent("has space")]).is_err());
+        assert!(validate_custom_agents(&[agent("a:b")]).is_err());
+        assert!(validate_custom_agents(&[agent("codex")]).is_err());
+        assert!(validate_custom_agents(&[agent("my-agent_2")]).is_ok());
+    }
+
+    #[test]
+    fn validate_rejects_duplicates_and_empty_command() {
+        assert!(validate_custom_agents(&[agent("a"), agent("a")]).is_err());
+        let mut no_command = agent("a");
+        no_command.command = " ".to_string();
+        assert!(validate_custom_agents(&[no_command]).is_err());
+    }
+}
@@ -2,10 +2,13 @@ use serde::{Deserialize, Serialize};
 use std::env;
 use std::net::{IpAddr, Ipv4Addr};
 
+use crate::agents::{validate_custom_agents, CustomAgentConfig};
 use crate::lan::LanDiscoveryConfig;
+use crate::notifications::{validate_notification_rules, NotificationRule};
 use crate::paths::ConfigPaths;
 
 use crate::remote::{default_frontend_url, RemoteConfig};
+use crate::web::WebUiConfig;
 
 fn default_host() -> IpAddr {
     IpAddr::V4(Ipv4Addr::UNSPECIFIED)
@@ -41,6 +44,14 @@ pub struct DaemonConfig {
 
     #[serde(default)]
     pub lan: LanDiscoveryConfig,
+    #[serde(default)]
+    pub web: WebUiConfig,
+    /// User-defined agent CLIs (`[[agents]]` tables).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub agents: Vec<CustomAgentConfig>,
+    /// Notification rules for agent activity (`[[notifications]]` tables).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notifications: Vec<NotificationRule>,
     #[serde(skip)]
     paths: Option<ConfigPaths>,
 }
@@ -55,6 +66,9 @@ impl Default for DaemonConfig {
             remote: RemoteConfig::default(),
 
             lan: LanDiscoveryConfig::default(),
+            web: WebUiConfig::default(),
+            agents: Vec::new(),
+            notifications: Vec::new(),
             paths: None,
         }
     }
@@ -101,7 +115,8 @@ impl DaemonConfig {
         if self.port == 0 {
             anyhow::bail!("port must not be 0");
         }
-        if self.frontend_url.is_empty() {
+        // A daemon serving its own UI needs no hosted frontend.
+        if self.frontend_url.is_empty() && !self.web.enabled {
             anyhow::bail!("frontend_url must not be empty (set LOOPWIRE_FRONTEND_URL)");
         }
         if self.remote.invite_ttl_seconds == 0 {
@@ -110,17 +125,40 @@ impl DaemonConfig {
         if self.remote.provider_order.is_empty() {
             anyhow::bail!("remote.provider_order must not be empty");
         }
-        if self.remote.frontend_connect_url.is_empty() {
+        if self.remote.frontend_connect_url.is_empty() && !self.web.enabled {
             anyhow::bail!(
                 "remote.frontend_connect_url must not be empty (set LOOPWIRE_FRONTEND_URL)"
             );
         }
+        validate_custom_agents(&self.agents)?;
+        validate_notification_rules(&self.notifications)?;
         Ok(())
     }
 
     pub fn bind_addr(&self) -> String {
         format!("{}:{}", self.host, self.port)
     }
+
+    /// Port of the separate preview listener used while the web UI is
+    /// served (`web.preview_port`, or the daemon port + 1).
+    pub fn preview_port(&self) -> u16 {
+        self.web
+            .preview_port
+            .unwrap_or_else(|| self.port.saturating_add(1))
+    }
+
+    /// `http://` URL of the daemon as seen from this machine.
+    pub fn local_url(&self) -> String {
+        let host = if self.host.is_unspecified() || self.host.is_loopback() {
+            "localhost".to_string()
+        } else {
+            match self.host {
+                IpAddr::V4(ip) => ip.to_string(),
+                IpAddr::V6(ip) => format!("[{ip}]"),
+            }
+        };
+        format!("http://{host}:{}", self.port)
+    }
 }
 
 #[cfg(test)]
@@ -138,6 +176,7 @@ mod tests {
         let config = DaemonConfig::default();
         assert_eq!(config.host, IpAddr::V4(Ipv4Addr::UNSPECIFIED));
         assert_eq!(config.port, 9400);
+        assert_eq!(config.preview_port(), 9401);
         assert_eq!(config.frontend_url, default_frontend_url());
         assert!(config.lan.enabled);
     }
@@ -218,6 +257,53 @@ mod tests {
         assert!(config.validate().is_err());
     }
 
+    #[test]
+    fn load_with_custom_agents() {
+        let paths = test_paths();
+        paths.ensure_config_dir().unwrap();
+        std::fs::write(
+            paths.config_path(),
+            "frontend_url = \"http://example.com\"\n[remote]\nfrontend_connect_url = \"http://example.com/connect\"\n\n[[agents]]\nid = \"aider\"\ncommand = \"aider\"\n",
+        )
+        .unwrap();
+        let config = DaemonConfig::load_from(&paths).unwrap();
+        assert_eq!(config.agents.len(), 1);
+        assert_eq!(config.agents[0].id, "aider");
+    }
+
+    #[test]
+    fn load_with_notification_rules() {
+        let paths = test_paths();
+        paths.ensure_config_dir().unwrap();
+        let base = "frontend_url = \"http://example.com\"\n[remote]\nfrontend_connect_url = \"http://example.com/connect\"\n\n[[notifications]]\nsink = \"webhook\"\nurl = \"https://hooks.example.com\"\n";
+        std::fs::write(paths.config_path(), base).unwrap();
+        let config = DaemonConfig::load_from(&paths).unwrap();
+        assert_eq!(config.notifications.len(), 1);
+
+        // A command rule without a command is rejected.
+        std::fs::write(
+            paths.config_path(),
+            format!("{base}\n[[notifications]]\nsink = \"command\"\n"),
+        )
+        .unwrap();
+        assert!(DaemonConfig::load_from(&paths).is_err());
+    }
+
+    #[test]
+    fn validate_rejects_duplicate_custom_agents() {
+        let mut config = DaemonConfig {
+            frontend_url: "http://example.com".to_string(),
+            ..DaemonConfig::default()
+        };
+        config.remote.frontend_connect_url = "http://example.com/connect".to_string();
+        let agent: CustomAgentConfig =
+            toml::from_str("id = \"aider\"\ncommand = \"aider\"\n").unwrap();
+        config.agents = vec![agent.clone()];
+        assert!(config.validate().is_ok());
+        config.agents.push(agent);
+        assert!(config.validate().is_err());
+    }
+
     #[test]
     fn validate_rejects_zero_invite_ttl() {
         let mut config = DaemonConfig::default();
@@ -239,6 +325,28 @@ mod tests {
         assert!(config.validate().is_err());
     }
 
+    #[test]
+    fn validate_allows_no_frontend_when_serving_web_ui() {
+        let mut config = DaemonConfig {
+            frontend_url: String::new(),
+            ..DaemonConfig::default()
+        };
+        config.remote.frontend_connect_url = String::new();
+        config.web.enabled = true;
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn local_url_uses_localhost_for_wildcard_binds() {
+        let mut config = DaemonConfig {
+            port: 9400,
+            ..DaemonConfig::default()
+        };
+        assert_eq!(config.local_url(), "http://localhost:9400");
+        config.host = IpAddr::V4(Ipv4Addr::new(192, 168, 1, 5));
+        assert_eq!(config.local_url(), "http://192.168.1.5:9400");
+    }
+
     #[test]
     fn toml_roundtrip() {
         let config = DaemonConfig::default();
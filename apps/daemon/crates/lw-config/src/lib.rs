@@ -1,11 +1,19 @@
+pub mod agents;
 pub mod daemon;
 pub mod lan;
+pub mod notifications;
 pub mod paths;
+pub mod project;
 
 pub mod remote;
+pub mod web;
 
+pub use agents::CustomAgentConfig;
 pub use daemon::DaemonConfig;
 pub use lan::LanDiscoveryConfig;
+pub use notifications::{NotificationEvent, NotificationRule, NotificationSinkKind};
 pub use paths::ConfigPaths;
+pub use project::{ProjectAgentConfig, ProjectConfig, ProjectTask, PROJECT_CONFIG_FILE};
 
 pub use remote::RemoteConfig;
+pub use web::WebUiConfig;
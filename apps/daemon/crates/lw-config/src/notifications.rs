@@ -0,0 +1,205 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Activity transition a notification rule can subscribe to.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationEvent {
+    /// The agent finished its turn and waits for a prompt.
+    AwaitingUser,
+    /// The agent is blocked on a tool-approval dialog.
+    AwaitingApproval,
+    /// The agent process exited.
+    Exited,
+}
+
+impl NotificationEvent {
+    pub const ALL: [NotificationEvent; 3] = [
+        NotificationEvent::AwaitingUser,
+        NotificationEvent::AwaitingApproval,
+        NotificationEvent::Exited,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            NotificationEvent::AwaitingUser => "awaiting_user",
+            NotificationEvent::AwaitingApproval => "awaiting_approval",
+            NotificationEvent::Exited => "exited",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationSinkKind {
+    /// POST the JSON payload to `url`, signed with `secret` when set.
+    Webhook,
+    /// Run `command` with the JSON payload on stdin.
+    Command,
+    /// Send a `notification` message to connected WebSocket clients.
+    Websocket,
+}
+
+/// A notification rule declared in `config.toml`:
+///
+/// ```toml
+/// [[notifications]]
+/// sink = "webhook"
+/// url = "https://hooks.example.com/loopwire"
+/// secret = "change-me"
+/// events = ["awaiting_user", "exited"]
+/// workspaces = ["/home/me/project"]
+/// debounce_ms = 5000
+///
+/// [[notifications]]
+/// sink = "command"
+/// command = "notify-send"
+/// args = ["Loopwire"]
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct NotificationRule {
+    pub sink: NotificationSinkKind,
+    /// Webhook endpoint (`sink = "webhook"`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// HMAC-SHA256 key for the `X-Loopwire-Signature` header
+    /// (`sink = "webhook"`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+    /// Binary name or absolute path (`sink = "command"`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+    /// Transitions to deliver; all of them when omitted.
+    #[serde(default = "default_events")]
+    pub events: Vec<NotificationEvent>,
+    /// Workspace ids or paths to match; every workspace when empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub workspaces: Vec<String>,
+    /// Session ids to match; every session when empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sessions: Vec<String>,
+    /// How long a phase must hold before it is delivered. Phases left
+    /// earlier are dropped, so flapping agents don't spam.
+    #[serde(default = "default_debounce_ms")]
+    pub debounce_ms: u64,
+}
+
+fn default_events() -> Vec<NotificationEvent> {
+    NotificationEvent::ALL.to_vec()
+}
+
+fn default_debounce_ms() -> u64 {
+    2000
+}
+
+impl NotificationRule {
+    /// Whether this rule applies to `event` in the given workspace and
+    /// session. `workspaces` entries match either the id or the path.
+    pub fn matches(
+        &self,
+        event: NotificationEvent,
+        workspace_id: Option<&str>,
+        workspace_path: Option<&str>,
+        session_id: &str,
+    ) -> bool {
+        if !self.events.contains(&event) {
+            return false;
+        }
+        if !self.sessions.is_empty() && !self.sessions.iter().any(|s| s == session_id) {
+            return false;
+        }
+        if self.workspaces.is_empty() {
+            return true;
+        }
+        self.workspaces.iter().any(|w| {
+            workspace_id == Some(w.as_str())
+                || workspace_path.is_some_and(|path| Path::new(w) == Path::new(path))
+        })
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        match self.sink {
+            NotificationSinkKind::Webhook => {
+                let url = self.url.as_deref().unwrap_or_default();
+                if !url.starts_with("http://") && !url.starts_with("https://") {
+                    anyhow::bail!("notifications.url {url:?} must be an http(s) URL");
+                }
+            }
+            NotificationSinkKind::Command => {
+                if self.command.as_deref().is_none_or(|c| c.trim().is_empty()) {
+                    anyhow::bail!("notifications.command must not be empty for sink = \"command\"");
+                }
+            }
+            NotificationSinkKind::Websocket => {}
+        }
+        if self.events.is_empty() {
+            anyhow::bail!("notifications.events must not be empty");
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn validate_notification_rules(rules: &[NotificationRule]) -> anyhow::Result<()> {
+    rules.iter().try_for_each(NotificationRule::validate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(toml_str: &str) -> NotificationRule {
+        toml::from_str(toml_str).unwrap()
+    }
+
+    #[test]
+    fn defaults_cover_all_events() {
+        let rule = parse("sink = \"websocket\"\n");
+        assert_eq!(rule.events, NotificationEvent::ALL.to_vec());
+        assert_eq!(rule.debounce_ms, 2000);
+        assert!(rule.validate().is_ok());
+    }
+
+    #[test]
+    fn webhook_requires_http_url() {
+        assert!(parse("sink = \"webhook\"\n").validate().is_err());
+        assert!(parse("sink = \"webhook\"\nurl = \"ftp://x\"\n")
+            .validate()
+            .is_err());
+        assert!(parse("sink = \"webhook\"\nurl = \"https://x\"\n")
+            .validate()
+            .is_ok());
+    }
+
+    #[test]
+    fn command_requires_command() {
+        assert!(parse("sink = \"command\"\n").validate().is_err());
+        assert!(parse("sink = \"command\"\ncommand = \"notify-send\"\n")
+            .validate()
+            .is_ok());
+    }
+
+    #[test]
+    fn rejects_unknown_events() {
+        let result: Result<NotificationRule, _> =
+            toml::from_str("sink = \"websocket\"\nevents = [\"processing\"]\n");
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn matches_filters() {
+        let rule = parse(
+            "sink = \"websocket\"\nevents = [\"exited\"]\nworkspaces = [\"/repo\"]\nsessions = [\"s1\"]\n",
+        );
+        let (id, path) = (Some("w1"), Some("/repo"));
+        assert!(rule.matches(NotificationEvent::Exited, id, path, "s1"));
+        assert!(!rule.matches(NotificationEvent::AwaitingUser, id, path, "s1"));
+        assert!(!rule.matches(NotificationEvent::Exited, id, path, "s2"));
+        assert!(!rule.matches(NotificationEvent::Exited, Some("w2"), Some("/other"), "s1"));
+        assert!(!rule.matches(NotificationEvent::Exited, None, None, "s1"));
+
+        let by_id = parse("sink = \"websocket\"\nworkspaces = [\"w1\"]\n");
+        assert!(by_id.matches(NotificationEvent::AwaitingUser, id, None, "any"));
+    }
+}
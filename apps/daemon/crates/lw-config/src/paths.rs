@@ -41,10 +41,23 @@ impl ConfigPaths {
         self.base.join("bootstrap_token")
     }
 
+    /// Returns the bare session hash list of older versions, migrated to
+    /// `session_tokens_path()` on load.
     pub fn sessions_path(&self) -> PathBuf {
         self.base.join("session_hashes")
     }
 
+    /// Returns the file holding session tokens and their devices:
+    /// `~/.loopwire/sessions.json`
+    pub fn session_tokens_path(&self) -> PathBuf {
+        self.base.join("sessions.json")
+    }
+
+    /// Returns the file holding named API tokens: `~/.loopwire/api_tokens.json`
+    pub fn api_tokens_path(&self) -> PathBuf {
+        self.base.join("api_tokens.json")
+    }
+
     pub fn host_id_path(&self) -> PathBuf {
         self.base.join("host_id")
     }
@@ -57,6 +70,11 @@ impl ConfigPaths {
         self.base.join("bin")
     }
 
+    /// Returns the default dir for a build of the web UI: `~/.loopwire/web/`
+    pub fn web_dir(&self) -> PathBuf {
+        self.base.join("web")
+    }
+
     /// Returns the base dir for all workspace data: `~/.loopwire/workspaces/`
     pub fn workspaces_data_dir(&self) -> PathBuf {
         self.base.join("workspaces")
@@ -67,6 +85,17 @@ impl ConfigPaths {
         self.workspaces_data_dir().join(workspace_id.to_string())
     }
 
+    /// Returns the dir holding headless task records: `~/.loopwire/tasks/`
+    pub fn tasks_dir(&self) -> PathBuf {
+        self.base.join("tasks")
+    }
+
+    /// Returns the dir holding workspace task runs and their output:
+    /// `~/.loopwire/task_runs/`
+    pub fn task_runs_dir(&self) -> PathBuf {
+        self.base.join("task_runs")
+    }
+
     /// Ensure the config directory exists, creating it if necessary.
     pub fn ensure_config_dir(&self) -> anyhow::Result<PathBuf> {
         if !self.base.exists() {
@@ -95,9 +124,13 @@ mod tests {
         assert_eq!(paths.pid_path(), base.join("loopwired.pid"));
         assert_eq!(paths.token_path(), base.join("bootstrap_token"));
         assert_eq!(paths.sessions_path(), base.join("session_hashes"));
+        assert_eq!(paths.session_tokens_path(), base.join("sessions.json"));
+        assert_eq!(paths.api_tokens_path(), base.join("api_tokens.json"));
         assert_eq!(paths.host_id_path(), base.join("host_id"));
         assert_eq!(paths.trust_key_path(), base.join("remote_trust_key"));
         assert_eq!(paths.bin_dir(), base.join("bin"));
+        assert_eq!(paths.web_dir(), base.join("web"));
+        assert_eq!(paths.tasks_dir(), base.join("tasks"));
 
         assert_eq!(paths.workspaces_data_dir(), base.join("workspaces"));
         let ws_id = uuid::Uuid::nil();
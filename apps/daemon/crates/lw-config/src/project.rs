@@ -0,0 +1,291 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Component, Path};
+
+/// File name of the per-workspace config, read from the workspace root.
+pub const PROJECT_CONFIG_FILE: &str = ".loopwire.toml";
+
+/// Shared Loopwire settings committed with a repository as
+/// `.loopwire.toml`:
+///
+/// ```toml
+/// setup = ["npm install"]
+/// hidden = [".env*", "secrets/"]
+///
+/// [agent]
+/// default = "claude_code"
+/// args = ["--model", "opus"]
+///
+/// [env]
+/// NODE_ENV = "development"
+///
+/// [tasks.test]
+/// command = "npm test"
+/// description = "Run the unit tests"
+/// ```
+///
+/// The file comes with the repository, so only `hidden` applies until the
+/// user approves the file's current contents.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ProjectConfig {
+    #[serde(default)]
+    pub agent: ProjectAgentConfig,
+    /// Environment variables for agents started in the workspace.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    /// Shell commands run in order, in the agent's working directory,
+    /// before a new agent starts. The agent is not started if one fails.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub setup: Vec<String>,
+    /// Named shell commands, keyed by task name.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tasks: BTreeMap<String, ProjectTask>,
+    /// Paths the API neither lists nor serves: fs reads, directory browsing,
+    /// git status and diffs, and watch events. A pattern without `/`
+    /// matches any file or directory of that name; one with `/` matches
+    /// from the workspace root. `*` and `?` match within a path component.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hidden: Vec<String>,
+}
+
+/// Defaults for agents started without an explicit choice.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ProjectAgentConfig {
+    /// Agent type id used when a session request names none, e.g.
+    /// `claude_code` or the id of a custom agent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<String>,
+    /// Arguments added in front of the ones a session request passes.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ProjectTask {
+    /// Shell command, run from the workspace root.
+    pub command: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+impl ProjectConfig {
+    /// Reads `.loopwire.toml` from `workspace_root`. `None` when the
+    /// workspace has no such file.
+    pub fn load(workspace_root: &Path) -> anyhow::Result<Option<Self>> {
+        let path = workspace_root.join(PROJECT_CONFIG_FILE);
+        let content = match std::fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let config: ProjectConfig = toml::from_str(&content)?;
+        config.validate()?;
+        Ok(Some(config))
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if let Some(default) = &self.agent.default {
+            if default.trim().is_empty() {
+                anyhow::bail!("agent.default must not be empty");
+            }
+        }
+        if let Some(key) = self
+            .env
+            .keys()
+            .find(|key| key.is_empty() || key.contains(['=', '\0']))
+        {
+            anyhow::bail!("invalid environment variable name {key:?}");
+        }
+        if self.setup.iter().any(|command| command.trim().is_empty()) {
+            anyhow::bail!("setup commands must not be empty");
+        }
+        for (name, task) in &self.tasks {
+            if name.trim().is_empty() {
+                anyhow::bail!("task names must not be empty");
+            }
+            if task.command.trim().is_empty() {
+                anyhow::bail!("task {name:?} has an empty command");
+            }
+        }
+        for pattern in &self.hidden {
+            let trimmed = pattern.trim_matches('/');
+            if trimmed.is_empty() {
+                anyhow::bail!("hidden pattern {pattern:?} matches nothing");
+            }
+            if trimmed
+                .split('/')
+                .any(|part| part.is_empty() || part == "..")
+            {
+                anyhow::bail!("hidden pattern {pattern:?} is not a valid relative path");
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether `relative_path` (relative to the workspace root) or one of
+    /// its parent directories matches a `hidden` pattern.
+    pub fn is_hidden(&self, relative_path: &Path) -> bool {
+        let components: Vec<&str> = relative_path
+            .components()
+            .filter_map(|component| match component {
+                Component::Normal(name) => name.to_str(),
+                _ => None,
+            })
+            .collect();
+        if components.is_empty() {
+            return false;
+        }
+        self.hidden
+            .iter()
+            .any(|pattern| hidden_pattern_matches(pattern, &components))
+    }
+}
+
+fn hidden_pattern_matches(pattern: &str, components: &[&str]) -> bool {
+    let anchored = pattern.contains('/');
+    let pattern = pattern.trim_matches('/');
+    if !anchored {
+        return components
+            .iter()
+            .any(|component| wildcard_matches(pattern, component));
+    }
+    let parts: Vec<&str> = pattern.split('/').collect();
+    parts.len() <= components.len()
+        && parts
+            .iter()
+            .zip(components)
+            .all(|(part, component)| wildcard_matches(part, component))
+}
+
+/// Matches `name` against `pattern`, where `*` stands for any run of
+/// characters and `?` for a single one.
+fn wildcard_matches(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    n = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hidden(patterns: &[&str]) -> ProjectConfig {
+        ProjectConfig {
+            hidden: patterns.iter().map(|p| p.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn load_missing_file_is_none() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(ProjectConfig::load(dir.path()).unwrap().is_none());
+    }
+
+    #[test]
+    fn load_parses_every_section() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join(PROJECT_CONFIG_FILE),
+            r#"
+setup = ["npm install", "npm run build"]
+hidden = [".env"]
+
+[agent]
+default = "codex"
+args = ["--model", "o3"]
+
+[env]
+NODE_ENV = "development"
+
+[tasks.test]
+command = "npm test"
+description = "Run the unit tests"
+"#,
+        )
+        .unwrap();
+
+        let config = ProjectConfig::load(dir.path()).unwrap().unwrap();
+        assert_eq!(config.agent.default.as_deref(), Some("codex"));
+        assert_eq!(config.agent.args, vec!["--model", "o3"]);
+        assert_eq!(config.env["NODE_ENV"], "development");
+        assert_eq!(config.setup.len(), 2);
+        assert_eq!(config.tasks["test"].command, "npm test");
+        assert_eq!(config.hidden, vec![".env"]);
+    }
+
+    #[test]
+    fn load_rejects_invalid_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(PROJECT_CONFIG_FILE);
+        for content in [
+            "setup = \"not a list\"",
+            "setup = [\"  \"]",
+            "[env]\n\"A=B\" = \"1\"",
+            "[tasks.build]\ncommand = \"\"",
+            "hidden = [\"../outside\"]",
+            "hidden = [\"/\"]",
+        ] {
+            std::fs::write(&path, content).unwrap();
+            assert!(
+                ProjectConfig::load(dir.path()).is_err(),
+                "accepted {content:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn unanchored_patterns_match_any_component() {
+        let config = hidden(&[".env*", "node_modules"]);
+        assert!(config.is_hidden(Path::new(".env")));
+        assert!(config.is_hidden(Path::new("app/.env.local")));
+        assert!(config.is_hidden(Path::new("web/node_modules/react/index.js")));
+        assert!(!config.is_hidden(Path::new("src/env.rs")));
+        assert!(!config.is_hidden(Path::new(".")));
+
+        let config = hidden(&["secrets"]);
+        assert!(config.is_hidden(Path::new("a/secrets/x")));
+    }
+
+    #[test]
+    fn patterns_with_a_slash_match_from_the_root() {
+        let config = hidden(&["secrets/", "config/*.key"]);
+        assert!(config.is_hidden(Path::new("secrets")));
+        assert!(config.is_hidden(Path::new("./secrets/prod.json")));
+        assert!(!config.is_hidden(Path::new("a/secrets/x")));
+        assert!(config.is_hidden(Path::new("config/tls.key")));
+        assert!(!config.is_hidden(Path::new("config/app.toml")));
+        assert!(!config.is_hidden(Path::new("nested/config/tls.key")));
+    }
+
+    #[test]
+    fn wildcards() {
+        assert!(wildcard_matches("*.log", "build.log"));
+        assert!(wildcard_matches("a*b*c", "aXXbYc"));
+        assert!(wildcard_matches("?.txt", "a.txt"));
+        assert!(!wildcard_matches("?.txt", "ab.txt"));
+        assert!(!wildcard_matches("*.log", "build.log.gz"));
+    }
+}
@@ -0,0 +1,61 @@
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::path::PathBuf;
+
+/// Serving the web UI from the daemon itself (`[web]` table), for networks
+/// where the hosted frontend can't be loaded.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebUiConfig {
+    /// Serve a build of `apps/web` at `/`. Links and share URLs then point
+    /// at the daemon instead of `frontend_url`.
+    #[serde(default)]
+    pub enabled: bool,
+    /// Directory holding the build (`index.html` at its root). Falls back to
+    /// `~/.loopwire/web`, then to a build bundled into the binary.
+    #[serde(default = "default_web_dir", skip_serializing_if = "Option::is_none")]
+    pub dir: Option<PathBuf>,
+    /// Port the dev server preview proxy listens on while the UI is served.
+    /// Previewed apps then run on another origin than the UI and can't read
+    /// its stored session token. Defaults to the daemon port + 1.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preview_port: Option<u16>,
+}
+
+fn default_web_dir() -> Option<PathBuf> {
+    env::var_os("LOOPWIRE_WEB_DIR")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+}
+
+impl Default for WebUiConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            dir: default_web_dir(),
+            preview_port: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn disabled_by_default() {
+        let web: WebUiConfig = toml::from_str("").unwrap();
+        assert!(!web.enabled);
+        assert_eq!(web.preview_port, None);
+    }
+
+    #[test]
+    fn reads_enabled_and_dir() {
+        let web: WebUiConfig =
+            toml::from_str("enabled = true\ndir = \"/opt/loopwire/web\"\n").unwrap();
+        assert!(web.enabled);
+        assert_eq!(web.dir, Some(PathBuf::from("/opt/loopwire/web")));
+
+        let web: WebUiConfig = toml::from_str("preview_port = 9500\n").unwrap();
+        assert_eq!(web.preview_port, Some(9500));
+    }
+}
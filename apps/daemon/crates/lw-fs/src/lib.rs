@@ -1,11 +1,15 @@
 pub mod browse;
 pub mod read;
 pub mod security;
+pub mod trash;
 pub mod watch;
+pub mod write;
 
 pub use browse::{list_directory, suggest_roots, DirEntry, EntryKind};
 pub use read::{
     read_file, read_file_with_binary, read_file_with_limit, read_file_with_options, FileContent,
 };
 pub use security::{FsError, WorkspaceRegistry};
+pub use trash::{default_trash_dir, move_to_trash};
 pub use watch::{FsEvent, FsEventKind, FsWatcher};
+pub use write::{create_dir, create_file, move_path, write_file, FileStat, Precondition};
@@ -11,6 +11,9 @@ pub struct FileContent {
     pub size: u64,
     pub is_binary: bool,
     pub binary_content_base64: Option<String>,
+    /// Validator to send back as the write precondition.
+    pub etag: String,
+    pub modified_ms: Option<u64>,
 }
 
 /// Read a file's content with a default 10 MB size limit.
@@ -64,6 +67,8 @@ pub fn read_file_with_options(
         size,
         is_binary,
         binary_content_base64,
+        etag: crate::write::file_etag(&metadata),
+        modified_ms: crate::write::modified_ms(&metadata),
     })
 }
 
@@ -84,6 +89,11 @@ mod tests {
         assert_eq!(result.size, 11);
         assert!(!result.is_binary);
         assert_eq!(result.binary_content_base64, None);
+        assert_eq!(
+            result.etag,
+            crate::write::file_etag(&fs::metadata(&path).unwrap())
+        );
+        assert!(result.modified_ms.is_some());
     }
 
     #[test]
@@ -179,11 +189,14 @@ mod tests {
             size: 4,
             is_binary: false,
             binary_content_base64: None,
+            etag: "1-4".to_string(),
+            modified_ms: None,
         };
         let json = serde_json::to_value(&content).unwrap();
         assert_eq!(json["content"], "test");
         assert_eq!(json["size"], 4);
         assert_eq!(json["is_binary"], false);
         assert_eq!(json["binary_content_base64"], serde_json::Value::Null);
+        assert_eq!(json["etag"], "1-4");
     }
 }
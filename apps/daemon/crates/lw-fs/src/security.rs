@@ -15,6 +15,14 @@ pub enum FsError {
     WorkspaceNotRegistered(Uuid),
     #[error("File too large: {size} bytes (max {max} bytes)")]
     FileTooLarge { size: u64, max: u64 },
+    #[error("File was modified since it was read")]
+    Conflict { current_etag: Option<String> },
+    #[error("Path already exists")]
+    AlreadyExists,
+    #[error("Path not found")]
+    NotFound,
+    #[error("Cannot move a directory into itself")]
+    MoveIntoItself,
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 }
@@ -26,6 +34,10 @@ impl FsError {
             FsError::SymlinkEscape => "WORKSPACE_SYMLINK_ESCAPE",
             FsError::WorkspaceNotRegistered(_) => "WORKSPACE_NOT_REGISTERED",
             FsError::FileTooLarge { .. } => "FS_FILE_TOO_LARGE",
+            FsError::Conflict { .. } => "FS_CONFLICT",
+            FsError::AlreadyExists => "FS_ALREADY_EXISTS",
+            FsError::NotFound => "FS_NOT_FOUND",
+            FsError::MoveIntoItself => "FS_MOVE_INTO_ITSELF",
             FsError::Io(_) => "FS_IO_ERROR",
         }
     }
@@ -114,15 +126,23 @@ impl WorkspaceRegistry {
             let resolved = if target.exists() {
                 std::fs::canonicalize(&target)?
             } else {
-                // For non-existent paths, canonicalize parent and append filename
-                let parent = target.parent().unwrap_or(&target);
-                if parent.exists() {
-                    let canonical_parent = std::fs::canonicalize(parent)?;
-                    let file_name = target.file_name().unwrap_or_default();
-                    canonical_parent.join(file_name)
-                } else {
-                    target
+                // For non-existent paths, canonicalize the nearest existing
+                // ancestor and append the missing tail, so a symlinked
+                // directory can't smuggle a not-yet-created path outside.
+                let mut existing = target.as_path();
+                let mut missing = Vec::new();
+                while existing.symlink_metadata().is_err() {
+                    match (existing.parent(), existing.file_name()) {
+                        (Some(parent), Some(name)) => {
+                            missing.push(name.to_owned());
+                            existing = parent;
+                        }
+                        _ => break,
+                    }
                 }
+                let mut resolved = std::fs::canonicalize(existing)?;
+                resolved.extend(missing.iter().rev());
+                resolved
             };
 
             if !resolved.starts_with(&root) {
@@ -148,6 +168,16 @@ impl WorkspaceRegistry {
         None
     }
 
+    /// Workspaces whose root is `path` or one of its ancestors.
+    pub async fn containing(&self, path: &std::path::Path) -> Vec<Uuid> {
+        let workspaces = self.workspaces.read().await;
+        workspaces
+            .iter()
+            .filter(|(_, root)| path.starts_with(root))
+            .map(|(id, _)| *id)
+            .collect()
+    }
+
     pub async fn get_root(&self, workspace_id: &Uuid) -> Result<PathBuf, FsError> {
         let workspaces = self.workspaces.read().await;
         workspaces
@@ -194,6 +224,12 @@ mod tests {
             FsError::FileTooLarge { size: 100, max: 50 }.error_code(),
             "FS_FILE_TOO_LARGE"
         );
+        assert_eq!(
+            FsError::Conflict { current_etag: None }.error_code(),
+            "FS_CONFLICT"
+        );
+        assert_eq!(FsError::AlreadyExists.error_code(), "FS_ALREADY_EXISTS");
+        assert_eq!(FsError::NotFound.error_code(), "FS_NOT_FOUND");
         assert_eq!(
             FsError::Io(std::io::Error::new(std::io::ErrorKind::NotFound, "x")).error_code(),
             "FS_IO_ERROR"
@@ -320,6 +356,34 @@ mod tests {
         assert!(matches!(result, Err(FsError::SymlinkEscape)));
     }
 
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn resolve_rejects_missing_path_under_escaping_symlink() {
+        let (dir, registry, id) = setup();
+        let outside = TempDir::new().unwrap();
+        std::os::unix::fs::symlink(outside.path(), dir.path().join("linked")).unwrap();
+        registry
+            .register(id, dir.path().to_path_buf())
+            .await
+            .unwrap();
+
+        let result = registry.resolve(&id, "linked/new/dir/file.txt").await;
+        assert!(matches!(result, Err(FsError::SymlinkEscape)));
+    }
+
+    #[tokio::test]
+    async fn resolve_nonexistent_nested_path() {
+        let (dir, registry, id) = setup();
+        registry
+            .register(id, dir.path().to_path_buf())
+            .await
+            .unwrap();
+
+        let resolved = registry.resolve(&id, "a/b/c.txt").await.unwrap();
+        let root = fs::canonicalize(dir.path()).unwrap();
+        assert_eq!(resolved, root.join("a/b/c.txt"));
+    }
+
     #[tokio::test]
     async fn resolve_unregistered_workspace() {
         let registry = WorkspaceRegistry::new();
@@ -0,0 +1,246 @@
+use crate::security::FsError;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The current user's trash directory: `~/.Trash` on macOS, the freedesktop
+/// home trash (`$XDG_DATA_HOME/Trash`) elsewhere.
+pub fn default_trash_dir() -> Option<PathBuf> {
+    if cfg!(target_os = "macos") {
+        dirs::home_dir().map(|home| home.join(".Trash"))
+    } else {
+        dirs::data_dir().map(|data| data.join("Trash"))
+    }
+}
+
+/// Move `path` into `trash_dir` and return where it ended up.
+///
+/// Outside macOS this follows the freedesktop trash layout (`files/` plus a
+/// `.trashinfo` record in `info/`) so desktop file managers can restore it.
+pub fn move_to_trash(path: &Path, trash_dir: &Path) -> Result<PathBuf, FsError> {
+    if path.symlink_metadata().is_err() {
+        return Err(FsError::NotFound);
+    }
+    let freedesktop = !cfg!(target_os = "macos");
+    let files_dir = if freedesktop {
+        trash_dir.join("files")
+    } else {
+        trash_dir.to_path_buf()
+    };
+    let info_dir = trash_dir.join("info");
+    fs::create_dir_all(&files_dir)?;
+    if freedesktop {
+        fs::create_dir_all(&info_dir)?;
+    }
+
+    let name = path
+        .file_name()
+        .ok_or(FsError::NotFound)?
+        .to_string_lossy()
+        .to_string();
+    let mut counter = 1;
+    let (trashed, info_path) = loop {
+        let candidate = if counter == 1 {
+            name.clone()
+        } else {
+            format!("{name}.{counter}")
+        };
+        let trashed = files_dir.join(&candidate);
+        let info_path = info_dir.join(format!("{candidate}.trashinfo"));
+        counter += 1;
+        if trashed.symlink_metadata().is_ok() {
+            continue;
+        }
+        if freedesktop {
+            // Claim the name by creating the info record first, as the spec asks.
+            match fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&info_path)
+            {
+                Ok(_) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+        break (trashed, info_path);
+    };
+
+    let moved = (|| -> Result<(), FsError> {
+        if freedesktop {
+            fs::write(&info_path, trash_info(path))?;
+        }
+        match fs::rename(path, &trashed) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::CrossesDevices => {
+                copy_recursive(path, &trashed)?;
+                remove_recursive(path)?;
+                Ok(())
+            }
+            Err(e) => Err(e.into()),
+        }
+    })();
+    if let Err(e) = moved {
+        if freedesktop {
+            let _ = fs::remove_file(&info_path);
+        }
+        return Err(e);
+    }
+    Ok(trashed)
+}
+
+fn trash_info(original: &Path) -> String {
+    let deleted_at = chrono::Local::now().format("%Y-%m-%dT%H:%M:%S");
+    format!(
+        "[Trash Info]\nPath={}\nDeletionDate={}\n",
+        percent_encode_path(original),
+        deleted_at
+    )
+}
+
+fn percent_encode_path(path: &Path) -> String {
+    let raw = path.to_string_lossy();
+    let mut encoded = String::with_capacity(raw.len());
+    for byte in raw.bytes() {
+        if byte.is_ascii_alphanumeric() || b"/-_.~".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    encoded
+}
+
+/// Copies a tree for a move across filesystems. Symlinks are recreated as
+/// links rather than followed, so the copy never pulls in what they point to.
+fn copy_recursive(from: &Path, to: &Path) -> std::io::Result<()> {
+    let file_type = from.symlink_metadata()?.file_type();
+    if file_type.is_symlink() {
+        copy_symlink(from, to)
+    } else if file_type.is_dir() {
+        fs::create_dir(to)?;
+        for entry in fs::read_dir(from)? {
+            let entry = entry?;
+            copy_recursive(&entry.path(), &to.join(entry.file_name()))?;
+        }
+        Ok(())
+    } else {
+        fs::copy(from, to).map(|_| ())
+    }
+}
+
+#[cfg(unix)]
+fn copy_symlink(from: &Path, to: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(fs::read_link(from)?, to)
+}
+
+#[cfg(windows)]
+fn copy_symlink(from: &Path, to: &Path) -> std::io::Result<()> {
+    let target = fs::read_link(from)?;
+    if fs::metadata(from).map(|m| m.is_dir()).unwrap_or(false) {
+        std::os::windows::fs::symlink_dir(target, to)
+    } else {
+        std::os::windows::fs::symlink_file(target, to)
+    }
+}
+
+fn remove_recursive(path: &Path) -> std::io::Result<()> {
+    if path.symlink_metadata()?.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn trashed_files_dir(trash: &Path) -> PathBuf {
+        if cfg!(target_os = "macos") {
+            trash.to_path_buf()
+        } else {
+            trash.join("files")
+        }
+    }
+
+    #[test]
+    fn trashes_file_and_directory() {
+        let workspace = TempDir::new().unwrap();
+        let trash = TempDir::new().unwrap();
+        let file = workspace.path().join("notes.txt");
+        fs::write(&file, "keep me").unwrap();
+        let dir = workspace.path().join("build");
+        fs::create_dir_all(dir.join("out")).unwrap();
+
+        let trashed = move_to_trash(&file, trash.path()).unwrap();
+        assert!(!file.exists());
+        assert_eq!(fs::read_to_string(&trashed).unwrap(), "keep me");
+        assert_eq!(trashed, trashed_files_dir(trash.path()).join("notes.txt"));
+
+        let trashed_dir = move_to_trash(&dir, trash.path()).unwrap();
+        assert!(!dir.exists());
+        assert!(trashed_dir.join("out").is_dir());
+    }
+
+    #[test]
+    fn name_collisions_get_a_suffix() {
+        let workspace = TempDir::new().unwrap();
+        let trash = TempDir::new().unwrap();
+        let file = workspace.path().join("a.txt");
+
+        fs::write(&file, "1").unwrap();
+        let first = move_to_trash(&file, trash.path()).unwrap();
+        fs::write(&file, "2").unwrap();
+        let second = move_to_trash(&file, trash.path()).unwrap();
+
+        assert_ne!(first, second);
+        assert!(second.ends_with("a.txt.2"));
+        assert_eq!(fs::read_to_string(second).unwrap(), "2");
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    #[test]
+    fn writes_trash_info_record() {
+        let workspace = TempDir::new().unwrap();
+        let trash = TempDir::new().unwrap();
+        let file = workspace.path().join("my file.txt");
+        fs::write(&file, "x").unwrap();
+
+        move_to_trash(&file, trash.path()).unwrap();
+        let info = fs::read_to_string(trash.path().join("info/my file.txt.trashinfo")).unwrap();
+        assert!(info.starts_with("[Trash Info]\n"));
+        assert!(info.contains("my%20file.txt"));
+        assert!(info.contains("DeletionDate="));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn copy_keeps_symlinks_as_links() {
+        let workspace = TempDir::new().unwrap();
+        let outside = TempDir::new().unwrap();
+        fs::write(outside.path().join("secret"), "outside").unwrap();
+        let dir = workspace.path().join("dir");
+        fs::create_dir(&dir).unwrap();
+        std::os::unix::fs::symlink(outside.path().join("secret"), dir.join("file")).unwrap();
+        std::os::unix::fs::symlink(outside.path(), dir.join("tree")).unwrap();
+
+        let copy = workspace.path().join("copy");
+        copy_recursive(&dir, &copy).unwrap();
+        for name in ["file", "tree"] {
+            let link = copy.join(name);
+            assert!(link.symlink_metadata().unwrap().file_type().is_symlink());
+        }
+        assert_eq!(
+            fs::read_link(copy.join("file")).unwrap(),
+            outside.path().join("secret")
+        );
+    }
+
+    #[test]
+    fn missing_path_is_not_found() {
+        let trash = TempDir::new().unwrap();
+        let result = move_to_trash(&trash.path().join("missing"), trash.path());
+        assert!(matches!(result, Err(FsError::NotFound)));
+    }
+}
@@ -0,0 +1,323 @@
+use crate::security::FsError;
+use std::fs::{self, Metadata, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+/// What the caller believes about the file it is about to overwrite.
+/// A mismatch fails with [`FsError::Conflict`] instead of clobbering a
+/// concurrent edit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Precondition {
+    /// Overwrite unconditionally.
+    None,
+    /// The file's current [`file_etag`] must match.
+    Etag(String),
+    /// The file's current modification time (ms since epoch) must match.
+    ModifiedMs(u64),
+}
+
+/// Metadata returned after a successful write, to be sent back as the
+/// precondition of the next one.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct FileStat {
+    pub size: u64,
+    pub etag: String,
+    pub modified_ms: Option<u64>,
+}
+
+impl FileStat {
+    pub fn from_metadata(metadata: &Metadata) -> Self {
+        Self {
+            size: metadata.len(),
+            etag: file_etag(metadata),
+            modified_ms: modified_ms(metadata),
+        }
+    }
+}
+
+pub fn modified_ms(metadata: &Metadata) -> Option<u64> {
+    let modified = metadata.modified().ok()?;
+    let elapsed = modified.duration_since(UNIX_EPOCH).ok()?;
+    Some(elapsed.as_millis() as u64)
+}
+
+/// Weak validator derived from modification time and size. Cheap to compute
+/// and changes on every write the filesystem records.
+pub fn file_etag(metadata: &Metadata) -> String {
+    let nanos = metadata
+        .modified()
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map(|elapsed| elapsed.as_nanos())
+        .unwrap_or(0);
+    format!("{:x}-{:x}", nanos, metadata.len())
+}
+
+fn check_precondition(path: &Path, precondition: &Precondition) -> Result<(), FsError> {
+    if *precondition == Precondition::None {
+        return Ok(());
+    }
+    let metadata = match fs::metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Err(FsError::Conflict { current_etag: None });
+        }
+        Err(e) => return Err(e.into()),
+    };
+    let matches = match precondition {
+        Precondition::None => true,
+        Precondition::Etag(expected) => file_etag(&metadata) == *expected,
+        Precondition::ModifiedMs(expected) => modified_ms(&metadata) == Some(*expected),
+    };
+    if matches {
+        Ok(())
+    } else {
+        Err(FsError::Conflict {
+            current_etag: Some(file_etag(&metadata)),
+        })
+    }
+}
+
+/// Replace an existing file's content atomically (temp file + rename),
+/// keeping its permissions. Fails with [`FsError::Conflict`] when
+/// `precondition` no longer holds.
+pub fn write_file(
+    path: &Path,
+    contents: &[u8],
+    precondition: &Precondition,
+) -> Result<FileStat, FsError> {
+    check_precondition(path, precondition)?;
+
+    let parent = path.parent().ok_or(FsError::NotFound)?;
+    let file_name = path.file_name().ok_or(FsError::NotFound)?;
+    let temp_path = parent.join(format!(
+        ".{}.{}.lw-tmp",
+        file_name.to_string_lossy(),
+        uuid::Uuid::new_v4().simple()
+    ));
+
+    let staged = (|| -> Result<(), FsError> {
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&temp_path)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+        if let Ok(existing) = fs::metadata(path) {
+            fs::set_permissions(&temp_path, existing.permissions())?;
+        }
+        // Re-check right before the swap to keep the race window small.
+        check_precondition(path, precondition)?;
+        fs::rename(&temp_path, path)?;
+        Ok(())
+    })();
+    if let Err(e) = staged {
+        let _ = fs::remove_file(&temp_path);
+        return Err(e);
+    }
+
+    Ok(FileStat::from_metadata(&fs::metadata(path)?))
+}
+
+/// Create a new file, creating missing parent directories. Fails with
+/// [`FsError::AlreadyExists`] if anything already exists at `path`.
+pub fn create_file(path: &Path, contents: &[u8]) -> Result<FileStat, FsError> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)
+        .map_err(already_exists)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+    Ok(FileStat::from_metadata(&file.metadata()?))
+}
+
+/// Create a directory. With `recursive`, missing parents are created too.
+pub fn create_dir(path: &Path, recursive: bool) -> Result<(), FsError> {
+    if path.symlink_metadata().is_ok() {
+        return Err(FsError::AlreadyExists);
+    }
+    if recursive {
+        fs::create_dir_all(path)?;
+    } else {
+        fs::create_dir(path).map_err(already_exists)?;
+    }
+    Ok(())
+}
+
+/// Move or rename `from` to `to`, creating missing parent directories of
+/// `to`. Never overwrites an existing destination, and refuses to move a
+/// directory into its own subtree.
+pub fn move_path(from: &Path, to: &Path) -> Result<(), FsError> {
+    if from.symlink_metadata().is_err() {
+        return Err(FsError::NotFound);
+    }
+    if to.symlink_metadata().is_ok() {
+        return Err(FsError::AlreadyExists);
+    }
+    if to.starts_with(from) {
+        return Err(FsError::MoveIntoItself);
+    }
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::rename(from, to)?;
+    Ok(())
+}
+
+fn already_exists(e: std::io::Error) -> FsError {
+    if e.kind() == std::io::ErrorKind::AlreadyExists {
+        FsError::AlreadyExists
+    } else {
+        FsError::Io(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn etag_of(path: &Path) -> String {
+        file_etag(&fs::metadata(path).unwrap())
+    }
+
+    #[test]
+    fn write_with_matching_etag_replaces_content() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("a.txt");
+        fs::write(&path, "old").unwrap();
+
+        let stat = write_file(&path, b"new content", &Precondition::Etag(etag_of(&path))).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new content");
+        assert_eq!(stat.size, 11);
+        assert_eq!(stat.etag, etag_of(&path));
+    }
+
+    #[test]
+    fn write_with_stale_etag_conflicts() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("a.txt");
+        fs::write(&path, "old").unwrap();
+        let stale = etag_of(&path);
+        fs::write(&path, "edited by agent").unwrap();
+
+        let err = write_file(&path, b"mine", &Precondition::Etag(stale)).unwrap_err();
+        assert!(matches!(
+            err,
+            FsError::Conflict {
+                current_etag: Some(_)
+            }
+        ));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "edited by agent");
+    }
+
+    #[test]
+    fn write_with_modified_ms_precondition() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("a.txt");
+        fs::write(&path, "old").unwrap();
+        let mtime = modified_ms(&fs::metadata(&path).unwrap()).unwrap();
+
+        write_file(&path, b"new", &Precondition::ModifiedMs(mtime)).unwrap();
+        let err = write_file(&path, b"again", &Precondition::ModifiedMs(mtime + 1)).unwrap_err();
+        assert!(matches!(err, FsError::Conflict { .. }));
+    }
+
+    #[test]
+    fn write_conflicts_when_file_was_deleted() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("gone.txt");
+        let err = write_file(&path, b"x", &Precondition::Etag("1-1".into())).unwrap_err();
+        assert!(matches!(err, FsError::Conflict { current_etag: None }));
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn write_leaves_no_temp_files() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("a.txt");
+        fs::write(&path, "old").unwrap();
+        write_file(&path, b"new", &Precondition::None).unwrap();
+        let names: Vec<_> = fs::read_dir(dir.path()).unwrap().collect();
+        assert_eq!(names.len(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn write_preserves_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("run.sh");
+        fs::write(&path, "#!/bin/sh").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+
+        write_file(&path, b"#!/bin/sh\necho hi", &Precondition::None).unwrap();
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o755);
+    }
+
+    #[test]
+    fn create_file_makes_parents_and_rejects_existing() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("src/new/mod.rs");
+        let stat = create_file(&path, b"fn main() {}").unwrap();
+        assert_eq!(stat.size, 12);
+        assert!(matches!(
+            create_file(&path, b"again"),
+            Err(FsError::AlreadyExists)
+        ));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "fn main() {}");
+    }
+
+    #[test]
+    fn create_dir_recursive_and_existing() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("a/b/c");
+        assert!(create_dir(&path, false).is_err());
+        create_dir(&path, true).unwrap();
+        assert!(path.is_dir());
+        assert!(matches!(
+            create_dir(&path, true),
+            Err(FsError::AlreadyExists)
+        ));
+    }
+
+    #[test]
+    fn move_path_renames_and_never_overwrites() {
+        let dir = TempDir::new().unwrap();
+        let from = dir.path().join("a.txt");
+        let to = dir.path().join("nested/b.txt");
+        fs::write(&from, "a").unwrap();
+
+        move_path(&from, &to).unwrap();
+        assert!(!from.exists());
+        assert_eq!(fs::read_to_string(&to).unwrap(), "a");
+
+        fs::write(&from, "other").unwrap();
+        assert!(matches!(move_path(&from, &to), Err(FsError::AlreadyExists)));
+        assert!(matches!(
+            move_path(&dir.path().join("missing"), &dir.path().join("x")),
+            Err(FsError::NotFound)
+        ));
+    }
+
+    #[test]
+    fn move_path_rejects_moving_a_directory_into_itself() {
+        let dir = TempDir::new().unwrap();
+        let from = dir.path().join("src");
+        fs::create_dir(&from).unwrap();
+
+        assert!(matches!(
+            move_path(&from, &from.join("nested/src")),
+            Err(FsError::MoveIntoItself)
+        ));
+        assert!(!from.join("nested").exists());
+        // A sibling that merely shares the name prefix is fine.
+        move_path(&from, &dir.path().join("src2")).unwrap();
+    }
+}
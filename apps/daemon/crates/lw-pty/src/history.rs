@@ -1,3 +1,5 @@
+use crate::output_log::OutputLog;
+use crate::recording::{clip_timings, needs_timing, unix_millis, OutputRecording, OutputTiming};
 use std::collections::VecDeque;
 
 pub(crate) const OUTPUT_HISTORY_MAX_BYTES: usize = 8 * 1024 * 1024;
@@ -15,6 +17,11 @@ pub(crate) struct OutputHistory {
     max_bytes: usize,
     start_offset: usize,
     end_offset: usize,
+    /// When the retained output was read, for recordings of sessions
+    /// without a log.
+    timings: VecDeque<OutputTiming>,
+    /// On-disk copy of everything pushed, used to page past `start_offset`.
+    log: Option<OutputLog>,
 }
 
 impl OutputHistory {
@@ -25,14 +32,54 @@ impl OutputHistory {
             max_bytes,
             start_offset: 0,
             end_offset: 0,
+            timings: VecDeque::new(),
+            log: None,
         }
     }
 
+    /// History backed by `log`: the in-memory window is seeded with the
+    /// log's tail and new output continues at the log's end offset.
+    pub(crate) fn with_log(max_bytes: usize, log: OutputLog) -> Self {
+        let end_offset = log.end_offset();
+        let tail_start = end_offset.saturating_sub(max_bytes).max(log.start_offset());
+        let tail = match log.read_range(tail_start, end_offset) {
+            Ok(tail) => tail,
+            Err(err) => {
+                tracing::warn!(error = %err, "failed to read PTY output log tail");
+                Vec::new()
+            }
+        };
+        let mut history = Self::new(max_bytes);
+        history.start_offset = end_offset - tail.len();
+        history.end_offset = end_offset;
+        history.total_bytes = tail.len();
+        if !tail.is_empty() {
+            history.chunks.push_back(tail);
+        }
+        history.log = Some(log);
+        history
+    }
+
     pub(crate) fn push(&mut self, data: &[u8]) {
         if data.is_empty() {
             return;
         }
 
+        let now = unix_millis();
+        if let Some(log) = self.log.as_mut() {
+            if let Err(err) = log.append_at(data, now) {
+                tracing::warn!(error = %err, "failed to append PTY output log; disabling it");
+                self.log = None;
+            }
+        }
+
+        if needs_timing(self.timings.back(), now) {
+            self.timings.push_back(OutputTiming {
+                offset: self.end_offset,
+                unix_ms: now,
+            });
+        }
+
         let chunk = data.to_vec();
         self.total_bytes = self.total_bytes.saturating_add(chunk.len());
         self.end_offset = self.end_offset.saturating_add(chunk.len());
@@ -46,6 +93,13 @@ impl OutputHistory {
                 break;
             }
         }
+        while self
+            .timings
+            .get(1)
+            .is_some_and(|next| next.offset <= self.start_offset)
+        {
+            self.timings.pop_front();
+        }
     }
 
     pub(crate) fn snapshot(&self) -> Vec<u8> {
@@ -94,6 +148,20 @@ impl OutputHistory {
         let retained_start = self.start_offset;
         let retained_end = self.end_offset;
 
+        if let Some(log) = &self.log {
+            let requested_end = before_offset.unwrap_or(retained_end).min(retained_end);
+            if requested_end.saturating_sub(max_bytes) < retained_start
+                && log.start_offset() < retained_start
+            {
+                match log.slice_before(Some(requested_end), max_bytes) {
+                    Ok(slice) => return slice,
+                    Err(err) => {
+                        tracing::warn!(error = %err, "failed to read PTY output log");
+                    }
+                }
+            }
+        }
+
         if self.total_bytes == 0 || max_bytes == 0 || retained_start >= retained_end {
             return OutputSlice {
                 data: Vec::new(),
@@ -143,11 +211,35 @@ impl OutputHistory {
             }
         }
 
+        let floor = self
+            .log
+            .as_ref()
+            .map_or(retained_start, |log| log.start_offset().min(retained_start));
         OutputSlice {
             data: out,
             start_offset: start,
             end_offset: end,
-            has_more: start > retained_start,
+            has_more: start > floor,
+        }
+    }
+
+    /// The last `max_bytes` of output with their timings, reaching into the
+    /// log when the in-memory window is shorter.
+    pub(crate) fn recording(&self, max_bytes: usize) -> OutputRecording {
+        let slice = self.slice_before(None, max_bytes);
+        let timings = match &self.log {
+            Some(log) => log.timings(slice.start_offset, slice.end_offset),
+            None => clip_timings(
+                self.timings.iter().copied().collect(),
+                slice.start_offset,
+                slice.end_offset,
+            ),
+        };
+        OutputRecording {
+            data: slice.data,
+            start_offset: slice.start_offset,
+            timings,
+            size: None,
         }
     }
 }
@@ -173,6 +265,20 @@ mod tests {
         assert_eq!(history.chunks.len(), 1);
     }
 
+    #[test]
+    fn recording_keeps_timings_of_retained_output() {
+        let mut history = OutputHistory::new(6);
+        history.push(b"aaaa");
+        history.push(b"bbbb");
+        let recording = history.recording(1024);
+        assert_eq!(recording.data, b"bbbb");
+        assert_eq!(recording.start_offset, 4);
+        assert_eq!(recording.timings.len(), 1);
+        assert_eq!(recording.timings[0].offset, 4);
+        // Timings of evicted output are dropped.
+        assert_eq!(history.timings.len(), 1);
+    }
+
     #[test]
     fn evicts_oldest_when_over_max() {
         let mut history = OutputHistory::new(10);
@@ -250,6 +356,43 @@ mod tests {
         assert!(!slice.has_more);
     }
 
+    #[test]
+    fn with_log_seeds_tail_and_continues_offsets() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let mut log = OutputLog::open(tmp.path()).unwrap();
+        log.append(b"previous run output").unwrap();
+
+        let mut history = OutputHistory::with_log(8, log);
+        assert_eq!(history.snapshot(), b"n output");
+        assert_eq!(history.start_offset, 11);
+        assert_eq!(history.end_offset, 19);
+
+        history.push(b"!");
+        assert_eq!(history.end_offset, 20);
+        let reopened = OutputLog::open(tmp.path()).unwrap();
+        assert_eq!(reopened.read_range(0, 20).unwrap(), b"previous run output!");
+    }
+
+    #[test]
+    fn slice_before_pages_past_memory_into_log() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let log = OutputLog::open(tmp.path()).unwrap();
+        let mut history = OutputHistory::with_log(4, log);
+        history.push(b"aaaa");
+        history.push(b"bbbb");
+        history.push(b"cccc"); // memory holds "cccc", start_offset = 8
+
+        let slice = history.slice_before(Some(8), 6);
+        assert_eq!(slice.data, b"aabbbb");
+        assert_eq!(slice.start_offset, 2);
+        assert_eq!(slice.end_offset, 8);
+        assert!(slice.has_more);
+
+        let tail = history.slice_before(None, 4);
+        assert_eq!(tail.data, b"cccc");
+        assert!(tail.has_more);
+    }
+
     #[test]
     fn snapshot_chunked_returns_empty_when_max_chunk_bytes_is_zero() {
         let mut history = OutputHistory::new(1024);
@@ -1,10 +1,15 @@
 mod history;
 pub mod manager;
+mod output_log;
 mod platform;
 mod reader;
+mod recording;
+mod screen;
 pub mod session;
 
 pub use manager::PtyManager;
+pub use recording::{OutputRecording, OutputTiming};
+pub use screen::ScreenCapture;
 pub use session::PtySession;
 
 #[derive(Debug, thiserror::Error)]
@@ -1,19 +1,76 @@
+use crate::history::{OutputHistory, OUTPUT_HISTORY_MAX_BYTES};
+use crate::output_log::OutputLog;
+use crate::recording::OutputRecording;
 use crate::session::PtySession;
 use crate::PtyError;
 use std::collections::HashMap;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use uuid::Uuid;
 
 pub struct PtyManager {
     sessions: Arc<RwLock<HashMap<Uuid, Arc<PtySession>>>>,
+    output_log_dirs: std::sync::Mutex<HashMap<Uuid, PathBuf>>,
 }
 
 impl PtyManager {
     pub fn new() -> Self {
         Self {
             sessions: Arc::new(RwLock::new(HashMap::new())),
+            output_log_dirs: std::sync::Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Persist output of `session_id` under `dir`. Every PTY later created
+    /// for this id appends to the same log and starts with its tail as
+    /// history, so scrollback survives re-spawns and daemon restarts.
+    pub fn set_output_log_dir(&self, session_id: Uuid, dir: PathBuf) {
+        self.output_log_dirs
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(session_id, dir);
+    }
+
+    /// Forget and delete the on-disk output log of `session_id`.
+    pub fn remove_output_log(&self, session_id: &Uuid) {
+        let dir = self
+            .output_log_dirs
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(session_id);
+        if let Some(dir) = dir {
+            if let Err(err) = std::fs::remove_dir_all(&dir) {
+                if err.kind() != std::io::ErrorKind::NotFound {
+                    tracing::warn!(session_id = %session_id, error = %err, "failed to remove output log");
+                }
+            }
+        }
+    }
+
+    fn output_log_dir(&self, session_id: &Uuid) -> Option<PathBuf> {
+        self.output_log_dirs
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(session_id)
+            .cloned()
+    }
+
+    fn initial_history(&self, session_id: &Uuid) -> OutputHistory {
+        let Some(dir) = self.output_log_dir(session_id) else {
+            return OutputHistory::new(OUTPUT_HISTORY_MAX_BYTES);
+        };
+        match OutputLog::open(&dir) {
+            Ok(log) => OutputHistory::with_log(OUTPUT_HISTORY_MAX_BYTES, log),
+            Err(err) => {
+                tracing::warn!(
+                    session_id = %session_id,
+                    dir = %dir.display(),
+                    error = %err,
+                    "failed to open output log, keeping history in memory only"
+                );
+                OutputHistory::new(OUTPUT_HISTORY_MAX_BYTES)
+            }
         }
     }
 
@@ -26,8 +83,16 @@ impl PtyManager {
         env: Vec<(String, String)>,
         size: (u16, u16),
     ) -> Result<Arc<PtySession>, PtyError> {
-        let (cols, rows) = size;
-        let session = PtySession::spawn(session_id, program, args, working_dir, env, cols, rows)?;
+        let history = self.initial_history(&session_id);
+        let session = PtySession::spawn_with_history(
+            session_id,
+            program,
+            args,
+            working_dir,
+            env,
+            size,
+            history,
+        )?;
         let id = session.id;
         let session = Arc::new(session);
         self.sessions.write().await.insert(id, session.clone());
@@ -44,6 +109,48 @@ impl PtyManager {
             .ok_or(PtyError::SessionNotFound(*id))
     }
 
+    /// Page backwards through a session's output. Served from the live PTY
+    /// when there is one, otherwise from its on-disk log (e.g. a restored
+    /// session whose process has not been re-spawned yet).
+    pub async fn output_slice_before(
+        &self,
+        id: &Uuid,
+        before_offset: Option<usize>,
+        max_bytes: usize,
+    ) -> Result<(Vec<u8>, usize, usize, bool), PtyError> {
+        if let Ok(session) = self.get(id).await {
+            return Ok(session.output_slice_before(before_offset, max_bytes));
+        }
+        let dir = self
+            .output_log_dir(id)
+            .filter(|dir| dir.is_dir())
+            .ok_or(PtyError::SessionNotFound(*id))?;
+        let slice = OutputLog::open(&dir)?.slice_before(before_offset, max_bytes)?;
+        Ok((
+            slice.data,
+            slice.start_offset,
+            slice.end_offset,
+            slice.has_more,
+        ))
+    }
+
+    /// The last `max_bytes` of a session's output with their timings, from
+    /// the live PTY or, failing that, its on-disk log.
+    pub async fn output_recording(
+        &self,
+        id: &Uuid,
+        max_bytes: usize,
+    ) -> Result<OutputRecording, PtyError> {
+        if let Ok(session) = self.get(id).await {
+            return Ok(session.output_recording(max_bytes));
+        }
+        let dir = self
+            .output_log_dir(id)
+            .filter(|dir| dir.is_dir())
+            .ok_or(PtyError::SessionNotFound(*id))?;
+        Ok(OutputRecording::from_log_dir(&dir, max_bytes)?)
+    }
+
     pub async fn kill(&self, id: &Uuid) -> Result<(), PtyError> {
         tracing::debug!(session_id = %id, "killing session");
         let session = self.get(id).await?;
@@ -63,6 +170,17 @@ impl PtyManager {
             .collect()
     }
 
+    /// Child pids of the sessions that are still running.
+    pub async fn running_pids(&self) -> Vec<(Uuid, u32)> {
+        self.sessions
+            .read()
+            .await
+            .iter()
+            .filter(|(_, s)| !s.is_stopped())
+            .filter_map(|(id, s)| Some((*id, s.child_pid?)))
+            .collect()
+    }
+
     /// Removes all stopped sessions from the manager and returns their IDs.
     pub async fn reap_stopped(&self) -> Vec<Uuid> {
         let mut sessions = self.sessions.write().await;
@@ -142,6 +260,26 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn output_slice_before_reads_log_without_live_session() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let id = Uuid::new_v4();
+        let dir = tmp.path().join(id.to_string());
+        let mut log = OutputLog::open(&dir).unwrap();
+        log.append(b"from a previous daemon").unwrap();
+
+        let mgr = PtyManager::new();
+        assert!(mgr.output_slice_before(&id, None, 1024).await.is_err());
+
+        mgr.set_output_log_dir(id, dir.clone());
+        let (data, start, end, has_more) = mgr.output_slice_before(&id, None, 8).await.unwrap();
+        assert_eq!(data, b"s daemon");
+        assert_eq!((start, end, has_more), (14, 22, true));
+
+        mgr.remove_output_log(&id);
+        assert!(!dir.exists());
+    }
+
     #[tokio::test]
     async fn reap_stopped_on_empty_manager_returns_empty_vec() {
         let mgr = PtyManager::new();
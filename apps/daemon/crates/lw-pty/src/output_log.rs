@@ -0,0 +1,333 @@
+use crate::history::OutputSlice;
+use crate::recording::{clip_timings, needs_timing, OutputRecording, OutputTiming};
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+/// Size at which the active segment is closed and a new one started.
+pub(crate) const OUTPUT_LOG_SEGMENT_BYTES: usize = 4 * 1024 * 1024;
+/// Total bytes kept on disk per session; oldest segments are deleted first.
+pub(crate) const OUTPUT_LOG_MAX_BYTES: usize = 32 * 1024 * 1024;
+
+struct Segment {
+    start: usize,
+    len: usize,
+}
+
+impl Segment {
+    fn end(&self) -> usize {
+        self.start + self.len
+    }
+}
+
+/// Append-only on-disk copy of a session's PTY output.
+///
+/// The log is a directory of segment files named by the absolute output
+/// offset they start at, so offsets stay stable across rotations and daemon
+/// restarts and line up with [`crate::history::OutputHistory`] offsets.
+pub(crate) struct OutputLog {
+    dir: PathBuf,
+    segments: Vec<Segment>,
+    active: Option<File>,
+    /// Timing sidecar of the active segment.
+    active_timings: Option<File>,
+    last_timing: Option<OutputTiming>,
+    segment_bytes: usize,
+    max_bytes: usize,
+}
+
+fn segment_path(dir: &Path, start: usize) -> PathBuf {
+    dir.join(format!("{start:020}.log"))
+}
+
+/// One `<offset> <unix_ms>` line per [`OutputTiming`] of the segment.
+fn timing_path(dir: &Path, start: usize) -> PathBuf {
+    dir.join(format!("{start:020}.timing"))
+}
+
+impl OutputLog {
+    pub(crate) fn open(dir: &Path) -> std::io::Result<Self> {
+        Self::open_with_limits(dir, OUTPUT_LOG_SEGMENT_BYTES, OUTPUT_LOG_MAX_BYTES)
+    }
+
+    pub(crate) fn open_with_limits(
+        dir: &Path,
+        segment_bytes: usize,
+        max_bytes: usize,
+    ) -> std::io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let mut segments = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let name = entry.file_name();
+            let Some(start) = name
+                .to_str()
+                .and_then(|name| name.strip_suffix(".log"))
+                .and_then(|stem| stem.parse::<usize>().ok())
+            else {
+                continue;
+            };
+            let len = entry.metadata()?.len() as usize;
+            segments.push(Segment { start, len });
+        }
+        segments.sort_by_key(|segment| segment.start);
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            segments,
+            active: None,
+            active_timings: None,
+            last_timing: None,
+            segment_bytes: segment_bytes.max(1),
+            max_bytes,
+        })
+    }
+
+    pub(crate) fn start_offset(&self) -> usize {
+        self.segments.first().map_or(0, |segment| segment.start)
+    }
+
+    pub(crate) fn end_offset(&self) -> usize {
+        self.segments.last().map_or(0, Segment::end)
+    }
+
+    #[cfg(test)]
+    pub(crate) fn append(&mut self, data: &[u8]) -> std::io::Result<()> {
+        self.append_at(data, crate::recording::unix_millis())
+    }
+
+    /// Appends output read from the PTY at `unix_ms`.
+    pub(crate) fn append_at(&mut self, data: &[u8], unix_ms: u64) -> std::io::Result<()> {
+        if data.is_empty() {
+            return Ok(());
+        }
+        let needs_new_segment = self
+            .segments
+            .last()
+            .is_none_or(|segment| segment.len >= self.segment_bytes);
+        if needs_new_segment {
+            let start = self.end_offset();
+            self.active = None;
+            self.active_timings = None;
+            // Every segment's timings start with its first chunk.
+            self.last_timing = None;
+            self.segments.push(Segment { start, len: 0 });
+        }
+        if self.active.is_none() {
+            let start = self.segments.last().map_or(0, |segment| segment.start);
+            self.active = Some(
+                OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(segment_path(&self.dir, start))?,
+            );
+        }
+        if needs_timing(self.last_timing.as_ref(), unix_ms) {
+            let timing = OutputTiming {
+                offset: self.end_offset(),
+                unix_ms,
+            };
+            if self.active_timings.is_none() {
+                let start = self.segments.last().map_or(0, |segment| segment.start);
+                self.active_timings = Some(
+                    OpenOptions::new()
+                        .create(true)
+                        .append(true)
+                        .open(timing_path(&self.dir, start))?,
+                );
+            }
+            if let Some(file) = self.active_timings.as_mut() {
+                writeln!(file, "{} {}", timing.offset, timing.unix_ms)?;
+            }
+            self.last_timing = Some(timing);
+        }
+        if let Some(file) = self.active.as_mut() {
+            file.write_all(data)?;
+        }
+        if let Some(segment) = self.segments.last_mut() {
+            segment.len += data.len();
+        }
+        self.enforce_max_bytes();
+        Ok(())
+    }
+
+    fn enforce_max_bytes(&mut self) {
+        let mut total: usize = self.segments.iter().map(|segment| segment.len).sum();
+        while total > self.max_bytes && self.segments.len() > 1 {
+            let oldest = self.segments.remove(0);
+            total -= oldest.len;
+            let _ = fs::remove_file(segment_path(&self.dir, oldest.start));
+            let _ = fs::remove_file(timing_path(&self.dir, oldest.start));
+        }
+    }
+
+    /// Bytes in `[start, end)`, clamped to what is still on disk.
+    pub(crate) fn read_range(&self, start: usize, end: usize) -> std::io::Result<Vec<u8>> {
+        let start = start.max(self.start_offset());
+        let end = end.min(self.end_offset());
+        let mut out = Vec::with_capacity(end.saturating_sub(start));
+        for segment in &self.segments {
+            if segment.end() <= start || segment.start >= end {
+                continue;
+            }
+            let from = start.max(segment.start);
+            let to = end.min(segment.end());
+            let mut file = File::open(segment_path(&self.dir, segment.start))?;
+            file.seek(SeekFrom::Start((from - segment.start) as u64))?;
+            let mut buf = vec![0u8; to - from];
+            file.read_exact(&mut buf)?;
+            out.extend_from_slice(&buf);
+        }
+        Ok(out)
+    }
+
+    /// Same paging contract as `OutputHistory::slice_before`, served from disk.
+    pub(crate) fn slice_before(
+        &self,
+        before_offset: Option<usize>,
+        max_bytes: usize,
+    ) -> std::io::Result<OutputSlice> {
+        let retained_start = self.start_offset();
+        let end = before_offset
+            .unwrap_or(self.end_offset())
+            .min(self.end_offset())
+            .max(retained_start);
+        let start = end - (end - retained_start).min(max_bytes);
+        Ok(OutputSlice {
+            data: self.read_range(start, end)?,
+            start_offset: start,
+            end_offset: end,
+            has_more: start > retained_start,
+        })
+    }
+
+    /// Timings of the output in `[start, end)`. Segments without a timing
+    /// file, or with an unreadable one, contribute none.
+    pub(crate) fn timings(&self, start: usize, end: usize) -> Vec<OutputTiming> {
+        let mut timings = Vec::new();
+        for segment in &self.segments {
+            if segment.end() <= start || segment.start >= end {
+                continue;
+            }
+            let Ok(content) = fs::read_to_string(timing_path(&self.dir, segment.start)) else {
+                continue;
+            };
+            timings.extend(content.lines().filter_map(|line| {
+                let (offset, unix_ms) = line.split_once(' ')?;
+                Some(OutputTiming {
+                    offset: offset.parse().ok()?,
+                    unix_ms: unix_ms.parse().ok()?,
+                })
+            }));
+        }
+        clip_timings(timings, start, end)
+    }
+
+    /// The last `max_bytes` of output with their timings.
+    pub(crate) fn recording(&self, max_bytes: usize) -> std::io::Result<OutputRecording> {
+        let slice = self.slice_before(None, max_bytes)?;
+        Ok(OutputRecording {
+            timings: self.timings(slice.start_offset, slice.end_offset),
+            data: slice.data,
+            start_offset: slice.start_offset,
+            size: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn append_and_reopen_keeps_offsets() {
+        let tmp = TempDir::new().unwrap();
+        let dir = tmp.path().join("session");
+        let mut log = OutputLog::open(&dir).unwrap();
+        log.append(b"hello ").unwrap();
+        log.append(b"world").unwrap();
+        drop(log);
+
+        let log = OutputLog::open(&dir).unwrap();
+        assert_eq!(log.start_offset(), 0);
+        assert_eq!(log.end_offset(), 11);
+        assert_eq!(log.read_range(0, 11).unwrap(), b"hello world");
+        assert_eq!(log.read_range(6, 100).unwrap(), b"world");
+    }
+
+    #[test]
+    fn rotation_drops_oldest_segments() {
+        let tmp = TempDir::new().unwrap();
+        let dir = tmp.path().join("session");
+        let mut log = OutputLog::open_with_limits(&dir, 4, 8).unwrap();
+        for chunk in [b"aaaa", b"bbbb", b"cccc", b"dddd"] {
+            log.append(chunk).unwrap();
+        }
+        assert_eq!(log.start_offset(), 8);
+        assert_eq!(log.end_offset(), 16);
+        assert_eq!(log.read_range(0, 16).unwrap(), b"ccccdddd");
+        // Two segments and their timing files.
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 4);
+    }
+
+    #[test]
+    fn read_range_spans_segments() {
+        let tmp = TempDir::new().unwrap();
+        let dir = tmp.path().join("session");
+        let mut log = OutputLog::open_with_limits(&dir, 4, 1024).unwrap();
+        log.append(b"abcd").unwrap();
+        log.append(b"efgh").unwrap();
+        log.append(b"ij").unwrap();
+        assert_eq!(log.read_range(2, 9).unwrap(), b"cdefghi");
+    }
+
+    #[test]
+    fn slice_before_pages_backwards() {
+        let tmp = TempDir::new().unwrap();
+        let dir = tmp.path().join("session");
+        let mut log = OutputLog::open_with_limits(&dir, 4, 1024).unwrap();
+        log.append(b"abcdefghij").unwrap();
+
+        let tail = log.slice_before(None, 4).unwrap();
+        assert_eq!(tail.data, b"ghij");
+        assert_eq!((tail.start_offset, tail.end_offset), (6, 10));
+        assert!(tail.has_more);
+
+        let head = log.slice_before(Some(3), 4).unwrap();
+        assert_eq!(head.data, b"abc");
+        assert!(!head.has_more);
+    }
+
+    #[test]
+    fn records_timings_per_segment() {
+        let tmp = TempDir::new().unwrap();
+        let dir = tmp.path().join("session");
+        let mut log = OutputLog::open_with_limits(&dir, 4, 8).unwrap();
+        log.append_at(b"ab", 1_000).unwrap();
+        // Within the resolution of the previous chunk: no new timing.
+        log.append_at(b"cd", 1_005).unwrap();
+        log.append_at(b"efgh", 1_500).unwrap();
+        log.append_at(b"ij", 2_000).unwrap();
+        log.append_at(b"kl", 2_001).unwrap();
+
+        let at = |offset, unix_ms| OutputTiming { offset, unix_ms };
+        assert_eq!(log.timings(4, 12), vec![at(4, 1_500), at(8, 2_000)]);
+        // A range starting mid-chunk takes the timing of that chunk.
+        assert_eq!(log.timings(9, 12), vec![at(9, 2_000)]);
+
+        // The first segment was rotated out along with its timings.
+        let recording = OutputLog::open(&dir).unwrap().recording(1024).unwrap();
+        assert_eq!(recording.data, b"efghijkl");
+        assert_eq!(recording.start_offset, 4);
+        assert_eq!(recording.timings, vec![at(4, 1_500), at(8, 2_000)]);
+    }
+
+    #[test]
+    fn ignores_unrelated_files() {
+        let tmp = TempDir::new().unwrap();
+        let dir = tmp.path().to_path_buf();
+        fs::write(dir.join("notes.txt"), "x").unwrap();
+        let log = OutputLog::open(&dir).unwrap();
+        assert_eq!(log.end_offset(), 0);
+    }
+}
@@ -1,4 +1,5 @@
-use crate::history::{OutputHistory, OUTPUT_HISTORY_MAX_BYTES};
+use crate::history::OutputHistory;
+use crate::screen::TerminalScreen;
 use portable_pty::Child;
 use std::io::Read;
 use std::sync::Arc;
@@ -10,21 +11,39 @@ pub(crate) struct SessionChannels {
     pub output_tx: broadcast::Sender<Vec<u8>>,
     pub exit_tx: broadcast::Sender<Option<u32>>,
     pub output_history: Arc<std::sync::Mutex<OutputHistory>>,
+    pub screen: Arc<std::sync::Mutex<TerminalScreen>>,
     pub stopped: Arc<std::sync::atomic::AtomicBool>,
+    /// Set once the reader has finished: the child's exit code, if known.
+    pub exit_code: Arc<std::sync::OnceLock<Option<u32>>>,
 }
 
+#[cfg(test)]
 pub(crate) fn create_session_channels() -> SessionChannels {
+    create_session_channels_with_history(
+        OutputHistory::new(crate::history::OUTPUT_HISTORY_MAX_BYTES),
+        (80, 24),
+    )
+}
+
+pub(crate) fn create_session_channels_with_history(
+    history: OutputHistory,
+    (cols, rows): (u16, u16),
+) -> SessionChannels {
     let (output_tx, _) = broadcast::channel(4096);
     let (exit_tx, _) = broadcast::channel(4);
-    let output_history = Arc::new(std::sync::Mutex::new(OutputHistory::new(
-        OUTPUT_HISTORY_MAX_BYTES,
-    )));
+    // Output restored from a log is replayed so the screen starts where the
+    // previous process left it, as a client replaying history would see it.
+    let mut screen = TerminalScreen::new(cols, rows);
+    screen.process(&history.snapshot());
+    let output_history = Arc::new(std::sync::Mutex::new(history));
     let stopped = Arc::new(std::sync::atomic::AtomicBool::new(false));
     SessionChannels {
         output_tx,
         exit_tx,
         output_history,
+        screen: Arc::new(std::sync::Mutex::new(screen)),
         stopped,
+        exit_code: Arc::new(std::sync::OnceLock::new()),
     }
 }
 
@@ -36,7 +55,9 @@ pub(crate) struct ReaderThreadContext {
     pub output_tx: broadcast::Sender<Vec<u8>>,
     pub exit_tx: broadcast::Sender<Option<u32>>,
     pub output_history: Arc<std::sync::Mutex<OutputHistory>>,
+    pub screen: Arc<std::sync::Mutex<TerminalScreen>>,
     pub stopped: Arc<std::sync::atomic::AtomicBool>,
+    pub exit_code: Arc<std::sync::OnceLock<Option<u32>>>,
     pub child: Option<Arc<std::sync::Mutex<Box<dyn Child + Send + Sync>>>>,
     pub session_id: Uuid,
 }
@@ -58,7 +79,9 @@ fn run_reader_loop(ctx: ReaderThreadContext) {
         output_tx,
         exit_tx,
         output_history,
+        screen,
         stopped,
+        exit_code: exit_code_cell,
         child,
         session_id,
     } = ctx;
@@ -71,6 +94,10 @@ fn run_reader_loop(ctx: ReaderThreadContext) {
                 if let Ok(mut history) = output_history.lock() {
                     history.push(&buf[..n]);
                 }
+                // Sent under the screen lock so a client taking a snapshot
+                // and subscribing at once gets every chunk exactly once.
+                let mut screen = screen.lock().unwrap_or_else(|e| e.into_inner());
+                screen.process(&buf[..n]);
                 let _ = output_tx.send(buf[..n].to_vec());
             }
             Err(err) => {
@@ -102,6 +129,7 @@ fn run_reader_loop(ctx: ReaderThreadContext) {
         None
     };
 
+    let _ = exit_code_cell.set(exit_code);
     let _ = exit_tx.send(exit_code);
     tracing::debug!(session_id = %session_id, ?exit_code, "reader thread finished");
 }
@@ -149,7 +177,9 @@ mod tests {
             output_tx: channels.output_tx,
             exit_tx: channels.exit_tx,
             output_history: channels.output_history,
+            screen: channels.screen.clone(),
             stopped: channels.stopped,
+            exit_code: channels.exit_code.clone(),
             child: None,
             session_id: uuid::Uuid::nil(),
         };
@@ -166,7 +196,9 @@ mod tests {
             output_tx: channels.output_tx,
             exit_tx: channels.exit_tx,
             output_history: channels.output_history,
+            screen: channels.screen.clone(),
             stopped: channels.stopped.clone(),
+            exit_code: channels.exit_code.clone(),
             child: None,
             session_id: uuid::Uuid::nil(),
         };
@@ -186,7 +218,9 @@ mod tests {
             output_tx: channels.output_tx,
             exit_tx: channels.exit_tx,
             output_history: channels.output_history.clone(),
+            screen: channels.screen.clone(),
             stopped: channels.stopped.clone(),
+            exit_code: channels.exit_code.clone(),
             child: None,
             session_id: uuid::Uuid::nil(),
         };
@@ -198,6 +232,10 @@ mod tests {
 
         let received = output_rx.try_recv().unwrap();
         assert_eq!(received, b"hello world");
+        assert_eq!(
+            channels.screen.lock().unwrap().capture().lines[0],
+            "hello world"
+        );
 
         assert!(channels.stopped.load(std::sync::atomic::Ordering::SeqCst));
     }
@@ -233,7 +271,9 @@ mod tests {
             output_tx: channels.output_tx,
             exit_tx: channels.exit_tx,
             output_history: channels.output_history,
+            screen: channels.screen.clone(),
             stopped: channels.stopped.clone(),
+            exit_code: channels.exit_code.clone(),
             child: None,
             session_id: uuid::Uuid::nil(),
         };
@@ -255,7 +295,9 @@ mod tests {
             output_tx: channels.output_tx,
             exit_tx: channels.exit_tx,
             output_history: channels.output_history,
+            screen: channels.screen.clone(),
             stopped: channels.stopped.clone(),
+            exit_code: channels.exit_code.clone(),
             child: None,
             session_id: uuid::Uuid::nil(),
         };
@@ -278,7 +320,9 @@ mod tests {
             output_tx: channels.output_tx,
             exit_tx: channels.exit_tx,
             output_history: channels.output_history,
+            screen: channels.screen.clone(),
             stopped: channels.stopped.clone(),
+            exit_code: channels.exit_code.clone(),
             child: None,
             session_id: uuid::Uuid::nil(),
         };
@@ -300,7 +344,9 @@ mod tests {
             output_tx: channels.output_tx,
             exit_tx: channels.exit_tx,
             output_history: channels.output_history.clone(),
+            screen: channels.screen.clone(),
             stopped: channels.stopped.clone(),
+            exit_code: channels.exit_code.clone(),
             child: None,
             session_id: uuid::Uuid::nil(),
         };
@@ -0,0 +1,72 @@
+//! When a session's output arrived, kept alongside the output so it can be
+//! exported as a recording that plays back at the original pace.
+
+use crate::output_log::OutputLog;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Chunks arriving within this many milliseconds of the last recorded one
+/// share its timestamp, bounding timings to 100 per second of output.
+pub(crate) const TIMING_RESOLUTION_MS: u64 = 10;
+
+/// Output from `offset` on was read from the PTY at `unix_ms`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutputTiming {
+    pub offset: usize,
+    pub unix_ms: u64,
+}
+
+/// A stretch of a session's output with the times it was produced.
+#[derive(Debug, Clone, Default)]
+pub struct OutputRecording {
+    pub data: Vec<u8>,
+    /// Absolute offset of `data[0]`.
+    pub start_offset: usize,
+    /// Ascending by offset. Output before the first timing has none, e.g.
+    /// when it was logged by a daemon version that didn't record them.
+    pub timings: Vec<OutputTiming>,
+    /// Terminal size when the recording was taken, if a PTY is running.
+    pub size: Option<(u16, u16)>,
+}
+
+impl OutputRecording {
+    /// The last `max_bytes` of output logged under `dir`, without a running
+    /// daemon or PTY session.
+    pub fn from_log_dir(dir: &Path, max_bytes: usize) -> std::io::Result<Self> {
+        OutputLog::open(dir)?.recording(max_bytes)
+    }
+}
+
+pub(crate) fn unix_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis() as u64)
+}
+
+/// Whether output read at `now` needs its own timing after `last`.
+pub(crate) fn needs_timing(last: Option<&OutputTiming>, now: u64) -> bool {
+    last.is_none_or(|last| now >= last.unix_ms + TIMING_RESOLUTION_MS)
+}
+
+/// Restricts ascending `timings` to output in `[start, end)`. The timing of
+/// the chunk `start` falls in is moved up to `start`.
+pub(crate) fn clip_timings(
+    timings: Vec<OutputTiming>,
+    start: usize,
+    end: usize,
+) -> Vec<OutputTiming> {
+    let first = timings.partition_point(|timing| timing.offset <= start);
+    let mut clipped = Vec::with_capacity(timings.len() - first + 1);
+    if let Some(covering) = first.checked_sub(1).map(|index| timings[index]) {
+        clipped.push(OutputTiming {
+            offset: start,
+            ..covering
+        });
+    }
+    clipped.extend(
+        timings[first..]
+            .iter()
+            .take_while(|timing| timing.offset < end),
+    );
+    clipped
+}
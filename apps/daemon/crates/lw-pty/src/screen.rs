@@ -0,0 +1,126 @@
+//! Server-side terminal state for a PTY session. Output is fed through a VT
+//! parser as it's read, so a client attaching later can be sent a snapshot of
+//! the current screen instead of the whole output history.
+
+/// Plain-text capture of what a session's terminal currently shows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScreenCapture {
+    pub cols: u16,
+    pub rows: u16,
+    /// Zero-based.
+    pub cursor_row: u16,
+    /// Zero-based.
+    pub cursor_col: u16,
+    pub cursor_hidden: bool,
+    pub alternate_screen: bool,
+    /// One entry per visible row, trailing blanks trimmed.
+    pub lines: Vec<String>,
+}
+
+pub(crate) struct TerminalScreen {
+    parser: vt100::Parser,
+}
+
+impl TerminalScreen {
+    pub fn new(cols: u16, rows: u16) -> Self {
+        // Scrollback stays with `OutputHistory`; only the visible grid is kept.
+        Self {
+            parser: vt100::Parser::new(rows.max(1), cols.max(1), 0),
+        }
+    }
+
+    pub fn process(&mut self, data: &[u8]) {
+        self.parser.process(data);
+    }
+
+    pub fn resize(&mut self, cols: u16, rows: u16) {
+        self.parser.screen_mut().set_size(rows.max(1), cols.max(1));
+    }
+
+    /// `(cols, rows)`.
+    pub fn size(&self) -> (u16, u16) {
+        let (rows, cols) = self.parser.screen().size();
+        (cols, rows)
+    }
+
+    /// Escape sequences that redraw the screen, cursor and input modes on a
+    /// freshly reset terminal.
+    pub fn snapshot(&self) -> Vec<u8> {
+        let screen = self.parser.screen();
+        let mut out = Vec::new();
+        if screen.alternate_screen() {
+            out.extend_from_slice(b"\x1b[?1049h");
+        }
+        out.extend_from_slice(&screen.state_formatted());
+        out
+    }
+
+    pub fn capture(&self) -> ScreenCapture {
+        let screen = self.parser.screen();
+        let (rows, cols) = screen.size();
+        let (cursor_row, cursor_col) = screen.cursor_position();
+        ScreenCapture {
+            cols,
+            rows,
+            cursor_row,
+            cursor_col,
+            cursor_hidden: screen.hide_cursor(),
+            alternate_screen: screen.alternate_screen(),
+            lines: screen
+                .rows(0, cols)
+                .map(|line| line.trim_end().to_string())
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshot_reproduces_screen_and_cursor() {
+        let mut screen = TerminalScreen::new(20, 4);
+        screen.process(b"hello\r\n\x1b[1;31mred\x1b[0m\r\nprompt> ");
+
+        let mut replayed = TerminalScreen::new(20, 4);
+        replayed.process(&screen.snapshot());
+        assert_eq!(replayed.capture(), screen.capture());
+
+        let capture = screen.capture();
+        assert_eq!(capture.lines, vec!["hello", "red", "prompt>", ""]);
+        assert_eq!((capture.cursor_row, capture.cursor_col), (2, 8));
+    }
+
+    #[test]
+    fn snapshot_is_bounded_by_the_screen() {
+        let mut screen = TerminalScreen::new(80, 24);
+        for i in 0..10_000 {
+            screen.process(format!("line {i}\r\n").as_bytes());
+        }
+        assert!(screen.snapshot().len() < 4096);
+        assert_eq!(screen.capture().lines[22], "line 9999");
+    }
+
+    #[test]
+    fn snapshot_keeps_the_alternate_screen() {
+        let mut screen = TerminalScreen::new(20, 4);
+        screen.process(b"shell$ \x1b[?1049h\x1b[Hfull screen app");
+        assert!(screen.capture().alternate_screen);
+
+        let mut replayed = TerminalScreen::new(20, 4);
+        replayed.process(&screen.snapshot());
+        let capture = replayed.capture();
+        assert!(capture.alternate_screen);
+        assert_eq!(capture.lines[0], "full screen app");
+    }
+
+    #[test]
+    fn resize_changes_the_capture_size() {
+        let mut screen = TerminalScreen::new(80, 24);
+        screen.resize(100, 30);
+        let capture = screen.capture();
+        assert_eq!((capture.cols, capture.rows), (100, 30));
+        assert_eq!(capture.lines.len(), 30);
+    }
+}
@@ -1,5 +1,9 @@
-use crate::history::OutputHistory;
-use crate::reader::{create_session_channels, spawn_reader_thread, ReaderThreadContext};
+use crate::history::{OutputHistory, OUTPUT_HISTORY_MAX_BYTES};
+use crate::reader::{
+    create_session_channels_with_history, spawn_reader_thread, ReaderThreadContext,
+};
+use crate::recording::OutputRecording;
+use crate::screen::{ScreenCapture, TerminalScreen};
 use portable_pty::{native_pty_system, Child, MasterPty, PtySize};
 use std::io::Write;
 use std::path::Path;
@@ -16,9 +20,11 @@ pub struct PtySession {
     output_tx: broadcast::Sender<Vec<u8>>,
     exit_tx: broadcast::Sender<Option<u32>>,
     output_history: Arc<std::sync::Mutex<OutputHistory>>,
+    screen: Arc<std::sync::Mutex<TerminalScreen>>,
     // std::sync::atomic is used here instead of tokio::sync because `is_stopped()` is called
     // from both sync and async contexts (including the reader thread).
     stopped: Arc<std::sync::atomic::AtomicBool>,
+    exit_code: Arc<std::sync::OnceLock<Option<u32>>>,
 }
 
 impl PtySession {
@@ -30,6 +36,26 @@ impl PtySession {
         env: Vec<(String, String)>,
         cols: u16,
         rows: u16,
+    ) -> Result<Self, crate::PtyError> {
+        Self::spawn_with_history(
+            session_id,
+            program,
+            args,
+            working_dir,
+            env,
+            (cols, rows),
+            OutputHistory::new(OUTPUT_HISTORY_MAX_BYTES),
+        )
+    }
+
+    pub(crate) fn spawn_with_history(
+        session_id: Uuid,
+        program: &str,
+        args: &[&str],
+        working_dir: &Path,
+        env: Vec<(String, String)>,
+        (cols, rows): (u16, u16),
+        history: OutputHistory,
     ) -> Result<Self, crate::PtyError> {
         let pty_system = native_pty_system();
         let pair = pty_system
@@ -63,7 +89,7 @@ impl PtySession {
             .spawn_command(cmd)
             .map_err(|e| crate::PtyError::Pty(e.to_string()))?;
 
-        let channels = create_session_channels();
+        let channels = create_session_channels_with_history(history, (cols, rows));
 
         let reader = pair
             .master
@@ -83,7 +109,9 @@ impl PtySession {
             output_tx: channels.output_tx.clone(),
             exit_tx: channels.exit_tx.clone(),
             output_history: channels.output_history.clone(),
+            screen: channels.screen.clone(),
             stopped: channels.stopped.clone(),
+            exit_code: channels.exit_code.clone(),
             child: Some(child_arc.clone()),
             session_id,
         });
@@ -99,7 +127,9 @@ impl PtySession {
             output_tx: channels.output_tx,
             exit_tx: channels.exit_tx,
             output_history: channels.output_history,
+            screen: channels.screen,
             stopped: channels.stopped,
+            exit_code: channels.exit_code,
         })
     }
 
@@ -111,10 +141,46 @@ impl PtySession {
         self.exit_tx.subscribe()
     }
 
+    /// A snapshot of the current screen (see `screen_snapshot`) together
+    /// with a receiver for the output that follows it. Taken atomically, so
+    /// no chunk is both in the snapshot and received, or in neither.
+    pub fn subscribe_with_screen(&self) -> (Vec<u8>, broadcast::Receiver<Vec<u8>>) {
+        let screen = self.lock_screen();
+        (screen.snapshot(), self.output_tx.subscribe())
+    }
+
     pub fn seed_history(&self, data: &[u8]) {
         if let Ok(mut history) = self.output_history.lock() {
             history.push(data);
         }
+        self.lock_screen().process(data);
+    }
+
+    /// Escape sequences that redraw the current screen, cursor position and
+    /// input modes on a freshly reset terminal. Unlike the output history,
+    /// its size is bounded by the terminal size.
+    pub fn screen_snapshot(&self) -> Vec<u8> {
+        self.lock_screen().snapshot()
+    }
+
+    /// The text currently on screen.
+    pub fn screen_capture(&self) -> ScreenCapture {
+        self.lock_screen().capture()
+    }
+
+    /// The last `max_bytes` of output with the times they were read, at the
+    /// current terminal size.
+    pub fn output_recording(&self, max_bytes: usize) -> OutputRecording {
+        let mut recording = match self.output_history.lock() {
+            Ok(history) => history.recording(max_bytes),
+            Err(_) => OutputRecording::default(),
+        };
+        recording.size = Some(self.lock_screen().size());
+        recording
+    }
+
+    fn lock_screen(&self) -> std::sync::MutexGuard<'_, TerminalScreen> {
+        self.screen.lock().unwrap_or_else(|e| e.into_inner())
     }
 
     pub fn output_snapshot(&self) -> Vec<u8> {
@@ -170,6 +236,7 @@ impl PtySession {
                 })
                 .map_err(|e| crate::PtyError::Pty(e.to_string()))?;
         }
+        self.lock_screen().resize(cols, rows);
 
         Ok(())
     }
@@ -197,4 +264,11 @@ impl PtySession {
     pub fn is_stopped(&self) -> bool {
         self.stopped.load(std::sync::atomic::Ordering::SeqCst)
     }
+
+    /// `Some` once the process has exited and its output has been read,
+    /// holding the exit code when it could be collected. Unlike
+    /// `subscribe_exit`, this cannot miss an exit that happened earlier.
+    pub fn exit_code(&self) -> Option<Option<u32>> {
+        self.exit_code.get().copied()
+    }
 }
@@ -71,6 +71,25 @@ async fn output_snapshot_captures_history() {
     );
 }
 
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn screen_capture_tracks_output_and_resize() {
+    let id = Uuid::new_v4();
+    let session =
+        PtySession::spawn(id, "echo", &["screen_test"], &tmp_dir(), vec![], 80, 24).unwrap();
+
+    let mut exit_rx = session.subscribe_exit();
+    let _ = tokio::time::timeout(tokio::time::Duration::from_secs(5), exit_rx.recv()).await;
+
+    let capture = session.screen_capture();
+    assert_eq!(capture.lines[0], "screen_test");
+    assert_eq!((capture.cols, capture.rows), (80, 24));
+    assert!(String::from_utf8_lossy(&session.screen_snapshot()).contains("screen_test"));
+
+    session.resize(100, 30).await.ok();
+    let capture = session.screen_capture();
+    assert_eq!((capture.cols, capture.rows), (100, 30));
+}
+
 #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
 async fn write_to_session() {
     let id = Uuid::new_v4();
@@ -117,3 +136,17 @@ async fn resize_session() {
 
     session.kill().await.unwrap();
 }
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn exit_code_is_kept_after_exit() {
+    let id = Uuid::new_v4();
+    let session =
+        PtySession::spawn(id, "sh", &["-c", "exit 7"], &tmp_dir(), vec![], 80, 24).unwrap();
+
+    let deadline = tokio::time::Instant::now() + tokio::time::Duration::from_secs(5);
+    while session.exit_code().is_none() && tokio::time::Instant::now() < deadline {
+        tokio::time::sleep(tokio::time::Duration::from_millis(20)).await;
+    }
+    assert_eq!(session.exit_code(), Some(Some(7)));
+    assert!(session.is_stopped());
+}
@@ -1,4 +1,11 @@
-import { Activity, Circle, Hand, Keyboard, Loader2 } from "lucide-react";
+import {
+	Activity,
+	Circle,
+	Hand,
+	Keyboard,
+	Loader2,
+	ShieldAlert,
+} from "lucide-react";
 import type { AgentActivityPhase } from "../../../shared/stores/app-store";
 
 interface AgentActivityIconProps {
@@ -16,6 +23,8 @@ function activityLabel(phase: AgentActivityPhase): string {
 			return "Processing";
 		case "streaming_output":
 			return "Streaming output";
+		case "awaiting_approval":
+			return "Waiting for approval";
 		default:
 			return "Unknown state";
 	}
@@ -55,6 +64,12 @@ export function AgentActivityIcon({
 					<Activity size={14} className={`${className} text-sky-500`} />
 				</span>
 			);
+		case "awaiting_approval":
+			return (
+				<span aria-label={label} title={label}>
+					<ShieldAlert size={14} className={`${className} text-red-400`} />
+				</span>
+			);
 		default:
 			return (
 				<span aria-label={label} title={label}>
@@ -7,8 +7,9 @@ const TERM_WIRE_VERSION = 1;
 const TERM_FRAME_HEADER_SIZE = 30;
 const TERM_FRAME_HISTORY = 1;
 const TERM_FRAME_LIVE = 2;
+const TERM_FRAME_SNAPSHOT = 3;
 
-type TerminalOutputKind = "history" | "live";
+type TerminalOutputKind = "history" | "snapshot" | "live";
 
 interface ParsedFrame {
 	sessionId: string;
@@ -45,12 +46,14 @@ function parseOutputFrame(frame: Uint8Array): ParsedFrame | null {
 	if (frame[0] !== TERM_WIRE_VERSION) return null;
 
 	const kindCode = frame[1];
-	const kind =
+	const kind: TerminalOutputKind | null =
 		kindCode === TERM_FRAME_HISTORY
 			? "history"
-			: kindCode === TERM_FRAME_LIVE
-				? "live"
-				: null;
+			: kindCode === TERM_FRAME_SNAPSHOT
+				? "snapshot"
+				: kindCode === TERM_FRAME_LIVE
+					? "live"
+					: null;
 	if (!kind) return null;
 
 	const view = new DataView(frame.buffer, frame.byteOffset, frame.byteLength);
@@ -124,10 +127,25 @@ describe("parseOutputFrame", () => {
 		expect(result?.sessionId).toBe("12345678-1234-1234-1234-123456789abc");
 	});
 
+	it("parses valid snapshot frame", () => {
+		const payload = new TextEncoder().encode("\x1b[Hscreen");
+		const frame = buildFrame(
+			1,
+			TERM_FRAME_SNAPSHOT,
+			NIL_UUID_BYTES,
+			0n,
+			payload,
+		);
+		const result = parseOutputFrame(frame);
+
+		expect(result?.kind).toBe("snapshot");
+		expect(result?.payload).toEqual(payload);
+	});
+
 	it("rejects frame with unknown kind code", () => {
 		const frame = buildFrame(
 			1,
-			3,
+			4,
 			NIL_UUID_BYTES,
 			7n,
 			new Uint8Array([10, 20]),
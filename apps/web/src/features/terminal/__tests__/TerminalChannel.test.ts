@@ -63,6 +63,7 @@ describe("TerminalChannel", () => {
 		expect(socket.url).toContain("token=t+k");
 		expect(socket.url).toContain("cols=100");
 		expect(socket.url).toContain("rows=30");
+		expect(socket.url).toContain("replay=screen");
 
 		socket.readyState = MockWebSocket.OPEN;
 		socket.onopen?.();
@@ -142,6 +143,60 @@ describe("TerminalChannel", () => {
 		expect(handlers.onError).toHaveBeenCalled();
 	});
 
+	it("connects as a spectator and tracks the input lock", async () => {
+		const { TerminalChannel } = await import("../channel/TerminalChannel");
+		const onInputLockChange = vi.fn();
+		const channel = new TerminalChannel({
+			sessionId: "sid",
+			token: "token",
+			spectator: true,
+			handlers: {
+				onReady: vi.fn(),
+				onOutput: vi.fn(),
+				onExit: vi.fn(),
+				onError: vi.fn(),
+				onInputLockChange,
+			},
+		});
+		channel.connect();
+		const socket = MockWebSocket.instances[0];
+		if (!socket) throw new Error("missing socket");
+		expect(socket.url).toContain("mode=spectator");
+		socket.readyState = MockWebSocket.OPEN;
+		socket.onopen?.();
+
+		socket.onmessage?.({
+			data: JSON.stringify({
+				type: "ready",
+				session_id: "sid",
+				client_id: "me",
+			}),
+		});
+		const lockEvent = (holder: string | null) => ({
+			data: JSON.stringify({
+				type: "input_lock",
+				session_id: "sid",
+				locked: holder !== null,
+				holder,
+			}),
+		});
+		socket.onmessage?.(lockEvent("me"));
+		socket.onmessage?.(lockEvent("other"));
+		socket.onmessage?.(lockEvent(null));
+		expect(onInputLockChange.mock.calls.map(([state]) => state)).toEqual([
+			{ locked: true, heldByMe: true },
+			{ locked: true, heldByMe: false },
+			{ locked: false, heldByMe: false },
+		]);
+
+		expect(channel.lockInput()).toBe(true);
+		expect(channel.unlockInput()).toBe(true);
+		expect(socket.sent.map(String)).toEqual([
+			JSON.stringify({ type: "lock_input" }),
+			JSON.stringify({ type: "unlock_input" }),
+		]);
+	});
+
 	it("disconnects and marks not connected", async () => {
 		const { TerminalChannel } = await import("../channel/TerminalChannel");
 		const onConnectionChange = vi.fn();
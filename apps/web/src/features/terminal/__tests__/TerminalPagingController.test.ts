@@ -49,6 +49,20 @@ describe("TerminalPagingController", () => {
 		expect(controller.isInputSuppressed).toBe(false);
 	});
 
+	it("processFrame snapshot is written like history", () => {
+		const term = createMockTerminal(false);
+		controller.setTerminal(term as unknown as XTerm);
+
+		controller.processFrame("snapshot", new Uint8Array([1]));
+		controller.processFrame("live", new Uint8Array([2]));
+		expect(term.writes.length).toBe(1);
+		expect(controller.isInputSuppressed).toBe(true);
+
+		term.writes[0]?.cb?.();
+		expect(term.writes.length).toBe(2);
+		expect(controller.isInputSuppressed).toBe(false);
+	});
+
 	it("processFrame live writes bytes directly", () => {
 		const bytes = new Uint8Array([65, 66, 67]);
 		controller.processFrame("live", bytes);
@@ -4,6 +4,7 @@ const TERM_WIRE_VERSION = 1;
 const TERM_FRAME_HEADER_SIZE = 30;
 const TERM_FRAME_HISTORY = 1;
 const TERM_FRAME_LIVE = 2;
+const TERM_FRAME_SNAPSHOT = 3;
 const TERM_INPUT_BYTES_OPCODE = 1;
 const RECONNECT_INITIAL_MS = 1000;
 const RECONNECT_MAX_MS = 15000;
@@ -12,7 +13,13 @@ export interface TerminalReadyEvent {
 	sessionId: string;
 }
 
-export type TerminalOutputKind = "history" | "live";
+export type TerminalOutputKind = "history" | "snapshot" | "live";
+
+export interface TerminalInputLockState {
+	locked: boolean;
+	/** This channel holds the lock; other clients can't type or resize. */
+	heldByMe: boolean;
+}
 
 export interface TerminalOutputFrameMeta {
 	sessionId: string;
@@ -22,6 +29,14 @@ export interface TerminalOutputFrameMeta {
 interface TermReadyPayload {
 	type: "ready";
 	session_id: string;
+	client_id?: string;
+}
+
+interface TermInputLockPayload {
+	type: "input_lock";
+	session_id: string;
+	locked: boolean;
+	holder: string | null;
 }
 
 interface TermExitPayload {
@@ -37,7 +52,11 @@ interface TermErrorPayload {
 	retryable: boolean;
 }
 
-type TermServerEvent = TermReadyPayload | TermExitPayload | TermErrorPayload;
+type TermServerEvent =
+	| TermReadyPayload
+	| TermInputLockPayload
+	| TermExitPayload
+	| TermErrorPayload;
 
 export interface TerminalChannelHandlers {
 	onConnectionChange?: (connected: boolean) => void;
@@ -49,6 +68,7 @@ export interface TerminalChannelHandlers {
 	) => void;
 	onExit: (exitCode: number | null) => void;
 	onError: (message: string) => void;
+	onInputLockChange?: (state: TerminalInputLockState) => void;
 }
 
 export interface TerminalChannelOptions {
@@ -56,6 +76,8 @@ export interface TerminalChannelOptions {
 	token: string;
 	initialCols?: number;
 	initialRows?: number;
+	/** Watch only: the daemon rejects input and resizes. */
+	spectator?: boolean;
 	handlers: TerminalChannelHandlers;
 }
 
@@ -72,6 +94,8 @@ export class TerminalChannel {
 	private readonly handlers: TerminalChannelHandlers;
 	private readonly initialCols?: number;
 	private readonly initialRows?: number;
+	private readonly spectator: boolean;
+	private clientId: string | null = null;
 	private ws: WebSocket | null = null;
 	private reconnectTimer: number | null = null;
 	private reconnectDelayMs = RECONNECT_INITIAL_MS;
@@ -84,6 +108,7 @@ export class TerminalChannel {
 		this.token = options.token;
 		this.initialCols = options.initialCols;
 		this.initialRows = options.initialRows;
+		this.spectator = options.spectator ?? false;
 		this.handlers = options.handlers;
 	}
 
@@ -127,6 +152,15 @@ export class TerminalChannel {
 		return true;
 	}
 
+	/** Ask to become the session's only writer until `unlockInput`. */
+	lockInput(): boolean {
+		return this.sendJson({ type: "lock_input" });
+	}
+
+	unlockInput(): boolean {
+		return this.sendJson({ type: "unlock_input" });
+	}
+
 	sendResize(cols: number, rows: number): boolean {
 		if (cols <= 0 || rows <= 0) return false;
 		return this.sendJson({
@@ -139,9 +173,15 @@ export class TerminalChannel {
 	private openSocket(): void {
 		if (!this.shouldReconnect) return;
 
+		// Ask for a redraw of the current screen rather than the full output
+		// history; older output is paged in through the scrollback endpoint.
 		const params = new URLSearchParams({
 			token: this.token,
+			replay: "screen",
 		});
+		if (this.spectator) {
+			params.set("mode", "spectator");
+		}
 		if (typeof this.initialCols === "number" && this.initialCols > 0) {
 			params.set("cols", String(this.initialCols));
 		}
@@ -220,12 +260,21 @@ export class TerminalChannel {
 
 		const event = parsed as TermServerEvent;
 		if (event.type === "ready") {
+			this.clientId = event.client_id ?? null;
 			this.handlers.onReady({
 				sessionId: event.session_id,
 			});
 			return;
 		}
 
+		if (event.type === "input_lock") {
+			this.handlers.onInputLockChange?.({
+				locked: event.locked,
+				heldByMe: event.holder !== null && event.holder === this.clientId,
+			});
+			return;
+		}
+
 		if (event.type === "exit") {
 			this.handlers.onExit(event.exit_code);
 			// Allow one auto-reconnect after exit so the backend can
@@ -275,12 +324,14 @@ function parseOutputFrame(frame: Uint8Array): ParsedFrame | null {
 	if (frame[0] !== TERM_WIRE_VERSION) return null;
 
 	const kindCode = frame[1];
-	const kind =
+	const kind: TerminalOutputKind | null =
 		kindCode === TERM_FRAME_HISTORY
 			? "history"
-			: kindCode === TERM_FRAME_LIVE
-				? "live"
-				: null;
+			: kindCode === TERM_FRAME_SNAPSHOT
+				? "snapshot"
+				: kindCode === TERM_FRAME_LIVE
+					? "live"
+					: null;
 	if (!kind) return null;
 
 	const view = new DataView(frame.buffer, frame.byteOffset, frame.byteLength);
@@ -72,7 +72,7 @@ export class TerminalPagingController {
 		const terminal = this.terminal;
 		if (!terminal) return;
 
-		if (kind === "history") {
+		if (kind === "history" || kind === "snapshot") {
 			this.historyWriteInProgress = true;
 			this.suppressInput = true;
 			terminal.write(bytes, () => {
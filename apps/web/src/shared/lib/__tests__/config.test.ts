@@ -56,4 +56,25 @@ describe("config helpers", () => {
 		const mod = await import("../runtime/config");
 		expect(mod.getDaemonUrl()).toBe("");
 	});
+
+	it("uses its own origin when served by the daemon", async () => {
+		setLocationHref("http://192.168.1.12:9400/connect?x=1");
+		vi.stubGlobal("document", {
+			querySelector: (selector: string) =>
+				selector === 'meta[name="loopwire-daemon"]'
+					? { getAttribute: () => "same-origin" }
+					: null,
+		});
+		try {
+			const mod = await import("../runtime/config");
+			expect(mod.isServedByDaemon()).toBe(true);
+			expect(mod.getDaemonUrl()).toBe("http://192.168.1.12:9400");
+			expect(mod.getWsUrl()).toBe("ws://192.168.1.12:9400/api/v1/ws");
+			mod.enableManualDiscovery();
+			await mod.initDaemonDiscovery();
+			expect(discoverDaemonMock).not.toHaveBeenCalled();
+		} finally {
+			vi.unstubAllGlobals();
+		}
+	});
 });
@@ -7,6 +7,19 @@ const DISCOVERY_ENABLED_KEY = "loopwire_discovery_enabled";
 
 let discoveredUrl: string | null = null;
 
+/**
+ * Returns whether this page was served by the daemon itself, which marks its
+ * `index.html` so the app talks to its own origin.
+ */
+export function isServedByDaemon(): boolean {
+	if (typeof document === "undefined") return false;
+	return (
+		document
+			.querySelector('meta[name="loopwire-daemon"]')
+			?.getAttribute("content") === "same-origin"
+	);
+}
+
 function normalizeDaemonUrl(value: string): string | null {
 	const trimmed = value.trim();
 	if (!trimmed) return null;
@@ -103,7 +116,12 @@ export async function initDaemonDiscovery(): Promise<void> {
 	const hasQueryParam = !!readBackendParam();
 	const hasSessionOverride = !!sessionStorage.getItem(BACKEND_OVERRIDE_KEY);
 
-	if (hasEnvUrl || hasQueryParam || hasSessionOverride) {
+	if (
+		hasEnvUrl ||
+		hasQueryParam ||
+		hasSessionOverride ||
+		isServedByDaemon()
+	) {
 		return;
 	}
 
@@ -129,7 +147,8 @@ export function enableManualDiscovery(): void {
 }
 
 /**
- * Resolves the daemon base URL from query/session/env/discovery sources.
+ * Resolves the daemon base URL from query/session/same-origin/env/discovery
+ * sources.
  */
 export function getDaemonUrl(): string {
 	const fromQuery = readBackendParam();
@@ -147,6 +166,11 @@ export function getDaemonUrl(): string {
 		sessionStorage.removeItem(BACKEND_OVERRIDE_KEY);
 	}
 
+	if (isServedByDaemon()) {
+		const origin = normalizeDaemonUrl(window.location.href);
+		if (origin) return origin;
+	}
+
 	const envUrl = normalizeDaemonUrl(ENV_DAEMON_URL);
 	if (envUrl) return envUrl;
 
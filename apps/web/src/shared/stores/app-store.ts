@@ -46,7 +46,15 @@ export type AgentActivityPhase =
 	| "awaiting_user"
 	| "user_input"
 	| "processing"
-	| "streaming_output";
+	| "streaming_output"
+	| "awaiting_approval";
+
+export interface ApprovalRequest {
+	tool: string;
+	command?: string;
+	file?: string;
+	prompt: string;
+}
 
 export interface AgentActivity {
 	phase: AgentActivityPhase;
@@ -55,6 +63,7 @@ export interface AgentActivity {
 	last_input_at: string | null;
 	last_output_at: string | null;
 	reason: string;
+	approval?: ApprovalRequest;
 }
 
 export function defaultAgentActivity(): AgentActivity {